@@ -0,0 +1,104 @@
+//! Benchmarks over synthetic todo lists.
+//!
+//! Run with `cargo bench`.
+
+use chrono::Duration;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use tada::action::find::find_results;
+use tada::action::{select_items, SearchTerms, SortOrder};
+use tada::list::TidyOptions;
+use tada::util::find_items_by_string;
+use tada::List;
+
+const WORDS: [&str; 8] = [
+	"Buy", "milk", "Call", "dentist", "Fix", "bike", "Write", "report",
+];
+
+fn synthetic_text(n: usize) -> String {
+	let today = tada::today();
+	let mut text = String::new();
+	for i in 0..n {
+		let pri = (b'A' + (i % 5) as u8) as char;
+		let a = WORDS[i % WORDS.len()];
+		let b = WORDS[(i * 7 + 3) % WORDS.len()];
+		// Spread due dates from a month ago to a year ahead.
+		let due = today + Duration::days((i * 37 % 400) as i64 - 30);
+		text.push_str(&format!(
+			"({}) 2026-01-01 {} {} #{} +project{} @context{} due:{}\n",
+			pri,
+			a,
+			b,
+			i,
+			i % 10,
+			i % 4,
+			due.format("%Y-%m-%d"),
+		));
+	}
+	text
+}
+
+fn synthetic_list(n: usize) -> List {
+	List::from_string(synthetic_text(n)).unwrap()
+}
+
+fn bench_list(c: &mut Criterion) {
+	let list = synthetic_list(10_000);
+	let items = list.items();
+
+	c.bench_function("sort alphabetical 10k", |b| {
+		b.iter(|| SortOrder::Alphabetical.sort_items(black_box(items.clone())))
+	});
+
+	c.bench_function("find 10k", |b| {
+		b.iter(|| find_items_by_string(black_box("dentist"), items.clone()))
+	});
+
+	let big = synthetic_list(50_000);
+	let all = SearchTerms::new();
+	c.bench_function("select top 3 by urgency 50k", |b| {
+		b.iter(|| select_items(black_box(&big), &all, &SortOrder::Urgency, 3))
+	});
+
+	c.bench_function("tidy and smart sort 10k", |b| {
+		b.iter(|| {
+			let (tidied, _) = black_box(&list)
+				.but_tidy(&SortOrder::Smart, TidyOptions::default());
+			SortOrder::Smart
+				.sort_items(tidied.items())
+				.len()
+		})
+	});
+}
+
+fn bench_load(c: &mut Criterion) {
+	let dir = tempfile::tempdir().unwrap();
+	let path = dir.path().join("todo.txt");
+	std::fs::write(&path, synthetic_text(200_000)).unwrap();
+	let path = path.display().to_string();
+	let terms = SearchTerms::from_vec(Vec::from([
+		String::from("dentist"),
+		String::from("+project3"),
+	]));
+
+	let mut group = c.benchmark_group("find in 200k-line file");
+	group.sample_size(10);
+	group.bench_function("full load", |b| {
+		b.iter(|| {
+			let list = List::from_url(path.clone()).unwrap();
+			find_results(&terms, &list).len()
+		})
+	});
+	group.bench_function("streaming", |b| {
+		b.iter(|| {
+			let list = List::from_url_where(path.clone(), |l| {
+				terms.line_might_match(l)
+			})
+			.unwrap();
+			find_results(&terms, &list).len()
+		})
+	});
+	group.finish();
+}
+
+criterion_group!(benches, bench_list, bench_load);
+criterion_main!(benches);
@@ -1,25 +1,53 @@
 //! Implementation of the command-line interface.
 
-use crate::item::{Importance, Item};
+use crate::config::{Config, HintsConfig, Profile};
+use crate::error::Error;
+use crate::format::{Field, Template};
+use crate::item::{
+	parse_date_arg, today, Importance, Item, TshirtSize, Urgency,
+};
 use crate::list::{LineKind, List};
+use crate::util::*;
+use chrono::NaiveDate;
 use clap::{Arg, ArgAction, ArgMatches, Command};
 use console::Style;
-use promptly::prompt_default;
-use std::{env, fs, io};
-use substring::Substring;
+use log::{debug, info, trace};
+use std::collections::{HashMap, HashSet};
+use std::io::IsTerminal;
+use std::path::Path;
+use std::{env, fmt, fs, io};
 
 pub mod add;
+pub mod age;
 pub mod archive;
+pub mod count;
 pub mod done;
 pub mod edit;
+pub mod export;
 pub mod find;
+pub mod import;
 pub mod important;
+pub mod init;
+pub mod lint;
+pub mod modify;
+pub mod next;
 pub mod path;
+pub mod pri;
 pub mod pull;
 pub mod quick;
 pub mod remove;
+pub mod rename_context;
+pub mod rename_tag;
+pub mod review;
 pub mod show;
+pub mod snooze;
+pub mod start;
+pub mod stats;
+pub mod stop;
+pub mod sync;
 pub mod tidy;
+pub mod today;
+pub mod undo;
 pub mod urgent;
 pub mod zen;
 
@@ -29,6 +57,100 @@ pub struct Action {
 	pub command: Command,
 }
 
+/// Exit status returned by an action's `execute` function.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExitStatus {
+	/// The action succeeded.
+	Success,
+	/// The action matched or affected no tasks.
+	NoMatch,
+	/// The action failed, for example because a file could not be read.
+	Error,
+}
+
+impl ExitStatus {
+	/// Success if some tasks were matched or affected, NoMatch otherwise.
+	pub fn from_count(count: usize) -> Self {
+		if count > 0 {
+			Self::Success
+		} else {
+			Self::NoMatch
+		}
+	}
+
+	/// The process exit code for this status.
+	pub fn code(&self) -> i32 {
+		match self {
+			Self::Success => 0,
+			Self::NoMatch => 1,
+			Self::Error => 2,
+		}
+	}
+}
+
+/// What an action did to a list, for using tada as a library.
+///
+/// Library entry points like `done::run` take a list and a plain config
+/// struct, and return one of these rather than printing anything or saving
+/// the list.
+#[derive(Debug)]
+pub struct ActionOutcome {
+	/// The list with the changes made. It hasn't been saved.
+	pub list: List,
+	/// How many tasks were changed.
+	pub count: usize,
+	/// What was done, to follow on from "have" or "would have", like
+	/// "snoozed 2 tasks in todo.txt until 2024-06-10". Empty if nothing was.
+	pub messages: Vec<String>,
+}
+
+impl ActionOutcome {
+	/// An outcome where `count` tasks in `list` were changed, described by
+	/// `message` if there were any.
+	pub fn new(list: List, count: usize, message: String) -> Self {
+		let messages = if count > 0 { vec![message] } else { Vec::new() };
+		Self {
+			list,
+			count,
+			messages,
+		}
+	}
+
+	/// The exit status the command line would use for this outcome.
+	pub fn exit_status(&self) -> ExitStatus {
+		ExitStatus::from_count(self.count)
+	}
+}
+
+/// A list loaded from a file, possibly belonging to a named profile.
+pub struct ProfileList {
+	pub profile: Option<String>,
+	pub filename: String,
+	pub list: List,
+}
+
+impl ProfileList {
+	/// Merge several lists into one, for display purposes.
+	pub fn merge(lists: &[Self]) -> List {
+		let mut merged = List::new();
+		for pl in lists {
+			merged
+				.lines
+				.extend(pl.list.lines.iter().cloned());
+		}
+		merged
+	}
+}
+
+/// A place a file path can come from, and the path it gives, if any.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Candidate {
+	/// Where the path comes from, like "--file" or "TODO_DIR".
+	pub source: String,
+	/// The path from this source, or None if it isn't set.
+	pub path: Option<String>,
+}
+
 /// A type of file that tada can operate on.
 #[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord)]
 pub enum FileType {
@@ -39,13 +161,11 @@ pub enum FileType {
 impl FileType {
 	/// Given a set of options, determines the exact file path.
 	///
-	/// Uses environment variables `TODO_FILE`, `TODO_DIR`, and `DONE_FILE`
-	/// as fallbacks.
-	pub fn filename(&self, args: &ArgMatches) -> String {
-		match self {
-			Self::TodoTxt => Self::_filename_for_todotxt(args),
-			Self::DoneTxt => Self::_filename_for_donetxt(args),
-		}
+	/// Uses the `--profile` option or `TADA_PROFILE` environment variable to
+	/// look up paths in the config file, then environment variables
+	/// `TODO_FILE`, `TODO_DIR`, and `DONE_FILE` as fallbacks.
+	pub fn filename(&self, args: &ArgMatches) -> Result<String, Error> {
+		self.resolve(args).map(|(filename, _)| filename)
 	}
 
 	/// Human-readable label for the file type.
@@ -57,78 +177,353 @@ impl FileType {
 	}
 
 	/// Shortcut to determine the file path and load it as a List.
-	pub fn load(&self, args: &ArgMatches) -> List {
-		let filename = self.filename(args);
-		let label = self.label();
-		List::from_url(filename)
-			.unwrap_or_else(|_| panic!("Could not read {}", label))
+	pub fn load(&self, args: &ArgMatches) -> Result<List, Error> {
+		self._load(args, None)
+	}
+
+	/// Like `load`, but for read-only commands which only care about tasks
+	/// matching some search terms.
+	///
+	/// Local files are streamed, only keeping tasks which pass a quick text
+	/// check, plus tasks with an `id:` so it's still known which tasks are
+	/// waiting for others. The list mustn't be saved.
+	pub fn load_matching(
+		&self,
+		args: &ArgMatches,
+		terms: &SearchTerms,
+	) -> Result<List, Error> {
+		self._load(args, Some(terms))
+	}
+
+	fn _load(
+		&self,
+		args: &ArgMatches,
+		filter: Option<&SearchTerms>,
+	) -> Result<List, Error> {
+		let (filename, source) = self.resolve(args)?;
+		self._load_from(&filename, &source, args, filter)
+	}
+
+	/// Load a list from a path which has already been resolved.
+	fn _load_from(
+		&self,
+		filename: &str,
+		source: &str,
+		args: &ArgMatches,
+		filter: Option<&SearchTerms>,
+	) -> Result<List, Error> {
+		Self::_check_exists(filename, source)?;
+		let label = format!("{} {} (from {})", self.label(), filename, source);
+		let list =
+			Self::_read(filename, filter).map_err(|e| Error::Unreadable {
+				label: label.clone(),
+				message: e.to_string(),
+			})?;
+		match filter {
+			Some(_) => info!(
+				"Read {} tasks which might match from {}",
+				list.count_items(),
+				filename
+			),
+			None => info!(
+				"Read {} lines, {} of them tasks, from {}",
+				list.lines.len(),
+				list.count_items(),
+				filename
+			),
+		}
+		Self::_check_text(&list, &label, Self::_strict(args))?;
+		Ok(list)
+	}
+
+	/// Whether the `--strict` option was given.
+	fn _strict(args: &ArgMatches) -> bool {
+		args.try_get_one::<bool>("strict")
+			.ok()
+			.flatten()
+			.copied()
+			.unwrap_or(false)
+	}
+
+	/// Warn about lines which weren't valid UTF-8, or fail if strict.
+	fn _check_text(
+		list: &List,
+		label: &str,
+		strict: bool,
+	) -> Result<(), Error> {
+		if let (true, Some(&line)) = (strict, list.invalid_lines.first()) {
+			return Err(Error::InvalidText {
+				label: label.to_string(),
+				line,
+			});
+		}
+		for line in &list.invalid_lines {
+			eprintln!(
+				"Warning: line {} of {} isn't valid UTF-8; bad bytes have \
+				been replaced, and will stay replaced if the list is saved.",
+				line, label
+			);
+		}
+		Ok(())
+	}
+
+	fn _read(filename: &str, filter: Option<&SearchTerms>) -> io::Result<List> {
+		match filter {
+			Some(terms) => List::from_url_where(filename.to_string(), |line| {
+				line.contains("id:") || terms.line_might_match(line)
+			}),
+			None => List::from_url(filename.to_string()),
+		}
+	}
+
+	/// Load every list of this type, tagged with its profile name.
+	///
+	/// If the `--all-profiles` option was given, loads the list for each
+	/// profile in the config file. Otherwise just loads the usual list.
+	pub fn load_all(
+		&self,
+		args: &ArgMatches,
+	) -> Result<Vec<ProfileList>, Error> {
+		self._load_all(args, None)
+	}
+
+	/// Like `load_all`, but streaming each list like `load_matching`.
+	pub fn load_all_matching(
+		&self,
+		args: &ArgMatches,
+		terms: &SearchTerms,
+	) -> Result<Vec<ProfileList>, Error> {
+		self._load_all(args, Some(terms))
+	}
+
+	fn _load_all(
+		&self,
+		args: &ArgMatches,
+		filter: Option<&SearchTerms>,
+	) -> Result<Vec<ProfileList>, Error> {
+		let all = args
+			.try_get_one::<bool>("all-profiles")
+			.ok()
+			.flatten()
+			.copied()
+			.unwrap_or(false);
+		if !all {
+			let (filename, source) = self.resolve(args)?;
+			let list = self._load_from(&filename, &source, args, filter)?;
+			return Ok(Vec::from([ProfileList {
+				profile: None,
+				filename,
+				list,
+			}]));
+		}
+
+		let config = Config::load();
+		if config.profiles.is_empty() {
+			return Err(Error::NoProfiles);
+		}
+		let mut out = Vec::new();
+		for (name, profile) in config.profiles {
+			let filename = match self {
+				Self::TodoTxt => profile.todo_filename(),
+				Self::DoneTxt => profile.done_filename(),
+			};
+			let filename = match filename {
+				Some(f) => f,
+				None => continue,
+			};
+			let label = format!("{} for profile {}", self.label(), name);
+			let mut list = Self::_read(&filename, filter).map_err(|e| {
+				Error::Unreadable {
+					label: label.clone(),
+					message: e.to_string(),
+				}
+			})?;
+			Self::_check_text(&list, &label, Self::_strict(args))?;
+			list.set_source(&name);
+			out.push(ProfileList {
+				profile: Some(name),
+				filename,
+				list,
+			});
+		}
+		Ok(out)
+	}
+
+	/// Add the `--all-profiles` option to a Command.
+	pub fn add_all_profiles_arg(cmd: Command) -> Command {
+		cmd.arg(
+			Arg::new("all-profiles")
+				.num_args(0)
+				.long("all-profiles")
+				.help("Use the todo lists from every profile"),
+		)
+	}
+
+	/// The profile chosen by `--profile` or `TADA_PROFILE`, if any.
+	fn _profile(args: &ArgMatches) -> Result<Option<(String, Profile)>, Error> {
+		let name = match args.try_get_one::<String>("profile") {
+			Ok(Some(p)) => p.to_string(),
+			_ => env::var("TADA_PROFILE").unwrap_or_default(),
+		};
+		if name.is_empty() {
+			return Ok(None);
+		}
+		match Config::load().profile(&name) {
+			Some(p) => Ok(Some((name, p.clone()))),
+			None => Err(Error::UnknownProfile(name)),
+		}
 	}
 
-	fn _file_exists(path: &str) -> bool {
+	fn _file_exists(path: &Path) -> bool {
 		match fs::metadata(path) {
 			Ok(f) => f.is_file(),
 			Err(_) => false,
 		}
 	}
 
-	fn _filename_for_todotxt(args: &ArgMatches) -> String {
-		let local_only = *args.get_one::<bool>("local").unwrap_or(&false);
-		if local_only {
-			let names =
-				["todo.txt", "TODO", "TODO.TXT", "ToDo", "ToDo.txt", "todo"];
+	/// Look for a file with one of the given names in the current directory,
+	/// then each of its parents in turn, unless `--no-parent` was given.
+	fn _find_local(args: &ArgMatches, names: &[&str]) -> Result<String, Error> {
+		let no_parent = *args
+			.get_one::<bool>("no-parent")
+			.unwrap_or(&false);
+		let cwd = env::current_dir().unwrap();
+		match Self::_find_upwards(&cwd, names, !no_parent) {
+			Some(f) => Ok(f),
+			None => Err(Error::FileNotFound(String::from(names[0]))),
+		}
+	}
+
+	/// Look for a file with one of the given names in a directory, and
+	/// optionally in each of its parents, stopping at the first match.
+	fn _find_upwards(
+		start: &Path,
+		names: &[&str],
+		parents: bool,
+	) -> Option<String> {
+		let mut dir = Some(start);
+		while let Some(d) = dir {
+			debug!("Looking for {} in {}", names[0], d.display());
 			for n in names {
-				let qname = env::current_dir()
-					.unwrap()
-					.into_os_string()
-					.into_string()
-					.unwrap() + "/" + n;
+				let qname = d.join(n);
 				if Self::_file_exists(&qname) {
-					return qname;
+					return Some(qname.display().to_string());
 				}
 			}
-			panic!("Could not find a file called todo.txt or TODO in the current directory!")
+			dir = if parents { d.parent() } else { None };
 		}
+		None
+	}
 
-		if let Some(f) = args.get_one::<String>("file") {
-			return f.to_string();
-		};
-		if let Ok(f) = env::var("TODO_FILE") {
-			return f;
+	/// Every place the path could come from, in order of precedence.
+	///
+	/// The first candidate with a path is the one which is used. With
+	/// `--local`, the only candidate is the file found by searching.
+	pub fn candidates(
+		&self,
+		args: &ArgMatches,
+	) -> Result<Vec<Candidate>, Error> {
+		let (names, option, file_var, basename) = match self {
+			Self::TodoTxt => (
+				["todo.txt", "TODO", "TODO.TXT", "ToDo", "ToDo.txt", "todo"],
+				"file",
+				"TODO_FILE",
+				"todo.txt",
+			),
+			Self::DoneTxt => (
+				["done.txt", "DONE", "DONE.TXT", "Done", "Done.txt", "done"],
+				"done-file",
+				"DONE_FILE",
+				"done.txt",
+			),
 		};
-		let dir = env::var("TODO_DIR").unwrap_or_else(|_| {
-			env::var("HOME").expect("Could not determine path to todo.txt!")
-		});
-		dir + "/todo.txt"
-	}
 
-	fn _filename_for_donetxt(args: &ArgMatches) -> String {
 		let local_only = *args.get_one::<bool>("local").unwrap_or(&false);
 		if local_only {
-			let names =
-				["done.txt", "DONE", "DONE.TXT", "Done", "Done.txt", "done"];
-			for n in names {
-				let qname = env::current_dir()
-					.unwrap()
-					.into_os_string()
-					.into_string()
-					.unwrap() + "/" + n;
-				if Self::_file_exists(&qname) {
-					return qname;
-				}
-			}
-			panic!("Could not find a file called done.txt or DONE in the current directory!")
+			return Ok(Vec::from([Candidate {
+				source: String::from("--local"),
+				path: Some(Self::_find_local(args, &names)?),
+			}]));
 		}
 
-		if let Some(f) = args.get_one::<String>("done-file") {
-			return f.to_string();
+		let in_dir = |var: &str| {
+			env::var(var)
+				.ok()
+				.map(|d| format!("{}/{}", d, basename))
 		};
-		if let Ok(f) = env::var("DONE_FILE") {
-			return f;
-		};
-		let dir = env::var("TODO_DIR").unwrap_or_else(|_| {
-			env::var("HOME").expect("Could not determine path to done.txt!")
+		let mut out = Vec::from([Candidate {
+			source: format!("--{}", option),
+			path: args.get_one::<String>(option).cloned(),
+		}]);
+		if let Some((name, p)) = Self::_profile(args)? {
+			out.push(Candidate {
+				source: format!("profile {}", name),
+				path: match self {
+					Self::TodoTxt => p.todo_filename(),
+					Self::DoneTxt => p.done_filename(),
+				},
+			});
+		}
+		out.push(Candidate {
+			source: String::from(file_var),
+			path: env::var(file_var).ok(),
+		});
+		out.push(Candidate {
+			source: String::from("TODO_DIR"),
+			path: in_dir("TODO_DIR"),
+		});
+		out.push(Candidate {
+			source: String::from("HOME"),
+			path: in_dir("HOME"),
 		});
-		dir + "/done.txt"
+		Ok(out)
+	}
+
+	/// The path to use, and the source it came from, like "TODO_FILE".
+	pub fn resolve(
+		&self,
+		args: &ArgMatches,
+	) -> Result<(String, String), Error> {
+		let candidates = self.candidates(args)?;
+		for c in &candidates {
+			debug!(
+				"Path for {} from {}: {}",
+				self.label(),
+				c.source,
+				c.path.as_deref().unwrap_or("not set")
+			);
+		}
+		let (filename, source) = candidates
+			.into_iter()
+			.find_map(|c| c.path.map(|p| (p, c.source)))
+			.ok_or_else(|| Error::NoPath(self.label()))?;
+		info!("Using {} {} (from {})", self.label(), filename, source);
+		Ok((filename, source))
+	}
+
+	/// Check that a local file exists before trying to read it, so the
+	/// error can say where its path came from.
+	fn _check_exists(filename: &str, source: &str) -> Result<(), Error> {
+		let path = match List::local_path(filename) {
+			Some(p) => p,
+			None => return Ok(()),
+		};
+		if path.exists() {
+			return Ok(());
+		}
+		if source == "TODO_DIR" {
+			let dir = env::var("TODO_DIR").unwrap_or_default();
+			if !Path::new(&dir).is_dir() {
+				return Err(Error::MissingDir {
+					var: String::from("TODO_DIR"),
+					path: dir,
+				});
+			}
+		}
+		Err(Error::MissingList {
+			path: filename.to_string(),
+			source: source.to_string(),
+		})
 	}
 
 	/// Add some args to a Command so that it will expect a file of this type.
@@ -152,7 +547,14 @@ impl FileType {
 				.num_args(0)
 				.short('l')
 				.long("local")
-				.help("Look for files in local directory only"),
+				.help("Look for files here or in parent dirs"),
+		)
+		.arg(
+			Arg::new("no-parent")
+				.num_args(0)
+				.long("no-parent")
+				.requires("local")
+				.help("With --local, skip parent dirs"),
 		)
 	}
 
@@ -166,16 +568,121 @@ impl FileType {
 	}
 }
 
+/// First line of porcelain output, naming the version of the format.
+pub const PORCELAIN_HEADER: &str = "# tada-porcelain v1";
+
+/// The narrowest output allowed by `--max-width`, other than 0 for no
+/// limit, unless the output is plain or porcelain. Turning on more columns
+/// can raise this; see `Outputter::check_width`.
+pub const MIN_WIDTH: usize = 48;
+
+/// How much of each task's description must fit beside the other columns.
+const MIN_DESCRIPTION_WIDTH: usize = 20;
+
+/// Glyphs shown before each task with `--icons`; see `IconsConfig` for
+/// how to change them.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Icons {
+	pub overdue: String,
+	pub today: String,
+	pub soon: String,
+	pub small: String,
+	pub medium: String,
+	pub large: String,
+}
+
+impl Default for Icons {
+	fn default() -> Self {
+		Self {
+			overdue: String::from("🔥"),
+			today: String::from("📅"),
+			soon: String::from("🕐"),
+			small: String::new(),
+			medium: String::new(),
+			large: String::new(),
+		}
+	}
+}
+
+impl Icons {
+	/// The icon for a task's urgency, if any.
+	pub fn for_urgency(&self, urgency: Option<Urgency>) -> &str {
+		match urgency {
+			Some(Urgency::Overdue) => &self.overdue,
+			Some(Urgency::Today) => &self.today,
+			Some(Urgency::Soon) => &self.soon,
+			_ => "",
+		}
+	}
+
+	/// The icon for a task's size, if any.
+	pub fn for_size(&self, size: Option<TshirtSize>) -> &str {
+		match size {
+			Some(TshirtSize::Small) => &self.small,
+			Some(TshirtSize::Medium) => &self.medium,
+			Some(TshirtSize::Large) => &self.large,
+			None => "",
+		}
+	}
+
+	/// Pad an icon to the display width of the widest in its set, so
+	/// columns stay aligned. An empty set takes up no space at all.
+	fn pad(icon: &str, set: [&String; 3]) -> String {
+		let width = set
+			.iter()
+			.map(|i| console::measure_text_width(i))
+			.max()
+			.unwrap_or(0);
+		if width == 0 {
+			return String::new();
+		}
+		let padding = width - console::measure_text_width(icon);
+		format!("{}{} ", icon, " ".repeat(padding))
+	}
+}
+
 /// Provides pretty output for Item objects.
 pub struct Outputter {
 	pub width: usize,
 	pub colour: bool,
 	pub with_creation_date: bool,
 	pub with_completion_date: bool,
+	pub with_due_date: bool,
+	pub with_age: bool,
+	/// Ages above this many days are highlighted when `with_age` is set.
+	pub stale_days: i64,
 	pub with_line_numbers: bool,
 	pub with_newline: bool,
+	/// Show missing priorities and dates as `(?)` and `????-??-??`, not
+	/// blank space.
+	pub placeholders: bool,
+	/// Show completion as a `[x]` or `[ ]` checkbox, not `x` or nothing.
+	pub checkboxes: bool,
+	/// Show an icon before each task for its urgency and size.
+	pub icons: Option<Icons>,
+	/// End descriptions which are cut short to fit the width with `…`.
+	pub ellipsis: bool,
+	/// Write items as tab-separated fields for scripts; see `write_item`.
+	pub porcelain: bool,
+	/// Write items as todo.txt lines, and nothing else; see `write_item`.
+	pub plain: bool,
+	/// Write items using this template instead of the usual layout.
+	pub template: Option<Template>,
+	/// Write a diff of each list which is changed; see `SaveMode::save_with_diff`.
+	pub print_diff: bool,
+	/// Write housekeeping warnings; see `write_warnings`.
+	pub hints: bool,
 	pub line_number_digits: usize,
+	pub source_width: usize,
+	/// Profile and line number of tasks waiting for unfinished tasks.
+	pub blocked: HashSet<(Option<String>, usize)>,
+	/// Where tasks, headings, and diffs are written; stdout by default.
 	pub io: Box<dyn io::Write>,
+	/// Where notices, statuses, prompts, and errors are written; stderr by
+	/// default, so they don't get mixed into output which is piped or
+	/// captured.
+	pub msg_io: Box<dyn io::Write>,
+	porcelain_started: bool,
 }
 
 impl Outputter {
@@ -186,17 +693,77 @@ impl Outputter {
 			colour: false,
 			with_creation_date: false,
 			with_completion_date: false,
+			with_due_date: false,
+			with_age: false,
+			stale_days: 30,
 			with_line_numbers: false,
 			with_newline: true,
+			placeholders: true,
+			checkboxes: false,
+			icons: None,
+			ellipsis: true,
+			porcelain: false,
+			plain: false,
+			template: None,
+			print_diff: false,
+			hints: true,
 			line_number_digits: 2,
+			source_width: 0,
+			blocked: HashSet::new(),
 			io: Box::new(io::stdout()),
+			msg_io: Box::new(io::stderr()),
+			porcelain_started: false,
+		}
+	}
+
+	/// Adjust column widths to suit items from the given list, so line
+	/// numbers are padded to the same width.
+	pub fn fit_to_list(&mut self, list: &List) {
+		self.line_number_digits = list.line_count().to_string().len();
+	}
+
+	/// Adjust column widths to suit items from the given lists.
+	///
+	/// The profile column is only shown if some of the lists belong to profiles.
+	pub fn fit_to_lists(&mut self, lists: &[ProfileList]) {
+		self.line_number_digits = lists
+			.iter()
+			.map(|pl| pl.list.line_count().to_string().len())
+			.max()
+			.unwrap_or(1);
+		self.source_width = lists
+			.iter()
+			.filter_map(|pl| pl.profile.as_ref())
+			.map(|p| p.len())
+			.max()
+			.unwrap_or(0);
+	}
+
+	/// Remember which tasks in a list are waiting for unfinished tasks, so
+	/// they can be dimmed like tasks which can't be started yet.
+	pub fn note_blocked(&mut self, list: &List) {
+		for (item, _) in list.blocked_items() {
+			self.blocked
+				.insert((item.source().map(String::from), item.line_number()));
 		}
 	}
 
-	/// Alternative constructor, which detects width from the terminal
+	/// Whether an item was noted as waiting for unfinished tasks.
+	pub fn is_blocked(&self, i: &Item) -> bool {
+		self.blocked
+			.contains(&(i.source().map(String::from), i.line_number()))
+	}
+
+	/// Alternative constructor, which detects width from the terminal.
+	///
+	/// If output isn't going to a terminal, or its size is unknown, the
+	/// width is zero, so nothing is truncated.
 	pub fn new_based_on_terminal() -> Self {
 		let term = console::Term::stdout();
-		let (_height, width) = term.size();
+		let width = match term.size_checked() {
+			Some((_height, width)) if term.is_term() => width,
+			_ => 0,
+		};
 		Self::new(width.into())
 	}
 
@@ -227,7 +794,7 @@ impl Outputter {
 					.aliases(["maxwidth"])
 					.value_parser(clap::value_parser!(usize))
 					.value_name("COLS")
-					.help("Maximum width of terminal output"),
+					.help("Maximum width of output, or 0 for no limit"),
 			)
 			.arg(
 				Arg::new("show-lines")
@@ -251,6 +818,65 @@ impl Outputter {
 					.aliases(["showfinished", "finished"])
 					.help("Show 'finished' dates for tasks"),
 			)
+			.arg(
+				Arg::new("show-due")
+					.num_args(0)
+					.long("show-due")
+					.help("Show due dates for tasks"),
+			)
+			.arg(
+				Arg::new("show-age")
+					.num_args(0)
+					.long("show-age")
+					.help("Show how many days old tasks are"),
+			)
+			.arg(
+				Arg::new("no-placeholders")
+					.num_args(0)
+					.long("no-placeholders")
+					.help("Leave missing priorities and dates blank"),
+			)
+			.arg(
+				Arg::new("checkboxes")
+					.num_args(0)
+					.long("checkboxes")
+					.help("Show completion as [x] or [ ] checkboxes"),
+			)
+			.arg(
+				Arg::new("icons")
+					.num_args(0)
+					.long("icons")
+					.help("Show icons for urgency and size"),
+			)
+			.arg(
+				Arg::new("no-ellipsis")
+					.num_args(0)
+					.long("no-ellipsis")
+					.help("Don't end cut-off descriptions with …"),
+			)
+			.arg(
+				Arg::new("porcelain")
+					.num_args(0)
+					.long("porcelain")
+					.help("Tab-separated output for scripts"),
+			)
+			.arg(
+				Arg::new("plain")
+					.num_args(0)
+					.long("plain")
+					.conflicts_with_all(["porcelain", "template"])
+					.help("Output tasks as plain todo.txt lines"),
+			)
+			.arg(
+				Arg::new("template")
+					.long("format")
+					.value_name("TEMPLATE")
+					.value_parser(|s: &str| {
+						Template::parse(s).map_err(|e| e.to_string())
+					})
+					.conflicts_with("porcelain")
+					.help("Show tasks using a template, like '%n %d'"),
+			)
 	}
 
 	/// Initialize from minimal ArgMatches.
@@ -263,6 +889,18 @@ impl Outputter {
 		} else {
 			console::colors_enabled()
 		};
+		cfg.print_diff = args
+			.try_get_one::<bool>("print-diff")
+			.ok()
+			.flatten()
+			.copied()
+			.unwrap_or(false);
+		cfg.hints = !args
+			.try_get_one::<bool>("no-hints")
+			.ok()
+			.flatten()
+			.copied()
+			.unwrap_or(false);
 		cfg
 	}
 
@@ -273,17 +911,77 @@ impl Outputter {
 		cfg.with_completion_date =
 			*args.get_one::<bool>("show-finished").unwrap();
 		cfg.with_line_numbers = *args.get_one::<bool>("show-lines").unwrap();
-		cfg.width = *args
-			.get_one::<usize>("max-width")
-			.unwrap_or(&cfg.width);
-		if cfg.width < 48 {
-			panic!("max-width must be at least 48!");
+		cfg.with_due_date = *args.get_one::<bool>("show-due").unwrap();
+		cfg.with_age = *args.get_one::<bool>("show-age").unwrap();
+		cfg.placeholders = !*args.get_one::<bool>("no-placeholders").unwrap();
+		cfg.checkboxes = *args.get_one::<bool>("checkboxes").unwrap();
+		cfg.ellipsis = !*args.get_one::<bool>("no-ellipsis").unwrap();
+		if *args.get_one::<bool>("icons").unwrap() {
+			cfg.icons = Some(Config::load().icons.icons());
+		}
+		if cfg.with_age {
+			cfg.stale_days = Config::load().dates.stale_days();
+		}
+		cfg.porcelain = *args.get_one::<bool>("porcelain").unwrap();
+		cfg.plain = *args.get_one::<bool>("plain").unwrap();
+		cfg.template = args.get_one::<Template>("template").cloned();
+		if cfg.porcelain || cfg.plain {
+			cfg.colour = false;
+		}
+		if let Some(width) = args.get_one::<usize>("max-width") {
+			cfg.width = *width;
+			if let Err(e) = cfg.check_width() {
+				// Unlike `write_error`, clap doesn't end the line itself.
+				clap::Error::raw(
+					clap::error::ErrorKind::ValueValidation,
+					format!("{}\n", e),
+				)
+				.exit();
+			}
 		}
 		cfg
 	}
 
+	/// Check the output width leaves room for the columns which are turned
+	/// on. Porcelain and plain output aren't truncated, so any width will do.
+	pub fn check_width(&self) -> Result<(), String> {
+		if self.width == 0 || self.porcelain || self.plain {
+			return Ok(());
+		}
+		let min = self.min_width();
+		if self.width < min {
+			return Err(format!(
+				"--max-width must be at least {} with these columns, or 0 \
+				for no limit",
+				min
+			));
+		}
+		Ok(())
+	}
+
+	/// In porcelain mode, write the version header before anything else.
+	fn start_porcelain(&mut self) {
+		if self.porcelain && !self.porcelain_started {
+			writeln!(self.io, "{}", PORCELAIN_HEADER).expect("panik");
+			self.porcelain_started = true;
+		}
+	}
+
+	/// In porcelain mode, lines other than items are comments.
+	fn porcelain_comment(&self, text: String) -> String {
+		if self.porcelain {
+			format!("# {}", text)
+		} else {
+			text
+		}
+	}
+
 	/// Write a heading row.
 	pub fn write_heading(&mut self, heading: String) {
+		if self.plain {
+			return;
+		}
+		self.start_porcelain();
 		let stream = &mut self.io;
 		let mut hh: String = format!("# {}", heading);
 		if self.colour {
@@ -303,14 +1001,36 @@ impl Outputter {
 
 	/// Write a separator row.
 	pub fn write_separator(&mut self) {
+		if self.porcelain || self.plain {
+			return;
+		}
 		let stream = &mut self.io;
 		writeln!(stream).expect("panik");
 	}
 
+	/// Write a placeholder under a heading with no items, so the heading
+	/// isn't left bare.
+	pub fn write_empty_group(&mut self) {
+		if self.porcelain || self.plain {
+			return;
+		}
+		let stream = &mut self.io;
+		writeln!(stream, "  —").expect("panik");
+	}
+
+	/// Write a line of a command's results which isn't a task, like a count
+	/// or a report. Unlike status lines, these go to `io`, with the tasks.
+	pub fn write_data(&mut self, data: String) {
+		writeln!(self.io, "{}", data).expect("panik");
+	}
+
 	/// Write a status line.
 	pub fn write_status(&mut self, status: String) {
-		let stream = &mut self.io;
-		let mut hh: String = status;
+		if self.plain {
+			return;
+		}
+		let mut hh: String = self.porcelain_comment(status);
+		let stream = &mut self.msg_io;
 		if self.colour {
 			let s = Style::new()
 				.white()
@@ -327,8 +1047,11 @@ impl Outputter {
 
 	/// Write a hint line.
 	pub fn write_notice(&mut self, hint: String) {
-		let stream = &mut self.io;
-		let mut hh: String = hint;
+		if self.plain {
+			return;
+		}
+		let mut hh: String = self.porcelain_comment(hint);
+		let stream = &mut self.msg_io;
 		if self.colour {
 			let s = Style::new().magenta().force_styling(true);
 			hh = s.apply_to(hh).to_string();
@@ -340,10 +1063,32 @@ impl Outputter {
 		}
 	}
 
+	/// Write housekeeping warnings as notices, after a blank line.
+	///
+	/// Nothing is written if there are no warnings, or hints are off.
+	pub fn write_warnings(&mut self, warnings: Vec<Warning>) {
+		if !self.hints || self.plain || warnings.is_empty() {
+			return;
+		}
+		if !self.porcelain {
+			writeln!(self.msg_io).expect("panik");
+		}
+		for w in warnings {
+			self.write_notice(w.to_string());
+		}
+	}
+
+	/// Write a prompt, without a line break after it.
+	pub fn write_prompt(&mut self, prompt: String) {
+		let stream = &mut self.msg_io;
+		write!(stream, "{}", prompt).expect("panik");
+		stream.flush().expect("panik");
+	}
+
 	/// Write a error line.
 	pub fn write_error(&mut self, errstr: String) {
-		let stream = &mut self.io;
-		let mut hh: String = errstr;
+		let mut hh: String = self.porcelain_comment(errstr);
+		let stream = &mut self.msg_io;
 		if self.colour {
 			let s = Style::new().red().force_styling(true);
 			hh = s.apply_to(hh).to_string();
@@ -355,105 +1100,275 @@ impl Outputter {
 		}
 	}
 
+	/// Write the lines of a unified diff, colouring additions and removals.
+	pub fn write_diff(&mut self, lines: &[String]) {
+		for line in lines {
+			let style = if line.starts_with("+++") || line.starts_with("---") {
+				Style::new().bold()
+			} else if line.starts_with("@@") {
+				Style::new().cyan()
+			} else if line.starts_with('+') {
+				Style::new().green()
+			} else if line.starts_with('-') {
+				Style::new().red()
+			} else {
+				Style::new()
+			};
+			if self.colour {
+				let styled = style.force_styling(true).apply_to(line);
+				writeln!(self.io, "{}", styled).expect("panik");
+			} else {
+				writeln!(self.io, "{}", line).expect("panik");
+			}
+		}
+	}
+
 	/// Write an item. (Not in todo.txt format!)
 	///
-	/// Allows for pretty formatting, etc.
+	/// Allows for pretty formatting, etc. In porcelain mode, the item is
+	/// instead written as tab-separated fields; see `porcelain_fields`. In
+	/// plain mode, it's written in todo.txt format after all. If a template
+	/// is set, it is used instead, without colour.
 	pub fn write_item(&mut self, i: &Item) {
+		self.start_porcelain();
+		let r = if self.porcelain {
+			porcelain_fields(i).join("\t")
+		} else if self.plain {
+			i.to_string()
+		} else if let Some(t) = &self.template {
+			t.render(i)
+		} else {
+			self.render_segments(self.item_segments(i), i)
+		};
 		let stream = &mut self.io;
-		let mut r: String = String::new();
-
-		if i.completion() {
-			r.push_str("x ");
+		if self.with_newline {
+			writeln!(stream, "{}", r).expect("panik");
 		} else {
-			r.push_str("  ");
+			write!(stream, "{}", r).expect("panik");
+		}
+	}
+
+	/// The narrowest output width which leaves room for the columns
+	/// which are turned on, as well as part of each task's description.
+	pub fn min_width(&self) -> usize {
+		let columns: usize = self
+			.column_segments(&Item::new())
+			.iter()
+			.map(|(t, _)| console::measure_text_width(t))
+			.sum();
+		MIN_WIDTH.max(columns + MIN_DESCRIPTION_WIDTH)
+	}
+
+	/// Break an item into segments of text, each with an optional style.
+	///
+	/// The description is truncated to fit the output width, unless the
+	/// width is zero; see `truncate_words`.
+	fn item_segments(&self, i: &Item) -> Vec<(String, Option<Style>)> {
+		let mut segments = self.column_segments(i);
+
+		let used: usize = segments
+			.iter()
+			.map(|(t, _)| console::measure_text_width(t))
+			.sum();
+		let len = match self.width {
+			0 => usize::MAX,
+			width => width.saturating_sub(used),
+		};
+		// The task being tracked with `tada start` stands out most.
+		let style = match i.urgency() {
+			_ if i.started().is_some() && !i.completion() => {
+				Some(Style::new().cyan().bold())
+			}
+			Some(Urgency::Overdue) => Some(Style::new().red()),
+			Some(Urgency::Today) => Some(Style::new().yellow()),
+			_ => None,
+		};
+		let description = truncate_words(i.description(), len, self.ellipsis);
+		segments.push((description, style));
+
+		segments
+	}
+
+	/// The segments of text shown before an item's description, for the
+	/// columns which are turned on.
+	fn column_segments(&self, i: &Item) -> Vec<(String, Option<Style>)> {
+		let mut segments: Vec<(String, Option<Style>)> = Vec::new();
+		// Missing data is shown as a placeholder, or blank space of the
+		// same width, so columns stay aligned.
+		let missing = |placeholder: &str| {
+			if self.placeholders {
+				String::from(placeholder)
+			} else {
+				" ".repeat(placeholder.len())
+			}
+		};
+
+		let check = match (self.checkboxes, i.completion()) {
+			(false, true) => "x ",
+			(false, false) => "  ",
+			(true, true) => "[x] ",
+			(true, false) => "[ ] ",
+		};
+		segments.push((String::from(check), None));
+
+		// Icons aren't shown for completed tasks, but the space is kept.
+		if let Some(icons) = &self.icons {
+			let (urgency, size) = match i.completion() {
+				true => ("", ""),
+				false => (
+					icons.for_urgency(i.urgency()),
+					icons.for_size(i.tshirt_size()),
+				),
+			};
+			let urgencies = [&icons.overdue, &icons.today, &icons.soon];
+			let sizes = [&icons.small, &icons.medium, &icons.large];
+			segments.push((Icons::pad(urgency, urgencies), None));
+			segments.push((Icons::pad(size, sizes), None));
 		}
 
 		if i.priority() == '\0' {
-			r.push_str("(?) ");
+			segments.push((missing("(?) "), None));
 		} else {
 			let style = match i.importance() {
-				Some(Importance::A) => {
-					Style::new().red().bold().force_styling(true)
-				}
-				Some(Importance::B) => {
-					Style::new().yellow().bold().force_styling(true)
-				}
-				Some(Importance::C) => {
-					Style::new().green().bold().force_styling(true)
-				}
-				Some(_) => Style::new().bold().force_styling(true),
-				_ => Style::new(),
+				Some(Importance::A) => Some(Style::new().red().bold()),
+				Some(Importance::B) => Some(Style::new().yellow().bold()),
+				Some(Importance::C) => Some(Style::new().green().bold()),
+				Some(_) => Some(Style::new().bold()),
+				_ => None,
 			};
-			let paren = format!("({}) ", style.apply_to(i.priority()));
-			r.push_str(&paren);
+			segments.push((String::from("("), None));
+			segments.push((String::from(i.priority()), style));
+			segments.push((String::from(") "), None));
 		}
 
 		if self.with_completion_date {
-			if i.completion() && i.completion_date().is_some() {
-				let date = i
-					.completion_date()
+			let date = if i.completion() && i.completion_date().is_some() {
+				i.completion_date()
 					.unwrap()
 					.format("%Y-%m-%d ")
-					.to_string();
-				r.push_str(&date);
+					.to_string()
 			} else if i.completion() {
-				r.push_str("????-??-?? ");
+				missing("????-??-?? ")
 			} else {
-				r.push_str("           ");
-			}
+				String::from("           ")
+			};
+			segments.push((date, None));
 		}
 
 		if self.with_creation_date {
-			if i.creation_date().is_some() {
-				let date = i
-					.creation_date()
+			let date = if i.creation_date().is_some() {
+				i.creation_date()
 					.unwrap()
 					.format("%Y-%m-%d ")
-					.to_string();
-				r.push_str(&date);
+					.to_string()
 			} else {
-				r.push_str("????-??-?? ");
+				missing("????-??-?? ")
+			};
+			segments.push((date, None));
+		}
+
+		if self.with_due_date {
+			let date = match i.due_date() {
+				Some(d) => d.format("%Y-%m-%d ").to_string(),
+				None => String::from("           "),
+			};
+			segments.push((date, None));
+		}
+
+		if self.with_age {
+			match i.creation_date() {
+				Some(d) => {
+					let age = (today() - d).num_days();
+					let style = if age > self.stale_days {
+						Some(Style::new().yellow())
+					} else {
+						None
+					};
+					segments.push((format!("{:>4}d ", age), style));
+				}
+				None => segments.push((String::from("      "), None)),
 			}
 		}
 
-		if self.with_line_numbers {
-			r.push_str(
+		if self.source_width > 0 {
+			segments.push((
+				format!(
+					"{:<width$} ",
+					i.source().unwrap_or(""),
+					width = self.source_width
+				),
+				Some(Style::new().cyan()),
+			));
+		}
+
+		if self.with_line_numbers {
+			segments.push((
 				format!(
 					"#{:0width$} ",
 					i.line_number(),
 					width = self.line_number_digits
-				)
-				.as_str(),
-			)
+				),
+				None,
+			));
 		}
 
-		let len = self.width - console::strip_ansi_codes(&r).len();
-		r.push_str(i.description().substring(0, len));
+		segments
+	}
 
-		if i.completion() || !i.is_startable() {
-			if self.colour {
-				r = format!(
-					"{}",
-					Style::new()
-						.dim()
-						.force_styling(true)
-						.apply_to(console::strip_ansi_codes(&r).to_string())
-				);
-			} else {
-				r = console::strip_ansi_codes(&r).to_string();
-			}
-		} else if !self.colour {
-			r = console::strip_ansi_codes(&r).to_string();
+	/// Join segments into a single string, applying styles in one pass.
+	///
+	/// Completed, unstartable and blocked items are dimmed, which takes
+	/// priority over the segments' own styles.
+	fn render_segments(
+		&self,
+		segments: Vec<(String, Option<Style>)>,
+		i: &Item,
+	) -> String {
+		if !self.colour {
+			return segments.into_iter().map(|(t, _)| t).collect();
 		}
 
-		if self.with_newline {
-			writeln!(stream, "{}", r).expect("panik");
-		} else {
-			write!(stream, "{}", r).expect("panik");
+		if i.completion() || !i.is_startable() || self.is_blocked(i) {
+			let plain: String = segments.into_iter().map(|(t, _)| t).collect();
+			return Style::new()
+				.dim()
+				.force_styling(true)
+				.apply_to(plain)
+				.to_string();
 		}
+
+		segments
+			.into_iter()
+			.map(|(t, style)| match style {
+				Some(s) => s.force_styling(true).apply_to(t).to_string(),
+				None => t,
+			})
+			.collect()
 	}
 }
 
+/// The fields written for an item in porcelain mode.
+///
+/// These are, in order: line number, "x" if the task is complete,
+/// priority letter, creation date, completion date, due date, and the
+/// full description. Missing values are empty strings. Dates are in
+/// YYYY-MM-DD format, and any tabs in the description become spaces.
+pub fn porcelain_fields(i: &Item) -> Vec<String> {
+	let mut fields: Vec<String> = [
+		Field::LineNumber,
+		Field::Completion,
+		Field::Priority,
+		Field::CreationDate,
+		Field::CompletionDate,
+		Field::DueDate,
+	]
+	.iter()
+	.map(|f| f.value(i))
+	.collect();
+	fields.push(i.description().replace('\t', " "));
+	fields
+}
+
 impl Default for Outputter {
 	fn default() -> Self {
 		Self::new_based_on_terminal()
@@ -461,11 +1376,13 @@ impl Default for Outputter {
 }
 
 /// Whether the user has confirmed an action on an item.
-#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
 pub enum ConfirmationStatus {
 	Yes,
 	No,
 	Ask,
+	/// Ask, but pressing Enter means no.
+	AskDefaultNo,
 }
 
 impl ConfirmationStatus {
@@ -475,40 +1392,115 @@ impl ConfirmationStatus {
 			Self::No
 		} else if *args.get_one::<bool>("yes").unwrap() {
 			Self::Yes
+		} else if *args.get_one::<bool>("default-no").unwrap() {
+			Self::AskDefaultNo
 		} else {
 			Self::Ask
 		}
 	}
 
 	/// Possibly prompt a user for confirmation.
+	///
+	/// If `progress` is given, as which prompt this is and how many there
+	/// are, it is shown in the prompt. If stdin isn't a terminal, there's
+	/// nobody to ask, so the answer is no. Answers of "all" and "quit" are
+	/// returned as they are; see `confirm` to have them apply to later
+	/// prompts too.
 	pub fn check(
 		&self,
 		outputter: &mut Outputter,
 		prompt_phrase: &str,
 		yes_phrase: &str,
 		no_phrase: &str,
-	) -> bool {
-		match self {
-			ConfirmationStatus::Yes => {
-				outputter.write_notice(format!("{}\n", yes_phrase));
-				true
-			}
-			ConfirmationStatus::No => {
-				outputter.write_notice(format!("{}\n", no_phrase));
-				false
-			}
-			ConfirmationStatus::Ask => {
-				let response = prompt_default(prompt_phrase, true).unwrap();
-				if response {
-					outputter.write_notice(format!("{}\n", yes_phrase));
-				} else {
-					outputter.write_notice(format!("{}\n", no_phrase));
+		progress: Option<(usize, usize)>,
+	) -> Decision {
+		let decision = match self {
+			ConfirmationStatus::Yes => Decision::Yes,
+			ConfirmationStatus::No => Decision::No,
+			_ if !io::stdin().is_terminal() => Decision::No,
+			_ => self.ask(
+				outputter,
+				prompt_phrase,
+				progress,
+				&mut io::stdin().lock(),
+			),
+		};
+		if decision.is_yes() {
+			outputter.write_notice(format!("{}\n", yes_phrase));
+		} else {
+			outputter.write_notice(format!("{}\n", no_phrase));
+		}
+		decision
+	}
+
+	/// Write a prompt and read answers from `input` until one makes sense.
+	///
+	/// An empty answer gives the default, which is yes unless the status
+	/// is `AskDefaultNo`. The end of the input means quit.
+	pub fn ask(
+		&self,
+		outputter: &mut Outputter,
+		prompt_phrase: &str,
+		progress: Option<(usize, usize)>,
+		input: &mut dyn io::BufRead,
+	) -> Decision {
+		let default_yes = *self != ConfirmationStatus::AskDefaultNo;
+		loop {
+			outputter.write_prompt(prompt_text(
+				prompt_phrase,
+				progress,
+				default_yes,
+			));
+			let mut answer = String::new();
+			match input.read_line(&mut answer) {
+				Ok(0) | Err(_) => return Decision::Quit,
+				Ok(_) if answer.trim().is_empty() => {
+					return if default_yes {
+						Decision::Yes
+					} else {
+						Decision::No
+					};
+				}
+				Ok(_) => {
+					if let Some(d) = Decision::from_answer(&answer) {
+						return d;
+					}
 				}
-				response
 			}
 		}
 	}
 
+	/// Like `check`, but returns whether to go ahead, and after "all" or
+	/// "quit", stops asking and says yes or no to everything after.
+	pub fn confirm(
+		&mut self,
+		outputter: &mut Outputter,
+		prompt_phrase: &str,
+		yes_phrase: &str,
+		no_phrase: &str,
+		progress: Option<(usize, usize)>,
+	) -> bool {
+		let decision = self.check(
+			outputter,
+			prompt_phrase,
+			yes_phrase,
+			no_phrase,
+			progress,
+		);
+		self.remember(decision)
+	}
+
+	/// Remember an answer of "all" or "quit" for later prompts, and return
+	/// whether to go ahead with this one.
+	pub fn remember(&mut self, decision: Decision) -> bool {
+		match decision {
+			Decision::AllYes => *self = ConfirmationStatus::Yes,
+			Decision::Quit => *self = ConfirmationStatus::No,
+			Decision::Yes | Decision::No => {}
+		}
+		decision.is_yes()
+	}
+
 	/// Add some args to a Command so that it can prompt for yes/no questions.
 	pub fn add_args(cmd: Command) -> Command {
 		cmd.arg(
@@ -525,6 +1517,286 @@ impl ConfirmationStatus {
 				.long("no")
 				.help("Assume 'no' to prompts"),
 		)
+		.arg(
+			Arg::new("default-no")
+				.num_args(0)
+				.long("default-no")
+				.help("Make pressing Enter at prompts mean 'no'"),
+		)
+	}
+}
+
+/// The text of a confirmation prompt, like "Remove? (2 of 7) [Y]es/[n]o/[a]ll/[q]uit: ".
+///
+/// The default answer is shown in capitals.
+pub fn prompt_text(
+	prompt_phrase: &str,
+	progress: Option<(usize, usize)>,
+	default_yes: bool,
+) -> String {
+	let progress = match progress {
+		Some((n, total)) => format!(" ({} of {})", n, total),
+		None => String::new(),
+	};
+	let choices = if default_yes {
+		"[Y]es/[n]o/[a]ll/[q]uit"
+	} else {
+		"[y]es/[N]o/[a]ll/[q]uit"
+	};
+	format!("{}{} {}: ", prompt_phrase, progress, choices)
+}
+
+/// An answer to a confirmation prompt.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Decision {
+	/// Yes to this one.
+	Yes,
+	/// No to this one.
+	No,
+	/// Yes to this one and all the rest.
+	AllYes,
+	/// No to this one and all the rest.
+	Quit,
+}
+
+impl Decision {
+	/// Understand an answer typed at a prompt, like "y" or "all".
+	pub fn from_answer(answer: &str) -> Option<Self> {
+		match answer.trim().to_lowercase().as_str() {
+			"y" | "yes" => Some(Self::Yes),
+			"n" | "no" => Some(Self::No),
+			"a" | "all" => Some(Self::AllYes),
+			"q" | "quit" => Some(Self::Quit),
+			_ => None,
+		}
+	}
+
+	/// Whether to go ahead with this one.
+	pub fn is_yes(&self) -> bool {
+		matches!(self, Self::Yes | Self::AllYes)
+	}
+}
+
+/// Watches changes made to tasks by functions like
+/// `done::mark_items_done_in_list`, so they can be vetoed or logged.
+///
+/// Both methods do nothing by default.
+pub trait MutationObserver {
+	/// Called before any tasks are changed, with how many tasks match, so
+	/// at most how many times `before_change` will be called.
+	fn will_change(&mut self, _count: usize) {}
+
+	/// Called before a task is changed. Returning false leaves it alone.
+	fn before_change(&mut self, _item: &Item) -> bool {
+		true
+	}
+
+	/// Called after a task is changed, with the old and new versions. For
+	/// a task which was removed, both are the removed task.
+	fn after_change(&mut self, _old: &Item, _new: &Item) {}
+}
+
+/// A `MutationObserver` which allows every change.
+pub struct NoObserver;
+
+impl MutationObserver for NoObserver {}
+
+/// A `MutationObserver` which allows every change, and writes each one with
+/// a function like `zen::write_rescheduled`.
+pub struct ChangeWriter<'a> {
+	pub outputter: &'a mut Outputter,
+	pub write: fn(&Item, &Item, &mut Outputter),
+}
+
+impl MutationObserver for ChangeWriter<'_> {
+	fn after_change(&mut self, old: &Item, new: &Item) {
+		(self.write)(old, new, self.outputter);
+	}
+}
+
+/// A `MutationObserver` which shows each task and asks the user whether to
+/// change it, as the command line does.
+pub struct Confirmer<'a> {
+	pub outputter: &'a mut Outputter,
+	pub status: ConfirmationStatus,
+	pub prompt_phrase: &'a str,
+	pub yes_phrase: &'a str,
+	pub no_phrase: &'a str,
+	/// How many unfinished tasks are waiting for each task id.
+	pub waiting: HashMap<String, usize>,
+	/// How many tasks will be asked about, and have been so far.
+	pub total: usize,
+	pub asked: usize,
+}
+
+impl<'a> Confirmer<'a> {
+	/// Ask with the given phrases, like "Remove?", "Removing", and "Keeping".
+	pub fn new(
+		outputter: &'a mut Outputter,
+		status: ConfirmationStatus,
+		prompt_phrase: &'a str,
+		yes_phrase: &'a str,
+		no_phrase: &'a str,
+	) -> Self {
+		Self {
+			outputter,
+			status,
+			prompt_phrase,
+			yes_phrase,
+			no_phrase,
+			waiting: HashMap::new(),
+			total: 0,
+			asked: 0,
+		}
+	}
+
+	/// Warn before changing a task which other unfinished tasks in the
+	/// list are waiting for, with an `after:` tag.
+	pub fn with_dependents(mut self, list: &List) -> Self {
+		self.waiting = HashMap::new();
+		for item in list
+			.items()
+			.into_iter()
+			.filter(|i| !i.completion())
+		{
+			for id in item.after() {
+				*self.waiting.entry(id).or_insert(0) += 1;
+			}
+		}
+		self
+	}
+}
+
+impl MutationObserver for Confirmer<'_> {
+	fn will_change(&mut self, count: usize) {
+		self.total = count;
+		self.asked = 0;
+	}
+
+	fn before_change(&mut self, item: &Item) -> bool {
+		self.asked += 1;
+		self.outputter.write_item(item);
+		let waiting = item
+			.id()
+			.and_then(|id| self.waiting.get(&id))
+			.copied()
+			.unwrap_or(0);
+		if waiting > 0 {
+			self.outputter.write_notice(format!(
+				"{} other tasks are waiting for this one.",
+				waiting
+			));
+		}
+		// Progress only helps when there's more than one task to ask about.
+		let progress =
+			Some((self.asked, self.total)).filter(|(_, total)| *total > 1);
+		self.status.confirm(
+			self.outputter,
+			self.prompt_phrase,
+			self.yes_phrase,
+			self.no_phrase,
+			progress,
+		)
+	}
+}
+
+/// Whether an action should save its changes, or only describe them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SaveMode {
+	Save,
+	DryRun,
+}
+
+impl SaveMode {
+	/// Initialize from ArgMatches.
+	pub fn from_argmatches(args: &ArgMatches) -> Self {
+		if *args.get_one::<bool>("dry-run").unwrap() {
+			Self::DryRun
+		} else {
+			Self::Save
+		}
+	}
+
+	/// Add `--dry-run` and `--print-diff` args to a Command.
+	pub fn add_args(cmd: Command) -> Command {
+		cmd.arg(
+			Arg::new("dry-run")
+				.num_args(0)
+				.long("dry-run")
+				.help("Show what would change, but save nothing"),
+		)
+		.arg(
+			Arg::new("print-diff")
+				.num_args(0)
+				.long("print-diff")
+				.help("Show a diff of the changes to the list"),
+		)
+	}
+
+	/// Save a list back to where it came from, unless this is a dry run.
+	pub fn save(&self, list: &List) -> Result<(), Error> {
+		match self {
+			Self::Save => list.save(),
+			Self::DryRun => Ok(()),
+		}
+	}
+
+	/// Save a list like `save`, first writing a diff against its text
+	/// before the change if `--print-diff` was given or this is a dry run.
+	///
+	/// If the list's text is the same as before, nothing is written, so the
+	/// file's modification time is left alone and no backup is made.
+	///
+	/// Returns whether the list was changed, so callers can write a status
+	/// line to suit. Nothing else is written to the outputter.
+	pub fn save_with_diff(
+		&self,
+		list: &List,
+		before: &str,
+		outputter: &mut Outputter,
+	) -> Result<bool, Error> {
+		let after = list.serialize();
+		if after == before {
+			return Ok(false);
+		}
+		if outputter.print_diff || *self == Self::DryRun {
+			let diff = unified_diff(before, &after, &list.display_name());
+			outputter.write_diff(&diff);
+		}
+		self.save(list)?;
+		Ok(true)
+	}
+
+	/// A status line saying what was done, or in a dry run, what would have
+	/// been done.
+	///
+	/// The `would_have` text should follow on from "would have", like
+	/// "removed 2 tasks".
+	pub fn status(&self, done: String, would_have: String) -> String {
+		match self {
+			Self::Save => done,
+			Self::DryRun => format!("(dry run) would have {}", would_have),
+		}
+	}
+
+	/// Status lines for an action's outcome: each of its messages, as done
+	/// or as would have been done, or "No actions taken." if there are none.
+	pub fn outcome_status(&self, outcome: &ActionOutcome) -> Vec<String> {
+		if outcome.messages.is_empty() {
+			return vec![String::from("No actions taken.")];
+		}
+		outcome
+			.messages
+			.iter()
+			.map(|m| {
+				let mut chars = m.chars();
+				let done = match chars.next() {
+					Some(c) => c.to_uppercase().chain(chars).collect(),
+					None => String::new(),
+				};
+				self.status(done, m.clone())
+			})
+			.collect()
 	}
 }
 
@@ -558,62 +1830,283 @@ impl SearchTerms {
 			Arg::new("search-term")
 				.action(ArgAction::Append)
 				.required(true)
-				.help("A tag, context, line number, or string"),
+				.help("A tag, context, line number, id, or string"),
+		)
+	}
+
+	/// Add some args to a Command so that it can accept search terms, but
+	/// doesn't require them.
+	pub fn add_optional_args(cmd: Command) -> Command {
+		cmd.arg(
+			Arg::new("search-term")
+				.action(ArgAction::Append)
+				.help("Only include tasks matching these terms"),
 		)
 	}
 
 	/// Read search terms from ArgMatches.
+	///
+	/// If no search terms were given, returns an empty set of search terms.
 	pub fn from_argmatches(args: &ArgMatches) -> Self {
 		let terms = args
 			.get_many::<String>("search-term")
-			.unwrap()
-			.cloned()
-			.collect();
+			.map(|t| t.cloned().collect())
+			.unwrap_or_default();
 		Self { terms }
 	}
 
+	/// A quick check of a raw line from a todo list, which is false only if
+	/// the task on it can't match all the terms, like `find_results`.
+	///
+	/// Line number references can't be checked this way, so always pass.
+	pub fn line_might_match(&self, line: &str) -> bool {
+		let lc_line = line.to_lowercase();
+		self.terms.iter().all(|term| {
+			let needle = match parse_id_reference(term) {
+				Some(id) => format!("id:{}", id),
+				None if !term.starts_with(['@', '+'])
+					&& parse_line_reference(term).is_some() =>
+				{
+					return true
+				}
+				// Values may be dates or numbers written differently, so
+				// only the key can be looked for.
+				None => match parse_kv_term(term) {
+					Some(kv) => format!("{}:", kv.key),
+					None => term.to_string(),
+				},
+			};
+			lc_line.contains(&needle.to_lowercase())
+		})
+	}
+
 	/// Given an item, checks whether the item matches at least one term.
 	pub fn item_matches(&self, item: &Item) -> bool {
-		for term in &self.terms {
-			match term.chars().next() {
-				Some('@') => {
-					if item.has_context(term) {
-						return true;
-					}
-				}
-				Some('+') => {
-					if item.has_tag(term) {
-						return true;
-					}
-				}
-				Some('#') => {
-					let n: usize = term.get(1..).unwrap().parse().unwrap();
-					if item.line_number() == n {
-						return true;
-					}
-				}
-				_ => {
-					let lc_term = term.to_lowercase();
-					if item
-						.description()
-						.to_lowercase()
-						.contains(&lc_term)
-					{
-						return true;
-					}
-				}
+		self.terms.iter().any(|term| {
+			let matched = Self::_term_matches(term, item);
+			log_term_match(item, term, matched);
+			matched
+		})
+	}
+
+	/// Whether an item matches a single search term.
+	fn _term_matches(term: &str, item: &Item) -> bool {
+		match term.chars().next() {
+			Some('@') => item.has_context(term),
+			Some('+') => item.has_tag(term),
+			_ if parse_id_reference(term).is_some() => {
+				item.id().as_deref() == parse_id_reference(term)
+			}
+			_ if parse_line_reference(term).is_some() => {
+				let reference = parse_line_reference(term).unwrap();
+				item_matches_line_reference(item, reference)
 			}
+			_ if parse_kv_term(term).is_some() => {
+				parse_kv_term(term).unwrap().matches(item)
+			}
+			_ => item
+				.description()
+				.to_lowercase()
+				.contains(&term.to_lowercase()),
 		}
-		false
 	}
 }
 
+/// Log whether a task matched a search term, when logging in full detail.
+pub fn log_term_match(item: &Item, term: &str, matched: bool) {
+	trace!(
+		"Line {} {} {:?}",
+		item.line_number(),
+		if matched { "matches" } else { "doesn't match" },
+		term
+	);
+}
+
 impl Default for SearchTerms {
 	fn default() -> Self {
 		Self::new()
 	}
 }
 
+/// Structure for holding command-line filters, which pick out tasks by
+/// their due date, size, priority, and whether they can be started.
+///
+/// Every filter given must match, as well as any search terms.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Filters {
+	pub overdue: bool,
+	pub due_before: Option<NaiveDate>,
+	pub due_after: Option<NaiveDate>,
+	pub no_due: bool,
+	pub startable: bool,
+	pub blocked: bool,
+	pub size: Option<TshirtSize>,
+	/// The most and least important priority letters to include.
+	pub priority: Option<(char, char)>,
+	/// Tasks with any of these urgencies are included.
+	pub urgencies: Vec<Urgency>,
+}
+
+impl Filters {
+	/// Add some args to a Command so that it can accept filters.
+	pub fn add_args(cmd: Command) -> Command {
+		cmd.arg(
+			Arg::new("overdue")
+				.num_args(0)
+				.long("overdue")
+				.help("Only include overdue tasks"),
+		)
+		.arg(
+			Arg::new("due-before")
+				.long("due-before")
+				.value_name("DATE")
+				.value_parser(parse_filter_date)
+				.help("Only include tasks due before a date"),
+		)
+		.arg(
+			Arg::new("due-after")
+				.long("due-after")
+				.value_name("DATE")
+				.value_parser(parse_filter_date)
+				.help("Only include tasks due after a date"),
+		)
+		.arg(
+			Arg::new("no-due")
+				.num_args(0)
+				.long("no-due")
+				.conflicts_with_all(["overdue", "due-before", "due-after"])
+				.help("Only include tasks without a due date"),
+		)
+		.arg(
+			Arg::new("startable")
+				.num_args(0)
+				.long("startable")
+				.help("Only include tasks which can be started now"),
+		)
+		.arg(
+			Arg::new("blocked")
+				.num_args(0)
+				.long("blocked")
+				.conflicts_with("startable")
+				.help("Only include tasks waiting for others"),
+		)
+		.arg(
+			Arg::new("size-is")
+				.long("size-is")
+				.value_name("SIZE")
+				.value_parser(|s: &str| {
+					s.parse::<TshirtSize>()
+						.map_err(|e| e.to_string())
+				})
+				.help("Only include tasks of this size, like S"),
+		)
+		.arg(
+			Arg::new("priority")
+				.long("priority")
+				.value_name("A-C")
+				.value_parser(parse_priority_range)
+				.help("Only include tasks with these priorities"),
+		)
+		.arg(
+			Arg::new("urgency-is")
+				.long("urgency-is")
+				.value_name("WHEN")
+				.action(ArgAction::Append)
+				.value_parser(|s: &str| {
+					s.parse::<Urgency>().map_err(|e| e.to_string())
+				})
+				.help("Only include tasks with this urgency"),
+		)
+	}
+
+	/// Read filters from ArgMatches.
+	pub fn from_argmatches(args: &ArgMatches) -> Self {
+		Self {
+			overdue: *args.get_one::<bool>("overdue").unwrap(),
+			due_before: args.get_one::<NaiveDate>("due-before").copied(),
+			due_after: args.get_one::<NaiveDate>("due-after").copied(),
+			no_due: *args.get_one::<bool>("no-due").unwrap(),
+			startable: *args.get_one::<bool>("startable").unwrap(),
+			blocked: *args.get_one::<bool>("blocked").unwrap(),
+			size: args.get_one::<TshirtSize>("size-is").copied(),
+			priority: args
+				.get_one::<(char, char)>("priority")
+				.copied(),
+			urgencies: args
+				.get_many::<Urgency>("urgency-is")
+				.into_iter()
+				.flatten()
+				.copied()
+				.collect(),
+		}
+	}
+
+	/// Whether any filters were given.
+	pub fn is_active(&self) -> bool {
+		*self != Self::default()
+	}
+
+	/// Given an item from a list, checks whether it matches every filter.
+	///
+	/// Tasks without a size count as medium, like when grouping by size.
+	pub fn item_matches(&self, list: &List, item: &Item) -> bool {
+		let due = item.due_date();
+		(!self.overdue || item.urgency() == Some(Urgency::Overdue))
+			&& self
+				.due_before
+				.is_none_or(|d| due.is_some_and(|due| due < d))
+			&& self
+				.due_after
+				.is_none_or(|d| due.is_some_and(|due| due > d))
+			&& (!self.no_due || due.is_none())
+			&& (!self.startable || list.is_item_startable(item))
+			&& (!self.blocked
+				|| (!item.completion() && !list.blockers(item).is_empty()))
+			&& self
+				.size
+				.is_none_or(|s| item.tshirt_size().unwrap_or_default() == s)
+			&& self
+				.priority
+				.is_none_or(|(from, to)| (from..=to).contains(&item.priority()))
+			&& (self.urgencies.is_empty()
+				|| item
+					.urgency()
+					.is_some_and(|u| self.urgencies.contains(&u)))
+	}
+
+	/// Only the items from a list which match every filter.
+	pub fn apply<'a>(
+		&self,
+		list: &List,
+		items: Vec<&'a Item>,
+	) -> Vec<&'a Item> {
+		items
+			.into_iter()
+			.filter(|i| self.item_matches(list, i))
+			.collect()
+	}
+}
+
+/// Accept a date for a filter, like "2024-06-01" or "next_friday".
+fn parse_filter_date(s: &str) -> Result<NaiveDate, String> {
+	parse_date_arg(s).ok_or_else(|| {
+		String::from("expected a date like 2024-06-01 or next_friday")
+	})
+}
+
+/// Accept a priority, like "B", or a range of priorities, like "A-C",
+/// returning the most and least important letters.
+pub fn parse_priority_range(s: &str) -> Result<(char, char), String> {
+	let (from, to) = match s.split_once('-') {
+		Some((from, to)) => (parse_priority(from)?, parse_priority(to)?),
+		None => {
+			let p = parse_priority(s)?;
+			(p, p)
+		}
+	};
+	Ok((from.min(to), from.max(to)))
+}
+
 /// An order for sorting items into.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SortOrder {
@@ -688,41 +2181,119 @@ impl SortOrder {
 	}
 
 	/// Sort items by this sort order.
+	///
+	/// The sort is stable, so items which compare equal keep their order.
 	pub fn sort_items<'a>(&self, items: Vec<&'a Item>) -> Vec<&'a Item> {
-		let mut out = items.clone();
+		let count = items.len();
+		self.top_items(items, count)
+	}
+
+	/// Return the first `count` items in this sort order, sorted.
+	///
+	/// Gives the same result as sorting everything and taking `count`, but
+	/// only the chosen items get fully sorted.
+	pub fn top_items<'a>(
+		&self,
+		items: Vec<&'a Item>,
+		count: usize,
+	) -> Vec<&'a Item> {
 		match self {
 			SortOrder::Urgency => {
-				out.sort_by_cached_key(|i| i.urgency().unwrap_or_default())
+				top_by_key(items, count, |i| i.urgency().unwrap_or_default())
 			}
-			SortOrder::Importance => {
-				out.sort_by_cached_key(|i| i.importance().unwrap_or_default())
+			SortOrder::Importance => top_by_key(items, count, |i| {
+				// Tasks with no priority come after those explicitly marked (D).
+				(i.importance().unwrap_or_default(), i.importance().is_none())
+			}),
+			SortOrder::TshirtSize => top_by_key(items, count, |i| {
+				i.tshirt_size().unwrap_or_default()
+			}),
+			SortOrder::Alphabetical => {
+				top_by_key(items, count, |i| i.description().to_lowercase())
 			}
-			SortOrder::TshirtSize => {
-				out.sort_by_cached_key(|i| i.tshirt_size().unwrap_or_default())
+			SortOrder::DueDate => {
+				top_by_key(items, count, |i| i.due_datetime())
 			}
-			SortOrder::Alphabetical => {
-				out.sort_by_cached_key(|i| i.description().to_lowercase())
+			SortOrder::Original => {
+				top_by_key(items, count, |i| i.line_number())
 			}
-			SortOrder::DueDate => out.sort_by_cached_key(|i| i.due_date()),
-			SortOrder::Original => out.sort_by_cached_key(|i| i.line_number()),
-			SortOrder::Smart => out.sort_by_cached_key(|i| i.smart_key()),
-		};
-		out
+			SortOrder::Smart => top_by_key(items, count, |i| i.smart_key()),
+		}
+	}
+}
+
+/// Pick the first `count` items by key, breaking ties by original position.
+fn top_by_key<K: Ord>(
+	items: Vec<&Item>,
+	count: usize,
+	key: impl Fn(&Item) -> K,
+) -> Vec<&Item> {
+	if count == 0 {
+		return Vec::new();
+	}
+	let mut keyed: Vec<(K, usize, &Item)> = items
+		.into_iter()
+		.enumerate()
+		.map(|(n, i)| (key(i), n, i))
+		.collect();
+	let cmp = |a: &(K, usize, &Item), b: &(K, usize, &Item)| {
+		a.0.cmp(&b.0).then(a.1.cmp(&b.1))
+	};
+	if count < keyed.len() {
+		keyed.select_nth_unstable_by(count - 1, cmp);
+		keyed.truncate(count);
 	}
+	keyed.sort_unstable_by(cmp);
+	keyed.into_iter().map(|(_, _, i)| i).collect()
 }
 
 /// A chosen grouping for items.
-#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
 pub enum Grouping {
 	None,
 	Urgency,
 	Importance,
 	TshirtSize,
+	Context,
+	DueDate,
+}
+
+/// An error raised when given an unknown grouping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidGrouping {
+	pub given: String,
+}
+
+impl fmt::Display for InvalidGrouping {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let valid: Vec<String> = Grouping::all()
+			.iter()
+			.map(|g| String::from(g.to_string()))
+			.collect();
+		write!(
+			f,
+			"unknown grouping '{}' (expected one of: {})",
+			self.given,
+			valid.join(", ")
+		)
+	}
 }
 
 impl Grouping {
+	/// Add some args to a Command so that it can accept a grouping.
 	pub fn add_args(cmd: Command) -> Command {
 		cmd.arg(
+			Arg::new("group")
+				.num_args(1)
+				.short('g')
+				.long("group")
+				.value_name("BY")
+				.value_parser(|s: &str| {
+					Self::from_string(s).map_err(|e| e.to_string())
+				})
+				.help("Group by 'urgency', 'importance', 'size', 'context', 'due', or 'none'"),
+		)
+		.arg(
 			Arg::new("importance")
 				.num_args(0)
 				.short('i')
@@ -748,7 +2319,17 @@ impl Grouping {
 		)
 	}
 
+	/// Read grouping from ArgMatches.
+	///
+	/// With `--plain`, there are no headings, so tasks aren't grouped.
 	pub fn from_argmatches(args: &ArgMatches) -> Self {
+		if let Ok(Some(true)) = args.try_get_one::<bool>("plain") {
+			return Self::None;
+		}
+		if let Ok(Some(g)) = args.try_get_one::<Self>("group") {
+			return *g;
+		}
+
 		let g = args
 			.get_one::<bool>("importance")
 			.unwrap_or(&false);
@@ -770,6 +2351,178 @@ impl Grouping {
 
 		Self::None
 	}
+
+	/// Accept string groupings like "urgency" and return a Grouping.
+	pub fn from_string(groupby: &str) -> Result<Self, InvalidGrouping> {
+		match groupby.to_lowercase().as_str() {
+			"none" | "no" | "off" => Ok(Self::None),
+			"urgency" | "urgent" | "urg" => Ok(Self::Urgency),
+			"importance" | "import" | "imp" | "important" | "priority"
+			| "pri" => Ok(Self::Importance),
+			"size" | "tshirt-size" | "tshirtsize" | "tshirt" | "quick" => {
+				Ok(Self::TshirtSize)
+			}
+			"context" | "contexts" | "ctx" | "@" => Ok(Self::Context),
+			"due" | "due-date" | "duedate" | "date" | "day" => {
+				Ok(Self::DueDate)
+			}
+			_ => Err(InvalidGrouping {
+				given: String::from(groupby),
+			}),
+		}
+	}
+
+	/// The canonical name for this grouping.
+	pub fn to_string(&self) -> &str {
+		match self {
+			Self::None => "none",
+			Self::Urgency => "urgency",
+			Self::Importance => "importance",
+			Self::TshirtSize => "size",
+			Self::Context => "context",
+			Self::DueDate => "due",
+		}
+	}
+
+	/// Returns a list of known groupings.
+	pub fn all() -> Vec<Self> {
+		Vec::from([
+			Self::None,
+			Self::Urgency,
+			Self::Importance,
+			Self::TshirtSize,
+			Self::Context,
+			Self::DueDate,
+		])
+	}
+
+	/// Output items split into groups, each group sorted by the given order.
+	pub fn write_items(
+		&self,
+		items: Vec<&Item>,
+		sort_order: &SortOrder,
+		outputter: &mut Outputter,
+	) {
+		self._write_items(items, Some(sort_order), outputter)
+	}
+
+	/// Output items split into groups, keeping the order they are given in.
+	pub fn write_sorted_items(
+		&self,
+		items: Vec<&Item>,
+		outputter: &mut Outputter,
+	) {
+		self._write_items(items, None, outputter)
+	}
+
+	/// Split items into headed groups, in the order they are output.
+	///
+	/// Items within each group keep the order they are given in. With no
+	/// grouping, all the items are in one group with an empty heading.
+	pub fn split_items<'a>(
+		&self,
+		items: Vec<&'a Item>,
+	) -> Vec<(String, Vec<&'a Item>)> {
+		let mut groups = Vec::new();
+		match self {
+			Self::Urgency => {
+				let mut split = group_items_by_urgency(items);
+				for u in Urgency::all() {
+					if let Some(group) = split.remove(&u) {
+						groups.push((String::from(u.label()), group));
+					}
+				}
+			}
+			Self::Importance => {
+				let mut split = group_items_by_importance(items);
+				for u in Importance::all() {
+					if let Some(group) = split.remove(&u) {
+						groups.push((String::from(u.label()), group));
+					}
+				}
+			}
+			Self::TshirtSize => {
+				let mut split = group_items_by_size(items);
+				for u in TshirtSize::all() {
+					if let Some(group) = split.remove(&u) {
+						groups.push((String::from(u.label()), group));
+					}
+				}
+			}
+			Self::Context => {
+				let mut split = group_items_by_context(items);
+				let mut keys: Vec<String> = split
+					.keys()
+					.filter(|k| !k.is_empty())
+					.cloned()
+					.collect();
+				keys.sort();
+				for k in keys {
+					let group = split.remove(&k).unwrap();
+					groups.push((format!("@{}", k), group));
+				}
+				if let Some(group) = split.remove("") {
+					groups.push((String::from("No context"), group));
+				}
+			}
+			Self::DueDate => {
+				// Everything overdue is grouped together, rather than by
+				// the day it was due.
+				let today = today();
+				let mut split = group_items_by_due_date(items);
+				let mut dates: Vec<NaiveDate> =
+					split.keys().flatten().copied().collect();
+				dates.sort();
+				let overdue: Vec<&Item> = dates
+					.iter()
+					.filter(|d| **d < today)
+					.flat_map(|d| split.remove(&Some(*d)).unwrap())
+					.collect();
+				if !overdue.is_empty() {
+					groups.push((String::from("Overdue"), overdue));
+				}
+				for d in dates.into_iter().filter(|d| *d >= today) {
+					let group = split.remove(&Some(d)).unwrap();
+					groups.push((day_heading(d), group));
+				}
+				if let Some(group) = split.remove(&None) {
+					groups.push((String::from("No due date"), group));
+				}
+			}
+			Self::None => groups.push((String::new(), items)),
+		}
+		groups
+	}
+
+	fn _write_items<'a>(
+		&self,
+		items: Vec<&'a Item>,
+		sort_order: Option<&SortOrder>,
+		outputter: &mut Outputter,
+	) {
+		let sort = |items: Vec<&'a Item>| match sort_order {
+			Some(order) => order.sort_items(items),
+			None => items,
+		};
+		if *self == Self::None {
+			for i in sort(items).iter() {
+				outputter.write_item(i);
+			}
+			return;
+		}
+		for (heading, group) in self.split_items(items) {
+			outputter.write_heading(heading);
+			for i in sort(group).iter() {
+				outputter.write_item(i);
+			}
+			outputter.write_separator();
+		}
+	}
+}
+
+/// The heading for tasks due on a day, like "Friday 2024-06-07".
+pub fn day_heading(date: NaiveDate) -> String {
+	date.format("%A %Y-%m-%d").to_string()
 }
 
 /// Represents a user-expressed number of items to be output.
@@ -804,58 +2557,133 @@ impl OutputCount {
 }
 
 /// Helper for subcommands like `important`, `urgent`, etc.
+///
+/// Only tasks matching all the terms in `filter` are considered; an empty
+/// filter matches every task.
 pub fn execute_simple_list_action(
 	args: &ArgMatches,
 	selection_order: SortOrder,
-) {
+	filter: &SearchTerms,
+) -> Result<ExitStatus, Error> {
 	let output_order = SortOrder::from_argmatches(args, selection_order);
 	let output_count = OutputCount::from_argmatches(args);
 
-	let list = FileType::TodoTxt.load(args);
+	let list = FileType::TodoTxt.load_matching(args, filter)?;
 
 	let mut outputter = Outputter::from_argmatches(args);
-	outputter.line_number_digits = list.lines.len().to_string().len();
+	outputter.fit_to_list(&list);
+
+	let selected =
+		select_items(&list, filter, &selection_order, output_count.count);
+
+	// The selection comes out sorted, so only re-sort for a different order.
+	let grouping = Grouping::from_argmatches(args);
+	if output_order == selection_order {
+		grouping.write_sorted_items(selected, &mut outputter);
+	} else {
+		grouping.write_items(selected, &output_order, &mut outputter);
+	}
 
-	let selected = selection_order
-		.sort_items(list.items())
+	Ok(ExitStatus::Success)
+}
+
+/// Pick the first `count` tasks from a list in the selection order, after
+/// filtering out tasks which don't match the filter, are complete, or
+/// can't be started yet, including tasks waiting for unfinished tasks.
+pub fn select_items<'a>(
+	list: &'a List,
+	filter: &'a SearchTerms,
+	selection_order: &SortOrder,
+	count: usize,
+) -> Vec<&'a Item> {
+	let candidates: Vec<&Item> = find::find_results(filter, list)
 		.into_iter()
-		.filter(|i| i.is_startable() && !i.completion())
-		.take(output_count.count)
+		.filter(|i| !i.completion())
 		.collect();
+	// Checking what a task waits for is slow, so only check the best few,
+	// widening the net if too many of them can't be started.
+	let mut window = count;
+	loop {
+		let top = selection_order.top_items(candidates.clone(), window);
+		let exhausted = top.len() < window;
+		let selected: Vec<&Item> = top
+			.into_iter()
+			.filter(|i| list.is_item_startable(i))
+			.take(count)
+			.collect();
+		if selected.len() == count || exhausted {
+			return selected;
+		}
+		window *= 2;
+	}
+}
 
-	for i in output_order.sort_items(selected).iter() {
-		outputter.write_item(i);
+/// Parse a priority letter given on the command line.
+pub fn parse_priority(s: &str) -> Result<char, String> {
+	let mut chars = s.chars();
+	match (chars.next(), chars.next()) {
+		(Some(c), None) if c.is_ascii_alphabetic() => {
+			Ok(c.to_ascii_uppercase())
+		}
+		_ => Err(String::from("expected a single letter from A to Z")),
 	}
 }
 
-/// Show warnings if the todo list contains a large number of blank lines,
-/// completed items, etc.
-pub fn maybe_housekeeping_warnings(outputter: &mut Outputter, list: &List) {
-	let mut done_blank = false;
+/// Something about a list which suggests it could do with housekeeping.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Warning {
+	/// The list has this many completed tasks, which could be archived.
+	Completed(usize),
+	/// The list has this many blank/comment lines, which could be tidied.
+	Blank(usize),
+	/// Tasks with these ids are waiting for each other.
+	Cycle(Vec<String>),
+}
 
-	let count_completed = list.count_completed();
-	if count_completed > 9 {
-		if !done_blank {
-			outputter.write_separator();
-			done_blank = true;
+impl fmt::Display for Warning {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Completed(n) => write!(
+				f,
+				"There are {} finished tasks. Consider running `tada archive`.",
+				n
+			),
+			Self::Blank(n) => write!(
+				f,
+				"There are {} blank/comment lines. Consider running `tada tidy`.",
+				n
+			),
+			Self::Cycle(ids) => write!(
+				f,
+				"Tasks with ids {} are waiting for each other, so can never start.",
+				ids.join(", ")
+			),
 		}
-		outputter.write_notice(format!(
-			"There are {} finished tasks. Consider running `tada archive`.",
-			count_completed
-		));
+	}
+}
+
+/// Find housekeeping warnings for a list, like a large number of blank
+/// lines or completed items, with thresholds from the config file.
+///
+/// Pass them to `Outputter::write_warnings`.
+pub fn housekeeping_warnings(list: &List, hints: &HintsConfig) -> Vec<Warning> {
+	let mut warnings = Vec::new();
+
+	let count_completed = list.count_completed();
+	if count_completed > hints.max_completed() {
+		warnings.push(Warning::Completed(count_completed));
 	}
 
 	let count_blank = list.count_blank();
-	if count_blank > 9 {
-		if !done_blank {
-			outputter.write_separator();
-			// done_blank = true;
-		}
-		outputter.write_notice(format!(
-			"There are {} blank/comment lines. Consider running `tada tidy`.",
-			count_blank
-		));
+	if count_blank > hints.max_blank() {
+		warnings.push(Warning::Blank(count_blank));
+	}
+
+	for cycle in list.dependency_cycles() {
+		warnings.push(Warning::Cycle(cycle));
 	}
+
+	warnings
 }
 
 // TODO TEST: Action
@@ -864,7 +2692,1456 @@ pub fn maybe_housekeeping_warnings(outputter: &mut Outputter, list: &List) {
 // TODO TEST: ConfirmationStatus
 // TODO TEST: SearchTerms
 // TODO TEST: SortOrder
-// TODO TEST: Grouping
 // TODO TEST: OutputCount
-// TODO TEST: execute_simple_list_action()
-// TODO TEST: maybe_housekeeping_warnings()
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_grouping_from_string() {
+		let cases = [
+			(Grouping::None, ["none", "no", "off", "NONE"].as_slice()),
+			(Grouping::Urgency, ["urgency", "urgent", "urg"].as_slice()),
+			(
+				Grouping::Importance,
+				[
+					"importance",
+					"import",
+					"imp",
+					"important",
+					"priority",
+					"pri",
+				]
+				.as_slice(),
+			),
+			(
+				Grouping::TshirtSize,
+				["size", "tshirt-size", "tshirtsize", "tshirt", "quick"]
+					.as_slice(),
+			),
+			(
+				Grouping::Context,
+				["context", "contexts", "ctx", "@"].as_slice(),
+			),
+			(
+				Grouping::DueDate,
+				["due", "due-date", "duedate", "date", "day"].as_slice(),
+			),
+		];
+		for (expected, aliases) in cases {
+			for a in aliases {
+				assert_eq!(Ok(expected), Grouping::from_string(a), "{}", a);
+			}
+			assert_eq!(
+				Ok(expected),
+				Grouping::from_string(expected.to_string())
+			);
+		}
+
+		let err = Grouping::from_string("colour").unwrap_err();
+		assert_eq!(
+			"unknown grouping 'colour' (expected one of: none, urgency, importance, size, context, due)",
+			err.to_string()
+		);
+	}
+
+	#[test]
+	fn test_grouping_from_argmatches() {
+		let cmd = Grouping::add_args(Command::new("test"));
+		let m = cmd
+			.clone()
+			.get_matches_from(vec!["test", "--group", "ctx"]);
+		assert_eq!(Grouping::Context, Grouping::from_argmatches(&m));
+		let m = cmd.clone().get_matches_from(vec!["test", "-u"]);
+		assert_eq!(Grouping::Urgency, Grouping::from_argmatches(&m));
+		let m = cmd.clone().get_matches_from(vec!["test"]);
+		assert_eq!(Grouping::None, Grouping::from_argmatches(&m));
+		assert!(cmd
+			.try_get_matches_from(vec!["test", "--group", "bogus"])
+			.is_err());
+	}
+
+	#[test]
+	fn test_grouping_from_argmatches_plain() {
+		let cmd = Outputter::add_args(Grouping::add_args(Command::new("test")));
+		let m = cmd.get_matches_from(vec!["test", "--group", "ctx", "--plain"]);
+		assert_eq!(Grouping::None, Grouping::from_argmatches(&m));
+	}
+
+	#[test]
+	fn test_grouping_split_items_by_due_date() {
+		let items = [
+			Item::parse("Foo due:2024-06-08"),
+			Item::parse("Bar due:2024-06-01"),
+			Item::parse("Baz"),
+			Item::parse("Bat due:2024-06-05"),
+			Item::parse("Quux due:2024-06-07"),
+		];
+		let friday = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+		let groups = crate::item::with_today(friday, || {
+			Grouping::DueDate.split_items(items.iter().collect())
+		});
+		let got: Vec<(String, Vec<&str>)> = groups
+			.into_iter()
+			.map(|(h, g)| (h, g.iter().map(|i| i.description()).collect()))
+			.collect();
+		assert_eq!(
+			vec![
+				(
+					String::from("Overdue"),
+					vec!["Bar due:2024-06-01", "Bat due:2024-06-05"]
+				),
+				(
+					String::from("Friday 2024-06-07"),
+					vec!["Quux due:2024-06-07"]
+				),
+				(
+					String::from("Saturday 2024-06-08"),
+					vec!["Foo due:2024-06-08"]
+				),
+				(String::from("No due date"), vec!["Baz"]),
+			],
+			got
+		);
+	}
+
+	#[test]
+	fn test_housekeeping_warnings() {
+		let list_of = |done: usize, blank: usize| {
+			let mut text = String::from("Foo\n");
+			text.push_str(&"x Bar\n".repeat(done));
+			text.push_str(&"\n".repeat(blank));
+			List::from_string(text).unwrap()
+		};
+		let hints = HintsConfig::default();
+
+		assert!(housekeeping_warnings(&list_of(9, 9), &hints).is_empty());
+		assert_eq!(
+			vec![Warning::Completed(10), Warning::Blank(10)],
+			housekeeping_warnings(&list_of(10, 10), &hints)
+		);
+		assert_eq!(
+			vec![Warning::Blank(10)],
+			housekeeping_warnings(&list_of(9, 10), &hints)
+		);
+
+		let hints = HintsConfig {
+			max_completed: Some(0),
+			max_blank: Some(20),
+		};
+		assert_eq!(
+			vec![Warning::Completed(1)],
+			housekeeping_warnings(&list_of(1, 20), &hints)
+		);
+
+		let list = List::from_string(String::from(
+			"Foo id:a after:b\nBar id:b after:a\n",
+		))
+		.unwrap();
+		assert_eq!(
+			vec![Warning::Cycle(vec![String::from("a"), String::from("b")])],
+			housekeeping_warnings(&list, &HintsConfig::default())
+		);
+		assert_eq!(
+			"There are 10 finished tasks. Consider running `tada archive`.",
+			Warning::Completed(10).to_string()
+		);
+	}
+
+	#[test]
+	fn test_decision_from_answer() {
+		assert_eq!(None, Decision::from_answer(""));
+		assert_eq!(Some(Decision::Yes), Decision::from_answer(" Y "));
+		assert_eq!(Some(Decision::No), Decision::from_answer("no"));
+		assert_eq!(Some(Decision::AllYes), Decision::from_answer("a"));
+		assert_eq!(Some(Decision::AllYes), Decision::from_answer("ALL"));
+		assert_eq!(Some(Decision::Quit), Decision::from_answer("q"));
+		assert_eq!(None, Decision::from_answer("maybe"));
+		assert!(Decision::AllYes.is_yes());
+		assert!(!Decision::Quit.is_yes());
+	}
+
+	#[test]
+	fn test_confirmation_status_remember() {
+		let mut status = ConfirmationStatus::Ask;
+		assert!(status.remember(Decision::Yes));
+		assert!(!status.remember(Decision::No));
+		assert_eq!(ConfirmationStatus::Ask, status);
+		assert!(status.remember(Decision::AllYes));
+		assert_eq!(ConfirmationStatus::Yes, status);
+
+		let mut status = ConfirmationStatus::Ask;
+		assert!(!status.remember(Decision::Quit));
+		assert_eq!(ConfirmationStatus::No, status);
+	}
+
+	#[test]
+	fn test_prompt_text() {
+		assert_eq!(
+			"Remove? [Y]es/[n]o/[a]ll/[q]uit: ",
+			prompt_text("Remove?", None, true)
+		);
+		assert_eq!(
+			"Remove? (2 of 7) [y]es/[N]o/[a]ll/[q]uit: ",
+			prompt_text("Remove?", Some((2, 7)), false)
+		);
+	}
+
+	#[test]
+	fn test_confirmation_status_ask() {
+		let dir = tempfile::tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.msg_io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+
+		let ask = |status: ConfirmationStatus,
+		           o: &mut Outputter,
+		           input: &str| {
+			status.ask(o, "Remove?", Some((1, 2)), &mut io::Cursor::new(input))
+		};
+		assert_eq!(Decision::Yes, ask(ConfirmationStatus::Ask, &mut o, "\n"));
+		assert_eq!(
+			Decision::No,
+			ask(ConfirmationStatus::AskDefaultNo, &mut o, "\n")
+		);
+		assert_eq!(
+			Decision::AllYes,
+			ask(ConfirmationStatus::AskDefaultNo, &mut o, "maybe\na\n")
+		);
+		assert_eq!(Decision::Quit, ask(ConfirmationStatus::Ask, &mut o, ""));
+		assert_eq!(
+			"Remove? (1 of 2) [Y]es/[n]o/[a]ll/[q]uit: \
+			Remove? (1 of 2) [y]es/[N]o/[a]ll/[q]uit: \
+			Remove? (1 of 2) [y]es/[N]o/[a]ll/[q]uit: \
+			Remove? (1 of 2) [y]es/[N]o/[a]ll/[q]uit: \
+			Remove? (1 of 2) [Y]es/[n]o/[a]ll/[q]uit: ",
+			fs::read_to_string(buffer_filename).unwrap()
+		);
+	}
+
+	#[test]
+	fn test_exit_status() {
+		assert_eq!(ExitStatus::Success, ExitStatus::from_count(3));
+		assert_eq!(ExitStatus::NoMatch, ExitStatus::from_count(0));
+		assert_eq!(0, ExitStatus::Success.code());
+		assert_eq!(1, ExitStatus::NoMatch.code());
+		assert_eq!(2, ExitStatus::Error.code());
+	}
+
+	#[test]
+	fn test_parse_priority() {
+		assert_eq!(Ok('A'), parse_priority("A"));
+		assert_eq!(Ok('C'), parse_priority("c"));
+		assert!(parse_priority("AB").is_err());
+		assert!(parse_priority("1").is_err());
+		assert!(parse_priority("").is_err());
+	}
+
+	#[test]
+	fn test_sort_items_by_importance() {
+		let list = List::from_string(String::from(
+			"(G) Foo\nBar\n(F) Baz\n(D) Bat\n(E) Qux\n(A) Quux\n",
+		))
+		.unwrap();
+		let sorted: Vec<String> = SortOrder::Importance
+			.sort_items(list.items())
+			.iter()
+			.map(|i| i.description().to_string())
+			.collect();
+		assert_eq!(
+			Vec::from(["Quux", "Bat", "Bar", "Qux", "Baz", "Foo"]),
+			sorted
+		);
+	}
+
+	#[test]
+	fn test_sort_items_by_due_date() {
+		let list = List::from_string(String::from(
+			"Gym due:2000-01-01T19:00
+			Shop due:2000-01-01
+			Report due:2000-01-01T17:00
+			Call due:1999-12-31
+",
+		))
+		.unwrap();
+		let sorted: Vec<String> = SortOrder::DueDate
+			.sort_items(list.items())
+			.iter()
+			.map(|i| {
+				i.description()
+					.split(' ')
+					.next()
+					.unwrap()
+					.to_string()
+			})
+			.collect();
+		assert_eq!(Vec::from(["Call", "Report", "Gym", "Shop"]), sorted);
+	}
+
+	#[test]
+	fn test_top_items() {
+		let list = List::from_string(String::from(
+			"(B) Bravo due:2000-01-03\n\
+			(A) alpha\n\
+			(C) Charlie due:2000-01-01 @m\n\
+			(A) Delta due:2000-01-01 @xl\n\
+			Echo @s\n\
+			(B) alpha\n\
+			Foxtrot due:2000-01-02\n",
+		))
+		.unwrap();
+		let items = list.items();
+		for order in [
+			SortOrder::Urgency,
+			SortOrder::Importance,
+			SortOrder::TshirtSize,
+			SortOrder::Alphabetical,
+			SortOrder::DueDate,
+			SortOrder::Original,
+			SortOrder::Smart,
+		] {
+			let sorted = order.sort_items(items.clone());
+			for count in 0..=items.len() + 1 {
+				let expected: Vec<&Item> =
+					sorted.iter().take(count).copied().collect();
+				assert_eq!(
+					expected,
+					order.top_items(items.clone(), count),
+					"{:?} top {}",
+					order,
+					count
+				);
+			}
+		}
+	}
+
+	#[test]
+	fn test_select_items() {
+		let list = List::from_string(String::from(
+			"(A) Alpha @work\n\
+			(C) Bravo @work due:2000-01-01\n\
+			(B) Charlie @home due:2000-01-01\n\
+			x (A) Delta @work\n\
+			(B) Echo @work start:3999-01-01\n\
+			(E) Foxtrot @work\n\
+			(A) Golf @work after:x\n\
+			Hotel id:x\n",
+		))
+		.unwrap();
+		let descriptions = |items: Vec<&Item>| -> Vec<String> {
+			items
+				.iter()
+				.map(|i| i.description().to_string())
+				.collect()
+		};
+
+		let all = SearchTerms::new();
+		assert_eq!(
+			Vec::from([
+				"Bravo @work due:2000-01-01",
+				"Charlie @home due:2000-01-01"
+			]),
+			descriptions(select_items(&list, &all, &SortOrder::Urgency, 2))
+		);
+
+		let work = SearchTerms::from_string("@work");
+		assert_eq!(
+			Vec::from(["Bravo @work due:2000-01-01", "Alpha @work"]),
+			descriptions(select_items(&list, &work, &SortOrder::Urgency, 2))
+		);
+		assert_eq!(
+			Vec::from(["Alpha @work", "Bravo @work due:2000-01-01"]),
+			descriptions(select_items(&list, &work, &SortOrder::Importance, 2))
+		);
+
+		// Golf is waiting for Hotel.
+		let golf = SearchTerms::from_string("Golf");
+		assert!(select_items(&list, &golf, &SortOrder::Urgency, 9).is_empty());
+
+		let selected = select_items(&list, &work, &SortOrder::Urgency, 2);
+		let dir = tempfile::tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		Grouping::None.write_items(selected, &SortOrder::Alphabetical, &mut o);
+		let got_output = fs::read_to_string(buffer_filename).unwrap();
+		assert!(
+			got_output.find("Alpha").unwrap()
+				< got_output.find("Bravo").unwrap()
+		);
+
+		// Already in urgency order, so left alone.
+		let selected = select_items(&list, &work, &SortOrder::Urgency, 2);
+		let buffer_filename = dir
+			.path()
+			.join("buffer2.txt")
+			.display()
+			.to_string();
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		Grouping::None.write_sorted_items(selected, &mut o);
+		let got_output = fs::read_to_string(buffer_filename).unwrap();
+		assert!(
+			got_output.find("Bravo").unwrap()
+				< got_output.find("Alpha").unwrap()
+		);
+	}
+
+	#[test]
+	fn test_save_mode() {
+		let cmd = SaveMode::add_args(Command::new("x"));
+		let m = cmd
+			.clone()
+			.get_matches_from(vec!["x", "--dry-run"]);
+		assert_eq!(SaveMode::DryRun, SaveMode::from_argmatches(&m));
+		let m = cmd.get_matches_from(vec!["x"]);
+		assert_eq!(SaveMode::Save, SaveMode::from_argmatches(&m));
+
+		let (done, would) = (String::from("Did it!"), String::from("done it"));
+		assert_eq!(
+			"Did it!",
+			SaveMode::Save.status(done.clone(), would.clone())
+		);
+		assert_eq!(
+			"(dry run) would have done it",
+			SaveMode::DryRun.status(done, would)
+		);
+
+		let list = List::new();
+		assert!(SaveMode::DryRun.save(&list).is_ok());
+		assert!(SaveMode::Save.save(&list).is_err());
+	}
+
+	#[test]
+	fn test_action_outcome() {
+		let outcome =
+			ActionOutcome::new(List::new(), 2, String::from("snoozed 2 tasks"));
+		assert_eq!(ExitStatus::Success, outcome.exit_status());
+		assert_eq!(
+			vec!["Snoozed 2 tasks"],
+			SaveMode::Save.outcome_status(&outcome)
+		);
+		assert_eq!(
+			vec!["(dry run) would have snoozed 2 tasks"],
+			SaveMode::DryRun.outcome_status(&outcome)
+		);
+
+		let outcome =
+			ActionOutcome::new(List::new(), 0, String::from("snoozed 0 tasks"));
+		assert!(outcome.messages.is_empty());
+		assert_eq!(ExitStatus::NoMatch, outcome.exit_status());
+		assert_eq!(
+			vec!["No actions taken."],
+			SaveMode::DryRun.outcome_status(&outcome)
+		);
+	}
+
+	#[test]
+	fn test_save_with_diff() {
+		let dir = tempfile::tempdir().unwrap();
+		let buffer_filename = dir.path().join("buffer.txt");
+		let mut o = Outputter::new(80);
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+
+		let mut list = List::new();
+		list.url = Some(String::from("/x/todo.txt"));
+		list.lines
+			.push(crate::list::Line::from_string(String::from("x Foo"), 1));
+		assert!(SaveMode::DryRun
+			.save_with_diff(&list, "Foo\n", &mut o)
+			.unwrap());
+		assert_eq!(
+			"--- /x/todo.txt\n\
+			+++ /x/todo.txt\n\
+			@@ -1,1 +1,1 @@\n\
+			-Foo\n\
+			+x Foo\n",
+			fs::read_to_string(&buffer_filename).unwrap()
+		);
+
+		let mut o = Outputter::new(80);
+		o.colour = true;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		o.write_diff(&[String::from("+x Foo")]);
+		let got = fs::read_to_string(&buffer_filename).unwrap();
+		assert!(got.contains("\u{1b}[32m+x Foo"));
+	}
+
+	#[test]
+	fn test_save_with_diff_unchanged() {
+		let dir = tempfile::tempdir().unwrap();
+		let todo_filename = dir.path().join("todo.txt");
+		fs::write(&todo_filename, "Foo\n\nBar\n").unwrap();
+		let old = std::time::SystemTime::UNIX_EPOCH
+			+ std::time::Duration::from_secs(1_000_000_000);
+		fs::File::options()
+			.write(true)
+			.open(&todo_filename)
+			.unwrap()
+			.set_modified(old)
+			.unwrap();
+		let buffer_filename = dir.path().join("buffer.txt");
+		let buffer = fs::File::create(&buffer_filename).unwrap();
+		let mut o = Outputter::new(80);
+		o.print_diff = true;
+		o.msg_io = Box::new(buffer.try_clone().unwrap());
+		o.io = Box::new(buffer);
+
+		let list = List::from_url(todo_filename.display().to_string()).unwrap();
+		let before = list.serialize();
+		assert!(!SaveMode::Save
+			.save_with_diff(&list, &before, &mut o)
+			.unwrap());
+		assert_eq!("", fs::read_to_string(&buffer_filename).unwrap());
+		assert_eq!("Foo\n\nBar\n", fs::read_to_string(&todo_filename).unwrap());
+		assert_eq!(
+			old,
+			fs::metadata(&todo_filename)
+				.unwrap()
+				.modified()
+				.unwrap()
+		);
+	}
+
+	#[test]
+	fn test_find_upwards() {
+		let dir = tempfile::tempdir().unwrap();
+		let nested = dir.path().join("a").join("b");
+		fs::create_dir_all(&nested).unwrap();
+		let names = ["todo.txt", "TODO"];
+
+		assert_eq!(None, FileType::_find_upwards(&nested, &names, true));
+
+		let top = dir.path().join("TODO");
+		fs::write(&top, "").unwrap();
+		assert_eq!(
+			Some(top.display().to_string()),
+			FileType::_find_upwards(&nested, &names, true)
+		);
+		assert_eq!(None, FileType::_find_upwards(&nested, &names, false));
+
+		let mid = dir.path().join("a").join("todo.txt");
+		fs::write(&mid, "").unwrap();
+		assert_eq!(
+			Some(mid.display().to_string()),
+			FileType::_find_upwards(&nested, &names, true)
+		);
+
+		fs::create_dir(nested.join("todo.txt")).unwrap();
+		assert_eq!(
+			Some(mid.display().to_string()),
+			FileType::_find_upwards(&nested, &names, true)
+		);
+	}
+
+	#[test]
+	fn test_load_invalid_utf8() {
+		let path = concat!(
+			env!("CARGO_MANIFEST_DIR"),
+			"/tests/fixtures/latin1-todo.txt"
+		);
+		let cmd = FileType::TodoTxt
+			.add_args(Command::new("test"))
+			.arg(Arg::new("strict").num_args(0).long("strict"));
+
+		let args = cmd
+			.clone()
+			.get_matches_from(vec!["test", "--file", path]);
+		let list = FileType::TodoTxt.load(&args).unwrap();
+		assert_eq!(Vec::from([1, 2]), list.invalid_lines);
+
+		let args =
+			cmd.get_matches_from(vec!["test", "--file", path, "--strict"]);
+		assert_eq!(
+			Some(Error::InvalidText {
+				label: format!("todo list {} (from --file)", path),
+				line: 1,
+			}),
+			FileType::TodoTxt.load(&args).err()
+		);
+	}
+
+	#[test]
+	fn test_line_might_match() {
+		let line = "(A) Call Bob @Phone +Work id:ab3f";
+		let yes = |t: &[&str]| {
+			SearchTerms::from_vec(t.iter().map(|t| t.to_string()).collect())
+				.line_might_match(line)
+		};
+		assert!(yes(&[]));
+		assert!(yes(&["bob", "@phone", "+work"]));
+		assert!(yes(&["%ab3f"]));
+		assert!(yes(&["id:AB3F"]));
+		assert!(yes(&["#99"]));
+		// With no `work` profile, this is text to look for.
+		assert!(!yes(&["work#99"]));
+		assert!(!yes(&["bob", "alice"]));
+		assert!(!yes(&["@home"]));
+		assert!(!yes(&["%cd12"]));
+
+		let line = "Call Bob due:2024-06-07 ref:x1";
+		let yes = |t: &str| SearchTerms::from_string(t).line_might_match(line);
+		assert!(yes("ref:*"));
+		assert!(yes("due:today"));
+		assert!(yes("DUE:>2024-06-01"));
+		assert!(!yes("est:*"));
+	}
+
+	#[test]
+	fn test_resolve_and_check_exists() {
+		let dir = tempfile::tempdir().unwrap();
+		let todo = dir.path().join("t.txt").display().to_string();
+		let cmd = FileType::TodoTxt.add_args(Command::new("test"));
+		let args = cmd.get_matches_from(vec!["test", "--file", &todo]);
+
+		let candidates = FileType::TodoTxt.candidates(&args).unwrap();
+		assert_eq!("--file", candidates[0].source);
+		assert_eq!("HOME", candidates.last().unwrap().source);
+		assert_eq!(
+			(todo.clone(), String::from("--file")),
+			FileType::TodoTxt.resolve(&args).unwrap()
+		);
+
+		assert_eq!(
+			Err(Error::MissingList {
+				path: todo.clone(),
+				source: String::from("--file"),
+			}),
+			FileType::_check_exists(&todo, "--file")
+		);
+		fs::write(&todo, "").unwrap();
+		assert_eq!(Ok(()), FileType::_check_exists(&todo, "--file"));
+
+		fs::write(&todo, "Foo @home\n# Bar\nBar after:x\nBaz id:x\n").unwrap();
+		let bar = SearchTerms::from_string("bar");
+		let list = FileType::TodoTxt
+			.load_matching(&args, &bar)
+			.unwrap();
+		assert_eq!(
+			Vec::from([3, 4]),
+			list.items()
+				.iter()
+				.map(|i| i.line_number())
+				.collect::<Vec<_>>()
+		);
+		assert!(!list.is_item_startable(list.items()[0]));
+		assert_eq!(
+			Ok(()),
+			FileType::_check_exists("https://example.com/todo.txt", "--file")
+		);
+	}
+}
+
+#[cfg(test)]
+mod tests_outputter {
+	use super::*;
+	use chrono::{Duration, NaiveDate};
+	use tempfile::tempdir;
+
+	fn _write_item(colour: bool, i: &Item) -> String {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let mut o = Outputter::new(9999);
+		o.colour = colour;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		o.write_item(i);
+		fs::read_to_string(buffer_filename).unwrap()
+	}
+
+	#[test]
+	fn test_write_item_with_source() {
+		let mut work = List::from_string(String::from("Foo\nBar\n")).unwrap();
+		work.set_source("work");
+		let mut home = List::from_string(String::from("Baz\n")).unwrap();
+		home.set_source("home2");
+		let lists = Vec::from([
+			ProfileList {
+				profile: Some(String::from("work")),
+				filename: String::from("work.txt"),
+				list: work,
+			},
+			ProfileList {
+				profile: Some(String::from("home2")),
+				filename: String::from("home.txt"),
+				list: home,
+			},
+		]);
+		let merged = ProfileList::merge(&lists);
+		assert_eq!(3, merged.count_items());
+
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.with_line_numbers = true;
+		o.fit_to_lists(&lists);
+		assert_eq!(5, o.source_width);
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		for i in merged.items() {
+			o.write_item(i);
+		}
+		assert_eq!(
+			"  (?) work  #1 Foo\n  (?) work  #2 Bar\n  (?) home2 #1 Baz\n",
+			fs::read_to_string(buffer_filename).unwrap()
+		);
+	}
+
+	#[test]
+	fn test_write_data() {
+		let dir = tempdir().unwrap();
+		let data_filename = dir
+			.path()
+			.join("data.txt")
+			.display()
+			.to_string();
+		let msg_filename = dir.path().join("msg.txt").display().to_string();
+		let mut o = Outputter::new(9999);
+		o.plain = true;
+		o.io = Box::new(fs::File::create(data_filename.clone()).unwrap());
+		o.msg_io = Box::new(fs::File::create(msg_filename.clone()).unwrap());
+		o.write_data(String::from("42"));
+		o.write_status(String::from("Done."));
+		assert_eq!("42\n", fs::read_to_string(data_filename).unwrap());
+		assert_eq!("", fs::read_to_string(msg_filename).unwrap());
+	}
+
+	#[test]
+	fn test_write_item_overdue() {
+		let i = Item::parse("(A) Foo due:1970-01-01");
+		assert_eq!(
+			"  (\u{1b}[31m\u{1b}[1mA\u{1b}[0m) \
+			\u{1b}[31mFoo due:1970-01-01\u{1b}[0m\n",
+			_write_item(true, &i)
+		);
+		assert_eq!("  (A) Foo due:1970-01-01\n", _write_item(false, &i));
+	}
+
+	#[test]
+	fn test_write_item_today() {
+		let today = today().format("%Y-%m-%d");
+		let i = Item::parse(&format!("Foo due:{}", today));
+		assert_eq!(
+			format!("  (?) \u{1b}[33mFoo due:{}\u{1b}[0m\n", today),
+			_write_item(true, &i)
+		);
+		assert_eq!(
+			format!("  (?) Foo due:{}\n", today),
+			_write_item(false, &i)
+		);
+	}
+
+	#[test]
+	fn test_write_item_completed() {
+		let i = Item::parse("x (B) Foo due:1970-01-01");
+		assert_eq!(
+			"\u{1b}[2mx (B) Foo due:1970-01-01\u{1b}[0m\n",
+			_write_item(true, &i)
+		);
+		assert_eq!("x (B) Foo due:1970-01-01\n", _write_item(false, &i));
+	}
+
+	#[test]
+	fn test_write_item_unstartable() {
+		let tomorrow = today() + Duration::days(1);
+		let i = Item::parse(&format!(
+			"(C) Foo start:{}",
+			tomorrow.format("%Y-%m-%d")
+		));
+		assert_eq!(
+			format!(
+				"\u{1b}[2m  (C) Foo start:{}\u{1b}[0m\n",
+				tomorrow.format("%Y-%m-%d")
+			),
+			_write_item(true, &i)
+		);
+	}
+
+	#[test]
+	fn test_write_item_active() {
+		let i = Item::parse("Foo due:1970-01-01 started:2024-06-01T09:00");
+		assert_eq!(
+			"  (?) \u{1b}[36m\u{1b}[1mFoo due:1970-01-01 started:2024-06-01T09:00\u{1b}[0m\n",
+			_write_item(true, &i)
+		);
+	}
+
+	#[test]
+	fn test_write_item_due_and_age() {
+		let mut o = Outputter::new(48);
+		o.with_due_date = true;
+		o.with_age = true;
+		o.stale_days = 7;
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		let friday = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+		crate::item::with_today(friday, || {
+			o.write_item(&Item::parse(
+				"(A) 2024-06-05 Foo with a long description due:2024-06-10",
+			));
+			o.write_item(&Item::parse("Bar"));
+			o.colour = true;
+			o.write_item(&Item::parse("2024-05-01 Baz"));
+		});
+		assert_eq!(
+			"  (A) 2024-06-10    2d Foo with a long…\n  \
+			(?)                  Bar\n  \
+			(?)            \u{1b}[33m  37d \u{1b}[0mBaz\n",
+			fs::read_to_string(buffer_filename).unwrap()
+		);
+	}
+
+	#[test]
+	fn test_write_item_narrow() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let item = Item::parse("(A) 2024-06-05 Foo due:2024-06-10");
+		for width in [20, 0] {
+			let mut o = Outputter::new(width);
+			o.with_creation_date = true;
+			o.with_due_date = true;
+			o.with_line_numbers = true;
+			o.line_number_digits = 3;
+			o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+			o.write_item(&item);
+			let got = fs::read_to_string(&buffer_filename).unwrap();
+			let expected = if width == 0 {
+				"  (A) 2024-06-05 2024-06-10 #000 Foo due:2024-06-10\n"
+			} else {
+				"  (A) 2024-06-05 2024-06-10 #000 \n"
+			};
+			assert_eq!(expected, got);
+		}
+
+		let cmd = Outputter::add_args(Command::new("x"));
+		let m = cmd
+			.clone()
+			.get_matches_from(vec!["x", "--max-width", "0"]);
+		assert_eq!(0, Outputter::from_argmatches(&m).width);
+		let m = cmd.get_matches_from(vec!["x", "--max-width", "60"]);
+		assert_eq!(60, Outputter::from_argmatches(&m).width);
+	}
+
+	#[test]
+	fn test_filters_from_argmatches() {
+		let cmd = Filters::add_args(Command::new("x"));
+		let m = cmd.clone().get_matches_from(vec!["x"]);
+		assert!(!Filters::from_argmatches(&m).is_active());
+
+		let m = cmd.clone().get_matches_from(vec![
+			"x",
+			"--overdue",
+			"--due-before",
+			"2024-06-10",
+			"--due-after",
+			"2024-06-01",
+			"--startable",
+			"--size-is",
+			"s",
+			"--priority",
+			"C-a",
+			"--urgency-is",
+			"overdue",
+			"--urgency-is",
+			"Next week",
+		]);
+		assert_eq!(
+			Filters {
+				overdue: true,
+				due_before: NaiveDate::from_ymd_opt(2024, 6, 10),
+				due_after: NaiveDate::from_ymd_opt(2024, 6, 1),
+				no_due: false,
+				startable: true,
+				blocked: false,
+				size: Some(TshirtSize::Small),
+				priority: Some(('A', 'C')),
+				urgencies: Vec::from([Urgency::Overdue, Urgency::NextWeek]),
+			},
+			Filters::from_argmatches(&m)
+		);
+
+		let friday = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+		crate::item::with_today(friday, || {
+			let m = cmd.clone().get_matches_from(vec![
+				"x",
+				"--due-before",
+				"tomorrow",
+			]);
+			assert_eq!(
+				NaiveDate::from_ymd_opt(2024, 6, 8),
+				Filters::from_argmatches(&m).due_before
+			);
+		});
+
+		for bad in [
+			vec!["x", "--due-before", "someday"],
+			vec!["x", "--size-is", "huge"],
+			vec!["x", "--priority", "1"],
+			vec!["x", "--urgency-is", "whenever"],
+			vec!["x", "--startable", "--blocked"],
+			vec!["x", "--no-due", "--overdue"],
+		] {
+			assert!(cmd.clone().try_get_matches_from(bad).is_err());
+		}
+
+		assert_eq!(Ok(('B', 'B')), parse_priority_range("b"));
+		assert_eq!(Ok(('A', 'C')), parse_priority_range("A-C"));
+		assert_eq!(Ok(('A', 'C')), parse_priority_range("C-A"));
+		assert!(parse_priority_range("A-").is_err());
+	}
+
+	#[test]
+	fn test_filters_item_matches() {
+		let list = List::from_string(String::from(
+			"(A) Overdue small due:2024-06-05 @S\n\
+			(B) Overdue large due:2024-06-06 @L\n\
+			(C) Due today due:2024-06-07\n\
+			Due next week due:2024-06-12 @S\n\
+			(D) No due date\n\
+			Not yet start:2024-06-20\n\
+			(A) Waiting after:a due:2024-06-01\n\
+			Blocker id:a @S\n\
+			x (A) Done after:a due:2024-06-01\n",
+		))
+		.unwrap();
+		let date = |d: u32| NaiveDate::from_ymd_opt(2024, 6, d);
+		let friday = date(7).unwrap();
+		crate::item::with_today(friday, || {
+			let pick = |filters: Filters| {
+				filters
+					.apply(&list, list.items())
+					.iter()
+					.map(|i| i.line_number())
+					.collect::<Vec<usize>>()
+			};
+			let all = Vec::from([1, 2, 3, 4, 5, 6, 7, 8, 9]);
+			assert_eq!(all, pick(Filters::default()));
+			assert_eq!(
+				vec![1, 2, 7, 9],
+				pick(Filters {
+					overdue: true,
+					..Filters::default()
+				})
+			);
+			assert_eq!(
+				vec![1, 2, 7, 9],
+				pick(Filters {
+					due_before: date(7),
+					..Filters::default()
+				})
+			);
+			assert_eq!(
+				vec![2, 3, 4],
+				pick(Filters {
+					due_after: date(5),
+					..Filters::default()
+				})
+			);
+			assert_eq!(
+				vec![5, 6, 8],
+				pick(Filters {
+					no_due: true,
+					..Filters::default()
+				})
+			);
+			assert_eq!(
+				vec![1, 2, 3, 4, 5, 8],
+				pick(Filters {
+					startable: true,
+					..Filters::default()
+				})
+			);
+			assert_eq!(
+				vec![7],
+				pick(Filters {
+					blocked: true,
+					..Filters::default()
+				})
+			);
+			assert_eq!(
+				vec![1, 4, 8],
+				pick(Filters {
+					size: Some(TshirtSize::Small),
+					..Filters::default()
+				})
+			);
+			assert_eq!(
+				vec![3, 5, 6, 7, 9],
+				pick(Filters {
+					size: Some(TshirtSize::Medium),
+					..Filters::default()
+				})
+			);
+			assert_eq!(
+				vec![1, 2, 7, 9],
+				pick(Filters {
+					priority: Some(('A', 'B')),
+					..Filters::default()
+				})
+			);
+			assert_eq!(
+				vec![3, 4],
+				pick(Filters {
+					urgencies: Vec::from([Urgency::Today, Urgency::NextWeek]),
+					..Filters::default()
+				})
+			);
+
+			// Filters combine with each other.
+			assert_eq!(
+				vec![1],
+				pick(Filters {
+					overdue: true,
+					size: Some(TshirtSize::Small),
+					..Filters::default()
+				})
+			);
+			assert_eq!(
+				vec![1, 2],
+				pick(Filters {
+					overdue: true,
+					startable: true,
+					priority: Some(('A', 'B')),
+					..Filters::default()
+				})
+			);
+			assert_eq!(
+				vec![2],
+				pick(Filters {
+					due_before: date(7),
+					due_after: date(5),
+					..Filters::default()
+				})
+			);
+
+			// And with search terms.
+			let terms = SearchTerms::from_string("overdue");
+			let found: Vec<usize> = Filters {
+				size: Some(TshirtSize::Large),
+				..Filters::default()
+			}
+			.apply(&list, find::find_results(&terms, &list))
+			.iter()
+			.map(|i| i.line_number())
+			.collect();
+			assert_eq!(vec![2], found);
+		});
+	}
+
+	#[test]
+	fn test_write_item_placeholders_and_checkboxes() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let items = [
+			Item::parse("(A) 2024-06-01 Foo"),
+			Item::parse("Bar"),
+			Item::parse("x 2024-06-05 2024-06-01 Baz"),
+			Item::parse("x Bat"),
+		];
+		let write = |placeholders: bool, checkboxes: bool| {
+			let mut o = Outputter::new(9999);
+			o.with_completion_date = true;
+			o.with_creation_date = true;
+			o.placeholders = placeholders;
+			o.checkboxes = checkboxes;
+			o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+			for i in &items {
+				o.write_item(i);
+			}
+			fs::read_to_string(&buffer_filename).unwrap()
+		};
+
+		let expected = |lines: [&str; 4]| format!("{}\n", lines.join("\n"));
+		assert_eq!(
+			expected([
+				"  (A)            2024-06-01 Foo",
+				"  (?)            ????-??-?? Bar",
+				"x (?) 2024-06-05 2024-06-01 Baz",
+				"x (?) ????-??-?? ????-??-?? Bat",
+			]),
+			write(true, false)
+		);
+		assert_eq!(
+			expected([
+				"  (A)            2024-06-01 Foo",
+				"                            Bar",
+				"x     2024-06-05 2024-06-01 Baz",
+				"x                           Bat",
+			]),
+			write(false, false)
+		);
+		assert_eq!(
+			expected([
+				"[ ] (A)            2024-06-01 Foo",
+				"[ ] (?)            ????-??-?? Bar",
+				"[x] (?) 2024-06-05 2024-06-01 Baz",
+				"[x] (?) ????-??-?? ????-??-?? Bat",
+			]),
+			write(true, true)
+		);
+		assert_eq!(
+			expected([
+				"[ ] (A)            2024-06-01 Foo",
+				"[ ]                           Bar",
+				"[x]     2024-06-05 2024-06-01 Baz",
+				"[x]                           Bat",
+			]),
+			write(false, true)
+		);
+
+		let cmd = Outputter::add_args(Command::new("x"));
+		let m = cmd.clone().get_matches_from(vec!["x"]);
+		let o = Outputter::from_argmatches(&m);
+		assert!(o.placeholders);
+		assert!(!o.checkboxes);
+		let m = cmd.get_matches_from(vec![
+			"x",
+			"--no-placeholders",
+			"--checkboxes",
+		]);
+		let o = Outputter::from_argmatches(&m);
+		assert!(!o.placeholders);
+		assert!(o.checkboxes);
+	}
+
+	#[test]
+	fn test_write_item_icons() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let items = [
+			Item::parse("Foo due:2024-06-05"),
+			Item::parse("Bar due:2024-06-07 @S"),
+			Item::parse("Baz due:2024-06-08 @L"),
+			Item::parse("x Bat due:2024-06-05"),
+			Item::parse("Quux due:2024-06-30 @S"),
+		];
+		let write = |icons: Icons, width: usize| {
+			let mut o = Outputter::new(width);
+			o.icons = Some(icons);
+			o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+			for i in &items {
+				o.write_item(i);
+			}
+			fs::read_to_string(&buffer_filename).unwrap()
+		};
+
+		let expected = |lines: [&str; 5]| format!("{}\n", lines.join("\n"));
+		let friday = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+		crate::item::with_today(friday, || {
+			assert_eq!(
+				expected([
+					"  🔥 (?) Foo due:2024-06-05",
+					"  📅 (?) Bar due:2024-06-07 @S",
+					"  🕐 (?) Baz due:2024-06-08 @L",
+					"x    (?) Bat due:2024-06-05",
+					"     (?) Quux due:2024-06-30 @S",
+				]),
+				write(Icons::default(), 0)
+			);
+			let ascii = Icons {
+				overdue: String::from("[!]"),
+				today: String::from("[*]"),
+				soon: String::from("~"),
+				small: String::from("."),
+				medium: String::from(".."),
+				large: String::from("..."),
+			};
+			assert_eq!(
+				expected([
+					"  [!]     (?) Foo due:2024-06-05",
+					"  [*] .   (?) Bar due:2024-06-07 @S",
+					"  ~   ... (?) Baz due:2024-06-08 @L",
+					"x         (?) Bat due:2024-06-05",
+					"      .   (?) Quux due:2024-06-30 @S",
+				]),
+				write(ascii, 0)
+			);
+			// Icons count towards the width by how much space they take
+			// up, not their length in bytes.
+			assert_eq!(
+				expected([
+					"  🔥 (?) Foo…",
+					"  📅 (?) Bar…",
+					"  🕐 (?) Baz…",
+					"x    (?) Bat…",
+					"     (?) Quux…",
+				]),
+				write(Icons::default(), 17)
+			);
+		});
+
+		let cmd = Outputter::add_args(Command::new("x"));
+		let m = cmd.clone().get_matches_from(vec!["x"]);
+		assert_eq!(None, Outputter::from_argmatches(&m).icons);
+		let m = cmd.get_matches_from(vec!["x", "--icons"]);
+		assert!(Outputter::from_argmatches(&m).icons.is_some());
+	}
+
+	#[test]
+	fn test_max_width() {
+		let cmd = Outputter::add_args(Command::new("x"));
+		assert!(cmd
+			.clone()
+			.try_get_matches_from(vec!["x", "--max-width", "wide"])
+			.is_err());
+
+		// Plain and porcelain output aren't truncated, so accept any width.
+		for style in ["--plain", "--porcelain"] {
+			let m = cmd
+				.clone()
+				.try_get_matches_from(vec!["x", style, "--max-width", "20"])
+				.unwrap();
+			assert_eq!(20, Outputter::from_argmatches(&m).width);
+		}
+
+		let mut o = Outputter::new(MIN_WIDTH);
+		assert_eq!(MIN_WIDTH, o.min_width());
+		assert!(o.check_width().is_ok());
+		o.with_creation_date = true;
+		o.with_completion_date = true;
+		o.with_line_numbers = true;
+		assert_eq!(52, o.min_width());
+		assert!(o.check_width().is_err());
+		o.width = 51;
+		assert!(o.check_width().is_err());
+		o.width = 52;
+		assert!(o.check_width().is_ok());
+		o.width = 0;
+		assert!(o.check_width().is_ok());
+		o.width = 20;
+		o.plain = true;
+		assert!(o.check_width().is_ok());
+	}
+
+	#[test]
+	fn test_fit_to_list() {
+		let text: String = (1..=120)
+			.map(|n| format!("Task {}\n", n))
+			.collect();
+		let list = List::from_string(text).unwrap();
+		let lists = [ProfileList {
+			profile: None,
+			filename: String::from("todo.txt"),
+			list: List::from_string(list.serialize()).unwrap(),
+		}];
+
+		let mut a = Outputter::new(48);
+		a.fit_to_list(&list);
+		let mut b = Outputter::new(48);
+		b.fit_to_lists(&lists);
+		for mut o in [a, b] {
+			assert_eq!(3, o.line_number_digits);
+			let dir = tempdir().unwrap();
+			let buffer_filename = dir
+				.path()
+				.join("buffer.txt")
+				.display()
+				.to_string();
+			o.with_line_numbers = true;
+			o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+			o.write_item(list.item_at(7).unwrap());
+			o.write_item(list.item_at(120).unwrap());
+			assert_eq!(
+				"  (?) #007 Task 7\n  (?) #120 Task 120\n",
+				fs::read_to_string(buffer_filename).unwrap()
+			);
+		}
+	}
+
+	#[test]
+	fn test_write_item_template() {
+		let mut o = Outputter::new(48);
+		o.colour = true;
+		o.template = Some(Template::parse("%-4p|%d").unwrap());
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		o.write_item(&Item::parse("(A) Foo due:1970-01-01"));
+		assert_eq!(
+			"A   |Foo due:1970-01-01\n",
+			fs::read_to_string(buffer_filename).unwrap()
+		);
+	}
+
+	#[test]
+	fn test_write_item_porcelain() {
+		let list = List::from_string(String::from(
+			"x 2024-06-05 2024-06-01 Foo\tbar due:2024-06-04\n(B) Baz\n",
+		))
+		.unwrap();
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let msg_filename = dir.path().join("msg.txt").display().to_string();
+		let mut o = Outputter::new(10);
+		o.porcelain = true;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		o.msg_io = Box::new(fs::File::create(msg_filename.clone()).unwrap());
+		o.write_heading(String::from("Tasks"));
+		for i in list.items() {
+			o.write_item(i);
+		}
+		o.write_separator();
+		o.write_status(String::from("Done."));
+		assert_eq!(
+			"# tada-porcelain v1\n\
+			# Tasks\n\
+			1\tx\t\t2024-06-01\t2024-06-05\t2024-06-04\tFoo bar due:2024-06-04\n\
+			2\t\tB\t\t\t\tBaz\n",
+			fs::read_to_string(buffer_filename).unwrap()
+		);
+		assert_eq!("# Done.\n", fs::read_to_string(msg_filename).unwrap());
+	}
+
+	#[test]
+	fn test_write_warnings() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let mut o = Outputter::new(9999);
+		o.msg_io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		o.write_warnings(Vec::new());
+		o.write_warnings(vec![Warning::Blank(12)]);
+		o.hints = false;
+		o.write_warnings(vec![Warning::Completed(12)]);
+		assert_eq!(
+			"\nThere are 12 blank/comment lines. Consider running `tada tidy`.\n",
+			fs::read_to_string(buffer_filename).unwrap()
+		);
+	}
+
+	#[test]
+	fn test_write_item_plain() {
+		let list = List::from_string(String::from(
+			"x 2024-06-05 2024-06-01 Foo due:2024-06-04\n(B) Baz +Qux\n",
+		))
+		.unwrap();
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let mut o = Outputter::new(10);
+		o.plain = true;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		o.write_heading(String::from("Tasks"));
+		for i in list.items() {
+			o.write_item(i);
+		}
+		o.write_separator();
+		o.write_status(String::from("Done."));
+		o.write_notice(String::from("Hint."));
+		assert_eq!(
+			"x 2024-06-05 2024-06-01 Foo due:2024-06-04\n(B) Baz +Qux\n",
+			fs::read_to_string(buffer_filename).unwrap()
+		);
+	}
+
+	#[test]
+	fn test_write_item_blocked() {
+		let list =
+			List::from_string(String::from("Foo id:x\n(C) Bar after:x\n"))
+				.unwrap();
+		let mut o = Outputter::new(9999);
+		o.note_blocked(&list);
+		assert!(!o.is_blocked(list.items()[0]));
+		assert!(o.is_blocked(list.items()[1]));
+
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		o.colour = true;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		o.write_item(list.items()[1]);
+		assert_eq!(
+			"\u{1b}[2m  (C) Bar after:x\u{1b}[0m\n",
+			fs::read_to_string(buffer_filename).unwrap()
+		);
+	}
+
+	#[test]
+	fn test_write_item_truncation() {
+		let mut o = Outputter::new(10);
+		o.colour = true;
+		let i = Item::parse("(D) Foo bar baz due:1970-01-01");
+		let segments = o.item_segments(&i);
+		let got: String = segments
+			.iter()
+			.map(|(t, _)| t.clone())
+			.collect();
+		assert_eq!("  (D) Foo…", got);
+
+		// Without the ellipsis, the cut still falls between words.
+		o.ellipsis = false;
+		let got: String = o
+			.item_segments(&i)
+			.iter()
+			.map(|(t, _)| t.clone())
+			.collect();
+		assert_eq!("  (D) Foo", got);
+
+		// A description which exactly fits isn't cut.
+		let i = Item::parse("(D) Foo bar");
+		let o = Outputter::new(13);
+		let got: String = o
+			.item_segments(&i)
+			.iter()
+			.map(|(t, _)| t.clone())
+			.collect();
+		assert_eq!("  (D) Foo bar", got);
+
+		let cmd = Outputter::add_args(Command::new("x"));
+		let m = cmd.clone().get_matches_from(vec!["x"]);
+		assert!(Outputter::from_argmatches(&m).ellipsis);
+		let m = cmd.get_matches_from(vec!["x", "--no-ellipsis"]);
+		assert!(!Outputter::from_argmatches(&m).ellipsis);
+	}
+}
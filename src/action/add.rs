@@ -1,17 +1,41 @@
 //! Add a task to the todo list
 
 use crate::action::*;
-use crate::item::{Item, Urgency};
-use crate::list::{Line, List};
-use clap::{Arg, ArgMatches, Command};
+use crate::config::Config;
+use crate::item::{today, FixupNote, FixupRule, Item, Urgency};
+use crate::list::{new_id, Line, LineKind, List};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use promptly::prompt;
+use std::collections::HashSet;
+use std::io::{BufRead, IsTerminal};
 
 /// Options for the `add` subcommand.
 pub fn get_action() -> Action {
 	let name = String::from("add");
 	let mut command = Command::new("add")
 		.about("Add a task to the todo list")
-		.after_help("After success, displays the added task.")
-		.arg(Arg::new("task").help("Task text (may use todo.txt features)"));
+		.after_help(
+			"After success, displays the added tasks.\n\n\
+			If no tasks are given, tasks are read from STDIN, one per line. \
+			If STDIN is a terminal, you will be prompted for a task.\n\n\
+			When reading tasks from a file or STDIN, blank lines and \
+			comments are skipped.\n\n\
+			With --id, or `auto = true` in the [ids] section of the config \
+			file, each task is given a unique `id:` tag.\n\n\
+			Fixup rules, which --no-fix can skip, are: dates, priority, \
+			contexts, spacing, punctuation, and equals.",
+		)
+		.arg(
+			Arg::new("task")
+				.action(ArgAction::Append)
+				.help("Task text (may use todo.txt features)"),
+		)
+		.arg(
+			Arg::new("from-file")
+				.long("from-file")
+				.value_name("FILE")
+				.help("Read tasks from a file, one per line"),
+		);
 
 	command = FileType::TodoTxt.add_args(command);
 	command = AddActionConfig::add_args(command);
@@ -23,8 +47,15 @@ pub fn get_action() -> Action {
 pub struct AddActionConfig {
 	pub no_date: bool,
 	pub no_fixup: bool,
+	/// Fixup rules which shouldn't be followed.
+	pub no_fix: Vec<FixupRule>,
 	pub urgency: Option<Urgency>,
+	pub priority: Option<char>,
+	pub contexts: Vec<String>,
+	pub tags: Vec<String>,
+	pub force: bool,
 	pub quiet: bool,
+	pub with_id: bool,
 	pub outputter: Outputter,
 }
 
@@ -40,8 +71,14 @@ impl AddActionConfig {
 		Self {
 			no_date: false,
 			no_fixup: false,
+			no_fix: Vec::new(),
 			urgency: None,
+			priority: None,
+			contexts: Vec::new(),
+			tags: Vec::new(),
+			force: false,
 			quiet: false,
+			with_id: false,
 			outputter: Outputter::default(),
 		}
 	}
@@ -65,6 +102,17 @@ impl AddActionConfig {
 					.aliases(["nofixup"])
 					.help("Don't try to fix task syntax"),
 			)
+			.arg(
+				Arg::new("no-fix")
+					.long("no-fix")
+					.value_name("RULES")
+					.value_delimiter(',')
+					.action(ArgAction::Append)
+					.value_parser(|s: &str| {
+						FixupRule::from_string(s).map_err(|e| e.to_string())
+					})
+					.help("Skip these fixup rules, like 'priority,spacing'"),
+			)
 			.arg(
 				Arg::new("quiet")
 					.num_args(0)
@@ -101,6 +149,40 @@ impl AddActionConfig {
 					.long("next-month")
 					.aliases(["nextmonth"])
 					.help("Include a due date the end of next month"),
+			)
+			.arg(
+				Arg::new("priority")
+					.short('p')
+					.long("priority")
+					.value_name("LETTER")
+					.value_parser(parse_priority)
+					.help("Give the task a priority"),
+			)
+			.arg(
+				Arg::new("context")
+					.long("context")
+					.value_name("CONTEXT")
+					.action(ArgAction::Append)
+					.help("Give the task a context (may be repeated)"),
+			)
+			.arg(
+				Arg::new("tag")
+					.long("tag")
+					.value_name("TAG")
+					.action(ArgAction::Append)
+					.help("Give the task a tag (may be repeated)"),
+			)
+			.arg(
+				Arg::new("force")
+					.num_args(0)
+					.long("force")
+					.help("Allow --priority to replace an existing priority"),
+			)
+			.arg(
+				Arg::new("id")
+					.num_args(0)
+					.long("id")
+					.help("Give the task a unique id"),
 			);
 		Outputter::add_args(cmd)
 	}
@@ -109,6 +191,10 @@ impl AddActionConfig {
 	pub fn from_argmatches(args: &ArgMatches) -> Self {
 		let no_date = *args.get_one::<bool>("no-date").unwrap();
 		let no_fixup = *args.get_one::<bool>("no-fixup").unwrap();
+		let no_fix = match args.get_many::<FixupRule>("no-fix") {
+			Some(r) => r.copied().collect(),
+			None => Vec::new(),
+		};
 		let urgency = if *args.get_one::<bool>("today").unwrap() {
 			Some(Urgency::Today)
 		} else if *args.get_one::<bool>("soon").unwrap() {
@@ -120,13 +206,31 @@ impl AddActionConfig {
 		} else {
 			None
 		};
+		let priority = args.get_one::<char>("priority").copied();
+		let contexts = match args.get_many::<String>("context") {
+			Some(c) => c.cloned().collect(),
+			None => Vec::new(),
+		};
+		let tags = match args.get_many::<String>("tag") {
+			Some(t) => t.cloned().collect(),
+			None => Vec::new(),
+		};
+		let force = *args.get_one::<bool>("force").unwrap();
 		let quiet = *args.get_one::<bool>("quiet").unwrap();
+		let with_id =
+			*args.get_one::<bool>("id").unwrap() || Config::load().ids.auto();
 		let outputter = Outputter::from_argmatches(args);
 		Self {
 			no_date,
 			no_fixup,
+			no_fix,
 			urgency,
+			priority,
+			contexts,
+			tags,
+			force,
 			quiet,
+			with_id,
 			outputter,
 		}
 	}
@@ -134,73 +238,327 @@ impl AddActionConfig {
 
 /// Execute the `add` subcommand.
 #[cfg(not(tarpaulin_include))]
-pub fn execute(args: &ArgMatches) {
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
 	let mut cfg = AddActionConfig::from_argmatches(args);
-	let input = args.get_one::<String>("task").unwrap();
-	let new_line = process_line(input, &cfg);
+	let mut inputs = tasks_from_argmatches(args)?;
+	if inputs.is_empty() {
+		let stdin = io::stdin();
+		if stdin.is_terminal() {
+			inputs.push(prompt::<String, _>("Task").map_err(|e| {
+				Error::Unreadable {
+					label: String::from("task"),
+					message: e.to_string(),
+				}
+			})?);
+		} else {
+			inputs = read_tasks(stdin.lock(), "STDIN")?;
+		}
+	}
 
-	if !cfg.quiet {
-		cfg.outputter
-			.write_item(new_line.item.as_ref().unwrap());
+	let filename = FileType::TodoTxt.filename(args)?;
+	let mut ids = if cfg.with_id {
+		List::from_url(filename.clone())
+			.map(|l| l.ids())
+			.unwrap_or_default()
+	} else {
+		HashSet::new()
+	};
+
+	let mut new_lines: Vec<Line> = Vec::new();
+	for input in inputs {
+		let mut new_line = process_line(&input, &mut cfg)?;
+		if cfg.with_id {
+			new_line = assign_id(new_line, &mut ids);
+		}
+		if !cfg.quiet {
+			cfg.outputter
+				.write_item(new_line.item.as_ref().unwrap());
+		}
+		new_lines.push(new_line);
+	}
+
+	if new_lines.is_empty() {
+		return Ok(ExitStatus::Success);
 	}
 
-	let filename = FileType::TodoTxt.filename(args);
-	List::append_lines_to_url(filename, Vec::from([&new_line]));
+	List::append_lines_to_url(filename, new_lines.iter().collect())?;
+
+	Ok(ExitStatus::Success)
+}
+
+/// Read tasks from a reader, one per line, skipping blank lines and comments.
+///
+/// Fails if the text can't be read, or isn't valid UTF-8, with `label`
+/// naming where it came from.
+pub fn read_tasks<R: BufRead>(
+	reader: R,
+	label: &str,
+) -> Result<Vec<String>, Error> {
+	let mut tasks = Vec::new();
+	for line in reader.lines() {
+		let line = line.map_err(|e| Error::Unreadable {
+			label: String::from(label),
+			message: e.to_string(),
+		})?;
+		if Line::from_string(line.clone(), 0).kind == LineKind::Item {
+			tasks.push(line);
+		}
+	}
+	Ok(tasks)
+}
+
+/// Collect the tasks given as arguments, and any tasks in a `--from-file` file.
+///
+/// Does not read from STDIN. Fails if the file can't be read.
+pub fn tasks_from_argmatches(args: &ArgMatches) -> Result<Vec<String>, Error> {
+	let mut tasks: Vec<String> = match args.get_many::<String>("task") {
+		Some(t) => t.cloned().collect(),
+		None => Vec::new(),
+	};
+	if let Some(filename) = args.get_one::<String>("from-file") {
+		let f = fs::File::open(filename).map_err(|e| Error::Unreadable {
+			label: filename.clone(),
+			message: e.to_string(),
+		})?;
+		tasks.append(&mut read_tasks(io::BufReader::new(f), filename)?);
+	}
+	Ok(tasks)
 }
 
 /// Process a line to be added to a todo list.
-pub fn process_line(input: &str, cfg: &AddActionConfig) -> Line {
+///
+/// Unless `cfg.quiet` is set, notes about any fixups made are written to
+/// the config's outputter.
+pub fn process_line(
+	input: &str,
+	cfg: &mut AddActionConfig,
+) -> Result<Line, Error> {
+	let (item, notes) = build_item(input, cfg)?;
+	if !cfg.quiet && cfg.outputter.hints {
+		for note in notes {
+			cfg.outputter.write_notice(note.to_string());
+		}
+	}
+	Ok(Line::from_item(item))
+}
+
+/// Build the task to add for a line of input, as `cfg` says, without
+/// writing anything.
+///
+/// Also returns notes about any fixups made.
+pub fn build_item(
+	input: &str,
+	cfg: &AddActionConfig,
+) -> Result<(Item, Vec<FixupNote>), Error> {
 	let mut item = Item::parse(input);
 
 	if item.creation_date().is_none() && !cfg.no_date {
-		item.set_creation_date(chrono::Utc::now().date_naive());
+		item.set_creation_date(today());
 	}
 
+	item = decorate_item(item, cfg)?;
+
 	if let Some(u) = cfg.urgency {
 		item.set_urgency(u);
 	}
 
-	if !cfg.no_fixup {
-		item = item.fixup(!cfg.quiet);
+	if cfg.no_fixup {
+		return Ok((item, Vec::new()));
+	}
+	Ok(item.fixup_except(&cfg.no_fix))
+}
+
+/// Run the `add` action on a list, without saving it.
+///
+/// Each input becomes a new task at the end of the list. Fixup notes aren't
+/// reported. Fails without adding anything if a task already has a
+/// different priority to the one requested, unless `cfg.force` is set.
+pub fn run(
+	list: List,
+	inputs: &[String],
+	cfg: &AddActionConfig,
+) -> Result<ActionOutcome, Error> {
+	let mut ids = list.ids();
+	let mut new_lines = Vec::new();
+	for input in inputs {
+		let line = Line::from_item(build_item(input, cfg)?.0);
+		new_lines.push(if cfg.with_id {
+			assign_id(line, &mut ids)
+		} else {
+			line
+		});
+	}
+
+	let mut new_list = list;
+	let count = new_lines.len();
+	for line in new_lines {
+		new_list.push_item(line.item.unwrap());
+	}
+	let message =
+		format!("added {} tasks to {}", count, new_list.display_name());
+	Ok(ActionOutcome::new(new_list, count, message))
+}
+
+/// Give the task on a line a unique `id:` tag, unless it already has one.
+///
+/// The new id is added to `existing`, so tasks added together get
+/// different ids.
+pub fn assign_id(line: Line, existing: &mut HashSet<String>) -> Line {
+	match &line.item {
+		Some(item) if item.id().is_none() => {
+			let mut item = item.clone();
+			item.set_kv("id", &new_id(existing));
+			Line::from_item(item)
+		}
+		_ => line,
+	}
+}
+
+/// Apply the priority, contexts, and tags requested in the config to an item.
+pub fn decorate_item(
+	mut item: Item,
+	cfg: &AddActionConfig,
+) -> Result<Item, Error> {
+	if let Some(p) = cfg.priority {
+		if item.priority() != '\0' && item.priority() != p && !cfg.force {
+			return Err(Error::ConflictingPriority {
+				existing: item.priority(),
+				requested: p,
+			});
+		}
+		item.set_priority(p);
+	}
+
+	for ctx in &cfg.contexts {
+		let ctx = ctx.trim_start_matches('@');
+		if !ctx.is_empty() && !item.has_context(ctx) {
+			item.set_description(format!("{} @{}", item.description(), ctx));
+		}
 	}
 
-	Line::from_item(item)
+	for tag in &cfg.tags {
+		let tag = tag.trim_start_matches('+');
+		if !tag.is_empty() && !item.has_tag(tag) {
+			item.set_description(format!("{} +{}", item.description(), tag));
+		}
+	}
+
+	Ok(item)
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use crate::list::LineKind;
+	use tempfile::tempdir;
 
 	#[test]
 	fn test_get_action() {
 		assert_eq!(String::from("add"), get_action().name);
 	}
 
+	#[test]
+	fn test_read_tasks() {
+		let input = io::Cursor::new("Foo @home\n\n  \n# Baz\n(A) Bar\n");
+		assert_eq!(
+			Ok(Vec::from([
+				String::from("Foo @home"),
+				String::from("(A) Bar")
+			])),
+			read_tasks(input, "STDIN")
+		);
+
+		let input = io::Cursor::new("");
+		assert_eq!(Ok(Vec::new()), read_tasks(input, "STDIN"));
+
+		let input = io::Cursor::new(b"Foo\n\xff\xfeBar\n".to_vec());
+		match read_tasks(input, "STDIN") {
+			Err(Error::Unreadable { label, .. }) => assert_eq!("STDIN", label),
+			other => panic!("expected an error, got {:?}", other),
+		}
+	}
+
+	#[test]
+	fn test_tasks_from_argmatches() {
+		let matches = get_action()
+			.command
+			.get_matches_from(vec!["add"]);
+		assert_eq!(Ok(Vec::new()), tasks_from_argmatches(&matches));
+
+		let matches = get_action().command.get_matches_from(vec![
+			"add",
+			"Foo",
+			"Bar @home",
+		]);
+		assert_eq!(
+			Ok(Vec::from([String::from("Foo"), String::from("Bar @home")])),
+			tasks_from_argmatches(&matches)
+		);
+
+		let dir = tempdir().unwrap();
+		let filename = dir
+			.path()
+			.join("tasks.txt")
+			.display()
+			.to_string();
+		fs::write(&filename, "# Comment\nBaz\n\n(B) Bat\n").unwrap();
+		let matches = get_action().command.get_matches_from(vec![
+			"add",
+			"Foo",
+			"--from-file",
+			&filename,
+		]);
+		assert_eq!(
+			Ok(Vec::from([
+				String::from("Foo"),
+				String::from("Baz"),
+				String::from("(B) Bat"),
+			])),
+			tasks_from_argmatches(&matches)
+		);
+
+		let missing = dir
+			.path()
+			.join("missing.txt")
+			.display()
+			.to_string();
+		let matches = get_action().command.get_matches_from(vec![
+			"add",
+			"--from-file",
+			&missing,
+		]);
+		let err = tasks_from_argmatches(&matches).unwrap_err();
+		assert_eq!(2, err.code());
+		assert!(err
+			.to_string()
+			.starts_with(&format!("could not read {}: ", missing)));
+	}
+
 	#[test]
 	fn test_process_line() {
-		let cfg = AddActionConfig {
+		let mut cfg = AddActionConfig {
 			no_date: true,
 			no_fixup: true,
 			urgency: None,
 			quiet: true,
-			outputter: Outputter::default(),
+			..AddActionConfig::new()
 		};
-		let line = process_line(&String::from("ABC start:today"), &cfg);
+		let line =
+			process_line(&String::from("ABC start:today"), &mut cfg).unwrap();
 		assert_eq!(LineKind::Item, line.kind);
 		let item = line.item.unwrap();
 		assert_eq!("ABC start:today", item.description());
 		assert_eq!(None, item.creation_date());
 		assert_eq!("today", item.kv().get("start").unwrap());
 
-		let cfg = AddActionConfig {
+		let mut cfg = AddActionConfig {
 			no_date: false,
 			no_fixup: false,
 			urgency: Some(Urgency::Today),
 			quiet: true,
-			outputter: Outputter::default(),
+			..AddActionConfig::new()
 		};
-		let line = process_line(&String::from("ABC start:today"), &cfg);
+		let line =
+			process_line(&String::from("ABC start:today"), &mut cfg).unwrap();
 		assert_eq!(LineKind::Item, line.kind);
 		let item = line.item.unwrap();
 		assert!(item.creation_date().is_some());
@@ -208,6 +566,124 @@ mod tests {
 		assert_eq!(item.creation_date(), item.due_date());
 		assert_ne!("today", item.kv().get("start").unwrap());
 	}
+
+	#[test]
+	fn test_assign_id() {
+		let mut existing = HashSet::from([String::from("ab3f")]);
+
+		let line =
+			assign_id(Line::from_string(String::from("Foo"), 1), &mut existing);
+		let id = line.item.as_ref().unwrap().id().unwrap();
+		assert_eq!(format!("Foo id:{}", id), line.text);
+		assert_ne!("ab3f", id);
+		assert_eq!(2, existing.len());
+
+		let line = Line::from_string(String::from("Bar id:xyz"), 2);
+		assert_eq!("Bar id:xyz", assign_id(line, &mut existing).text);
+
+		let line = Line::from_string(String::from("# Baz"), 3);
+		assert_eq!("# Baz", assign_id(line, &mut existing).text);
+		assert_eq!(2, existing.len());
+	}
+
+	#[test]
+	fn test_run() {
+		let list =
+			List::from_string(String::from("Foo id:ab3f\n# Bar\n")).unwrap();
+		let cfg = AddActionConfig {
+			no_date: true,
+			quiet: true,
+			with_id: true,
+			contexts: Vec::from([String::from("home")]),
+			..AddActionConfig::new()
+		};
+		let inputs = Vec::from([String::from("Baz"), String::from("(B) Bat")]);
+		let outcome = run(list, &inputs, &cfg).unwrap();
+		assert_eq!(2, outcome.count);
+		assert_eq!(4, outcome.list.lines.len());
+		let added = outcome.list.item_at(3).unwrap();
+		assert!(added.description().starts_with("Baz @home"));
+		assert!(added.id().is_some());
+		assert_ne!(Some(String::from("ab3f")), added.id());
+		assert_eq!('B', outcome.list.item_at(4).unwrap().priority());
+		assert_eq!(1, outcome.messages.len());
+
+		let list = List::from_string(String::from("Foo\n")).unwrap();
+		let cfg = AddActionConfig {
+			priority: Some('A'),
+			quiet: true,
+			..AddActionConfig::new()
+		};
+		let inputs = Vec::from([String::from("Baz"), String::from("(B) Bat")]);
+		assert_eq!(
+			Some(Error::ConflictingPriority {
+				existing: 'B',
+				requested: 'A',
+			}),
+			run(list, &inputs, &cfg).err()
+		);
+	}
+}
+
+#[cfg(test)]
+mod tests_decorate_item {
+	use super::*;
+
+	fn _cfg(
+		priority: Option<char>,
+		contexts: &[&str],
+		tags: &[&str],
+	) -> AddActionConfig {
+		AddActionConfig {
+			priority,
+			contexts: contexts.iter().map(|c| c.to_string()).collect(),
+			tags: tags.iter().map(|t| t.to_string()).collect(),
+			..AddActionConfig::new()
+		}
+	}
+
+	#[test]
+	fn test_decorate_nothing() {
+		let cfg = _cfg(None, &[], &[]);
+		let got = decorate_item(Item::parse("(B) Foo @home"), &cfg).unwrap();
+		assert_eq!("(B) Foo @home", format!("{}", got));
+	}
+
+	#[test]
+	fn test_decorate_priority() {
+		let cfg = _cfg(Some('A'), &[], &[]);
+
+		let got = decorate_item(Item::parse("Foo"), &cfg).unwrap();
+		assert_eq!("(A) Foo", format!("{}", got));
+
+		let got = decorate_item(Item::parse("(A) Foo"), &cfg).unwrap();
+		assert_eq!("(A) Foo", format!("{}", got));
+
+		let got = decorate_item(Item::parse("(B) Foo"), &cfg);
+		assert_eq!(
+			Err(Error::ConflictingPriority {
+				existing: 'B',
+				requested: 'A'
+			}),
+			got.map(|i| format!("{}", i))
+		);
+
+		let cfg = AddActionConfig { force: true, ..cfg };
+		let got = decorate_item(Item::parse("(B) Foo"), &cfg).unwrap();
+		assert_eq!("(A) Foo", format!("{}", got));
+		assert_eq!(Some(Importance::A), got.importance());
+	}
+
+	#[test]
+	fn test_decorate_contexts_and_tags() {
+		let cfg = _cfg(None, &["home", "@Phone"], &["project-x", "+Foo"]);
+
+		let got = decorate_item(Item::parse("Foo"), &cfg).unwrap();
+		assert_eq!("Foo @home @Phone +project-x +Foo", format!("{}", got));
+
+		let got = decorate_item(Item::parse("Call +foo @HOME"), &cfg).unwrap();
+		assert_eq!("Call +foo @HOME @Phone +project-x", format!("{}", got));
+	}
 }
 
 #[cfg(test)]
@@ -217,19 +693,19 @@ mod tests_add_action_config {
 	#[test]
 	fn test_new() {
 		let cfg = AddActionConfig::new();
-		assert_eq!(false, cfg.no_date);
-		assert_eq!(false, cfg.no_fixup);
+		assert!(!cfg.no_date);
+		assert!(!cfg.no_fixup);
 		assert_eq!(None, cfg.urgency);
-		assert_eq!(false, cfg.quiet);
+		assert!(!cfg.quiet);
 	}
 
 	#[test]
 	fn test_default() {
 		let cfg = AddActionConfig::default();
-		assert_eq!(false, cfg.no_date);
-		assert_eq!(false, cfg.no_fixup);
+		assert!(!cfg.no_date);
+		assert!(!cfg.no_fixup);
 		assert_eq!(None, cfg.urgency);
-		assert_eq!(false, cfg.quiet);
+		assert!(!cfg.quiet);
 	}
 
 	#[test]
@@ -238,10 +714,10 @@ mod tests_add_action_config {
 			.command
 			.get_matches_from(vec!["add"]);
 		let cfg = AddActionConfig::from_argmatches(&matches);
-		assert_eq!(false, cfg.no_date);
-		assert_eq!(false, cfg.no_fixup);
+		assert!(!cfg.no_date);
+		assert!(!cfg.no_fixup);
 		assert_eq!(None, cfg.urgency);
-		assert_eq!(false, cfg.quiet);
+		assert!(!cfg.quiet);
 
 		let matches = get_action().command.get_matches_from(vec![
 			"add",
@@ -249,10 +725,10 @@ mod tests_add_action_config {
 			"--no-date",
 		]);
 		let cfg = AddActionConfig::from_argmatches(&matches);
-		assert_eq!(true, cfg.no_date);
-		assert_eq!(false, cfg.no_fixup);
+		assert!(cfg.no_date);
+		assert!(!cfg.no_fixup);
 		assert_eq!(Some(Urgency::Today), cfg.urgency);
-		assert_eq!(false, cfg.quiet);
+		assert!(!cfg.quiet);
 
 		let matches = get_action().command.get_matches_from(vec![
 			"add",
@@ -260,19 +736,19 @@ mod tests_add_action_config {
 			"--no-fixup",
 		]);
 		let cfg = AddActionConfig::from_argmatches(&matches);
-		assert_eq!(false, cfg.no_date);
-		assert_eq!(true, cfg.no_fixup);
+		assert!(!cfg.no_date);
+		assert!(cfg.no_fixup);
 		assert_eq!(Some(Urgency::Soon), cfg.urgency);
-		assert_eq!(false, cfg.quiet);
+		assert!(!cfg.quiet);
 
 		let matches = get_action()
 			.command
 			.get_matches_from(vec!["add", "-W"]);
 		let cfg = AddActionConfig::from_argmatches(&matches);
-		assert_eq!(false, cfg.no_date);
-		assert_eq!(false, cfg.no_fixup);
+		assert!(!cfg.no_date);
+		assert!(!cfg.no_fixup);
 		assert_eq!(Some(Urgency::NextWeek), cfg.urgency);
-		assert_eq!(false, cfg.quiet);
+		assert!(!cfg.quiet);
 
 		let matches = get_action().command.get_matches_from(vec![
 			"add",
@@ -282,9 +758,47 @@ mod tests_add_action_config {
 			"--quiet",
 		]);
 		let cfg = AddActionConfig::from_argmatches(&matches);
-		assert_eq!(true, cfg.no_date);
-		assert_eq!(true, cfg.no_fixup);
+		assert!(cfg.no_date);
+		assert!(cfg.no_fixup);
 		assert_eq!(Some(Urgency::NextMonth), cfg.urgency);
-		assert_eq!(true, cfg.quiet);
+		assert!(cfg.quiet);
+
+		let matches = get_action().command.get_matches_from(vec![
+			"add",
+			"-p",
+			"b",
+			"--context",
+			"home",
+			"--context",
+			"@work",
+			"--tag",
+			"foo",
+			"--force",
+		]);
+		let cfg = AddActionConfig::from_argmatches(&matches);
+		assert_eq!(Some('B'), cfg.priority);
+		assert_eq!(
+			Vec::from([String::from("home"), String::from("@work")]),
+			cfg.contexts
+		);
+		assert_eq!(Vec::from([String::from("foo")]), cfg.tags);
+		assert!(cfg.force);
+
+		let matches = get_action().command.get_matches_from(vec![
+			"add",
+			"--no-fix",
+			"priority,spacing",
+			"--no-fix",
+			"Dates",
+		]);
+		let cfg = AddActionConfig::from_argmatches(&matches);
+		assert_eq!(
+			vec![FixupRule::Priority, FixupRule::Spacing, FixupRule::Dates],
+			cfg.no_fix
+		);
+		assert!(get_action()
+			.command
+			.try_get_matches_from(vec!["add", "--no-fix", "grammar"])
+			.is_err());
 	}
 }
@@ -0,0 +1,202 @@
+//! Raise the priority of tasks as their due dates approach
+
+use crate::action::*;
+use crate::config::Config;
+use crate::item::AgePolicy;
+use crate::list::List;
+use clap::{ArgMatches, Command};
+
+/// Options for the `age` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("age");
+	let mut command = Command::new("age")
+		.about("Raise the priority of tasks as their due dates approach")
+		.after_help(
+			"Overdue tasks and tasks due today are raised to at least (A), \
+			tasks due soon to at least (B), and tasks due this week to at \
+			least (C). Priorities are never lowered, so running this again \
+			changes nothing.\n\n\
+			If search terms are given, only matching tasks are changed.\n\n\
+			The [age] section of the config file can choose the priority for \
+			each urgency.",
+		);
+	command = FileType::TodoTxt.add_args(command);
+	command = Outputter::add_args(command);
+	command = SearchTerms::add_optional_args(command);
+	command = ConfirmationStatus::add_args(command);
+	command = SaveMode::add_args(command);
+	Action { name, command }
+}
+
+/// Execute the `age` subcommand.
+#[cfg(not(tarpaulin_include))]
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches(args);
+	let list = FileType::TodoTxt.load(args)?;
+	outputter.fit_to_list(&list);
+
+	let cfg = AgeActionConfig::from_argmatches(args);
+	let confirmation = ConfirmationStatus::from_argmatches(args);
+	let save_mode = SaveMode::from_argmatches(args);
+
+	let before = list.serialize();
+	let mut confirmer = Confirmer::new(
+		&mut outputter,
+		confirmation,
+		"Raise priority?",
+		"Raising",
+		"Skipping",
+	);
+	let outcome = run(list, &cfg, &mut confirmer)?;
+	if outcome.count > 0 {
+		save_mode.save_with_diff(&outcome.list, &before, &mut outputter)?;
+	}
+	for status in save_mode.outcome_status(&outcome) {
+		outputter.write_status(status);
+	}
+
+	outputter.write_warnings(housekeeping_warnings(
+		&outcome.list,
+		&Config::load().hints,
+	));
+
+	Ok(outcome.exit_status())
+}
+
+/// Config object for the `age` action.
+#[derive(Clone, Default)]
+pub struct AgeActionConfig {
+	pub search_terms: SearchTerms,
+	pub policy: AgePolicy,
+}
+
+impl AgeActionConfig {
+	/// Create an AgeActionConfig from an appropriate ArgMatches, with the
+	/// policy from the config file.
+	pub fn from_argmatches(args: &ArgMatches) -> Self {
+		Self {
+			search_terms: SearchTerms::from_argmatches(args),
+			policy: Config::load().age.policy(),
+		}
+	}
+}
+
+/// Run the `age` action on a list, without saving it.
+///
+/// The observer will be used to check whether each individual item should be
+/// altered.
+pub fn run(
+	list: List,
+	cfg: &AgeActionConfig,
+	observer: &mut dyn MutationObserver,
+) -> Result<ActionOutcome, Error> {
+	let (new_list, count) =
+		age_items_in_list(list, &cfg.search_terms, &cfg.policy, observer);
+	let message = format!(
+		"raised the priority of {} tasks in {}",
+		count,
+		new_list.display_name()
+	);
+	Ok(ActionOutcome::new(new_list, count, message))
+}
+
+/// Given a list, set of search terms, and policy, creates a copy of the list
+/// with the priority of each open task matching the search terms raised
+/// according to the policy. If there are no search terms, all open tasks
+/// are considered.
+///
+/// The observer will be used to check whether each individual item should be
+/// altered.
+///
+/// Also returns the number of items changed.
+pub fn age_items_in_list(
+	list: List,
+	search_terms: &SearchTerms,
+	policy: &AgePolicy,
+	observer: &mut dyn MutationObserver,
+) -> (List, usize) {
+	let matches = |item: &Item| {
+		(search_terms.terms.is_empty() || search_terms.item_matches(item))
+			&& policy.raise(item).is_some()
+	};
+	let mut new_list = list;
+	let mut count = 0;
+	observer.will_change(
+		new_list
+			.items()
+			.into_iter()
+			.filter(|i| matches(i))
+			.count(),
+	);
+	for mut item in new_list.iter_items_mut() {
+		if matches(&item) && observer.before_change(&item) {
+			count += 1;
+			let mut raised = item.clone();
+			raised.set_importance(policy.raise(&item).unwrap());
+			observer.after_change(&item, &raised);
+			*item = raised;
+		}
+	}
+	(new_list, count)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::NaiveDate;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("age"), get_action().name);
+	}
+
+	#[test]
+	fn test_age_items_in_list() {
+		let source = "(B) Foo due:2024-06-07\n\
+			(A) Bar due:2024-06-07\n\
+			\n\
+			Baz @work due:2024-06-08\n\
+			x Bat due:2024-06-01\n\
+			Quux due:2024-06-30\n";
+		let source_list = || List::from_string(String::from(source)).unwrap();
+		let friday = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+
+		crate::item::with_today(friday, || {
+			let (got, count) = age_items_in_list(
+				source_list(),
+				&SearchTerms::new(),
+				&AgePolicy::default(),
+				&mut NoObserver,
+			);
+			assert_eq!(2, count);
+			assert_eq!(
+				"(A) Foo due:2024-06-07\n\
+				(A) Bar due:2024-06-07\n\
+				\n\
+				(B) Baz @work due:2024-06-08\n\
+				x Bat due:2024-06-01\n\
+				Quux due:2024-06-30\n",
+				got.serialize()
+			);
+
+			// Running it again changes nothing.
+			let (again, count) = age_items_in_list(
+				List::from_string(got.serialize()).unwrap(),
+				&SearchTerms::new(),
+				&AgePolicy::default(),
+				&mut NoObserver,
+			);
+			assert_eq!(0, count);
+			assert_eq!(got.serialize(), again.serialize());
+
+			let (got, count) = age_items_in_list(
+				source_list(),
+				&SearchTerms::from_string("@work"),
+				&AgePolicy::default(),
+				&mut NoObserver,
+			);
+			assert_eq!(1, count);
+			assert!(got.serialize().starts_with("(B) Foo"));
+		});
+	}
+}
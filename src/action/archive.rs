@@ -2,32 +2,58 @@
 
 use crate::action::*;
 use crate::list::{Line, LineKind, List};
-use clap::{ArgMatches, Command};
+use clap::{Arg, ArgMatches, Command};
 
 /// Options for the `archive` subcommand.
 pub fn get_action() -> Action {
 	let name = String::from("archive");
 	let mut command = Command::new("archive")
-		.about("Move completed tasks from todo.txt to done.txt");
+		.about("Move completed tasks from todo.txt to done.txt")
+		.after_help(
+			"Each archived task is replaced by a blank line, so other tasks \
+			keep their line numbers. With --compact, they are removed \
+			instead, so later tasks are renumbered.",
+		);
 
 	command = FileType::TodoTxt.add_args(command);
 	command = FileType::DoneTxt.add_args(command);
 	command = Outputter::add_args_minimal(command);
+	command = command.arg(
+		Arg::new("compact")
+			.num_args(0)
+			.long("compact")
+			.help("Remove archived lines instead of blanking them"),
+	);
+	command = SaveMode::add_args(command);
 
 	Action { name, command }
 }
 
 /// Execute the `archive` subcommand.
 #[cfg(not(tarpaulin_include))]
-pub fn execute(args: &ArgMatches) {
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
 	let mut outputter = Outputter::from_argmatches_minimal(args);
-	let todo_filename = FileType::TodoTxt.filename(args);
-	let done_filename = FileType::DoneTxt.filename(args);
-	let (num, result) = run_archive(&todo_filename, &done_filename);
+	let todo_filename = FileType::TodoTxt.filename(args)?;
+	let done_filename = FileType::DoneTxt.filename(args)?;
+	let save_mode = SaveMode::from_argmatches(args);
+	let compact = *args.get_one::<bool>("compact").unwrap();
+	let (num, result) =
+		run_archive(&todo_filename, &done_filename, save_mode, compact)?;
 
 	if num > 0 {
-		outputter
-			.write_status(format!("Moved {} tasks to {}", num, done_filename));
+		outputter.write_status(save_mode.status(
+			format!("Moved {} tasks to {}", num, done_filename),
+			format!("moved {} tasks to {}", num, done_filename),
+		));
+		if compact {
+			outputter.write_status(save_mode.status(
+				format!(
+					"Compacted {} lines, so later tasks have new line numbers.",
+					num
+				),
+				format!("compacted {} lines", num),
+			));
+		}
 	} else {
 		outputter.write_status(format!(
 			"No complete tasks found in {}",
@@ -35,57 +61,120 @@ pub fn execute(args: &ArgMatches) {
 		));
 	}
 
-	maybe_housekeeping_warnings(&mut outputter, &result);
+	outputter
+		.write_warnings(housekeeping_warnings(&result, &Config::load().hints));
+
+	Ok(ExitStatus::Success)
 }
 
 /// Logic of archiving a todo.txt to a done.txt.
 ///
 /// Will read the todo.txt and if there are any completed tasks in it, replace them
 /// with blank lines (overwriting the original file), and append those completed tasks
-/// to the done.txt.
+/// to the done.txt. If `compact` is true, their lines are removed instead.
 ///
-/// If there are no completed tasks in the todo.txt, neither file should be written to.
+/// If there are no completed tasks in the todo.txt, or this is a dry run,
+/// neither file should be written to.
 ///
 /// Returns a tuple of the number of moved lines and the modified todo list.
-pub fn run_archive(todo_filename: &str, done_filename: &str) -> (i32, List) {
-	let todo = List::from_url(String::from(todo_filename))
-		.expect("Could not read todo list");
-	let mut new_todo: Vec<Line> = Vec::new();
-	let mut append_done: Vec<Line> = Vec::new();
-
-	let orig = todo.lines.clone();
-	let moved = run_archive_vec(&orig, &mut new_todo, &mut append_done);
+pub fn run_archive(
+	todo_filename: &str,
+	done_filename: &str,
+	save_mode: SaveMode,
+	compact: bool,
+) -> Result<(i32, List), Error> {
+	let todo = List::from_url(String::from(todo_filename)).map_err(|e| {
+		Error::Unreadable {
+			label: String::from(todo_filename),
+			message: e.to_string(),
+		}
+	})?;
+	let (outcome, append_done) = run(todo, &ArchiveActionConfig { compact })?;
+	let moved = outcome.count as i32;
 
-	if moved == 0 {
-		return (moved, todo);
+	if moved > 0 && save_mode == SaveMode::Save {
+		List::append_lines_to_url(
+			String::from(done_filename),
+			append_done.iter().collect(),
+		)?;
+		outcome
+			.list
+			.to_url(String::from(todo_filename))?;
 	}
+	Ok((moved, outcome.list))
+}
 
-	List::append_lines_to_url(
-		String::from(done_filename),
-		append_done.iter().collect(),
+/// Config object for the `archive` action.
+#[derive(Clone, Default)]
+pub struct ArchiveActionConfig {
+	/// Remove the lines of archived tasks, rather than leaving them blank.
+	pub compact: bool,
+}
+
+/// Run the `archive` action on a todo list, without saving it.
+///
+/// Also returns the completed tasks taken out of the list, as lines to
+/// append to a done.txt.
+pub fn run(
+	list: List,
+	cfg: &ArchiveActionConfig,
+) -> Result<(ActionOutcome, Vec<Line>), Error> {
+	let mut new_todo: Vec<Line> = Vec::new();
+	let mut append_done: Vec<Line> = Vec::new();
+	let moved = run_archive_vec(
+		&list.lines,
+		&mut new_todo,
+		&mut append_done,
+		cfg.compact,
 	);
-	let mut list = List::new();
-	list.lines = new_todo;
-	list.to_url(String::from(todo_filename));
-	(moved, list)
+
+	let mut new_list = list.new_like();
+	new_list.lines = new_todo;
+	let message =
+		format!("archived {} tasks from {}", moved, new_list.display_name());
+	Ok((
+		ActionOutcome::new(new_list, moved as usize, message),
+		append_done,
+	))
 }
 
 /// Logic of archiving a todo.txt to a done.txt, but with Vec<Line>.
 ///
+/// Each archived line is replaced with a blank line in `todo`, unless
+/// `compact` is true, when it is left out.
+///
 /// Returns the number of lines archived.
 pub fn run_archive_vec(
-	src: &Vec<Line>,
+	src: &[Line],
 	todo: &mut Vec<Line>,
 	done: &mut Vec<Line>,
+	compact: bool,
 ) -> i32 {
+	run_archive_vec_where(src, todo, done, compact, |_| true)
+}
+
+/// Like `run_archive_vec`, but only archives completed tasks on lines for
+/// which `select` returns true, given the line's index in `src`.
+///
+/// Returns the number of lines archived.
+pub fn run_archive_vec_where<F>(
+	src: &[Line],
+	todo: &mut Vec<Line>,
+	done: &mut Vec<Line>,
+	compact: bool,
+	select: F,
+) -> i32
+where
+	F: Fn(usize) -> bool,
+{
 	let mut moved = 0;
-	for line in src {
+	for (idx, line) in src.iter().enumerate() {
 		match line.kind {
 			LineKind::Blank => todo.push(line.clone()),
 			LineKind::Comment => todo.push(line.clone()),
 			LineKind::Item => {
 				let item = line.item.as_ref().expect("Expected a task!");
-				if item.completion() {
+				if item.completion() && select(idx) {
 					let new = Line {
 						kind: LineKind::Item,
 						text: line.text.clone(),
@@ -94,7 +183,9 @@ pub fn run_archive_vec(
 					};
 					moved += 1;
 					done.push(new);
-					todo.push(Line::new_blank())
+					if !compact {
+						todo.push(Line::new_blank())
+					}
 				} else {
 					todo.push(line.clone())
 				}
@@ -114,14 +205,6 @@ mod tests {
 		assert_eq!(String::from("archive"), get_action().name);
 	}
 
-	fn _eq_vecline(vl1: Vec<Line>, vl2: Vec<Line>) -> bool {
-		let mut l1 = List::new();
-		l1.lines = vl1;
-		let mut l2 = List::new();
-		l2.lines = vl2;
-		l1.serialize() == l2.serialize()
-	}
-
 	#[test]
 	pub fn test_run_archive_vec() {
 		let source: Vec<Line> = Vec::from([
@@ -149,10 +232,31 @@ mod tests {
 
 		let mut keep: Vec<Line> = Vec::new();
 		let mut archive: Vec<Line> = Vec::new();
-		let moved = run_archive_vec(&source, &mut keep, &mut archive);
+		let moved = run_archive_vec(&source, &mut keep, &mut archive, false);
 		assert_eq!(expected_moved, moved);
-		assert!(_eq_vecline(expected_keep, keep));
-		assert!(_eq_vecline(expected_archive, archive));
+		assert_eq!(expected_keep, keep);
+		assert_eq!(expected_archive, archive);
+
+		let mut keep: Vec<Line> = Vec::new();
+		let mut archive: Vec<Line> = Vec::new();
+		let moved = run_archive_vec_where(
+			&source,
+			&mut keep,
+			&mut archive,
+			false,
+			|i| i == 1,
+		);
+		assert_eq!(1, moved);
+		assert_eq!(source[0], keep[0]);
+		assert_eq!(Line::new_blank(), keep[1]);
+		assert_eq!(expected_archive[1..], archive);
+
+		let mut keep: Vec<Line> = Vec::new();
+		let mut archive: Vec<Line> = Vec::new();
+		let moved = run_archive_vec(&source, &mut keep, &mut archive, true);
+		assert_eq!(2, moved);
+		assert_eq!(expected_keep[2..], keep);
+		assert_eq!(expected_archive, archive);
 	}
 
 	#[test]
@@ -197,7 +301,7 @@ mod tests {
 		{
 			let mut l = List::new();
 			l.lines = initial_todo;
-			l.to_filename(todo_filename.clone());
+			l.to_filename(todo_filename.clone()).unwrap();
 		}
 
 		let done_filename = dir
@@ -209,24 +313,64 @@ mod tests {
 		{
 			let mut l = List::new();
 			l.lines = initial_done;
-			l.to_filename(done_filename.clone());
+			l.to_filename(done_filename.clone()).unwrap();
 		}
 
-		let (moved, result) = run_archive(&todo_filename, &done_filename);
+		let (moved, result) =
+			run_archive(&todo_filename, &done_filename, SaveMode::Save, false)
+				.unwrap();
 		assert_eq!(expected_moved, moved);
-		assert!(_eq_vecline(result.lines, expected_todo.clone()));
-		assert!(_eq_vecline(
+		assert_eq!(result.lines, expected_todo.clone());
+		assert_eq!(
 			List::from_filename(todo_filename.clone())
 				.unwrap()
 				.lines,
 			expected_todo
-		));
-		assert!(_eq_vecline(
+		);
+		assert_eq!(
 			List::from_filename(done_filename.clone())
 				.unwrap()
 				.lines,
 			expected_done
-		));
+		);
+	}
+
+	#[test]
+	fn test_run() {
+		let list =
+			List::from_string(String::from("x Foo\nBar\nx Baz\n")).unwrap();
+		let cfg = ArchiveActionConfig { compact: true };
+		let (outcome, done) = run(list, &cfg).unwrap();
+		assert_eq!(2, outcome.count);
+		assert_eq!("Bar\n", outcome.list.serialize());
+		assert_eq!(
+			Vec::from(["x Foo", "x Baz"]),
+			done.iter()
+				.map(|l| l.text.as_str())
+				.collect::<Vec<_>>()
+		);
+
+		let list = List::from_string(String::from("Bar\n")).unwrap();
+		let (outcome, done) = run(list, &cfg).unwrap();
+		assert_eq!(0, outcome.count);
+		assert!(outcome.messages.is_empty());
+		assert!(done.is_empty());
+
+		let dir = tempdir().unwrap();
+		let missing = dir
+			.path()
+			.join("todo.txt")
+			.display()
+			.to_string();
+		let done = dir
+			.path()
+			.join("done.txt")
+			.display()
+			.to_string();
+		match run_archive(&missing, &done, SaveMode::Save, false) {
+			Err(Error::Unreadable { label, .. }) => assert_eq!(missing, label),
+			other => panic!("expected an error, got {:?}", other.map(|r| r.0)),
+		}
 	}
 
 	#[test]
@@ -257,7 +401,7 @@ mod tests {
 		{
 			let mut l = List::new();
 			l.lines = initial_todo;
-			l.to_filename(todo_filename.clone());
+			l.to_filename(todo_filename.clone()).unwrap();
 		}
 
 		let done_filename = dir
@@ -267,14 +411,113 @@ mod tests {
 			.display()
 			.to_string();
 
-		let (moved, result) = run_archive(&todo_filename, &done_filename);
+		let (moved, result) =
+			run_archive(&todo_filename, &done_filename, SaveMode::Save, false)
+				.unwrap();
 		assert_eq!(expected_moved, moved);
-		assert!(_eq_vecline(result.lines, expected_todo.clone()));
-		assert!(_eq_vecline(
+		assert_eq!(result.lines, expected_todo.clone());
+		assert_eq!(
 			List::from_filename(todo_filename.clone())
 				.unwrap()
 				.lines,
 			expected_todo
-		));
+		);
+	}
+
+	#[test]
+	pub fn test_run_archive_dry_run() {
+		let dir = tempdir().unwrap();
+		let todo_filename = dir
+			.path()
+			.join("todo-X88.txt")
+			.display()
+			.to_string();
+		let done_filename = dir
+			.path()
+			.join("done-X88.txt")
+			.display()
+			.to_string();
+		fs::write(&todo_filename, "x Foo1\nBar\n").unwrap();
+		fs::write(&done_filename, "x Old1\n").unwrap();
+
+		let (moved, result) = run_archive(
+			&todo_filename,
+			&done_filename,
+			SaveMode::DryRun,
+			false,
+		)
+		.unwrap();
+		assert_eq!(1, moved);
+		assert_eq!("\nBar\n", result.serialize());
+		assert_eq!(
+			"x Foo1\nBar\n",
+			fs::read_to_string(&todo_filename).unwrap()
+		);
+		assert_eq!("x Old1\n", fs::read_to_string(&done_filename).unwrap());
+	}
+
+	#[test]
+	pub fn test_run_archive_compact() {
+		let dir = tempdir().unwrap();
+		let todo_filename = dir
+			.path()
+			.join("todo-X88.txt")
+			.display()
+			.to_string();
+		let done_filename = dir
+			.path()
+			.join("done-X88.txt")
+			.display()
+			.to_string();
+		fs::write(&todo_filename, "Foo\nx Bar\n\nx Baz\nBat\n").unwrap();
+		fs::write(&done_filename, "").unwrap();
+
+		let (moved, result) =
+			run_archive(&todo_filename, &done_filename, SaveMode::Save, true)
+				.unwrap();
+		assert_eq!(2, moved);
+		assert_eq!("Foo\n\nBat\n", result.serialize());
+		assert_eq!("Foo\n\nBat\n", fs::read_to_string(&todo_filename).unwrap());
+		assert_eq!(
+			"x Bar\nx Baz\n",
+			fs::read_to_string(&done_filename).unwrap()
+		);
+	}
+
+	#[test]
+	pub fn test_run_archive_nothing_to_move() {
+		let dir = tempdir().unwrap();
+		let todo_filename = dir
+			.path()
+			.join("todo-X89.txt")
+			.display()
+			.to_string();
+		let done_filename = dir
+			.path()
+			.join("done-X89.txt")
+			.display()
+			.to_string();
+		fs::write(&todo_filename, "Foo\n\nBar\n").unwrap();
+		fs::write(&done_filename, "x Old\n").unwrap();
+		let old = std::time::SystemTime::UNIX_EPOCH
+			+ std::time::Duration::from_secs(1_000_000_000);
+		for f in [&todo_filename, &done_filename] {
+			fs::File::options()
+				.write(true)
+				.open(f)
+				.unwrap()
+				.set_modified(old)
+				.unwrap();
+		}
+
+		let (moved, _) =
+			run_archive(&todo_filename, &done_filename, SaveMode::Save, true)
+				.unwrap();
+		assert_eq!(0, moved);
+		assert_eq!("Foo\n\nBar\n", fs::read_to_string(&todo_filename).unwrap());
+		assert_eq!("x Old\n", fs::read_to_string(&done_filename).unwrap());
+		for f in [&todo_filename, &done_filename] {
+			assert_eq!(old, fs::metadata(f).unwrap().modified().unwrap());
+		}
 	}
 }
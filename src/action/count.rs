@@ -0,0 +1,242 @@
+//! Count tasks, for scripts and status bars
+
+use crate::action::*;
+use crate::item::Urgency;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+
+/// Options for the `count` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("count");
+	let mut command = Command::new("count")
+		.about("Count tasks, for scripts and status bars")
+		.after_help(
+			"Prints just the number of open tasks. Filters can be combined, \
+			and a task is only counted if it matches all of them.\n\n\
+			With --by, prints a tab-separated line for each group instead, \
+			like \"@work<TAB>3\".\n\n\
+			Always exits successfully, even if the count is zero.",
+		);
+
+	command = FileType::TodoTxt.add_args(command);
+	command = command
+		.arg(
+			Arg::new("overdue")
+				.num_args(0)
+				.long("overdue")
+				.help("Only count overdue tasks"),
+		)
+		.arg(
+			Arg::new("due-today")
+				.num_args(0)
+				.long("due-today")
+				.help("Only count tasks due today"),
+		)
+		.arg(
+			Arg::new("startable")
+				.num_args(0)
+				.long("startable")
+				.help("Only count tasks which can be started now"),
+		)
+		.arg(
+			Arg::new("completed")
+				.num_args(0)
+				.long("completed")
+				.help("Count completed tasks instead of open ones"),
+		)
+		.arg(
+			Arg::new("context")
+				.long("context")
+				.value_name("CONTEXT")
+				.action(ArgAction::Append)
+				.help("Only count tasks with this context"),
+		)
+		.arg(
+			Arg::new("by")
+				.num_args(1)
+				.long("by")
+				.value_name("BY")
+				.value_parser(|s: &str| {
+					Grouping::from_string(s).map_err(|e| e.to_string())
+				})
+				.help("Count each group, like 'context' or 'size'"),
+		);
+
+	Action { name, command }
+}
+
+/// Execute the `count` subcommand.
+#[cfg(not(tarpaulin_include))]
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let filter = CountFilter::from_argmatches(args);
+	let grouping = args
+		.get_one::<Grouping>("by")
+		.copied()
+		.unwrap_or(Grouping::None);
+
+	let list = FileType::TodoTxt.load(args)?;
+	for line in count_lines(&list, &filter, grouping) {
+		println!("{}", line);
+	}
+
+	Ok(ExitStatus::Success)
+}
+
+/// Which tasks to count.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CountFilter {
+	pub overdue: bool,
+	pub due_today: bool,
+	pub startable: bool,
+	pub completed: bool,
+	pub contexts: Vec<String>,
+}
+
+impl CountFilter {
+	/// Read the filter from ArgMatches.
+	pub fn from_argmatches(args: &ArgMatches) -> Self {
+		Self {
+			overdue: *args.get_one::<bool>("overdue").unwrap(),
+			due_today: *args.get_one::<bool>("due-today").unwrap(),
+			startable: *args.get_one::<bool>("startable").unwrap(),
+			completed: *args.get_one::<bool>("completed").unwrap(),
+			contexts: args
+				.get_many::<String>("context")
+				.into_iter()
+				.flatten()
+				.cloned()
+				.collect(),
+		}
+	}
+
+	/// Whether an item in a list should be counted.
+	pub fn item_matches(&self, list: &List, item: &Item) -> bool {
+		item.completion() == self.completed
+			&& (!self.overdue || item.urgency() == Some(Urgency::Overdue))
+			&& (!self.due_today || item.urgency() == Some(Urgency::Today))
+			&& (!self.startable || list.is_item_startable(item))
+			&& self
+				.contexts
+				.iter()
+				.all(|c| item.has_context(c))
+	}
+}
+
+/// The lines to print for a count: just the number of matching tasks, or
+/// with a grouping, the heading and number of tasks for each group,
+/// separated by a tab.
+pub fn count_lines(
+	list: &List,
+	filter: &CountFilter,
+	grouping: Grouping,
+) -> Vec<String> {
+	let items: Vec<&Item> = list
+		.items()
+		.into_iter()
+		.filter(|i| filter.item_matches(list, i))
+		.collect();
+	if grouping == Grouping::None {
+		return Vec::from([items.len().to_string()]);
+	}
+	grouping
+		.split_items(items)
+		.into_iter()
+		.map(|(heading, group)| format!("{}\t{}", heading, group.len()))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("count"), get_action().name);
+	}
+
+	#[test]
+	fn test_from_argmatches() {
+		let cmd = get_action().command;
+		let m = cmd.clone().get_matches_from(vec![
+			"count",
+			"--overdue",
+			"--context",
+			"@work",
+			"--context",
+			"phone",
+			"--by",
+			"ctx",
+		]);
+		assert_eq!(
+			CountFilter {
+				overdue: true,
+				contexts: Vec::from([
+					String::from("@work"),
+					String::from("phone")
+				]),
+				..CountFilter::default()
+			},
+			CountFilter::from_argmatches(&m)
+		);
+		assert_eq!(Some(&Grouping::Context), m.get_one::<Grouping>("by"));
+		assert!(cmd
+			.try_get_matches_from(vec!["count", "--by", "colour"])
+			.is_err());
+	}
+
+	#[test]
+	fn test_count_lines() {
+		let list = List::from_string(String::from(
+			"Foo @work due:2024-06-05\n\
+			x Done @work due:2024-06-05\n\
+			Bar @work @phone due:2024-06-07\n\
+			Baz @home due:2024-06-07 start:2024-06-08\n\
+			Bat @work due:2024-06-07 after:a\n\
+			Blocker id:a\n",
+		))
+		.unwrap();
+		let friday = chrono::NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+		crate::item::with_today(friday, || {
+			let count = |filter: CountFilter, grouping: Grouping| {
+				count_lines(&list, &filter, grouping)
+			};
+			assert_eq!(
+				vec!["5"],
+				count(CountFilter::default(), Grouping::None)
+			);
+			let f = CountFilter {
+				completed: true,
+				..CountFilter::default()
+			};
+			assert_eq!(vec!["1"], count(f, Grouping::None));
+			let f = CountFilter {
+				overdue: true,
+				..CountFilter::default()
+			};
+			assert_eq!(vec!["1"], count(f, Grouping::None));
+			let f = CountFilter {
+				due_today: true,
+				startable: true,
+				..CountFilter::default()
+			};
+			assert_eq!(vec!["1"], count(f, Grouping::None));
+			let f = CountFilter {
+				contexts: Vec::from([
+					String::from("@WORK"),
+					String::from("phone"),
+				]),
+				..CountFilter::default()
+			};
+			assert_eq!(vec!["1"], count(f, Grouping::None));
+			let f = CountFilter {
+				overdue: true,
+				due_today: true,
+				..CountFilter::default()
+			};
+			assert_eq!(vec!["0"], count(f, Grouping::None));
+			assert_eq!(
+				vec!["@home\t1", "@phone\t1", "@work\t3", "No context\t1"],
+				count(CountFilter::default(), Grouping::Context)
+			);
+		});
+	}
+}
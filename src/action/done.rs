@@ -1,8 +1,10 @@
 //! Mark a task or tasks as done
 
+use crate::action::archive::run_archive_vec_where;
 use crate::action::*;
-use crate::item::Item;
-use crate::list::{LineKind, List};
+use crate::item::{parse_date_arg, today};
+use crate::list::{Line, List};
+use chrono::NaiveDate;
 use clap::{Arg, ArgMatches, Command};
 
 /// Options for the `done` subcommand.
@@ -21,89 +23,406 @@ pub fn get_action() -> Action {
 			.aliases(["nodate"])
 			.help("Don't automatically add a completion date to the task"),
 	);
+	command = command.arg(
+		Arg::new("date")
+			.long("date")
+			.value_name("DATE")
+			.value_parser(parse_completion_date)
+			.conflicts_with("no-date")
+			.help("Completion date, like 2024-06-01 or yesterday"),
+	);
+	command = command
+		.arg(
+			Arg::new("pri-tag")
+				.num_args(0)
+				.long("pri-tag")
+				.help("Move priority into a 'pri:' tag"),
+		)
+		.arg(
+			Arg::new("no-pri-tag")
+				.num_args(0)
+				.long("no-pri-tag")
+				.help("Keep priority as-is (default)"),
+		)
+		.arg(
+			Arg::new("keep-start")
+				.num_args(0)
+				.long("keep-start")
+				.help("Keep start dates which are in the future"),
+		);
+	command = command
+		.arg(
+			Arg::new("archive")
+				.num_args(0)
+				.long("archive")
+				.help("Move the tasks to done.txt straight away"),
+		)
+		.arg(
+			Arg::new("no-archive")
+				.num_args(0)
+				.long("no-archive")
+				.help("Leave the tasks in todo.txt (default)"),
+		)
+		.arg(
+			Arg::new("archive-all")
+				.num_args(0)
+				.long("archive-all")
+				.help("Also archive tasks which were already complete"),
+		)
+		.after_help(
+			"With --archive, TADA_AUTO_ARCHIVE=true, or `archive = true` in the \
+			[done] section of the config file, tasks marked complete are moved to done.txt, like \
+			running `tada archive` afterwards. Only the tasks marked complete \
+			by this command are moved, unless --archive-all is given.",
+		);
+	command = FileType::DoneTxt.add_args(command);
+	command = FileType::add_all_profiles_arg(command);
 	command = ConfirmationStatus::add_args(command);
+	command = SaveMode::add_args(command);
 
 	Action { name, command }
 }
 
+/// Whether to move priorities into a `pri:` tag when marking tasks done.
+///
+/// Uses the `TADA_PRI_TAG` environment variable as a fallback.
+pub fn preserve_priority_from_argmatches(args: &ArgMatches) -> bool {
+	if *args.get_one::<bool>("no-pri-tag").unwrap() {
+		false
+	} else if *args.get_one::<bool>("pri-tag").unwrap() {
+		true
+	} else {
+		preserve_priority_default()
+	}
+}
+
+/// Whether to move priorities into a `pri:` tag by default, according to the
+/// `TADA_PRI_TAG` environment variable.
+pub fn preserve_priority_default() -> bool {
+	matches!(
+		env::var("TADA_PRI_TAG")
+			.unwrap_or_default()
+			.to_lowercase()
+			.as_str(),
+		"1" | "true" | "yes" | "on"
+	)
+}
+
+/// Accept a completion date, like "2024-06-01" or "yesterday".
+fn parse_completion_date(s: &str) -> Result<NaiveDate, String> {
+	parse_date_arg(s).ok_or_else(|| {
+		String::from(
+			"expected a date like 2024-06-01, yesterday, or last_friday",
+		)
+	})
+}
+
+/// The date to mark tasks complete on: the `--date` option, or today, or
+/// None with `--no-date`.
+pub fn completion_date_from_argmatches(args: &ArgMatches) -> Option<NaiveDate> {
+	if *args.get_one::<bool>("no-date").unwrap() {
+		return None;
+	}
+	Some(
+		args.get_one::<NaiveDate>("date")
+			.copied()
+			.unwrap_or_else(today),
+	)
+}
+
+/// Whether to move tasks to done.txt after marking them complete.
+///
+/// Uses the `TADA_AUTO_ARCHIVE` environment variable or the config file as
+/// a fallback.
+pub fn archive_from_argmatches(args: &ArgMatches) -> bool {
+	if *args.get_one::<bool>("archive-all").unwrap() {
+		true
+	} else if *args.get_one::<bool>("no-archive").unwrap() {
+		false
+	} else if *args.get_one::<bool>("archive").unwrap() {
+		true
+	} else {
+		Config::load().done.archive()
+	}
+}
+
+/// The done.txt to archive tasks from a list to, if it has one.
+fn done_filename_for(
+	profile: &Option<String>,
+	args: &ArgMatches,
+) -> Result<Option<String>, Error> {
+	match profile {
+		Some(name) => Ok(Config::load()
+			.profile(name)
+			.and_then(|p| p.done_filename())),
+		None => FileType::DoneTxt.filename(args).map(Some),
+	}
+}
+
 /// Execute the `done` subcommand.
 #[cfg(not(tarpaulin_include))]
-pub fn execute(args: &ArgMatches) {
-	let todo_filename = FileType::TodoTxt.filename(args);
-	let list = List::from_url(todo_filename.clone())
-		.expect("Could not read todo list");
-	let search_terms = SearchTerms::from_argmatches(args);
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
 	let mut outputter = Outputter::from_argmatches(args);
-	outputter.line_number_digits = list.lines.len().to_string().len();
-	let confirmation = ConfirmationStatus::from_argmatches(args);
-	let include_date = !*args.get_one::<bool>("no-date").unwrap();
+	let lists = FileType::TodoTxt.load_all(args)?;
+	outputter.fit_to_lists(&lists);
+	let cfg = DoneActionConfig::from_argmatches(args);
+	let mut confirmation = ConfirmationStatus::from_argmatches(args);
+	let save_mode = SaveMode::from_argmatches(args);
 
-	let (count, new_list) = mark_items_done_in_list(
-		list,
-		search_terms,
-		&mut outputter,
-		confirmation,
-		include_date,
-	);
+	if let Some(d) = cfg.completion_date.filter(|d| *d > today()) {
+		outputter.write_notice(format!(
+			"The completion date {} is in the future.",
+			d.format("%Y-%m-%d")
+		));
+		if !confirmation.confirm(
+			&mut outputter,
+			"Use it anyway?",
+			"Using it anyway",
+			"Not using it",
+			None,
+		) {
+			outputter.write_status(String::from("No actions taken."));
+			return Ok(ExitStatus::NoMatch);
+		}
+	}
+
+	let archive = archive_from_argmatches(args);
+	let archive_all = *args.get_one::<bool>("archive-all").unwrap();
+
+	let mut total = 0;
+	let mut archived = 0;
+	let mut done_filenames: Vec<String> = Vec::new();
+	let mut changed: Vec<String> = Vec::new();
+	let mut new_lists = Vec::new();
+	for pl in lists {
+		let before = pl.list.lines.clone();
+		let before_text = pl.list.serialize();
+		let mut confirmer = Confirmer::new(
+			&mut outputter,
+			confirmation,
+			"Mark finished?",
+			"Marking finished",
+			"Skipping",
+		)
+		.with_dependents(&pl.list);
+		let outcome = run(pl.list, &cfg, &mut confirmer)?;
+		let (count, mut new_list) = (outcome.count, outcome.list);
+		// Answering "all" or "quit" goes for the remaining lists too.
+		confirmation = confirmer.status;
+		let mut to_archive = Vec::new();
+		if archive && (count > 0 || archive_all) {
+			match done_filename_for(&pl.profile, args)? {
+				Some(done_filename) => {
+					to_archive =
+						archive_completed(&before, &mut new_list, archive_all);
+					if !to_archive.is_empty() {
+						if save_mode == SaveMode::Save {
+							List::append_lines_to_url(
+								done_filename.clone(),
+								to_archive.iter().collect(),
+							)?;
+						}
+						let name = tilde_path(&done_filename);
+						if !done_filenames.contains(&name) {
+							done_filenames.push(name);
+						}
+					}
+				}
+				None => outputter.write_notice(format!(
+					"Profile {} has no done.txt, so its tasks weren't archived.",
+					pl.profile.as_deref().unwrap_or_default()
+				)),
+			}
+		}
+		if count > 0 || !to_archive.is_empty() {
+			save_mode.save_with_diff(
+				&new_list,
+				&before_text,
+				&mut outputter,
+			)?;
+			changed.push(new_list.display_name());
+		}
+		total += count;
+		archived += to_archive.len();
+		new_lists.push(new_list);
+	}
 
-	if count > 0 {
-		new_list.to_url(todo_filename);
-		outputter.write_status(format!("Marked {} tasks complete!", count));
+	if archived > 0 {
+		let archived_what = if archived == total {
+			String::from("them")
+		} else {
+			format!("{} tasks", archived)
+		};
+		let to = done_filenames.join(", ");
+		outputter.write_status(save_mode.status(
+			format!(
+				"Marked {} tasks complete and archived {} to {}",
+				total, archived_what, to
+			),
+			format!(
+				"marked {} tasks complete and archived {} to {}",
+				total, archived_what, to
+			),
+		));
+	} else if total > 0 {
+		let to = changed.join(", ");
+		outputter.write_status(save_mode.status(
+			format!("Marked {} tasks complete in {}", total, to),
+			format!("marked {} tasks complete in {}", total, to),
+		));
 	} else {
 		outputter.write_status(String::from("No actions taken."));
 	}
 
-	maybe_housekeeping_warnings(&mut outputter, &new_list);
+	for new_list in new_lists {
+		outputter.write_warnings(housekeeping_warnings(
+			&new_list,
+			&Config::load().hints,
+		));
+	}
+
+	Ok(ExitStatus::from_count(total))
+}
+
+/// Config object for the `done` action.
+#[derive(Clone)]
+pub struct DoneActionConfig {
+	pub search_terms: SearchTerms,
+	/// The date tasks are marked complete on, if any.
+	pub completion_date: Option<NaiveDate>,
+	/// Move priorities into a `pri:` tag.
+	pub preserve_priority: bool,
+	/// Bring start dates after the completion date back to it.
+	pub clear_start: bool,
+}
+
+impl Default for DoneActionConfig {
+	fn default() -> Self {
+		Self {
+			search_terms: SearchTerms::new(),
+			completion_date: Some(today()),
+			preserve_priority: false,
+			clear_start: true,
+		}
+	}
+}
+
+impl DoneActionConfig {
+	/// Create a DoneActionConfig from an appropriate ArgMatches.
+	///
+	/// Uses the `TADA_PRI_TAG` environment variable as a fallback.
+	pub fn from_argmatches(args: &ArgMatches) -> Self {
+		Self {
+			search_terms: SearchTerms::from_argmatches(args),
+			completion_date: completion_date_from_argmatches(args),
+			preserve_priority: preserve_priority_from_argmatches(args),
+			clear_start: !*args.get_one::<bool>("keep-start").unwrap(),
+		}
+	}
+}
+
+/// Run the `done` action on a list, without saving or archiving it.
+///
+/// The observer will be used to check whether each individual item should be
+/// altered.
+pub fn run(
+	list: List,
+	cfg: &DoneActionConfig,
+	observer: &mut dyn MutationObserver,
+) -> Result<ActionOutcome, Error> {
+	let (count, new_list) = mark_items_done_in_list(
+		list,
+		cfg.search_terms.clone(),
+		observer,
+		cfg.completion_date,
+		cfg.preserve_priority,
+		cfg.clear_start,
+	);
+	let message = format!(
+		"marked {} tasks complete in {}",
+		count,
+		new_list.display_name()
+	);
+	Ok(ActionOutcome::new(new_list, count, message))
 }
 
 /// Return a new list with certain tasks in the given list marked as complete, based on the
 /// search terms. Also returns a count of items modified.
+///
+/// The `observer` is asked before each matching task is changed, and told
+/// afterwards. If a `completion_date` is given, it is added to each
+/// completed task.
+///
+/// If `preserve_priority` is true, each completed task's priority is moved into
+/// a `pri:` tag. If `clear_start` is true, start dates after the completion
+/// date are brought back to it.
 pub fn mark_items_done_in_list(
 	input: List,
 	search_terms: SearchTerms,
-	outputter: &mut Outputter,
-	status: ConfirmationStatus,
-	include_date: bool,
+	observer: &mut dyn MutationObserver,
+	completion_date: Option<NaiveDate>,
+	preserve_priority: bool,
+	clear_start: bool,
 ) -> (usize, List) {
-	let mut new_list = List::new();
+	let mut new_list = input;
 	let mut count: usize = 0;
+	observer.will_change(
+		new_list
+			.items()
+			.into_iter()
+			.filter(|i| search_terms.item_matches(i) && !i.completion())
+			.count(),
+	);
 
-	for line in input.lines {
-		match line.kind {
-			LineKind::Item => {
-				let item = line.item.clone().unwrap();
-				if search_terms.item_matches(&item)
-					&& (!item.completion())
-					&& check_if_complete(&item, outputter, status)
-				{
-					count += 1;
-					new_list.lines.push(line.but_done(include_date));
-				} else {
-					new_list.lines.push(line);
-				}
-			}
-			_ => new_list.lines.push(line),
+	for mut item in new_list.iter_items_mut() {
+		if search_terms.item_matches(&item)
+			&& (!item.completion())
+			&& observer.before_change(&item)
+		{
+			count += 1;
+			let done =
+				item.but_done(completion_date, preserve_priority, clear_start);
+			observer.after_change(&item, &done);
+			*item = done;
 		}
 	}
 
+	new_list.renumber();
 	(count, new_list)
 }
 
-/// Asks whether to mark an item as complete, and prints out the response before returning a bool.
-pub fn check_if_complete(
-	item: &Item,
-	outputter: &mut Outputter,
-	status: ConfirmationStatus,
-) -> bool {
-	outputter.write_item(item);
-	status.check(outputter, "Mark finished?", "Marking finished", "Skipping")
+/// Take completed tasks out of a list, returning them as lines for
+/// done.txt.
+///
+/// Unless `all` is true, only tasks which weren't already complete in
+/// `before`, the lines of the list before any tasks were marked, are taken.
+/// The list must have the same lines as `before`, in the same order.
+pub fn archive_completed(
+	before: &[Line],
+	list: &mut List,
+	all: bool,
+) -> Vec<Line> {
+	let was_complete = |idx: usize| {
+		before
+			.get(idx)
+			.and_then(|l| l.item.as_ref())
+			.map(|i| i.completion())
+			.unwrap_or(false)
+	};
+	let mut todo = Vec::new();
+	let mut done = Vec::new();
+	run_archive_vec_where(&list.lines, &mut todo, &mut done, false, |idx| {
+		all || !was_complete(idx)
+	});
+	list.lines = todo;
+	list.renumber();
+	done
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
 	use crate::list::Line;
-	use chrono::Utc;
 	use tempfile::tempdir;
 
 	#[test]
@@ -112,7 +431,7 @@ mod tests {
 	}
 
 	#[test]
-	fn test_check_if_complete() {
+	fn test_confirmer() {
 		let dir = tempdir().unwrap();
 		let buffer_filename = dir
 			.path()
@@ -124,21 +443,108 @@ mod tests {
 
 		let mut o = Outputter::new(9999);
 		o.colour = false;
-		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
-		let r = check_if_complete(&i, &mut o, ConfirmationStatus::Yes);
-		assert_eq!(true, r);
+		let buffer = fs::File::create(buffer_filename.clone()).unwrap();
+		o.msg_io = Box::new(buffer.try_clone().unwrap());
+		o.io = Box::new(buffer);
+		let r = Confirmer::new(
+			&mut o,
+			ConfirmationStatus::Yes,
+			"Mark finished?",
+			"Marking finished",
+			"Skipping",
+		)
+		.before_change(&i);
+		assert!(r);
 		let got_output = fs::read_to_string(buffer_filename.clone()).unwrap();
 		assert_eq!(String::from("  (?) XYZ\nMarking finished\n\n"), got_output);
 
 		let mut o = Outputter::new(9999);
 		o.colour = false;
-		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
-		let r = check_if_complete(&i, &mut o, ConfirmationStatus::No);
-		assert_eq!(false, r);
+		let buffer = fs::File::create(buffer_filename.clone()).unwrap();
+		o.msg_io = Box::new(buffer.try_clone().unwrap());
+		o.io = Box::new(buffer);
+		let r = Confirmer::new(
+			&mut o,
+			ConfirmationStatus::No,
+			"Mark finished?",
+			"Marking finished",
+			"Skipping",
+		)
+		.before_change(&i);
+		assert!(!r);
 		let got_output = fs::read_to_string(buffer_filename.clone()).unwrap();
 		assert_eq!(String::from("  (?) XYZ\nSkipping\n\n"), got_output);
 	}
 
+	#[test]
+	fn test_confirmer_with_dependents() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir.path().join("buffer.txt");
+		let mut list = List::new();
+		list.lines = Vec::from([
+			Line::from_string(String::from("Foo id:f"), 1),
+			Line::from_string(String::from("Bar after:f"), 2),
+			Line::from_string(String::from("Baz after:f"), 3),
+			Line::from_string(String::from("x Bat after:f"), 4),
+		]);
+
+		let mut o = Outputter::new(9999);
+		let buffer = fs::File::create(buffer_filename.clone()).unwrap();
+		o.msg_io = Box::new(buffer.try_clone().unwrap());
+		o.io = Box::new(buffer);
+		let mut confirmer = Confirmer::new(
+			&mut o,
+			ConfirmationStatus::Yes,
+			"Mark finished?",
+			"Marking finished",
+			"Skipping",
+		)
+		.with_dependents(&list);
+		assert!(confirmer.before_change(list.item_at(1).unwrap()));
+		assert_eq!(
+			"  (?) Foo id:f\n\
+			2 other tasks are waiting for this one.\n\
+			Marking finished\n\n",
+			fs::read_to_string(buffer_filename).unwrap()
+		);
+	}
+
+	/// Skips tasks with a `@later` context, and logs what changed.
+	struct Logger {
+		log: Vec<String>,
+	}
+
+	impl MutationObserver for Logger {
+		fn before_change(&mut self, item: &Item) -> bool {
+			!item.has_context("later")
+		}
+
+		fn after_change(&mut self, old: &Item, new: &Item) {
+			self.log.push(format!("{} => {}", old, new));
+		}
+	}
+
+	#[test]
+	fn test_mark_items_done_with_observer() {
+		let mut list = List::new();
+		list.lines = Vec::from([
+			Line::from_string(String::from("Foo"), 1),
+			Line::from_string(String::from("Foo @later"), 2),
+		]);
+		let mut logger = Logger { log: Vec::new() };
+		let (count, got) = mark_items_done_in_list(
+			list,
+			SearchTerms::from_string("foo"),
+			&mut logger,
+			None,
+			false,
+			true,
+		);
+		assert_eq!(1, count);
+		assert_eq!("x Foo\nFoo @later\n", got.serialize());
+		assert_eq!(vec!["Foo => x Foo"], logger.log);
+	}
+
 	#[test]
 	fn test_mark_items_done_in_list() {
 		let lines: Vec<Line> = Vec::from([
@@ -158,9 +564,10 @@ mod tests {
 			SearchTerms {
 				terms: vec![String::from("foo")],
 			},
-			&mut o,
-			ConfirmationStatus::Yes,
+			&mut NoObserver,
+			None,
 			false,
+			true,
 		);
 
 		assert_eq!(1, count);
@@ -179,8 +586,9 @@ mod tests {
 			SearchTerms {
 				terms: vec![String::from("foo")],
 			},
-			&mut o,
-			ConfirmationStatus::Yes,
+			&mut NoObserver,
+			Some(today()),
+			false,
 			true,
 		);
 
@@ -191,9 +599,167 @@ mod tests {
 				x {} 2000-01-02 Foo\n\
 				# Foo\n\
 				Bar\n",
-				Utc::now().date_naive().format("%Y-%m-%d")
+				today().format("%Y-%m-%d")
 			),
 			got.serialize()
 		);
+
+		let mut initial_list = List::new();
+		initial_list.lines = Vec::from([
+			Line::from_string(String::from("(A) Foo"), 0),
+			Line::from_string(String::from("Foo"), 0),
+		]);
+		let (count, got) = mark_items_done_in_list(
+			initial_list,
+			SearchTerms {
+				terms: vec![String::from("foo")],
+			},
+			&mut NoObserver,
+			None,
+			true,
+			true,
+		);
+
+		assert_eq!(2, count);
+		assert_eq!(
+			"x Foo pri:A\n\
+			x Foo\n",
+			got.serialize()
+		);
+
+		let mut initial_list = List::new();
+		initial_list.lines = Vec::from([
+			Line::from_string(String::from("Foo start:2999-01-01"), 0),
+			Line::from_string(String::from("Foo start:2999-01-02"), 0),
+		]);
+		let d = NaiveDate::from_ymd_opt(2024, 6, 3);
+		let (_, got) = mark_items_done_in_list(
+			initial_list,
+			SearchTerms::from_string("foo"),
+			&mut NoObserver,
+			d,
+			false,
+			true,
+		);
+		assert_eq!(
+			"x 2024-06-03 2024-06-03 Foo start:2024-06-03\n\
+			x 2024-06-03 2024-06-03 Foo start:2024-06-03\n",
+			got.serialize()
+		);
+	}
+
+	#[test]
+	fn test_archive_completed() {
+		let before: Vec<Line> = Vec::from([
+			Line::from_string(String::from("x 2000-01-01 Foo"), 0),
+			Line::from_string(String::from("Foo"), 0),
+			Line::from_string(String::from("Bar"), 0),
+		]);
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.io = Box::new(Vec::<u8>::new());
+
+		let mut initial_list = List::new();
+		initial_list.lines = before.clone();
+		let (_, marked) = mark_items_done_in_list(
+			initial_list,
+			SearchTerms {
+				terms: vec![String::from("foo")],
+			},
+			&mut NoObserver,
+			None,
+			false,
+			true,
+		);
+
+		let mut list = List::new();
+		list.lines = marked.lines.clone();
+		let done = archive_completed(&before, &mut list, false);
+		assert_eq!(
+			vec!["x Foo"],
+			done.iter()
+				.map(|l| l.text.as_str())
+				.collect::<Vec<_>>()
+		);
+		assert_eq!("x 2000-01-01 Foo\n\nBar\n", list.serialize());
+
+		let mut list = marked;
+		let done = archive_completed(&before, &mut list, true);
+		assert_eq!(2, done.len());
+		assert_eq!("\n\nBar\n", list.serialize());
+		assert_eq!(3, list.item_at(3).unwrap().line_number());
+	}
+
+	#[test]
+	fn test_completion_date_from_argmatches() {
+		let matches = get_action().command.get_matches_from(vec![
+			"done",
+			"--date",
+			"2024-06-03",
+			"foo",
+		]);
+		assert_eq!(
+			NaiveDate::from_ymd_opt(2024, 6, 3),
+			completion_date_from_argmatches(&matches)
+		);
+
+		let matches = get_action().command.get_matches_from(vec![
+			"done",
+			"--no-date",
+			"foo",
+		]);
+		assert_eq!(None, completion_date_from_argmatches(&matches));
+
+		assert!(get_action()
+			.command
+			.try_get_matches_from(vec!["done", "--date", "someday", "foo"])
+			.is_err());
+		assert!(get_action()
+			.command
+			.try_get_matches_from(vec![
+				"done",
+				"--date",
+				"2024-06-03",
+				"--no-date",
+				"foo"
+			])
+			.is_err());
+	}
+
+	#[test]
+	fn test_archive_from_argmatches() {
+		let matches = get_action().command.get_matches_from(vec![
+			"done",
+			"--archive",
+			"--no-archive",
+			"foo",
+		]);
+		assert!(!archive_from_argmatches(&matches));
+
+		let matches = get_action().command.get_matches_from(vec![
+			"done",
+			"--no-archive",
+			"--archive-all",
+			"foo",
+		]);
+		assert!(archive_from_argmatches(&matches));
+	}
+
+	#[test]
+	fn test_preserve_priority_from_argmatches() {
+		let matches = get_action().command.get_matches_from(vec![
+			"done",
+			"--pri-tag",
+			"foo",
+		]);
+		assert!(preserve_priority_from_argmatches(&matches));
+
+		let matches = get_action().command.get_matches_from(vec![
+			"done",
+			"--pri-tag",
+			"--no-pri-tag",
+			"foo",
+		]);
+		assert!(!preserve_priority_from_argmatches(&matches));
 	}
 }
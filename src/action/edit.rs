@@ -1,7 +1,13 @@
 //! Open your todo list in your editor
 
+use crate::action::find::find_results;
+use crate::action::lint::{describe, Severity};
 use crate::action::*;
-use clap::{ArgMatches, Command};
+use crate::config::Config;
+use crate::list::{Line, List};
+use clap::{Arg, ArgMatches, Command};
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
 use std::{env, io, process};
 
 /// Options for the `edit` subcommand.
@@ -9,37 +15,337 @@ pub fn get_action() -> Action {
 	let name = String::from("edit");
 	let mut command = Command::new("edit")
 		.about("Open your todo list in your editor")
-		.after_help("Ensure the EDITOR environent variable is set.");
+		.after_help(
+			"The editor is the `editor` setting in the [edit] section of the \
+			config file, or else the VISUAL or EDITOR environment variable, \
+			or else vi. It may include arguments, like `code --wait`.\n\n\
+			If search terms are given, the editor opens at the matching task, \
+			using `+LINE` for most editors or `--goto FILE:LINE` for VS Code. \
+			Set EDITOR_LINE_FLAG to change this, like `--line {line}`, where \
+			{line} and {file} are replaced by the line number and file name.\n\n\
+			With --inline, the matching task is printed, then a replacement \
+			for it is read from STDIN instead of opening an editor. If STDIN \
+			is a terminal, the task is ready to change. Replacing it with \
+			nothing offers to delete it, leaving a blank line so other \
+			tasks keep their numbers.",
+		);
 	command = FileType::TodoTxt.add_args(command);
+	command = FileType::DoneTxt.add_args(command);
+	command = Outputter::add_args_minimal(command);
+	command = command
+		.arg(
+			Arg::new("search-term")
+				.action(clap::ArgAction::Append)
+				.help("Open the editor at the task matching these terms"),
+		)
+		.arg(
+			Arg::new("first")
+				.num_args(0)
+				.long("first")
+				.help("If several tasks match, use the first"),
+		)
+		.arg(
+			Arg::new("done")
+				.num_args(0)
+				.long("done")
+				.help("Edit done.txt instead of todo.txt"),
+		)
+		.arg(
+			Arg::new("inline")
+				.num_args(0)
+				.long("inline")
+				.requires("search-term")
+				.help("Edit the matching task here, without an editor"),
+		);
+	command = ConfirmationStatus::add_args(command);
+	command = SaveMode::add_args(command);
 	Action { name, command }
 }
 
 /// Execute the `edit` subcommand.
 #[cfg(not(tarpaulin_include))]
-pub fn execute(args: &ArgMatches) {
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches_minimal(args);
+	let file_type = if *args.get_one::<bool>("done").unwrap() {
+		FileType::DoneTxt
+	} else {
+		FileType::TodoTxt
+	};
+	let filename = file_type.filename(args)?;
+
+	let search_terms = SearchTerms::from_argmatches(args);
+	let (list, line) = if search_terms.terms.is_empty() {
+		(None, None)
+	} else {
+		let list = file_type.load(args)?;
+		let found: Vec<usize> = find_results(&search_terms, &list)
+			.into_iter()
+			.map(|i| i.line_number())
+			.collect();
+		let line = match found[..] {
+			[] => {
+				outputter.write_status(String::from("No matching task."));
+				return Ok(ExitStatus::NoMatch);
+			}
+			[num] => Some(num),
+			_ if *args.get_one::<bool>("first").unwrap() => Some(found[0]),
+			_ => {
+				outputter.write_error(format!(
+					"{} tasks matched; be more specific, or use --first.",
+					found.len()
+				));
+				return Ok(ExitStatus::Error);
+			}
+		};
+		(Some(list), line)
+	};
+
+	if *args.get_one::<bool>("inline").unwrap() {
+		return execute_inline(args, list.unwrap(), line.unwrap(), outputter);
+	}
+
 	let editor =
 		editor().unwrap_or_else(|_| panic!("Could not determine EDITOR"));
-	let filename = FileType::TodoTxt.filename(args);
-	open_file_in_editor(editor, filename).unwrap();
+	open_file_in_editor(editor, filename, line).unwrap();
+
+	Ok(ExitStatus::Success)
+}
+
+/// Edit the task on a line of a list in place, reading its replacement from
+/// STDIN, and save the list.
+#[cfg(not(tarpaulin_include))]
+fn execute_inline(
+	args: &ArgMatches,
+	list: List,
+	num: usize,
+	mut outputter: Outputter,
+) -> Result<ExitStatus, Error> {
+	let confirmation = ConfirmationStatus::from_argmatches(args);
+	let save_mode = SaveMode::from_argmatches(args);
+	let current = list.lines[num - 1].text.clone();
+	writeln!(outputter.io, "{}", current).expect("panik");
+
+	let replacement = if io::stdin().is_terminal() {
+		rustyline::Editor::<()>::new()
+			.readline_with_initial("Task: ", (&current, ""))
+			.ok()
+	} else {
+		read_replacement(&mut io::stdin().lock())
+	};
+	let replacement = match replacement {
+		Some(r) if r.trim() != current.trim() => r,
+		_ => {
+			outputter.write_status(String::from("No changes made."));
+			return Ok(ExitStatus::Success);
+		}
+	};
+
+	let item = match parse_replacement(&replacement) {
+		Ok(item) => item,
+		Err(e) => {
+			outputter.write_error(e);
+			return Ok(ExitStatus::Error);
+		}
+	};
+	let (done, would_have) = if item.is_some() {
+		(
+			format!("Replaced line {}.", num),
+			format!("replaced line {}", num),
+		)
+	} else if confirmation
+		.check(&mut outputter, "Delete task?", "Deleting", "Keeping", None)
+		.is_yes()
+	{
+		(
+			format!("Deleted line {}.", num),
+			format!("deleted line {}", num),
+		)
+	} else {
+		outputter.write_status(String::from("No changes made."));
+		return Ok(ExitStatus::Success);
+	};
+
+	let before = list.serialize();
+	let cfg = EditActionConfig {
+		line: num,
+		replacement: item,
+	};
+	let outcome = run(list, &cfg)?;
+	if save_mode.save_with_diff(&outcome.list, &before, &mut outputter)? {
+		outputter.write_status(save_mode.status(done, would_have));
+	} else {
+		outputter.write_status(String::from("No changes made."));
+	}
+
+	Ok(ExitStatus::Success)
+}
+
+/// Read a replacement for a task from input, without its line ending.
+///
+/// Returns None at the end of the input.
+pub fn read_replacement(input: &mut dyn BufRead) -> Option<String> {
+	let mut text = String::new();
+	match input.read_line(&mut text) {
+		Ok(0) | Err(_) => None,
+		Ok(_) => Some(text.trim_end_matches(['\n', '\r']).to_string()),
+	}
+}
+
+/// Check the replacement for a task, giving None if it's empty, meaning the
+/// task should be deleted.
+///
+/// Besides text which isn't a task at all, mistakes which `tada lint`
+/// reports as errors are rejected.
+pub fn parse_replacement(text: &str) -> Result<Option<Item>, String> {
+	if text.trim().is_empty() {
+		return Ok(None);
+	}
+	let item = Item::try_parse(text).map_err(|e| e.to_string())?;
+	let (_, notes) = item.fixup_except(&[]);
+	for note in notes {
+		if let (Severity::Error, message) = describe(&note) {
+			return Err(format!("The task {}", message));
+		}
+	}
+	Ok(Some(item))
+}
+
+/// Config object for editing a task in place, like `edit --inline`.
+#[derive(Clone, Default)]
+pub struct EditActionConfig {
+	/// The line number of the task to edit.
+	pub line: usize,
+	/// The task to replace it with, or None to delete it.
+	pub replacement: Option<Item>,
 }
 
-/// Figure out the editor to use based on the environment.
+/// Edit a task in a list in place, without saving it.
+///
+/// Nothing is changed if the line isn't in the list, or the replacement is
+/// the same as the task already there.
+pub fn run(list: List, cfg: &EditActionConfig) -> Result<ActionOutcome, Error> {
+	let current = cfg
+		.line
+		.checked_sub(1)
+		.and_then(|idx| list.lines.get(idx));
+	let unchanged = match (current, &cfg.replacement) {
+		(None, _) => true,
+		(Some(l), Some(item)) => l.text == format!("{}", item),
+		(Some(_), None) => false,
+	};
+	if unchanged {
+		return Ok(ActionOutcome::new(list, 0, String::new()));
+	}
+	let new_list = edit_line_in_list(list, cfg.line, cfg.replacement.clone());
+	let verb = if cfg.replacement.is_some() {
+		"replaced"
+	} else {
+		"deleted"
+	};
+	let message =
+		format!("{} line {} of {}", verb, cfg.line, new_list.display_name());
+	Ok(ActionOutcome::new(new_list, 1, message))
+}
+
+/// Given a list, replaces the line at a line number with a task, or removes
+/// it if there is no task.
+///
+/// A removed line is left blank so that other tasks keep their numbers.
+pub fn edit_line_in_list(list: List, num: usize, item: Option<Item>) -> List {
+	let mut new_list = list;
+	match item {
+		Some(item) => {
+			new_list.replace_line(num, Line::from_item(item));
+		}
+		None => {
+			new_list.remove_line(num, true);
+		}
+	}
+	new_list
+}
+
+/// Figure out the editor to use based on the config file and environment.
+///
+/// The `editor` setting in the config file wins, then `VISUAL`, then
+/// `EDITOR`. Empty values are skipped, and if none is set, `vi` is used.
 pub fn editor() -> Result<String, env::VarError> {
-	match env::var("EDITOR") {
-		Ok(result) => return Ok(result),
-		Err(env::VarError::NotPresent) => {}
-		Err(error) => return Err(error),
+	if let Some(result) = Config::load().edit.editor() {
+		return Ok(result);
+	}
+	for var in ["VISUAL", "EDITOR"] {
+		match env::var(var) {
+			Ok(result) if !result.trim().is_empty() => return Ok(result),
+			Ok(_) | Err(env::VarError::NotPresent) => {}
+			Err(error) => return Err(error),
+		}
 	}
 	Ok("vi".to_string())
 }
 
-/// Open a file in an editor.
+/// The arguments to pass an editor so it opens a file, at a line if given.
+///
+/// `line_flag` is a template like `+{line}` or `--goto {file}:{line}`,
+/// split on whitespace. If it doesn't mention `{file}`, the file name is
+/// added after it. With no template, VS Code gets `--goto FILE:LINE` and
+/// other editors get `+LINE FILE`, which vi, Emacs, nano and others accept.
+pub fn editor_args(
+	editor: &str,
+	filename: &str,
+	line: Option<usize>,
+	line_flag: Option<&str>,
+) -> Vec<String> {
+	let line = match line {
+		Some(l) => l.to_string(),
+		None => return Vec::from([filename.to_string()]),
+	};
+	let template = match line_flag {
+		Some(t) => t.to_string(),
+		None => {
+			let program = Path::new(editor)
+				.file_name()
+				.map(|n| n.to_string_lossy().to_string())
+				.unwrap_or_default();
+			match program.as_str() {
+				"code" | "code-insiders" | "codium" => {
+					String::from("--goto {file}:{line}")
+				}
+				_ => String::from("+{line}"),
+			}
+		}
+	};
+	let mut args: Vec<String> = template
+		.split_whitespace()
+		.map(|a| {
+			a.replace("{line}", &line)
+				.replace("{file}", filename)
+		})
+		.collect();
+	if !template.contains("{file}") {
+		args.push(filename.to_string());
+	}
+	args
+}
+
+/// Open a file in an editor, at a particular line if one is given.
+///
+/// The editor may include arguments, like `code --wait`. See `editor_args`
+/// for how the line is passed, which can be changed by setting
+/// `EDITOR_LINE_FLAG`.
 pub fn open_file_in_editor(
 	editor: String,
 	filename: String,
+	line: Option<usize>,
 ) -> Result<process::ExitStatus, io::Error> {
-	let mut cmd = process::Command::new(editor);
-	let cmd = cmd.args([filename]);
+	let mut words = editor.split_whitespace();
+	let program = words.next().unwrap_or("vi");
+	let line_flag = env::var("EDITOR_LINE_FLAG").ok();
+	let mut cmd = process::Command::new(program);
+	let cmd = cmd.args(words).args(editor_args(
+		program,
+		&filename,
+		line,
+		line_flag.as_deref(),
+	));
 	let mut child = cmd.spawn()?;
 	child.wait()
 }
@@ -47,6 +353,7 @@ pub fn open_file_in_editor(
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use std::fs;
 	use tempfile::tempdir;
 
 	#[test]
@@ -56,9 +363,17 @@ mod tests {
 
 	#[test]
 	fn test_editor() {
+		env::remove_var("VISUAL");
 		env::set_var("EDITOR", "cat");
 		assert!(editor().is_ok());
 		assert_eq!("cat", editor().unwrap());
+
+		env::set_var("VISUAL", "code --wait");
+		assert_eq!("code --wait", editor().unwrap());
+
+		env::set_var("VISUAL", "");
+		assert_eq!("cat", editor().unwrap());
+		env::remove_var("VISUAL");
 	}
 
 	#[test]
@@ -71,16 +386,175 @@ mod tests {
 			.to_string();
 
 		// `cat FILE` with a non-existing file.
-		let exitcode =
-			open_file_in_editor(String::from("cat"), test_filename.clone())
-				.unwrap();
+		let exitcode = open_file_in_editor(
+			String::from("cat"),
+			test_filename.clone(),
+			None,
+		)
+		.unwrap();
 		assert!(!exitcode.success());
 
 		// `cat FILE` with an existing file.
-		List::new().to_filename(test_filename.clone());
-		let exitcode =
-			open_file_in_editor(String::from("cat"), test_filename.clone())
-				.unwrap();
+		List::new()
+			.to_filename(test_filename.clone())
+			.unwrap();
+		let exitcode = open_file_in_editor(
+			String::from("cat"),
+			test_filename.clone(),
+			None,
+		)
+		.unwrap();
+		assert!(exitcode.success());
+
+		// `cat -n FILE`, with an editor which takes arguments.
+		let exitcode = open_file_in_editor(
+			String::from("cat -n"),
+			test_filename.clone(),
+			None,
+		)
+		.unwrap();
 		assert!(exitcode.success());
 	}
+
+	#[cfg(unix)]
+	#[test]
+	fn test_open_file_in_editor_args() {
+		use std::os::unix::fs::PermissionsExt;
+
+		// A fake editor which writes each argument it gets on its own line.
+		let dir = tempdir().unwrap();
+		let script = dir.path().join("fake-editor");
+		let log = dir.path().join("args.txt");
+		fs::write(
+			&script,
+			format!("#!/bin/sh\nprintf '%s\\n' \"$@\" > '{}'\n", log.display()),
+		)
+		.unwrap();
+		fs::set_permissions(&script, fs::Permissions::from_mode(0o755))
+			.unwrap();
+
+		let exitcode = open_file_in_editor(
+			format!("{}  --wait -n", script.display()),
+			String::from("todo.txt"),
+			None,
+		)
+		.unwrap();
+		assert!(exitcode.success());
+		assert_eq!("--wait\n-n\ntodo.txt\n", fs::read_to_string(&log).unwrap());
+	}
+
+	#[test]
+	fn test_read_replacement() {
+		let mut input = io::Cursor::new("(A) Foo\r\n\nBar");
+		assert_eq!(Some(String::from("(A) Foo")), read_replacement(&mut input));
+		assert_eq!(Some(String::new()), read_replacement(&mut input));
+		assert_eq!(Some(String::from("Bar")), read_replacement(&mut input));
+		assert_eq!(None, read_replacement(&mut input));
+	}
+
+	#[test]
+	fn test_parse_replacement() {
+		assert_eq!(
+			"(A) Foo due:2024-06-01",
+			parse_replacement(" (A) Foo due:2024-06-01 ")
+				.unwrap()
+				.unwrap()
+				.to_string()
+		);
+		assert!(parse_replacement("").unwrap().is_none());
+		assert!(parse_replacement("  ").unwrap().is_none());
+		assert_eq!(
+			Err(String::from("'# Foo' is a comment, so isn't a task")),
+			parse_replacement("# Foo")
+		);
+		assert_eq!(
+			Err(String::from("The task has `due=`, which should be `due:`.")),
+			parse_replacement("Foo due=2024-06-01")
+		);
+	}
+
+	#[test]
+	fn test_edit_line_in_list() {
+		let dir = tempdir().unwrap();
+		let filename = dir
+			.path()
+			.join("todo.txt")
+			.display()
+			.to_string();
+		fs::write(&filename, "(A) Foo\n\nBar +baz\nBat\n").unwrap();
+
+		let list = List::from_filename(filename.clone()).unwrap();
+		let item = parse_replacement("(B) Bar +quux").unwrap();
+		let list = edit_line_in_list(list, 3, item);
+		assert_eq!(3, list.item_at(3).unwrap().line_number());
+		list.save().unwrap();
+		assert_eq!(
+			"(A) Foo\n\n(B) Bar +quux\nBat\n",
+			fs::read_to_string(&filename).unwrap()
+		);
+
+		let list = List::from_filename(filename.clone()).unwrap();
+		let list = edit_line_in_list(list, 1, None);
+		assert_eq!(3, list.item_at(3).unwrap().line_number());
+		list.save().unwrap();
+		assert_eq!(
+			"\n\n(B) Bar +quux\nBat\n",
+			fs::read_to_string(&filename).unwrap()
+		);
+	}
+
+	#[test]
+	fn test_run() {
+		let list =
+			List::from_string(String::from("(A) Foo\nBar +baz\n")).unwrap();
+		let cfg = EditActionConfig {
+			line: 2,
+			replacement: parse_replacement("Bar +quux").unwrap(),
+		};
+		let outcome = run(list, &cfg).unwrap();
+		assert_eq!(1, outcome.count);
+		assert_eq!("(A) Foo\nBar +quux\n", outcome.list.serialize());
+
+		// The same task again, or a line which isn't there, changes nothing.
+		let outcome = run(outcome.list, &cfg).unwrap();
+		assert_eq!(0, outcome.count);
+		let cfg = EditActionConfig {
+			line: 9,
+			replacement: None,
+		};
+		let outcome = run(outcome.list, &cfg).unwrap();
+		assert_eq!(0, outcome.count);
+
+		let cfg = EditActionConfig {
+			line: 1,
+			replacement: None,
+		};
+		let outcome = run(outcome.list, &cfg).unwrap();
+		assert_eq!(1, outcome.count);
+		assert_eq!("\nBar +quux\n", outcome.list.serialize());
+	}
+
+	#[test]
+	fn test_editor_args() {
+		assert_eq!(
+			Vec::from(["todo.txt"]),
+			editor_args("vim", "todo.txt", None, None)
+		);
+		assert_eq!(
+			Vec::from(["+27", "todo.txt"]),
+			editor_args("/usr/bin/vim", "todo.txt", Some(27), None)
+		);
+		assert_eq!(
+			Vec::from(["--goto", "todo.txt:27"]),
+			editor_args("code", "todo.txt", Some(27), None)
+		);
+		assert_eq!(
+			Vec::from(["--line", "27", "todo.txt"]),
+			editor_args("kate", "todo.txt", Some(27), Some("--line {line}"))
+		);
+		assert_eq!(
+			Vec::from(["todo.txt:27"]),
+			editor_args("subl", "todo.txt", Some(27), Some("{file}:{line}"))
+		);
+	}
 }
@@ -0,0 +1,73 @@
+//! Export tasks to other formats
+
+use crate::action::*;
+use crate::ics;
+use chrono::Utc;
+use clap::{Arg, ArgMatches, Command};
+use std::fs;
+use std::io::Write;
+
+/// Options for the `export` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("export");
+	let mut command = Command::new("export")
+		.about("Export tasks to another format")
+		.after_help(
+			"The only format so far is 'ics', which writes tasks with due \
+			dates as iCalendar to-dos, for use in calendar apps.\n\n\
+			Each to-do's UID comes from the task's `id:` tag if it has one, \
+			or else from its description, so calendar apps can recognise \
+			the same task when it is exported again.",
+		)
+		.arg(
+			Arg::new("format")
+				.value_name("FORMAT")
+				.value_parser(["ics"])
+				.required(true)
+				.help("The format to export to"),
+		)
+		.arg(
+			Arg::new("output")
+				.short('o')
+				.long("output")
+				.value_name("FILE")
+				.help("Write to a file instead of standard output"),
+		);
+
+	command = FileType::TodoTxt.add_args(command);
+
+	Action { name, command }
+}
+
+/// Execute the `export` subcommand.
+#[cfg(not(tarpaulin_include))]
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let list = FileType::TodoTxt.load(args)?;
+	let output = ics::calendar(&list.items(), Utc::now().naive_utc());
+
+	match args.get_one::<String>("output") {
+		Some(filename) => {
+			fs::write(filename, output).map_err(|e| Error::Unwritable {
+				url: filename.clone(),
+				message: e.to_string(),
+			})?;
+		}
+		None => {
+			std::io::stdout()
+				.write_all(output.as_bytes())
+				.unwrap();
+		}
+	}
+
+	Ok(ExitStatus::Success)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("export"), get_action().name);
+	}
+}
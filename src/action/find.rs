@@ -1,8 +1,8 @@
 //! Search for a task
 
 use crate::action::*;
-use crate::util::*;
-use clap::{ArgMatches, Command};
+use clap::{Arg, ArgMatches, Command};
+use log::{log_enabled, Level};
 
 /// Options for the `find` subcommand.
 pub fn get_action() -> Action {
@@ -10,13 +10,34 @@ pub fn get_action() -> Action {
 	let mut command = Command::new("find").about("Search for a task")
 		.after_help(
 			"Multiple search terms may be provided, which will be combined with an 'AND' operator.\n\n\
-			Searches are case-insensitive."
+			Searches are case-insensitive.\n\n\
+			A term like key:value matches tasks with that key-value tag. Use key:* to match any value, \
+			or key:<value and key:>value to compare dates or numbers.\n\n\
+			Exits with status 0 if any tasks matched, 1 if none matched, and 2 on errors."
 		);
 
 	command = FileType::TodoTxt.add_args(command);
 	command = Outputter::add_args(command);
 	command = SearchTerms::add_args(command);
+	command = Filters::add_args(command);
 	command = SortOrder::add_args(command, default_sort_order());
+	command = Grouping::add_args(command);
+	command = FileType::add_all_profiles_arg(command);
+	command = command
+		.arg(
+			Arg::new("count")
+				.num_args(0)
+				.long("count")
+				.help("Only print the number of matching tasks")
+				.conflicts_with("quiet"),
+		)
+		.arg(
+			Arg::new("quiet")
+				.num_args(0)
+				.short('q')
+				.long("quiet")
+				.help("Print nothing; just set the exit status"),
+		);
 
 	Action { name, command }
 }
@@ -28,27 +49,39 @@ pub fn default_sort_order() -> SortOrder {
 
 /// Execute the `find` subcommand.
 #[cfg(not(tarpaulin_include))]
-pub fn execute(args: &ArgMatches) {
-	let list = FileType::TodoTxt.load(args);
-
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
 	let mut outputter = Outputter::from_argmatches(args);
-	outputter.line_number_digits = list.lines.len().to_string().len();
-
 	let search_terms = SearchTerms::from_argmatches(args);
-	let results = find_results(&search_terms, &list);
-	let sort_order = SortOrder::from_argmatches(args, default_sort_order());
+	let lists = FileType::TodoTxt.load_all_matching(args, &search_terms)?;
+	outputter.fit_to_lists(&lists);
+	let list = ProfileList::merge(&lists);
+	outputter.note_blocked(&list);
 
-	for i in sort_order.sort_items(results).iter() {
-		outputter.write_item(i);
+	let results = Filters::from_argmatches(args)
+		.apply(&list, find_results(&search_terms, &list));
+	let status = ExitStatus::from_count(results.len());
+
+	if *args.get_one::<bool>("quiet").unwrap() {
+		return Ok(status);
+	}
+	if *args.get_one::<bool>("count").unwrap() {
+		outputter.write_data(results.len().to_string());
+		return Ok(status);
 	}
+
+	let sort_order = SortOrder::from_argmatches(args, default_sort_order());
+	let grouping = Grouping::from_argmatches(args);
+	grouping.write_items(results, &sort_order, &mut outputter);
+
+	Ok(status)
 }
 
 /// Execute the `find` subcommand via shortcut.
 #[cfg(not(tarpaulin_include))]
-pub fn execute_shortcut(term: &str) {
+pub fn execute_shortcut(term: &str) -> Result<ExitStatus, Error> {
 	let cmd = get_action().command;
 	let matches = cmd.get_matches_from(vec!["find", term]);
-	execute(&matches);
+	execute(&matches)
 }
 
 /// Given search terms and a list, returns items from the list matching the search terms.
@@ -84,12 +117,25 @@ pub fn find_results<'a, 'b: 'a>(
 ) -> Vec<&'a Item> {
 	let mut results = list.items();
 	for term in &search_terms.terms {
+		let before = log_enabled!(Level::Trace).then(|| results.clone());
 		results = match term.chars().next() {
 			Some('@') => find_items_by_context(term, results),
 			Some('+') => find_items_by_tag(term, results),
-			Some('#') => find_items_by_line_number(term, results),
+			_ if parse_id_reference(term).is_some() => {
+				find_items_by_id(term, results)
+			}
+			_ if parse_line_reference(term).is_some() => {
+				find_items_by_line_number(term, results)
+			}
+			_ if parse_kv_term(term).is_some() => {
+				find_items_by_kv(term, results)
+			}
 			_ => find_items_by_string(term, results),
 		};
+		for item in before.unwrap_or_default() {
+			let matched = results.iter().any(|r| std::ptr::eq(*r, item));
+			log_term_match(item, term, matched);
+		}
 	}
 	results
 }
@@ -97,6 +143,7 @@ pub fn find_results<'a, 'b: 'a>(
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use tempfile::tempdir;
 
 	#[test]
 	fn test_get_action() {
@@ -167,4 +214,138 @@ mod tests {
 		let t = SearchTerms::from_string("baz");
 		assert_eq!("", List::from_items(find_results(&t, &list)).serialize());
 	}
+
+	#[test]
+	fn test_find_results_with_hash() {
+		let list = List::from_string(String::from(
+			"Fix bug#42 crash
+Fix bug#4
+",
+		))
+		.unwrap();
+
+		// `bug` isn't a profile, so this is text to look for.
+		let t = SearchTerms::from_string("bug#42");
+		assert_eq!(
+			"Fix bug#42 crash\n",
+			List::from_items(find_results(&t, &list)).serialize(),
+		);
+		assert!(t.item_matches(list.items()[0]));
+		assert!(!t.item_matches(list.items()[1]));
+		assert!(t.line_might_match("Fix bug#42 crash"));
+		assert!(!t.line_might_match("Fix bug#4"));
+
+		let t = SearchTerms::from_string("#2");
+		assert_eq!(
+			"Fix bug#4\n",
+			List::from_items(find_results(&t, &list)).serialize(),
+		);
+	}
+
+	#[test]
+	fn test_find_results_by_id() {
+		let list =
+			List::from_string(String::from("Foo id:ab3f\nBar id:ab3f0\nBaz\n"))
+				.unwrap();
+		for term in ["%ab3f", "id:ab3f"] {
+			let t = SearchTerms::from_string(term);
+			assert_eq!(
+				"Foo id:ab3f\n",
+				List::from_items(find_results(&t, &list)).serialize(),
+			);
+			assert!(t.item_matches(list.items()[0]));
+			assert!(!t.item_matches(list.items()[1]));
+		}
+	}
+
+	#[test]
+	fn test_find_results_by_kv() {
+		let list = List::from_string(String::from(
+			"Foo ref:x1 due:2024-06-01
+			Bar due:2024-06-10
+			Baz see http://example.com
+",
+		))
+		.unwrap();
+		let cases = [
+			("ref:*", "Foo ref:x1 due:2024-06-01\n"),
+			("ref:X1", "Foo ref:x1 due:2024-06-01\n"),
+			("due:>2024-06-05", "Bar due:2024-06-10\n"),
+			("due:<2024-06-05", "Foo ref:x1 due:2024-06-01\n"),
+			("http://example", "Baz see http://example.com\n"),
+		];
+		for (term, expected) in cases {
+			let t = SearchTerms::from_string(term);
+			assert_eq!(
+				expected,
+				List::from_items(find_results(&t, &list)).serialize(),
+				"{}",
+				term
+			);
+			let matched: Vec<bool> = list
+				.items()
+				.iter()
+				.map(|i| t.item_matches(i))
+				.collect();
+			assert_eq!(1, matched.iter().filter(|m| **m).count(), "{}", term);
+		}
+	}
+
+	#[test]
+	fn test_grouped_find_output() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let list = List::from_string(String::from(
+			"Foo @work due:1970-01-01\n\
+			Bar @home\n\
+			Foo baz @work @home\n\
+			Foo bat\n",
+		))
+		.unwrap();
+		let t = SearchTerms::from_string("foo");
+		let results = find_results(&t, &list);
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		Grouping::Context.write_items(
+			results.clone(),
+			&SortOrder::Original,
+			&mut o,
+		);
+		let got_output = fs::read_to_string(buffer_filename.clone()).unwrap();
+		assert_eq!(
+			"# @home\n\
+			\x20 (?) Foo baz @work @home\n\
+			\n\
+			# @work\n\
+			\x20 (?) Foo @work due:1970-01-01\n\
+			\x20 (?) Foo baz @work @home\n\
+			\n\
+			# No context\n\
+			\x20 (?) Foo bat\n\
+			\n",
+			got_output
+		);
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		Grouping::Urgency.write_items(results, &SortOrder::Original, &mut o);
+		let got_output = fs::read_to_string(buffer_filename).unwrap();
+		assert_eq!(
+			"# Overdue\n\
+			\x20 (?) Foo @work due:1970-01-01\n\
+			\n\
+			# Soon\n\
+			\x20 (?) Foo baz @work @home\n\
+			\x20 (?) Foo bat\n\
+			\n",
+			got_output
+		);
+	}
 }
@@ -0,0 +1,154 @@
+//! Import tasks from other todo list managers
+
+use crate::action::*;
+use crate::import::{Format, Import, ImportOptions};
+use crate::list::{Line, List};
+use clap::{Arg, ArgMatches, Command};
+use std::fs;
+
+/// Options for the `import` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("import");
+	let mut command = Command::new("import")
+		.about("Import tasks from another todo list manager")
+		.after_help(
+			"The only format so far is 'todoist', for the JSON from Todoist's \
+			sync API. Projects become +tags, labels become @contexts, \
+			priorities p1 to p4 become (A) to (D), and due dates become due: \
+			tags.\n\n\
+			Imported tasks are added to the end of todo.txt. Comments, \
+			attachments, and other details with no place in todo.txt are \
+			left out, and counted in the summary.",
+		)
+		.arg(
+			Arg::new("format")
+				.value_name("FORMAT")
+				.value_parser(["todoist"])
+				.required(true)
+				.help("The format to import from"),
+		)
+		.arg(
+			Arg::new("input")
+				.value_name("INPUT")
+				.required(true)
+				.help("The file to import"),
+		)
+		.arg(
+			Arg::new("include-done")
+				.num_args(0)
+				.long("include-done")
+				.help("Also import completed tasks"),
+		);
+
+	command = FileType::TodoTxt.add_args(command);
+	command = Outputter::add_args(command);
+	command = SaveMode::add_args(command);
+
+	Action { name, command }
+}
+
+/// Execute the `import` subcommand.
+#[cfg(not(tarpaulin_include))]
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches(args);
+	let save_mode = SaveMode::from_argmatches(args);
+	let format =
+		Format::from_string(args.get_one::<String>("format").unwrap()).unwrap();
+	let input = args.get_one::<String>("input").unwrap();
+	let options = ImportOptions {
+		include_done: *args.get_one::<bool>("include-done").unwrap(),
+	};
+
+	let text = fs::read_to_string(input).map_err(|e| Error::Unreadable {
+		label: input.clone(),
+		message: e.to_string(),
+	})?;
+	let import =
+		format
+			.parse(&text, &options)
+			.map_err(|e| Error::Unreadable {
+				label: input.clone(),
+				message: e.to_string(),
+			})?;
+
+	let lines = write_import(&import, &save_mode, &mut outputter);
+	if save_mode == SaveMode::Save && !lines.is_empty() {
+		let filename = FileType::TodoTxt.filename(args)?;
+		List::append_lines_to_url(filename, lines.iter().collect())?;
+	}
+
+	Ok(ExitStatus::Success)
+}
+
+/// Show the imported tasks and a summary of what was left out, returning
+/// the lines to add to the todo list.
+pub fn write_import(
+	import: &Import,
+	save_mode: &SaveMode,
+	outputter: &mut Outputter,
+) -> Vec<Line> {
+	let mut lines = Vec::new();
+	for i in &import.items {
+		outputter.write_item(i);
+		lines.push(Line::from_item(i.clone()));
+	}
+
+	let count = import.items.len();
+	outputter.write_status(save_mode.status(
+		format!("Imported {} tasks.", count),
+		format!("imported {} tasks", count),
+	));
+	if import.skipped_done > 0 {
+		outputter.write_notice(format!(
+			"Skipped {} completed tasks; use --include-done to import them.",
+			import.skipped_done
+		));
+	}
+	if let Some(dropped) = import.dropped_summary() {
+		outputter.write_notice(format!("Left out {}.", dropped));
+	}
+
+	lines
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::item::Item;
+	use std::fs;
+	use tempfile::tempdir;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("import"), get_action().name);
+	}
+
+	#[test]
+	fn test_write_import() {
+		let mut import = Import::default();
+		import.items.push(Item::parse("(A) Foo +Bar"));
+		import.skipped_done = 2;
+		import.drop_field("comment");
+
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir.path().join("buffer.txt");
+		let mut o = Outputter::new(80);
+		o.colour = false;
+		let buffer = fs::File::create(buffer_filename.clone()).unwrap();
+		o.msg_io = Box::new(buffer.try_clone().unwrap());
+		o.io = Box::new(buffer);
+
+		let lines = write_import(&import, &SaveMode::Save, &mut o);
+		assert_eq!(1, lines.len());
+		assert_eq!("(A) Foo +Bar", lines[0].text);
+
+		let got = fs::read_to_string(buffer_filename).unwrap();
+		assert_eq!(
+			"  (A) Foo +Bar\n\
+			Imported 1 tasks.\n\
+			Skipped 2 completed tasks; use --include-done to import them.\n\
+			Left out 1 comment.\n",
+			got
+		);
+	}
+}
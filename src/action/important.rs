@@ -17,6 +17,8 @@ pub fn get_action() -> Action {
 	command = Outputter::add_args(command);
 	command = OutputCount::add_args(command);
 	command = SortOrder::add_args(command, default_sort_order());
+	command = Grouping::add_args(command);
+	command = SearchTerms::add_optional_args(command);
 	Action { name, command }
 }
 
@@ -27,8 +29,9 @@ pub fn default_sort_order() -> SortOrder {
 
 /// Execute the `important` subcommand.
 #[cfg(not(tarpaulin_include))]
-pub fn execute(args: &ArgMatches) {
-	execute_simple_list_action(args, default_sort_order());
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let filter = SearchTerms::from_argmatches(args);
+	execute_simple_list_action(args, default_sort_order(), &filter)
 }
 
 #[cfg(test)]
@@ -44,4 +47,19 @@ mod tests {
 	fn test_default_sort_order() {
 		assert_eq!(SortOrder::Importance, default_sort_order());
 	}
+
+	#[test]
+	fn test_same_args_as_urgent_and_quick() {
+		let arg_ids = |a: Action| {
+			a.command
+				.get_arguments()
+				.map(|arg| arg.get_id().to_string())
+				.collect::<Vec<String>>()
+		};
+		let ids = arg_ids(get_action());
+		assert!(ids.contains(&String::from("show-lines")));
+		assert!(ids.contains(&String::from("max-width")));
+		assert_eq!(ids, arg_ids(crate::action::urgent::get_action()));
+		assert_eq!(ids, arg_ids(crate::action::quick::get_action()));
+	}
 }
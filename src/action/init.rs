@@ -0,0 +1,261 @@
+//! Create a new todo list
+
+use crate::action::add::{process_line, AddActionConfig};
+use crate::action::*;
+use crate::list::Line;
+use crate::template;
+use clap::{Arg, ArgMatches, Command};
+use std::path::Path;
+
+/// Options for the `init` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("init");
+	let mut command = Command::new("init")
+		.about("Create a new todo list")
+		.after_help(
+			"Creates todo.txt and done.txt wherever other commands would \
+			look for them. With --local, they are created in the current \
+			directory.\n\n\
+			Existing files are not overwritten unless --force is given.",
+		)
+		.arg(Arg::new("task").help("A first task to add to the list"))
+		.arg(
+			Arg::new("force")
+				.num_args(0)
+				.long("force")
+				.help("Overwrite existing files"),
+		)
+		.arg(
+			Arg::new("empty")
+				.num_args(0)
+				.long("empty")
+				.help("Don't add explanatory comments"),
+		);
+
+	command = FileType::TodoTxt.add_args(command);
+	command = FileType::DoneTxt.add_args(command);
+	command = Outputter::add_args_minimal(command);
+
+	Action { name, command }
+}
+
+/// Execute the `init` subcommand.
+#[cfg(not(tarpaulin_include))]
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches_minimal(args);
+	let cwd = env::current_dir().unwrap();
+	let (todo_filename, done_filename) = init_paths(args, &cwd)?;
+	let force = *args.get_one::<bool>("force").unwrap();
+	let empty = *args.get_one::<bool>("empty").unwrap();
+	let task = args
+		.get_one::<String>("task")
+		.map(|t| t.as_str());
+
+	init_files(&todo_filename, &done_filename, force, empty, task)?;
+
+	outputter.write_status(format!("Created {}", todo_filename));
+	outputter.write_status(format!("Created {}", done_filename));
+
+	Ok(ExitStatus::Success)
+}
+
+/// Determine where the new todo.txt and done.txt should go.
+///
+/// With `--local`, this is always the given directory, even if there are
+/// lists in its parents. Otherwise the same rules as `FileType::filename`.
+pub fn init_paths(
+	args: &ArgMatches,
+	cwd: &Path,
+) -> Result<(String, String), Error> {
+	if *args.get_one::<bool>("local").unwrap_or(&false) {
+		return Ok((
+			cwd.join("todo.txt").display().to_string(),
+			cwd.join("done.txt").display().to_string(),
+		));
+	}
+	Ok((
+		FileType::TodoTxt.filename(args)?,
+		FileType::DoneTxt.filename(args)?,
+	))
+}
+
+/// Write a new todo.txt and done.txt, optionally with a first task.
+///
+/// Fails without writing anything if either file exists, unless `force`.
+/// Missing directories, like a `TODO_DIR` which doesn't exist yet, are
+/// created.
+pub fn init_files(
+	todo_filename: &str,
+	done_filename: &str,
+	force: bool,
+	empty: bool,
+	task: Option<&str>,
+) -> Result<(), Error> {
+	if !force {
+		for f in [todo_filename, done_filename] {
+			if List::from_url(String::from(f)).is_ok() {
+				return Err(Error::AlreadyExists(String::from(f)));
+			}
+		}
+	}
+
+	for f in [todo_filename, done_filename] {
+		if let Some(dir) = List::local_path(f)
+			.as_deref()
+			.and_then(Path::parent)
+		{
+			fs::create_dir_all(dir).map_err(|e| Error::Unwritable {
+				url: String::from(f),
+				message: e.to_string(),
+			})?;
+		}
+	}
+
+	initial_todo_list(empty, task).to_url(String::from(todo_filename))?;
+	template::done_list(empty).to_url(String::from(done_filename))?;
+	Ok(())
+}
+
+/// A new todo list, optionally with a first task.
+pub fn initial_todo_list(empty: bool, task: Option<&str>) -> List {
+	let mut list = template::todo_list(empty);
+	if let Some(t) = task {
+		if !empty {
+			list.lines.push(Line::new_blank());
+		}
+		let mut cfg = AddActionConfig {
+			quiet: true,
+			..AddActionConfig::new()
+		};
+		// Can't conflict, as the config doesn't set a priority.
+		let line = process_line(t, &mut cfg).unwrap();
+		list.lines.push(line);
+	}
+	list
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::config::HintsConfig;
+	use tempfile::tempdir;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("init"), get_action().name);
+	}
+
+	#[test]
+	fn test_init_paths() {
+		let dir = tempdir().unwrap();
+		let todo = dir.path().join("t.txt").display().to_string();
+		let done = dir.path().join("d.txt").display().to_string();
+		let cmd = get_action().command;
+
+		let args = cmd.clone().get_matches_from(vec![
+			"init",
+			"--file",
+			&todo,
+			"--done-file",
+			&done,
+		]);
+		assert_eq!(
+			(todo.clone(), done.clone()),
+			init_paths(&args, dir.path()).unwrap()
+		);
+		assert_eq!(todo, FileType::TodoTxt.filename(&args).unwrap());
+		assert_eq!(done, FileType::DoneTxt.filename(&args).unwrap());
+
+		// --local takes precedence over --file, as in FileType::filename,
+		// but doesn't need an existing file.
+		let sub = dir.path().join("sub");
+		let args = cmd.get_matches_from(vec![
+			"init",
+			"--local",
+			"--file",
+			&todo,
+			"--done-file",
+			&done,
+		]);
+		assert_eq!(
+			(
+				sub.join("todo.txt").display().to_string(),
+				sub.join("done.txt").display().to_string(),
+			),
+			init_paths(&args, &sub).unwrap()
+		);
+	}
+
+	#[test]
+	fn test_init_files() {
+		let dir = tempdir().unwrap();
+		let todo = dir
+			.path()
+			.join("todo.txt")
+			.display()
+			.to_string();
+		let done = dir
+			.path()
+			.join("done.txt")
+			.display()
+			.to_string();
+
+		init_files(&todo, &done, false, false, None).unwrap();
+		let list = List::from_url(todo.clone()).unwrap();
+		assert!(list.items().is_empty());
+		assert_eq!(LineKind::Comment, list.lines[0].kind);
+		let list = List::from_url(done.clone()).unwrap();
+		assert_eq!(LineKind::Comment, list.lines[0].kind);
+
+		assert_eq!(
+			Err(Error::AlreadyExists(todo.clone())),
+			init_files(&todo, &done, false, true, Some("Foo"))
+		);
+
+		init_files(&todo, &done, true, true, Some("Foo")).unwrap();
+		let list = List::from_url(todo.clone()).unwrap();
+		assert_eq!(1, list.lines.len());
+		assert_eq!("Foo", list.items()[0].description());
+		assert!(List::from_url(done).unwrap().lines.is_empty());
+
+		let sub = dir.path().join("new").join("dir");
+		let todo = sub.join("todo.txt").display().to_string();
+		let done = sub.join("done.txt").display().to_string();
+		init_files(&todo, &done, false, true, None).unwrap();
+		assert!(sub.join("todo.txt").is_file());
+	}
+
+	#[test]
+	fn test_initial_todo_list() {
+		let list = initial_todo_list(false, Some("(B) Foo"));
+		let last = list.lines.last().unwrap();
+		assert_eq!(LineKind::Item, last.kind);
+		assert_eq!('B', last.item.as_ref().unwrap().priority());
+		assert!(last
+			.item
+			.as_ref()
+			.unwrap()
+			.creation_date()
+			.is_some());
+
+		let list = initial_todo_list(true, None);
+		assert!(list.lines.is_empty());
+	}
+
+	#[test]
+	fn test_fresh_list_has_no_warnings() {
+		let hints = HintsConfig::default();
+		for list in [
+			initial_todo_list(false, None),
+			initial_todo_list(false, Some("Foo")),
+		] {
+			assert!(housekeeping_warnings(&list, &hints).is_empty());
+		}
+
+		// The first task added goes near the top.
+		let mut list = initial_todo_list(false, None);
+		list.push_item(Item::parse("Foo"));
+		assert!(list.lines.len() <= 3);
+		assert!(housekeeping_warnings(&list, &hints).is_empty());
+	}
+}
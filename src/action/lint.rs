@@ -0,0 +1,447 @@
+//! Check a todo list for mistakes
+
+use crate::action::*;
+use crate::config::LintConfig;
+use crate::item::{today, FixupNote, FixupRule};
+use clap::{Arg, ArgMatches, Command};
+
+/// Options for the `lint` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("lint");
+	let mut command = Command::new("lint")
+		.about("Check a todo list for mistakes")
+		.after_help(
+			"Reports errors, like lines which can't be read properly, \
+			syntax mistakes which `tada add` would have fixed, and dates \
+			which can't be understood. Also gives hints about duplicate \
+			tasks, finished tasks with no completion date, old tasks with \
+			no due date, and unknown key-value tags.\n\n\
+			Exits with status 2 if there are any errors, so it can be used \
+			in a pre-commit hook. Hints don't count; --no-hints hides them.\n\n\
+			Tag keys can be allowed, and the age of old tasks changed, in \
+			the [lint] section of the config file.",
+		);
+
+	command = FileType::TodoTxt.add_args(command);
+	command = Outputter::add_args_minimal(command);
+	command = command
+		.arg(
+			Arg::new("fix")
+				.num_args(0)
+				.long("fix")
+				.help("Fix syntax mistakes in place, where it's safe to"),
+		)
+		.arg(
+			Arg::new("pedantic")
+				.num_args(0)
+				.long("pedantic")
+				.help("Also suggest improvements, as `add` does"),
+		);
+	command = SaveMode::add_args(command);
+
+	Action { name, command }
+}
+
+/// How serious a problem found by `lint_list` is.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Severity {
+	/// Something is wrong, and `lint` should fail.
+	Error,
+	/// Something could be better.
+	Hint,
+}
+
+/// A problem found by `lint_list`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Finding {
+	pub line: usize,
+	pub severity: Severity,
+	pub message: String,
+}
+
+impl Finding {
+	fn error(line: usize, message: String) -> Self {
+		Self {
+			line,
+			severity: Severity::Error,
+			message,
+		}
+	}
+
+	fn hint(line: usize, message: String) -> Self {
+		Self {
+			line,
+			severity: Severity::Hint,
+			message,
+		}
+	}
+}
+
+/// Execute the `lint` subcommand.
+#[cfg(not(tarpaulin_include))]
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches_minimal(args);
+	let mut list = FileType::TodoTxt.load(args)?;
+	let config = Config::load().lint;
+	let pedantic = *args.get_one::<bool>("pedantic").unwrap();
+
+	if *args.get_one::<bool>("fix").unwrap() {
+		let save_mode = SaveMode::from_argmatches(args);
+		let before = list.serialize();
+		let (new_list, count) = fix_list(list);
+		list = new_list;
+		if count > 0 {
+			save_mode.save_with_diff(&list, &before, &mut outputter)?;
+		}
+		outputter.write_status(save_mode.status(
+			format!("Fixed {} tasks.", count),
+			format!("fixed {} tasks", count),
+		));
+	}
+
+	let findings = lint_list(&list, &config, pedantic);
+	let errors = write_findings(&findings, &mut outputter);
+	let hints = findings.len() - errors;
+	let name = list.display_name();
+	if findings.is_empty() {
+		outputter.write_status(format!("No problems found in {}.", name));
+	} else {
+		outputter.write_status(format!(
+			"Found {} errors and {} hints in {}.",
+			errors, hints, name
+		));
+	}
+
+	if errors > 0 {
+		Ok(ExitStatus::Error)
+	} else {
+		Ok(ExitStatus::Success)
+	}
+}
+
+/// Check each line of a list for problems, returning them in line order.
+///
+/// If `pedantic` is true, the hints `tada add` gives about improving new
+/// tasks are included too.
+pub fn lint_list(
+	list: &List,
+	config: &LintConfig,
+	pedantic: bool,
+) -> Vec<Finding> {
+	let mut findings: Vec<Finding> = list
+		.invalid_lines
+		.iter()
+		.map(|n| {
+			Finding::error(
+				*n,
+				String::from("isn't valid UTF-8, so bad bytes were replaced."),
+			)
+		})
+		.collect();
+
+	let keys = config.keys();
+	let today = today();
+	for line in &list.lines {
+		let item = match &line.item {
+			Some(i) if line.kind == LineKind::Item => i,
+			_ => continue,
+		};
+		let n = line.num;
+
+		let squash = |s: &str| {
+			s.split_whitespace()
+				.collect::<Vec<_>>()
+				.join(" ")
+		};
+		if squash(&line.text) != squash(&item.to_string()) {
+			findings.push(Finding::error(
+				n,
+				format!("can't be read properly; it reads as `{}`.", item),
+			));
+		}
+
+		let (_, notes) = item.fixup_except(&[]);
+		for note in notes {
+			match describe(&note) {
+				(Severity::Error, m) => findings.push(Finding::error(n, m)),
+				(Severity::Hint, m) if pedantic || is_date_hint(&note) => {
+					findings.push(Finding::hint(n, m))
+				}
+				_ => {}
+			}
+		}
+
+		if item.completion() && item.completion_date().is_none() {
+			findings.push(Finding::hint(
+				n,
+				String::from("is finished, but has no completion date."),
+			));
+		}
+
+		if let (false, None, Some(created)) =
+			(item.completion(), item.due_date(), item.creation_date())
+		{
+			let age = (today - created).num_days();
+			if age > config.max_age() {
+				findings.push(Finding::hint(
+					n,
+					format!("was added {} days ago, and has no due date.", age),
+				));
+			}
+		}
+
+		let mut unknown: Vec<&String> = item
+			.kv()
+			.keys()
+			.filter(|k| !keys.contains(k))
+			.collect();
+		unknown.sort();
+		for k in unknown {
+			findings
+				.push(Finding::hint(n, format!("has unknown tag `{}:`.", k)));
+		}
+	}
+
+	for (orig, dupe) in list.duplicates() {
+		findings.push(Finding::hint(
+			list.lines[dupe].num,
+			format!("duplicates line {}.", list.lines[orig].num),
+		));
+	}
+
+	findings.sort_by_key(|f| f.line);
+	findings
+}
+
+/// Describe a note from `Item::fixup` as a problem with a task.
+///
+/// Syntax mistakes which fixup would correct are errors; everything else
+/// is a hint.
+pub fn describe(note: &FixupNote) -> (Severity, String) {
+	match note {
+		FixupNote::MovedPriority { original, priority } => (
+			Severity::Error,
+			format!(
+				"has priority `{}`, which should be `({})` at the start.",
+				original, priority
+			),
+		),
+		FixupNote::SpacedContext { context } => (
+			Severity::Error,
+			format!(
+				"has context `@ {}`, which should be `@{}`.",
+				context, context
+			),
+		),
+		FixupNote::ExtraSpaces => {
+			(Severity::Error, String::from("has repeated spaces."))
+		}
+		FixupNote::TrailingDot { tag } => (
+			Severity::Error,
+			format!("has a full stop after tag `{}`.", tag),
+		),
+		FixupNote::EqualsForColon { key } => (
+			Severity::Error,
+			format!("has `{}=`, which should be `{}:`.", key, key),
+		),
+		FixupNote::NonIsoDate {
+			slot,
+			original,
+			corrected: None,
+		} => (
+			Severity::Error,
+			format!("has {} date `{}`, which isn't a date.", slot, original),
+		),
+		FixupNote::NonIsoDate {
+			slot,
+			original,
+			corrected: Some(_),
+		} => (
+			Severity::Hint,
+			format!(
+				"has {} date `{}`, which isn't in YYYY-MM-DD format.",
+				slot, original
+			),
+		),
+		FixupNote::MissingPriority => {
+			(Severity::Hint, String::from("has no priority."))
+		}
+		FixupNote::MissingDueDate => {
+			(Severity::Hint, String::from("has no due date."))
+		}
+		FixupNote::MissingSize => {
+			(Severity::Hint, String::from("has no size."))
+		}
+		FixupNote::TooLong => {
+			(Severity::Hint, String::from("has a long description."))
+		}
+		FixupNote::TooShort => {
+			(Severity::Hint, String::from("has a short description."))
+		}
+	}
+}
+
+/// Whether a note is about a natural language date, which is worth
+/// mentioning even when not being pedantic.
+fn is_date_hint(note: &FixupNote) -> bool {
+	matches!(note, FixupNote::NonIsoDate { .. })
+}
+
+/// Fix the syntax mistakes in each task which `lint_list` reports as
+/// errors.
+///
+/// Natural language dates are left alone, as rewriting them in a saved
+/// list would change their meaning. Returns the number of tasks changed.
+pub fn fix_list(list: List) -> (List, usize) {
+	let mut new_list = list;
+	let mut count = 0;
+	for mut item in new_list.iter_items_mut() {
+		let (fixed, _) = item.fixup_except(&[FixupRule::Dates]);
+		if fixed.to_string() != item.to_string() {
+			count += 1;
+			*item = fixed;
+		}
+	}
+	(new_list, count)
+}
+
+/// Write findings as errors and notices, each starting with its line
+/// number. Hints are left out if hints are turned off.
+///
+/// Returns the number of errors.
+pub fn write_findings(
+	findings: &[Finding],
+	outputter: &mut Outputter,
+) -> usize {
+	let mut errors = 0;
+	for f in findings {
+		let text = format!("Line {} {}", f.line, f.message);
+		match f.severity {
+			Severity::Error => {
+				errors += 1;
+				outputter.write_error(text);
+			}
+			Severity::Hint if outputter.hints => outputter.write_notice(text),
+			Severity::Hint => {}
+		}
+	}
+	errors
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::NaiveDate;
+	use tempfile::tempdir;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("lint"), get_action().name);
+	}
+
+	#[test]
+	fn test_lint_list() {
+		let list = List::from_string(String::from(
+			"(A) Fine task with a due date due:2024-06-10\n\
+			# comment\n\
+			Foo  bar due=2024-06-10\n\
+			x Finished without a date\n\
+			2023-01-01 Very old thing to do one day\n\
+			Foo at:09:30 ref:abc due:soonish\n\
+			Call mum due:2024-06-10\n\
+			Call mum due:2024-06-10\n\
+			(A) Later due:tomorrow\n",
+		))
+		.unwrap();
+		let friday = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+		let config = LintConfig {
+			keys: Some(vec![String::from("ref")]),
+			max_age: None,
+		};
+		let got = crate::item::with_today(friday, || {
+			lint_list(&list, &config, false)
+		});
+		let describe =
+			|f: &Finding| format!("{} {:?} {}", f.line, f.severity, f.message);
+		assert_eq!(
+			vec![
+				"3 Error has `due=`, which should be `due:`.",
+				"3 Error has repeated spaces.",
+				"4 Hint is finished, but has no completion date.",
+				"5 Hint was added 523 days ago, and has no due date.",
+				"6 Error has due date `soonish`, which isn't a date.",
+				"6 Hint has unknown tag `at:`.",
+				"8 Hint duplicates line 7.",
+				"9 Hint has due date `tomorrow`, which isn't in YYYY-MM-DD format.",
+			],
+			got.iter()
+				.map(describe)
+				.collect::<Vec<String>>()
+		);
+
+		let got =
+			crate::item::with_today(friday, || lint_list(&list, &config, true));
+		assert!(
+			got.contains(&Finding::hint(7, String::from("has no priority.")))
+		);
+	}
+
+	#[test]
+	fn test_lint_unreadable() {
+		let list =
+			List::from_string(String::from("x 2024-13-45 2024-01-01 Foo\n"))
+				.unwrap();
+		let got = lint_list(&list, &LintConfig::default(), false);
+		assert_eq!(Severity::Error, got[0].severity);
+		assert!(got[0]
+			.message
+			.starts_with("can't be read properly"));
+	}
+
+	#[test]
+	fn test_fix_list() {
+		let list = List::from_string(String::from(
+			"Foo  bar due=2024-06-10.\n(b) Baz @ home due:tomorrow\nFine\n",
+		))
+		.unwrap();
+		let (got, count) = fix_list(list);
+		assert_eq!(2, count);
+		assert_eq!(
+			"Foo bar due:2024-06-10\n(B) Baz @home due:tomorrow\nFine\n",
+			got.serialize()
+		);
+		let errors: Vec<Finding> =
+			lint_list(&got, &LintConfig::default(), false)
+				.into_iter()
+				.filter(|f| f.severity == Severity::Error)
+				.collect();
+		assert!(errors.is_empty());
+	}
+
+	#[test]
+	fn test_write_findings() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let findings = vec![
+			Finding::error(3, String::from("has repeated spaces.")),
+			Finding::hint(4, String::from("has no size.")),
+		];
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.msg_io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		assert_eq!(1, write_findings(&findings, &mut o));
+		o.hints = false;
+		assert_eq!(1, write_findings(&findings, &mut o));
+		assert_eq!(
+			"Line 3 has repeated spaces.\n\
+			Line 4 has no size.\n\
+			Line 3 has repeated spaces.\n",
+			fs::read_to_string(buffer_filename).unwrap()
+		);
+	}
+}
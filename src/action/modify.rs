@@ -0,0 +1,506 @@
+//! Modify the text of a task or tasks
+
+use crate::action::*;
+use crate::item::Item;
+use crate::list::List;
+use clap::{Arg, ArgMatches, Command};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+	/// Regular expression to match a priority at the start of some text.
+	static ref RE_LEADING_PRIORITY: Regex =
+		Regex::new(r"^[(]([A-Z])[)]\s+").unwrap();
+}
+
+/// Options for the `modify` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("modify");
+	let mut command = Command::new("modify")
+		.aliases(["mod"])
+		.about("Modify the text of a task or tasks")
+		.after_help(
+			"The --replace option takes an argument like 'old/new', replacing \
+			every occurrence of 'old' in the description with 'new'. Use '\\/' \
+			for a literal slash and '\\\\' for a literal backslash.\n\n\
+			If the text given to --prepend starts with a priority like '(A) ', \
+			it will be used to set the task's priority.",
+		);
+
+	command = FileType::TodoTxt.add_args(command);
+	command = Outputter::add_args(command);
+	command = SearchTerms::add_args(command);
+	command = ModifyActionConfig::add_args(command);
+	command = ConfirmationStatus::add_args(command);
+	command = SaveMode::add_args(command);
+
+	Action { name, command }
+}
+
+/// Config object for the `modify` action.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ModifyActionConfig {
+	pub append: Option<String>,
+	pub prepend: Option<String>,
+	pub replace: Option<Replacement>,
+	pub priority: Option<char>,
+	pub remove_due: bool,
+}
+
+/// An error raised when a modification would leave a task with no description.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmptyDescription;
+
+impl ModifyActionConfig {
+	/// Constructor.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add arguments to a clap Command for the `modify` action's options.
+	pub fn add_args(cmd: Command) -> Command {
+		cmd.arg(
+			Arg::new("append")
+				.long("append")
+				.value_name("TEXT")
+				.help("Append text to the description"),
+		)
+		.arg(
+			Arg::new("prepend")
+				.long("prepend")
+				.value_name("TEXT")
+				.help("Prepend text to the description"),
+		)
+		.arg(
+			Arg::new("replace")
+				.long("replace")
+				.value_name("OLD/NEW")
+				.value_parser(Replacement::parse)
+				.help("Replace text in the description"),
+		)
+		.arg(
+			Arg::new("set-priority")
+				.long("set-priority")
+				.value_name("LETTER")
+				.value_parser(parse_priority)
+				.help("Set the task's priority"),
+		)
+		.arg(
+			Arg::new("remove-due")
+				.num_args(0)
+				.long("remove-due")
+				.help("Remove the task's due date"),
+		)
+	}
+
+	/// Create a ModifyActionConfig from an appropriate ArgMatches.
+	pub fn from_argmatches(args: &ArgMatches) -> Self {
+		Self {
+			append: args.get_one::<String>("append").cloned(),
+			prepend: args.get_one::<String>("prepend").cloned(),
+			replace: args.get_one::<Replacement>("replace").cloned(),
+			priority: args.get_one::<char>("set-priority").copied(),
+			remove_due: *args.get_one::<bool>("remove-due").unwrap(),
+		}
+	}
+}
+
+/// A sed-style text replacement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replacement {
+	pub from: String,
+	pub to: String,
+}
+
+impl Replacement {
+	/// Parse a replacement like `old/new`.
+	///
+	/// A backslash escapes the next character, so `\/` is a literal slash and
+	/// `\\` is a literal backslash.
+	pub fn parse(s: &str) -> Result<Self, String> {
+		let mut parts: Vec<String> = Vec::from([String::new()]);
+		let mut chars = s.chars();
+		while let Some(c) = chars.next() {
+			match c {
+				'\\' => match chars.next() {
+					Some(e) => parts.last_mut().unwrap().push(e),
+					None => return Err(String::from("trailing backslash")),
+				},
+				'/' => parts.push(String::new()),
+				_ => parts.last_mut().unwrap().push(c),
+			}
+		}
+		if parts.len() != 2 {
+			return Err(String::from(
+				"expected exactly one unescaped '/' separating old and new text",
+			));
+		}
+		if parts[0].is_empty() {
+			return Err(String::from("text to replace cannot be empty"));
+		}
+		let to = parts.pop().unwrap();
+		let from = parts.pop().unwrap();
+		Ok(Self { from, to })
+	}
+
+	/// Apply the replacement to a string.
+	pub fn apply(&self, s: &str) -> String {
+		s.replace(&self.from, &self.to)
+	}
+}
+
+/// Execute the `modify` subcommand.
+#[cfg(not(tarpaulin_include))]
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches(args);
+	let list = FileType::TodoTxt.load(args)?;
+	outputter.fit_to_list(&list);
+
+	let search_terms = SearchTerms::from_argmatches(args);
+	let confirmation = ConfirmationStatus::from_argmatches(args);
+	let save_mode = SaveMode::from_argmatches(args);
+	let cfg = ModifyActionConfig::from_argmatches(args);
+
+	for item in refused_items(&list, &search_terms, &cfg) {
+		outputter.write_item(item);
+		outputter.write_error(String::from(
+			"Refusing to leave task with an empty description.",
+		));
+	}
+
+	let before = list.serialize();
+	let mut confirmer = Confirmer::new(
+		&mut outputter,
+		confirmation,
+		"Modify?",
+		"Modifying",
+		"Skipping",
+	);
+	let outcome = run(list, &search_terms, &cfg, &mut confirmer)?;
+	let (new_list, count) = (outcome.list, outcome.count);
+
+	if count > 0 {
+		save_mode.save_with_diff(&new_list, &before, &mut outputter)?;
+		let name = new_list.display_name();
+		outputter.write_status(save_mode.status(
+			format!("Modified {} tasks in {}.", count, name),
+			format!("modified {} tasks in {}", count, name),
+		));
+	} else {
+		outputter.write_status(String::from("No actions taken."));
+	}
+
+	outputter.write_warnings(housekeeping_warnings(
+		&new_list,
+		&Config::load().hints,
+	));
+
+	Ok(ExitStatus::from_count(count))
+}
+
+/// Apply the modifications in the config to an item.
+///
+/// Refuses to produce an item with an empty description.
+pub fn modify_item(
+	item: &Item,
+	cfg: &ModifyActionConfig,
+) -> Result<Item, EmptyDescription> {
+	let mut new = item.clone();
+	let mut description = new.description().to_string();
+
+	if let Some(r) = &cfg.replace {
+		description = r.apply(&description);
+	}
+
+	if let Some(p) = &cfg.prepend {
+		description = join_text(p, &description);
+		if let Some(caps) = RE_LEADING_PRIORITY.captures(&description.clone()) {
+			new.set_priority(caps[1].chars().next().unwrap());
+			description = String::from(&description[caps[0].len()..]);
+		}
+	}
+
+	if let Some(a) = &cfg.append {
+		description = join_text(&description, a);
+	}
+
+	new.set_description(description.trim().to_string());
+
+	if cfg.remove_due {
+		new.clear_kv("due");
+	}
+
+	if let Some(p) = cfg.priority {
+		new.set_priority(p);
+	}
+
+	if new.description().is_empty() {
+		return Err(EmptyDescription);
+	}
+
+	Ok(new)
+}
+
+/// Join two pieces of text, with a space between them unless either side
+/// already has one, so words and tags aren't run together.
+fn join_text(before: &str, after: &str) -> String {
+	if before.is_empty()
+		|| after.is_empty()
+		|| before.ends_with(char::is_whitespace)
+		|| after.starts_with(char::is_whitespace)
+	{
+		format!("{}{}", before, after)
+	} else {
+		format!("{} {}", before, after)
+	}
+}
+
+/// Run the `modify` action on the tasks in a list matching the search
+/// terms, without saving it.
+///
+/// The observer will be used to check whether each individual item should be
+/// altered. Tasks which would be left with an empty description are skipped;
+/// see `refused_items`.
+pub fn run(
+	list: List,
+	search_terms: &SearchTerms,
+	cfg: &ModifyActionConfig,
+	observer: &mut dyn MutationObserver,
+) -> Result<ActionOutcome, Error> {
+	let (new_list, count) =
+		modify_items_in_list(list, search_terms, cfg, observer);
+	let message =
+		format!("modified {} tasks in {}", count, new_list.display_name());
+	Ok(ActionOutcome::new(new_list, count, message))
+}
+
+/// The tasks in a list matching the search terms which the modifications
+/// would leave with an empty description, so won't be changed.
+pub fn refused_items<'a>(
+	list: &'a List,
+	search_terms: &SearchTerms,
+	cfg: &ModifyActionConfig,
+) -> Vec<&'a Item> {
+	list.items()
+		.into_iter()
+		.filter(|i| {
+			search_terms.item_matches(i) && modify_item(i, cfg).is_err()
+		})
+		.collect()
+}
+
+/// Given a list, set of search terms, and modifications, creates a copy of the list
+/// with all items matching the search terms modified in place.
+///
+/// The observer will be used to check whether each individual item should be
+/// altered. Items which wouldn't change, or would be left with an empty
+/// description, are left alone without asking.
+///
+/// Also returns the number of items changed.
+pub fn modify_items_in_list(
+	list: List,
+	search_terms: &SearchTerms,
+	cfg: &ModifyActionConfig,
+	observer: &mut dyn MutationObserver,
+) -> (List, usize) {
+	let modified = |item: &Item| {
+		if !search_terms.item_matches(item) {
+			return None;
+		}
+		modify_item(item, cfg)
+			.ok()
+			.filter(|new| format!("{}", new) != format!("{}", item))
+	};
+	let mut new_list = list;
+	let mut count = 0;
+	observer.will_change(
+		new_list
+			.items()
+			.into_iter()
+			.filter(|i| modified(i).is_some())
+			.count(),
+	);
+	for mut item in new_list.iter_items_mut() {
+		if let Some(new) = modified(&item) {
+			if observer.before_change(&item) {
+				count += 1;
+				observer.after_change(&item, &new);
+				*item = new;
+			}
+		}
+	}
+	(new_list, count)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::NaiveDate;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("modify"), get_action().name);
+	}
+
+	#[test]
+	fn test_replacement_parse() {
+		assert_eq!(
+			Ok(Replacement {
+				from: String::from("foo"),
+				to: String::from("bar"),
+			}),
+			Replacement::parse("foo/bar")
+		);
+		assert_eq!(
+			Ok(Replacement {
+				from: String::from("a/b"),
+				to: String::from("c\\d"),
+			}),
+			Replacement::parse("a\\/b/c\\\\d")
+		);
+		assert_eq!(
+			Ok(Replacement {
+				from: String::from("foo"),
+				to: String::new(),
+			}),
+			Replacement::parse("foo/")
+		);
+		assert!(Replacement::parse("foo").is_err());
+		assert!(Replacement::parse("foo/bar/baz").is_err());
+		assert!(Replacement::parse("/bar").is_err());
+		assert!(Replacement::parse("foo/bar\\").is_err());
+	}
+
+	#[test]
+	fn test_modify_item() {
+		let i = Item::parse("(B) 2000-01-01 Foo bar due:2000-02-01");
+
+		let cfg = ModifyActionConfig {
+			append: Some(String::from("@home")),
+			..ModifyActionConfig::new()
+		};
+		let got = modify_item(&i, &cfg).unwrap();
+		assert_eq!(
+			"(B) 2000-01-01 Foo bar due:2000-02-01 @home",
+			format!("{}", got)
+		);
+
+		let cfg = ModifyActionConfig {
+			prepend: Some(String::from("(A) Urgently")),
+			remove_due: true,
+			..ModifyActionConfig::new()
+		};
+		let got = modify_item(&i, &cfg).unwrap();
+		assert_eq!("(A) 2000-01-01 Urgently Foo bar", format!("{}", got));
+		assert_eq!(None, got.due_date());
+
+		let cfg = ModifyActionConfig {
+			replace: Some(Replacement::parse("bar/baz").unwrap()),
+			priority: Some('C'),
+			..ModifyActionConfig::new()
+		};
+		let got = modify_item(&i, &cfg).unwrap();
+		assert_eq!("(C) 2000-01-01 Foo baz due:2000-02-01", format!("{}", got));
+		assert_eq!(Some(Importance::C), got.importance());
+
+		// Text with its own space isn't given another.
+		let cfg = ModifyActionConfig {
+			prepend: Some(String::from("Urgently ")),
+			append: Some(String::from(" @home")),
+			..ModifyActionConfig::new()
+		};
+		let got = modify_item(&i, &cfg).unwrap();
+		assert_eq!(
+			"(B) 2000-01-01 Urgently Foo bar due:2000-02-01 @home",
+			format!("{}", got)
+		);
+
+		// Appending a due date after a tag keeps both.
+		let j = Item::parse("Foo +proj");
+		let cfg = ModifyActionConfig {
+			append: Some(String::from("due:2030-01-01")),
+			..ModifyActionConfig::new()
+		};
+		let got = modify_item(&j, &cfg).unwrap();
+		assert_eq!("Foo +proj due:2030-01-01", format!("{}", got));
+		assert!(got.has_tag("proj"));
+		assert_eq!(NaiveDate::from_ymd_opt(2030, 1, 1), got.due_date());
+
+		let cfg = ModifyActionConfig {
+			replace: Some(Replacement::parse("Foo bar/").unwrap()),
+			remove_due: true,
+			..ModifyActionConfig::new()
+		};
+		assert_eq!(Err(EmptyDescription), modify_item(&i, &cfg).map(|_| ()));
+	}
+
+	#[test]
+	fn test_modify_items_in_list() {
+		let source_list = List::from_string(String::from(
+			"Foo1\n\
+			\n\
+			Bar\n\
+			Foo2 due:2000-01-01\n",
+		))
+		.unwrap();
+
+		let (got, count) = modify_items_in_list(
+			source_list,
+			&SearchTerms::from_string("foo"),
+			&ModifyActionConfig {
+				append: Some(String::from("+x")),
+				remove_due: true,
+				..ModifyActionConfig::new()
+			},
+			&mut NoObserver,
+		);
+		assert_eq!(2, count);
+		assert_eq!(
+			"Foo1 +x\n\
+			\n\
+			Bar\n\
+			Foo2 +x\n",
+			got.serialize()
+		);
+
+		// Tasks which would be left empty are skipped.
+		let source_list =
+			List::from_string(String::from("Foo1\nFoo2\n")).unwrap();
+		let cfg = ModifyActionConfig {
+			replace: Some(Replacement::parse("Foo1/").unwrap()),
+			..ModifyActionConfig::new()
+		};
+		let terms = SearchTerms::from_string("foo");
+		assert_eq!(
+			Vec::from(["Foo1"]),
+			refused_items(&source_list, &terms, &cfg)
+				.iter()
+				.map(|i| i.description())
+				.collect::<Vec<_>>()
+		);
+		let outcome = run(source_list, &terms, &cfg, &mut NoObserver).unwrap();
+		assert_eq!(0, outcome.count);
+		assert!(outcome.messages.is_empty());
+	}
+
+	#[test]
+	fn test_from_argmatches() {
+		let matches = get_action().command.get_matches_from(vec![
+			"modify",
+			"#12",
+			"--append",
+			"due:2024-08-01",
+			"--replace",
+			"foo/bar",
+			"--set-priority",
+			"b",
+			"--remove-due",
+		]);
+		let cfg = ModifyActionConfig::from_argmatches(&matches);
+		assert_eq!(Some(String::from("due:2024-08-01")), cfg.append);
+		assert_eq!(None, cfg.prepend);
+		assert_eq!(Some(Replacement::parse("foo/bar").unwrap()), cfg.replace);
+		assert_eq!(Some('B'), cfg.priority);
+		assert!(cfg.remove_due);
+	}
+}
@@ -0,0 +1,209 @@
+//! Suggest the best task to do next
+
+use crate::action::*;
+use clap::{Arg, ArgMatches, Command};
+
+/// Options for the `next` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("next");
+	let mut command = Command::new("next")
+		.about("Suggest the best task to do next")
+		.after_help(
+			"Picks by urgency, then importance, then size, like smart sorting. \
+			Ties go to the task earliest in the file.\n\n\
+			Ignores tasks which are marked as already complete or \
+			can't be started yet.",
+		);
+	command = FileType::TodoTxt.add_args(command);
+	command = Outputter::add_args(command);
+	command = command
+		.arg(
+			Arg::new("count")
+				.num_args(1)
+				.short('n')
+				.long("count")
+				.value_parser(clap::value_parser!(usize))
+				.value_name("N")
+				.help("Number of tasks to suggest (default: 1)"),
+		)
+		.arg(
+			Arg::new("why")
+				.num_args(0)
+				.long("why")
+				.help("Explain why each task was picked"),
+		);
+	command = SearchTerms::add_optional_args(command);
+	Action { name, command }
+}
+
+/// The order tasks are picked in.
+pub fn selection_order() -> SortOrder {
+	SortOrder::Smart
+}
+
+/// Execute the `next` subcommand.
+#[cfg(not(tarpaulin_include))]
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let filter = SearchTerms::from_argmatches(args);
+	let count = *args.get_one::<usize>("count").unwrap_or(&1);
+	let why = *args.get_one::<bool>("why").unwrap();
+
+	let list = FileType::TodoTxt.load_matching(args, &filter)?;
+
+	let mut outputter = Outputter::from_argmatches(args);
+	outputter.fit_to_list(&list);
+
+	let selected = select_items(&list, &filter, &selection_order(), count);
+	for item in selected.iter() {
+		outputter.write_item(item);
+		if why {
+			let ties = count_ties(&list, &filter, item);
+			for reason in reasons(item, ties) {
+				outputter.write_notice(format!("    {}", reason));
+			}
+		}
+	}
+
+	Ok(ExitStatus::from_count(selected.len()))
+}
+
+/// The factors which decided where a task came in the smart order: its
+/// urgency, importance, and size, and how many other tasks tied with it.
+pub fn reasons(item: &Item, ties: usize) -> Vec<String> {
+	let (urgency, importance, size) = item.smart_key();
+	let mut reasons = Vec::from([
+		match item.due_date() {
+			Some(due) => {
+				format!("urgency: {} (due {})", urgency, due)
+			}
+			None => format!("urgency: no due date (ranked as {})", urgency),
+		},
+		match item.importance() {
+			Some(_) => format!(
+				"importance: {} (priority {})",
+				importance,
+				importance.to_char()
+			),
+			None => {
+				format!("importance: no priority (ranked as {})", importance)
+			}
+		},
+		match item.tshirt_size() {
+			Some(_) => format!("size: {}", size),
+			None => {
+				format!("size: not given (ranked as {})", size)
+			}
+		},
+	]);
+	if ties > 0 {
+		reasons.push(format!(
+			"tied with {} later task{}, so picked as earlier in the file",
+			ties,
+			if ties == 1 { "" } else { "s" },
+		));
+	}
+	reasons
+}
+
+/// Count the other tasks which could have been picked instead of `item`,
+/// because they have the same smart sorting key and are later in the file.
+pub fn count_ties(list: &List, filter: &SearchTerms, item: &Item) -> usize {
+	let key = item.smart_key();
+	let mut later = false;
+	let mut ties = 0;
+	for i in find::find_results(filter, list) {
+		if std::ptr::eq(i, item) {
+			later = true;
+		} else if later
+			&& !i.completion()
+			&& i.smart_key() == key
+			&& list.is_item_startable(i)
+		{
+			ties += 1;
+		}
+	}
+	ties
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("next"), get_action().name);
+	}
+
+	#[test]
+	fn test_selection_order() {
+		assert_eq!(SortOrder::Smart, selection_order());
+	}
+
+	#[test]
+	fn test_select_next() {
+		let list = List::from_string(String::from(
+			"(A) Blocked due:2024-06-06 after:x\n\
+			x (A) Done due:2024-06-06\n\
+			(A) Later start:2024-06-10 due:2024-06-06\n\
+			(C) Foo @home due:2024-06-06\n\
+			(B) Bar @work due:2024-06-06\n\
+			(B) Baz @work due:2024-06-06 @S\n\
+			(A) Bat @home due:2024-06-20\n\
+			Blocker id:x\n",
+		))
+		.unwrap();
+		let friday = chrono::NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+		crate::item::with_today(friday, || {
+			let pick = |terms: &str, count: usize| {
+				let filter = SearchTerms::from_string(terms);
+				select_items(&list, &filter, &selection_order(), count)
+					.iter()
+					.map(|i| i.description().to_string())
+					.collect::<Vec<String>>()
+			};
+			assert_eq!(vec!["Baz @work due:2024-06-06 @S"], pick("", 1));
+			assert_eq!(
+				vec![
+					"Baz @work due:2024-06-06 @S",
+					"Bar @work due:2024-06-06",
+					"Foo @home due:2024-06-06",
+				],
+				pick("", 3)
+			);
+			assert_eq!(vec!["Foo @home due:2024-06-06"], pick("@home", 1));
+		});
+	}
+
+	#[test]
+	fn test_reasons() {
+		let list = List::from_string(String::from(
+			"(B) Foo due:2024-06-06 @S\nBar\nBaz\nx Bat\nBaz\n",
+		))
+		.unwrap();
+		let items = list.items();
+		let friday = chrono::NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+		crate::item::with_today(friday, || {
+			let filter = SearchTerms::new();
+			assert_eq!(0, count_ties(&list, &filter, items[0]));
+			assert_eq!(
+				vec![
+					"urgency: Overdue (due 2024-06-06)",
+					"importance: Important (priority B)",
+					"size: Small",
+				],
+				reasons(items[0], 0)
+			);
+			assert_eq!(2, count_ties(&list, &filter, items[1]));
+			assert_eq!(1, count_ties(&list, &filter, items[2]));
+			assert_eq!(
+				vec![
+					"urgency: no due date (ranked as Soon)",
+					"importance: no priority (ranked as Normal)",
+					"size: not given (ranked as Medium)",
+					"tied with 2 later tasks, so picked as earlier in the file",
+				],
+				reasons(items[1], 2)
+			);
+		});
+	}
+}
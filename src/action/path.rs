@@ -1,7 +1,10 @@
 //! Prints the full path to your todo list
 
 use crate::action::*;
-use clap::{ArgMatches, Command};
+use clap::{Arg, ArgMatches, Command};
+use std::fs;
+use std::path::Path;
+use url::Url;
 
 /// Options for the `path` subcommand.
 pub fn get_action() -> Action {
@@ -9,18 +12,147 @@ pub fn get_action() -> Action {
 	let mut command = Command::new("path")
 		.about("Prints the full path to your todo list")
 		.after_help(
-			"This allows things like:\n\
-			\n  /path/to/some/editor `tada path`",
+			"With --explain, lists the places the path can come from, in \
+			order: --file (or --done-file), a --profile, TODO_FILE (or \
+			DONE_FILE), TODO_DIR, then HOME.\n\n\
+			This allows things like:\n\
+			\n  /path/to/some/editor `tada path`\n\
+			\n  $EDITOR $(tada path --done --create)",
+		)
+		.arg(
+			Arg::new("done")
+				.num_args(0)
+				.long("done")
+				.help("Print the path to done.txt instead"),
+		)
+		.arg(
+			Arg::new("dir")
+				.num_args(0)
+				.long("dir")
+				.help("Print the directory containing the file"),
+		)
+		.arg(
+			Arg::new("explain")
+				.num_args(0)
+				.long("explain")
+				.help(
+					"Show where the path could come from, and which was used",
+				),
+		)
+		.arg(
+			Arg::new("create")
+				.num_args(0)
+				.long("create")
+				.help("Create the file if it doesn't exist"),
 		);
 	command = FileType::TodoTxt.add_args(command);
+	command = FileType::DoneTxt.add_args(command);
 	Action { name, command }
 }
 
 /// Execute the `path` subcommand.
 #[cfg(not(tarpaulin_include))]
-pub fn execute(args: &ArgMatches) {
-	let f = FileType::TodoTxt.filename(args);
-	println!("{}", f);
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let file_type = if *args.get_one::<bool>("done").unwrap() {
+		FileType::DoneTxt
+	} else {
+		FileType::TodoTxt
+	};
+	if *args.get_one::<bool>("explain").unwrap() {
+		for line in explain(&file_type.candidates(args)?) {
+			println!("{}", line);
+		}
+		return Ok(ExitStatus::Success);
+	}
+	let f = file_type.filename(args)?;
+	if *args.get_one::<bool>("create").unwrap() && !create_if_missing(&f)? {
+		eprintln!("Not creating {}, as it isn't a local file.", f);
+	}
+	if *args.get_one::<bool>("dir").unwrap() {
+		println!("{}", containing_dir(&f));
+	} else {
+		println!("{}", f);
+	}
+
+	Ok(ExitStatus::Success)
+}
+
+/// Describe each candidate source for a path, in order of precedence,
+/// marking the one which is used.
+pub fn explain(candidates: &[Candidate]) -> Vec<String> {
+	let width = candidates
+		.iter()
+		.map(|c| c.source.len())
+		.max()
+		.unwrap_or(0);
+	let mut used = false;
+	candidates
+		.iter()
+		.map(|c| {
+			let detail = match &c.path {
+				Some(p) if !used => {
+					used = true;
+					format!("{}  <- used", p)
+				}
+				Some(p) => p.clone(),
+				None => String::from("(not set)"),
+			};
+			format!("{:width$}  {}", c.source, detail, width = width)
+		})
+		.collect()
+}
+
+/// Whether a path or URL refers to something other than a local file.
+fn is_remote(f: &str) -> bool {
+	match Url::parse(f) {
+		Ok(u) => u.scheme() != "file" && u.scheme().len() > 1,
+		Err(_) => false,
+	}
+}
+
+/// Create an empty file, and any missing directories leading to it, unless
+/// it already exists.
+///
+/// Returns false without doing anything if the file isn't local, like an
+/// HTTP URL.
+pub fn create_if_missing(f: &str) -> Result<bool, Error> {
+	if is_remote(f) {
+		return Ok(false);
+	}
+	let path = match Url::parse(f) {
+		Ok(u) if u.scheme() == "file" => u.to_file_path().unwrap(),
+		_ => Path::new(f).to_path_buf(),
+	};
+	if path.exists() {
+		return Ok(true);
+	}
+	let unwritable = |e: std::io::Error| Error::Unwritable {
+		url: f.to_string(),
+		message: e.to_string(),
+	};
+	if let Some(parent) = path
+		.parent()
+		.filter(|p| !p.as_os_str().is_empty())
+	{
+		fs::create_dir_all(parent).map_err(unwritable)?;
+	}
+	fs::write(&path, "").map_err(unwritable)?;
+	Ok(true)
+}
+
+/// The directory containing a file, or for a URL, the URL of the
+/// containing directory.
+pub fn containing_dir(f: &str) -> String {
+	if is_remote(f) {
+		return Url::parse(f)
+			.and_then(|u| u.join("."))
+			.map(|u| u.to_string())
+			.unwrap_or_else(|_| f.to_string());
+	}
+	match Path::new(f).parent() {
+		Some(p) if !p.as_os_str().is_empty() => p.display().to_string(),
+		_ => String::from("."),
+	}
 }
 
 #[cfg(test)]
@@ -31,4 +163,59 @@ mod tests {
 	fn test_get_action() {
 		assert_eq!(String::from("path"), get_action().name);
 	}
+
+	#[test]
+	fn test_explain() {
+		let candidates = [
+			Candidate {
+				source: String::from("--file"),
+				path: None,
+			},
+			Candidate {
+				source: String::from("TODO_DIR"),
+				path: Some(String::from("/x/todo.txt")),
+			},
+			Candidate {
+				source: String::from("HOME"),
+				path: Some(String::from("/home/me/todo.txt")),
+			},
+		];
+		assert_eq!(
+			vec![
+				"--file    (not set)",
+				"TODO_DIR  /x/todo.txt  <- used",
+				"HOME      /home/me/todo.txt",
+			],
+			explain(&candidates)
+		);
+	}
+
+	#[test]
+	fn test_containing_dir() {
+		assert_eq!("/home/x", containing_dir("/home/x/todo.txt"));
+		assert_eq!(".", containing_dir("todo.txt"));
+		assert_eq!(
+			"https://example.com/lists/",
+			containing_dir("https://example.com/lists/todo.txt")
+		);
+	}
+
+	#[test]
+	fn test_create_if_missing() {
+		let dir = tempfile::tempdir().unwrap();
+		let f = dir
+			.path()
+			.join("sub")
+			.join("done.txt")
+			.display()
+			.to_string();
+		assert!(create_if_missing(&f).unwrap());
+		assert_eq!("", fs::read_to_string(&f).unwrap());
+
+		fs::write(&f, "Foo\n").unwrap();
+		assert!(create_if_missing(&f).unwrap());
+		assert_eq!("Foo\n", fs::read_to_string(&f).unwrap());
+
+		assert!(!create_if_missing("https://example.com/todo.txt").unwrap());
+	}
 }
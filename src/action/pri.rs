@@ -0,0 +1,325 @@
+//! Set, raise, lower, or clear the priority of a task or tasks
+
+use crate::action::*;
+use crate::item::{Importance, Item};
+use crate::list::List;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+
+/// Options for the `pri` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("pri");
+	let mut command = Command::new("pri")
+		.aliases(["prioritise", "prioritize"])
+		.about("Set, raise, lower, or clear the priority of tasks")
+		.after_help(
+			"Unless --bump, --drop, or --clear is given, the first argument \
+			is the new priority letter, like `tada pri A @work`.\n\n\
+			Priorities after E are treated as E. Bumping a task with no \
+			priority makes it (C); dropping one makes it (E).",
+		);
+
+	command = FileType::TodoTxt.add_args(command);
+	command = Outputter::add_args(command);
+	command = command
+		.arg(
+			Arg::new("search-term")
+				.action(ArgAction::Append)
+				.required(true)
+				.value_name("[PRIORITY] SEARCH-TERM")
+				.help("A tag, context, line number, or string"),
+		)
+		.arg(
+			Arg::new("bump")
+				.num_args(0)
+				.long("bump")
+				.help("Raise priority by one level")
+				.conflicts_with_all(["drop", "clear"]),
+		)
+		.arg(
+			Arg::new("drop")
+				.num_args(0)
+				.long("drop")
+				.help("Lower priority by one level")
+				.conflicts_with("clear"),
+		)
+		.arg(
+			Arg::new("clear")
+				.num_args(0)
+				.long("clear")
+				.help("Remove priority"),
+		);
+	command = ConfirmationStatus::add_args(command);
+	command = SaveMode::add_args(command);
+
+	Action { name, command }
+}
+
+/// A change to make to the priority of tasks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriorityChange {
+	/// Set the priority to a particular letter.
+	Set(char),
+	/// Raise the priority one level.
+	Bump,
+	/// Lower the priority one level.
+	Drop,
+	/// Remove the priority.
+	Clear,
+}
+
+impl PriorityChange {
+	/// Read the change and search terms from ArgMatches.
+	///
+	/// If no flag was given, the first positional argument is taken to be the
+	/// new priority.
+	pub fn from_argmatches(
+		args: &ArgMatches,
+	) -> Result<(Self, SearchTerms), String> {
+		let mut terms: Vec<String> = args
+			.get_many::<String>("search-term")
+			.unwrap()
+			.cloned()
+			.collect();
+		let change = if *args.get_one::<bool>("bump").unwrap() {
+			Self::Bump
+		} else if *args.get_one::<bool>("drop").unwrap() {
+			Self::Drop
+		} else if *args.get_one::<bool>("clear").unwrap() {
+			Self::Clear
+		} else {
+			let p = parse_priority(&terms.remove(0))
+				.map_err(|e| format!("invalid priority: {}", e))?;
+			if terms.is_empty() {
+				return Err(String::from("no search terms given"));
+			}
+			Self::Set(p)
+		};
+		Ok((change, SearchTerms::from_vec(terms)))
+	}
+
+	/// Create a copy of the item with the change applied.
+	pub fn apply(&self, item: &Item) -> Item {
+		let mut new = item.clone();
+		match self {
+			Self::Set(p) => new.set_priority(*p),
+			Self::Bump => new.set_importance(
+				item.importance()
+					.map(|i| i.raised())
+					.unwrap_or(Importance::C),
+			),
+			Self::Drop => new.set_importance(
+				item.importance()
+					.map(|i| i.lowered())
+					.unwrap_or(Importance::E('E')),
+			),
+			Self::Clear => new.clear_importance(),
+		}
+		new
+	}
+}
+
+/// Execute the `pri` subcommand.
+#[cfg(not(tarpaulin_include))]
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches(args);
+	let (change, search_terms) = match PriorityChange::from_argmatches(args) {
+		Ok(x) => x,
+		Err(e) => {
+			outputter.write_error(e.to_string());
+			return Ok(ExitStatus::Error);
+		}
+	};
+
+	let list = FileType::TodoTxt.load(args)?;
+	outputter.fit_to_list(&list);
+
+	let confirmation = ConfirmationStatus::from_argmatches(args);
+	let save_mode = SaveMode::from_argmatches(args);
+	let cfg = PriActionConfig {
+		search_terms,
+		change,
+	};
+	let before = list.serialize();
+	let mut confirmer = Confirmer::new(
+		&mut outputter,
+		confirmation,
+		"Change priority?",
+		"Changing",
+		"Skipping",
+	);
+	let outcome = run(list, &cfg, &mut confirmer)?;
+	let (new_list, count) = (outcome.list, outcome.count);
+
+	if count > 0 {
+		save_mode.save_with_diff(&new_list, &before, &mut outputter)?;
+		let name = new_list.display_name();
+		outputter.write_status(save_mode.status(
+			format!("Changed the priority of {} tasks in {}.", count, name),
+			format!("changed the priority of {} tasks in {}", count, name),
+		));
+	} else {
+		outputter.write_status(String::from("No actions taken."));
+	}
+
+	outputter.write_warnings(housekeeping_warnings(
+		&new_list,
+		&Config::load().hints,
+	));
+
+	Ok(ExitStatus::from_count(count))
+}
+
+/// Config object for the `pri` action.
+#[derive(Clone)]
+pub struct PriActionConfig {
+	pub search_terms: SearchTerms,
+	pub change: PriorityChange,
+}
+
+/// Run the `pri` action on a list, without saving it.
+///
+/// The observer will be used to check whether each individual item should be
+/// altered.
+pub fn run(
+	list: List,
+	cfg: &PriActionConfig,
+	observer: &mut dyn MutationObserver,
+) -> Result<ActionOutcome, Error> {
+	let (new_list, count) =
+		change_priority_in_list(list, &cfg.search_terms, cfg.change, observer);
+	let message = format!(
+		"changed the priority of {} tasks in {}",
+		count,
+		new_list.display_name()
+	);
+	Ok(ActionOutcome::new(new_list, count, message))
+}
+
+/// Given a list, set of search terms, and a priority change, creates a copy of
+/// the list with the change applied to all incomplete items matching the
+/// search terms.
+///
+/// The observer will be used to check whether each individual item should be
+/// altered. Items whose priority would not change are left alone without
+/// asking.
+///
+/// Also returns the number of items changed.
+pub fn change_priority_in_list(
+	list: List,
+	search_terms: &SearchTerms,
+	change: PriorityChange,
+	observer: &mut dyn MutationObserver,
+) -> (List, usize) {
+	let changes = |item: &Item| {
+		search_terms.item_matches(item)
+			&& !item.completion()
+			&& change.apply(item).priority() != item.priority()
+	};
+	let mut new_list = list;
+	let mut count = 0;
+	observer.will_change(
+		new_list
+			.items()
+			.into_iter()
+			.filter(|i| changes(i))
+			.count(),
+	);
+	for mut item in new_list.iter_items_mut() {
+		if changes(&item) && observer.before_change(&item) {
+			count += 1;
+			let new = change.apply(&item);
+			observer.after_change(&item, &new);
+			*item = new;
+		}
+	}
+	(new_list, count)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("pri"), get_action().name);
+	}
+
+	#[test]
+	fn test_from_argmatches() {
+		let parse = |v: Vec<&str>| {
+			let m = get_action().command.get_matches_from(v);
+			PriorityChange::from_argmatches(&m).map(|(c, t)| (c, t.terms))
+		};
+		assert_eq!(
+			Ok((PriorityChange::Set('A'), Vec::from([String::from("@x")]))),
+			parse(Vec::from(["pri", "a", "@x"]))
+		);
+		assert_eq!(
+			Ok((PriorityChange::Bump, Vec::from([String::from("@x")]))),
+			parse(Vec::from(["pri", "--bump", "@x"]))
+		);
+		assert_eq!(
+			Ok((PriorityChange::Drop, Vec::from([String::from("@x")]))),
+			parse(Vec::from(["pri", "--drop", "@x"]))
+		);
+		assert_eq!(
+			Ok((PriorityChange::Clear, Vec::from([String::from("@x")]))),
+			parse(Vec::from(["pri", "--clear", "@x"]))
+		);
+		assert!(parse(Vec::from(["pri", "@x"])).is_err());
+		assert!(parse(Vec::from(["pri", "AB", "@x"])).is_err());
+	}
+
+	#[test]
+	fn test_apply() {
+		let check = |change: PriorityChange, from: &str, to: &str| {
+			let got = change.apply(&Item::parse(from));
+			assert_eq!(to, format!("{}", got));
+		};
+		check(PriorityChange::Set('B'), "Foo", "(B) Foo");
+		check(PriorityChange::Set('Q'), "(A) Foo", "(Q) Foo");
+		check(PriorityChange::Bump, "(A) Foo", "(A) Foo");
+		check(PriorityChange::Bump, "(B) Foo", "(A) Foo");
+		check(PriorityChange::Bump, "(C) Foo", "(B) Foo");
+		check(PriorityChange::Bump, "Foo", "(C) Foo");
+		check(PriorityChange::Bump, "(Q) Foo", "(D) Foo");
+		check(PriorityChange::Drop, "(A) Foo", "(B) Foo");
+		check(PriorityChange::Drop, "(E) Foo", "(E) Foo");
+		check(PriorityChange::Drop, "(Q) Foo", "(E) Foo");
+		check(PriorityChange::Drop, "Foo", "(E) Foo");
+		check(PriorityChange::Clear, "(A) Foo", "Foo");
+	}
+
+	#[test]
+	fn test_change_priority_in_list() {
+		let source_list = List::from_string(String::from(
+			"(B) Foo1\n\
+			\n\
+			(C) Bar\n\
+			(A) Foo2\n\
+			x (B) Foo3\n",
+		))
+		.unwrap();
+
+		let cfg = PriActionConfig {
+			search_terms: SearchTerms::from_string("foo"),
+			change: PriorityChange::Bump,
+		};
+		let outcome = run(source_list, &cfg, &mut NoObserver).unwrap();
+		assert_eq!(1, outcome.count);
+		assert_eq!(
+			"(A) Foo1\n\
+			\n\
+			(C) Bar\n\
+			(A) Foo2\n\
+			x (B) Foo3\n",
+			outcome.list.serialize()
+		);
+		assert_eq!(
+			Vec::from([String::from(
+				"changed the priority of 1 tasks in todo list"
+			)]),
+			outcome.messages
+		);
+	}
+}
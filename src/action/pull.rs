@@ -1,8 +1,8 @@
 //! Reschedule a task or tasks to be done today (or another date)
 
 use crate::action::*;
-use crate::item::{Item, Urgency};
-use crate::list::{LineKind, List};
+use crate::item::Urgency;
+use crate::list::List;
 use clap::{Arg, ArgMatches, Command};
 
 /// Options for the `pull` subcommand.
@@ -48,98 +48,146 @@ pub fn get_action() -> Action {
 				.help("Set a due date the end of next month"),
 		);
 	command = ConfirmationStatus::add_args(command);
+	command = SaveMode::add_args(command);
 
 	Action { name, command }
 }
 
-/// Execute the `pull` subcommand.
-pub fn execute(args: &ArgMatches) {
-	let todo_filename = FileType::TodoTxt.filename(args);
-	let list = List::from_url(todo_filename.clone())
-		.expect("Could not read todo list");
+/// Config object for the `pull` action.
+#[derive(Clone)]
+pub struct PullActionConfig {
+	pub search_terms: SearchTerms,
+	pub urgency: Urgency,
+}
+
+impl Default for PullActionConfig {
+	fn default() -> Self {
+		Self {
+			search_terms: SearchTerms::new(),
+			urgency: Urgency::Today,
+		}
+	}
+}
+
+impl PullActionConfig {
+	/// Create a PullActionConfig from an appropriate ArgMatches.
+	pub fn from_argmatches(args: &ArgMatches) -> Self {
+		let urgency = if *args.get_one::<bool>("today").unwrap() {
+			Urgency::Today
+		} else if *args.get_one::<bool>("soon").unwrap() {
+			Urgency::Soon
+		} else if *args.get_one::<bool>("next-week").unwrap() {
+			Urgency::NextWeek
+		} else if *args.get_one::<bool>("next-month").unwrap() {
+			Urgency::NextMonth
+		} else {
+			Urgency::Today
+		};
+		Self {
+			search_terms: SearchTerms::from_argmatches(args),
+			urgency,
+		}
+	}
+}
 
+/// Execute the `pull` subcommand.
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
 	let mut outputter = Outputter::from_argmatches(args);
-	outputter.line_number_digits = list.lines.len().to_string().len();
+	let list = FileType::TodoTxt.load(args)?;
+	outputter.fit_to_list(&list);
 
-	let search_terms = SearchTerms::from_argmatches(args);
+	let cfg = PullActionConfig::from_argmatches(args);
 	let confirmation = ConfirmationStatus::from_argmatches(args);
-	let urgency = if *args.get_one::<bool>("today").unwrap() {
-		Urgency::Today
-	} else if *args.get_one::<bool>("soon").unwrap() {
-		Urgency::Soon
-	} else if *args.get_one::<bool>("next-week").unwrap() {
-		Urgency::NextWeek
-	} else if *args.get_one::<bool>("next-month").unwrap() {
-		Urgency::NextMonth
-	} else {
-		Urgency::Today
-	};
+	let save_mode = SaveMode::from_argmatches(args);
 
-	let (new_list, count) = pull_items_forward_in_list(
-		list,
-		search_terms,
-		urgency,
-		confirmation,
+	let before = list.serialize();
+	let mut confirmer = Confirmer::new(
 		&mut outputter,
+		confirmation,
+		"Reschedule?",
+		"Rescheduling",
+		"Skipping",
 	);
-	if count > 0 {
-		new_list.to_url(todo_filename);
+	let outcome = run(list, &cfg, &mut confirmer)?;
+	if outcome.count > 0 {
+		save_mode.save_with_diff(&outcome.list, &before, &mut outputter)?;
+	}
+	for status in save_mode.outcome_status(&outcome) {
+		outputter.write_status(status);
 	}
 
-	maybe_housekeeping_warnings(&mut outputter, &new_list);
+	outputter.write_warnings(housekeeping_warnings(
+		&outcome.list,
+		&Config::load().hints,
+	));
+
+	Ok(outcome.exit_status())
+}
+
+/// Run the `pull` action on a list, without saving it.
+///
+/// The observer will be used to check whether each individual item should be
+/// altered.
+pub fn run(
+	list: List,
+	cfg: &PullActionConfig,
+	observer: &mut dyn MutationObserver,
+) -> Result<ActionOutcome, Error> {
+	let (new_list, count) = pull_items_forward_in_list(
+		list,
+		cfg.search_terms.clone(),
+		cfg.urgency,
+		observer,
+	);
+	let message =
+		format!("rescheduled {} tasks in {}", count, new_list.display_name());
+	Ok(ActionOutcome::new(new_list, count, message))
 }
 
 /// Given a list, set of search terms, and an urgency, creates a copy of the list
 /// with all items matching the search terms "pulled forward" to have that urgency.
 ///
-/// The confirmation status and outputter will be used to check whether each
-/// individual item should be altered.
+/// The observer will be used to check whether each individual item should be
+/// altered.
 ///
 /// Also returns the number of items changed.
 pub fn pull_items_forward_in_list(
 	list: List,
 	search_terms: SearchTerms,
 	urgency: Urgency,
-	confirmation: ConfirmationStatus,
-	outputter: &mut Outputter,
+	observer: &mut dyn MutationObserver,
 ) -> (List, usize) {
-	let mut new_list = List::new();
+	let mut new_list = list;
 	let mut count = 0;
-	for line in list.lines {
-		match line.kind {
-			LineKind::Item => {
-				let item = line.item.clone().unwrap();
-				if search_terms.item_matches(&item)
-					&& (!item.completion())
-					&& check_if_pull(&item, outputter, confirmation)
-				{
-					count += 1;
-					new_list.lines.push(line.but_pull(urgency));
-				} else {
-					new_list.lines.push(line);
-				}
-			}
-			_ => new_list.lines.push(line),
+	observer.will_change(
+		new_list
+			.items()
+			.into_iter()
+			.filter(|i| search_terms.item_matches(i) && !i.completion())
+			.count(),
+	);
+	for mut item in new_list.iter_items_mut() {
+		if search_terms.item_matches(&item)
+			&& (!item.completion())
+			&& observer.before_change(&item)
+		{
+			count += 1;
+			let pulled = item.but_pull(urgency);
+			observer.after_change(&item, &pulled);
+			*item = pulled;
 		}
 	}
+	new_list.renumber();
 	(new_list, count)
 }
 
-/// Asks whether to pull an item, and prints out the response before returning a bool.
-pub fn check_if_pull(
-	item: &Item,
-	outputter: &mut Outputter,
-	status: ConfirmationStatus,
-) -> bool {
-	outputter.write_item(item);
-	status.check(outputter, "Reschedule?", "Rescheduling", "Skipping")
-}
-
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::item::today;
+	use crate::list::LineEnding;
 	use crate::Line;
-	use chrono::{Duration, Utc};
+	use chrono::Duration;
 	use tempfile::tempdir;
 
 	#[test]
@@ -148,7 +196,7 @@ mod tests {
 	}
 
 	#[test]
-	fn test_check_if_pull() {
+	fn test_confirmer() {
 		let dir = tempdir().unwrap();
 		let buffer_filename = dir
 			.path()
@@ -160,17 +208,35 @@ mod tests {
 
 		let mut o = Outputter::new(9999);
 		o.colour = false;
-		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
-		let r = check_if_pull(&i, &mut o, ConfirmationStatus::Yes);
-		assert_eq!(true, r);
+		let buffer = fs::File::create(buffer_filename.clone()).unwrap();
+		o.msg_io = Box::new(buffer.try_clone().unwrap());
+		o.io = Box::new(buffer);
+		let r = Confirmer::new(
+			&mut o,
+			ConfirmationStatus::Yes,
+			"Reschedule?",
+			"Rescheduling",
+			"Skipping",
+		)
+		.before_change(&i);
+		assert!(r);
 		let got_output = fs::read_to_string(buffer_filename.clone()).unwrap();
 		assert_eq!(String::from("  (?) XYZ\nRescheduling\n\n"), got_output);
 
 		let mut o = Outputter::new(9999);
 		o.colour = false;
-		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
-		let r = check_if_pull(&i, &mut o, ConfirmationStatus::No);
-		assert_eq!(false, r);
+		let buffer = fs::File::create(buffer_filename.clone()).unwrap();
+		o.msg_io = Box::new(buffer.try_clone().unwrap());
+		o.io = Box::new(buffer);
+		let r = Confirmer::new(
+			&mut o,
+			ConfirmationStatus::No,
+			"Reschedule?",
+			"Rescheduling",
+			"Skipping",
+		)
+		.before_change(&i);
+		assert!(!r);
 		let got_output = fs::read_to_string(buffer_filename.clone()).unwrap();
 		assert_eq!(String::from("  (?) XYZ\nSkipping\n\n"), got_output);
 	}
@@ -185,6 +251,14 @@ mod tests {
 				Line::from_string(String::from("Bar"), 0),
 			]),
 			path: None,
+			url: None,
+			etag: None,
+			last_modified: None,
+			invalid_lines: Vec::new(),
+			line_ending: LineEnding::Lf,
+			missing_final_newline: false,
+			bom: false,
+			partial: false,
 		};
 
 		let (got, count) = pull_items_forward_in_list(
@@ -193,29 +267,48 @@ mod tests {
 				terms: Vec::from([String::from("foo")]),
 			},
 			Urgency::Soon,
-			ConfirmationStatus::Yes,
-			&mut Outputter::new(1000),
+			&mut NoObserver,
 		);
 		assert_eq!(2, count);
 
 		let got_items = got.items();
 
-		let item = got_items.get(0).unwrap();
-		assert_eq!(Some(Utc::now().date_naive()), item.start_date());
-		assert_eq!(
-			Some(Utc::now().date_naive() + Duration::days(2)),
-			item.due_date()
-		);
+		let item = got_items.first().unwrap();
+		assert_eq!(Some(today()), item.start_date());
+		assert_eq!(Some(today() + Duration::days(2)), item.due_date());
 
 		let item = got_items.get(1).unwrap();
 		assert_eq!(None, item.start_date());
-		assert_eq!(
-			Some(Utc::now().date_naive() + Duration::days(2)),
-			item.due_date()
-		);
+		assert_eq!(Some(today() + Duration::days(2)), item.due_date());
 
 		let item = got_items.get(2).unwrap();
 		assert_eq!(None, item.start_date());
 		assert_eq!(None, item.due_date());
 	}
+
+	#[test]
+	fn test_run() {
+		let list =
+			List::from_string(String::from("Foo\nBar\nFoo bar\n")).unwrap();
+		let cfg = PullActionConfig {
+			search_terms: SearchTerms::from_string("foo"),
+			..PullActionConfig::default()
+		};
+		let outcome = run(list, &cfg, &mut NoObserver).unwrap();
+		assert_eq!(2, outcome.count);
+		assert_eq!(vec!["rescheduled 2 tasks in todo list"], outcome.messages);
+		assert_eq!(
+			Some(Urgency::Today),
+			outcome.list.item_at(3).unwrap().urgency()
+		);
+		assert_eq!(None, outcome.list.item_at(2).unwrap().urgency());
+
+		let cfg = PullActionConfig {
+			search_terms: SearchTerms::from_string("baz"),
+			..PullActionConfig::default()
+		};
+		let outcome = run(outcome.list, &cfg, &mut NoObserver).unwrap();
+		assert_eq!(ExitStatus::NoMatch, outcome.exit_status());
+		assert!(outcome.messages.is_empty());
+	}
 }
@@ -17,6 +17,8 @@ pub fn get_action() -> Action {
 	command = Outputter::add_args(command);
 	command = OutputCount::add_args(command);
 	command = SortOrder::add_args(command, default_sort_order());
+	command = Grouping::add_args(command);
+	command = SearchTerms::add_optional_args(command);
 	Action { name, command }
 }
 
@@ -25,10 +27,11 @@ pub fn default_sort_order() -> SortOrder {
 	SortOrder::TshirtSize
 }
 
-/// Execute the `important` subcommand.
+/// Execute the `quick` subcommand.
 #[cfg(not(tarpaulin_include))]
-pub fn execute(args: &ArgMatches) {
-	execute_simple_list_action(args, default_sort_order());
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let filter = SearchTerms::from_argmatches(args);
+	execute_simple_list_action(args, default_sort_order(), &filter)
 }
 
 #[cfg(test)]
@@ -1,8 +1,7 @@
 //! Remove a task or tasks
 
 use crate::action::*;
-use crate::item::Item;
-use crate::list::{Line, LineKind, List};
+use crate::list::List;
 use clap::{ArgMatches, Command};
 
 /// Options for the `remove` subcommand.
@@ -15,85 +14,128 @@ pub fn get_action() -> Action {
 	command = FileType::TodoTxt.add_args(command);
 	command = Outputter::add_args(command);
 	command = SearchTerms::add_args(command);
+	command = FileType::add_all_profiles_arg(command);
 	command = ConfirmationStatus::add_args(command);
+	command = SaveMode::add_args(command);
 
 	Action { name, command }
 }
 
 /// Execute the `remove` subcommand.
-pub fn execute(args: &ArgMatches) {
-	let todo_filename = FileType::TodoTxt.filename(args);
-	let list = List::from_url(todo_filename.clone())
-		.expect("Could not read todo list");
-
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
 	let mut outputter = Outputter::from_argmatches(args);
-	outputter.line_number_digits = list.lines.len().to_string().len();
+	let lists = FileType::TodoTxt.load_all(args)?;
+	outputter.fit_to_lists(&lists);
 
-	let search_terms = SearchTerms::from_argmatches(args);
-	let confirmation = ConfirmationStatus::from_argmatches(args);
+	let cfg = RemoveActionConfig {
+		search_terms: SearchTerms::from_argmatches(args),
+	};
+	let mut confirmation = ConfirmationStatus::from_argmatches(args);
+	let save_mode = SaveMode::from_argmatches(args);
 
-	let (new_list, count) = remove_items_from_list(
-		list,
-		search_terms,
-		confirmation,
-		&mut outputter,
-	);
+	let mut count = 0;
+	let mut changed: Vec<String> = Vec::new();
+	for pl in lists {
+		let before = pl.list.serialize();
+		let mut confirmer = Confirmer::new(
+			&mut outputter,
+			confirmation,
+			"Remove?",
+			"Removing",
+			"Keeping",
+		)
+		.with_dependents(&pl.list);
+		let outcome = run(pl.list, &cfg, &mut confirmer)?;
+		// Answering "all" or "quit" goes for the remaining lists too.
+		confirmation = confirmer.status;
+		if outcome.count > 0 {
+			save_mode.save_with_diff(&outcome.list, &before, &mut outputter)?;
+			changed.push(outcome.list.display_name());
+		}
+		count += outcome.count;
+	}
 
 	if count > 0 {
-		new_list.to_url(todo_filename);
-		outputter.write_status(format!("Removed {} tasks!", count));
+		let from = changed.join(", ");
+		outputter.write_status(save_mode.status(
+			format!("Removed {} tasks from {}", count, from),
+			format!("removed {} tasks from {}", count, from),
+		));
 	} else {
 		outputter.write_status(String::from("No actions taken."));
 	}
+
+	Ok(ExitStatus::from_count(count))
+}
+
+/// Config object for the `remove` action.
+#[derive(Clone, Default)]
+pub struct RemoveActionConfig {
+	pub search_terms: SearchTerms,
+}
+
+/// Run the `remove` action on a list, without saving it.
+///
+/// Removed tasks are replaced with blank lines, so other tasks keep their
+/// line numbers. The observer will be used to check whether each individual
+/// item should be removed.
+pub fn run(
+	list: List,
+	cfg: &RemoveActionConfig,
+	observer: &mut dyn MutationObserver,
+) -> Result<ActionOutcome, Error> {
+	let (new_list, count) =
+		remove_items_from_list(list, cfg.search_terms.clone(), observer);
+	let message =
+		format!("removed {} tasks from {}", count, new_list.display_name());
+	Ok(ActionOutcome::new(new_list, count, message))
 }
 
 /// Given a list and set of search terms, creates a copy of the list but without any items
 /// matching the search terms. (In fact, replaces removed items with a blank line.)
 ///
-/// The confirmation status and outputter will be used to check whether each individual item
-/// should be altered.
+/// The observer will be used to check whether each individual item should be
+/// altered.
 ///
 /// Also returns the number of items removed.
 pub fn remove_items_from_list(
 	list: List,
 	search_terms: SearchTerms,
-	confirmation: ConfirmationStatus,
-	outputter: &mut Outputter,
+	observer: &mut dyn MutationObserver,
 ) -> (List, usize) {
-	let mut new_list = List::new();
+	let mut new_list = list;
 	let mut count = 0;
-	for line in list.lines {
-		match line.kind {
-			LineKind::Item => {
-				let item = line.item.clone().unwrap();
-				if search_terms.item_matches(&item)
-					&& check_if_delete(&item, outputter, confirmation)
-				{
-					count += 1;
-					new_list.lines.push(Line::new_blank());
-				} else {
-					new_list.lines.push(line);
+	observer.will_change(
+		new_list
+			.items()
+			.into_iter()
+			.filter(|i| search_terms.item_matches(i))
+			.count(),
+	);
+	for num in 1..=new_list.lines.len() {
+		let remove = match new_list.item_at(num) {
+			Some(item) => {
+				search_terms.item_matches(item) && observer.before_change(item)
+			}
+			None => false,
+		};
+		if remove {
+			count += 1;
+			if let Some(old) = new_list.remove_line(num, true) {
+				if let Some(item) = old.item {
+					observer.after_change(&item, &item);
 				}
 			}
-			_ => new_list.lines.push(line),
 		}
 	}
+	new_list.renumber();
 	(new_list, count)
 }
 
-/// Asks whether to delete an item, and prints out the response before returning a bool.
-pub fn check_if_delete(
-	item: &Item,
-	outputter: &mut Outputter,
-	status: ConfirmationStatus,
-) -> bool {
-	outputter.write_item(item);
-	status.check(outputter, "Remove?", "Removing", "Keeping")
-}
-
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::list::{Line, LineEnding, LineKind};
 	use tempfile::tempdir;
 
 	#[test]
@@ -102,7 +144,7 @@ mod tests {
 	}
 
 	#[test]
-	fn test_check_if_delete() {
+	fn test_confirmer() {
 		let dir = tempdir().unwrap();
 		let buffer_filename = dir
 			.path()
@@ -114,21 +156,58 @@ mod tests {
 
 		let mut o = Outputter::new(9999);
 		o.colour = false;
-		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
-		let r = check_if_delete(&i, &mut o, ConfirmationStatus::Yes);
-		assert_eq!(true, r);
+		let buffer = fs::File::create(buffer_filename.clone()).unwrap();
+		o.msg_io = Box::new(buffer.try_clone().unwrap());
+		o.io = Box::new(buffer);
+		let r = Confirmer::new(
+			&mut o,
+			ConfirmationStatus::Yes,
+			"Remove?",
+			"Removing",
+			"Keeping",
+		)
+		.before_change(&i);
+		assert!(r);
 		let got_output = fs::read_to_string(buffer_filename.clone()).unwrap();
 		assert_eq!(String::from("  (?) XYZ\nRemoving\n\n"), got_output);
 
 		let mut o = Outputter::new(9999);
 		o.colour = false;
-		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
-		let r = check_if_delete(&i, &mut o, ConfirmationStatus::No);
-		assert_eq!(false, r);
+		let buffer = fs::File::create(buffer_filename.clone()).unwrap();
+		o.msg_io = Box::new(buffer.try_clone().unwrap());
+		o.io = Box::new(buffer);
+		let r = Confirmer::new(
+			&mut o,
+			ConfirmationStatus::No,
+			"Remove?",
+			"Removing",
+			"Keeping",
+		)
+		.before_change(&i);
+		assert!(!r);
 		let got_output = fs::read_to_string(buffer_filename.clone()).unwrap();
 		assert_eq!(String::from("  (?) XYZ\nKeeping\n\n"), got_output);
 	}
 
+	#[test]
+	fn test_remove_items_will_change() {
+		struct Counter(usize);
+		impl MutationObserver for Counter {
+			fn will_change(&mut self, count: usize) {
+				self.0 = count;
+			}
+		}
+		let list =
+			List::from_string(String::from("Foo\nBar\nFoo bar\n")).unwrap();
+		let mut counter = Counter(0);
+		remove_items_from_list(
+			list,
+			SearchTerms::from_string("foo"),
+			&mut counter,
+		);
+		assert_eq!(2, counter.0);
+	}
+
 	#[test]
 	fn test_remove_items_from_list() {
 		let source_list = List {
@@ -139,6 +218,14 @@ mod tests {
 				Line::from_string(String::from("Bar"), 0),
 			]),
 			path: None,
+			url: None,
+			etag: None,
+			last_modified: None,
+			invalid_lines: Vec::new(),
+			line_ending: LineEnding::Lf,
+			missing_final_newline: false,
+			bom: false,
+			partial: false,
 		};
 
 		let (got, count) = remove_items_from_list(
@@ -146,8 +233,7 @@ mod tests {
 			SearchTerms {
 				terms: Vec::from([String::from("foo")]),
 			},
-			ConfirmationStatus::Yes,
-			&mut Outputter::new(1000),
+			&mut NoObserver,
 		);
 		assert_eq!(2, count);
 
@@ -155,5 +241,11 @@ mod tests {
 		assert_eq!(LineKind::Blank, got.lines[1].kind);
 		assert_eq!(LineKind::Blank, got.lines[2].kind);
 		assert_eq!(LineKind::Item, got.lines[3].kind);
+
+		// Removed tasks leave blank lines, so the others keep their numbers.
+		for (idx, line) in got.lines.iter().enumerate() {
+			assert_eq!(idx + 1, line.num);
+		}
+		assert_eq!(4, got.item_at(4).unwrap().line_number());
 	}
 }
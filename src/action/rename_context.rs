@@ -0,0 +1,58 @@
+//! Rename a context throughout the todo list
+
+use crate::action::rename_tag::{execute_rename, rename_action};
+use crate::action::*;
+use clap::ArgMatches;
+
+/// Options for the `rename-context` subcommand.
+pub fn get_action() -> Action {
+	rename_action("rename-context", '@')
+}
+
+/// Execute the `rename-context` subcommand.
+#[cfg(not(tarpaulin_include))]
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	execute_rename(args, '@')
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::action::rename_tag::{rename_in_list, Renaming};
+	use crate::list::List;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("rename-context"), get_action().name);
+	}
+
+	#[test]
+	fn test_rename_in_list() {
+		let source_list = List::from_string(String::from(
+			"Call Bob @office @OFFICE\n\
+			Email +office @office-hours\n\
+			Tidy desk @Office\n",
+		))
+		.unwrap();
+		let m = get_action().command.get_matches_from(vec![
+			"rename-context",
+			"@office",
+			"@work",
+		]);
+		let renaming = Renaming::new(
+			'@',
+			m.get_one::<String>("old").unwrap(),
+			m.get_one::<String>("new").unwrap(),
+		);
+
+		let (got, count) =
+			rename_in_list(source_list, &renaming, &mut NoObserver);
+		assert_eq!(2, count);
+		assert_eq!(
+			"Call Bob @work @work\n\
+			Email +office @office-hours\n\
+			Tidy desk @work\n",
+			got.serialize()
+		);
+	}
+}
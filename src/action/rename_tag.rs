@@ -0,0 +1,327 @@
+//! Rename a tag throughout the todo list
+
+use crate::action::*;
+use crate::list::List;
+use clap::{Arg, ArgMatches, Command};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+lazy_static! {
+	/// Regular expression to match each word of a description, the same
+	/// way tags and contexts are found in one.
+	static ref RE_WORD: Regex = Regex::new(r"\S+").unwrap();
+}
+
+/// Options for the `rename-tag` subcommand.
+pub fn get_action() -> Action {
+	rename_action("rename-tag", '+')
+}
+
+/// Execute the `rename-tag` subcommand.
+#[cfg(not(tarpaulin_include))]
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	execute_rename(args, '+')
+}
+
+/// What a sigil marks, like "tag" for `+`.
+fn kind_of(sigil: char) -> &'static str {
+	match sigil {
+		'@' => "context",
+		_ => "tag",
+	}
+}
+
+/// Options for a rename subcommand, for tags or contexts.
+pub fn rename_action(name: &'static str, sigil: char) -> Action {
+	let kind = kind_of(sigil);
+	let mut command = Command::new(name)
+		.about(format!("Rename a {} throughout the todo list", kind))
+		.after_help(format!(
+			"Every task with the old {kind} is changed, whatever its case, \
+			but {sigil}{example}-v2 isn't the same {kind} as {sigil}{example}, \
+			so is left alone. The {sigil} can be left out.\n\n\
+			Tasks in done.txt aren't changed.",
+			kind = kind,
+			sigil = sigil,
+			example = if sigil == '@' { "office" } else { "website" },
+		));
+
+	command = FileType::TodoTxt.add_args(command);
+	command = Outputter::add_args(command);
+	command = command
+		.arg(
+			Arg::new("old")
+				.required(true)
+				.value_name("OLD")
+				.value_parser(move |s: &str| parse_name(sigil, s))
+				.help(format!("The {} to rename", kind)),
+		)
+		.arg(
+			Arg::new("new")
+				.required(true)
+				.value_name("NEW")
+				.value_parser(move |s: &str| parse_name(sigil, s))
+				.help(format!("What to rename the {} to", kind)),
+		);
+	command = ConfirmationStatus::add_args(command);
+	command = SaveMode::add_args(command);
+
+	Action {
+		name: String::from(name),
+		command,
+	}
+}
+
+/// Accept a tag or context name, with or without its sigil, and return
+/// it without.
+pub fn parse_name(sigil: char, s: &str) -> Result<String, String> {
+	let name = s.strip_prefix(sigil).unwrap_or(s);
+	if name.is_empty() || name.contains(char::is_whitespace) {
+		return Err(format!(
+			"expected a {} like {}{}",
+			kind_of(sigil),
+			sigil,
+			if sigil == '@' { "work" } else { "project" },
+		));
+	}
+	Ok(String::from(name))
+}
+
+/// Execute a rename subcommand, for tags or contexts.
+#[cfg(not(tarpaulin_include))]
+pub fn execute_rename(
+	args: &ArgMatches,
+	sigil: char,
+) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches(args);
+	let list = FileType::TodoTxt.load(args)?;
+	outputter.fit_to_list(&list);
+
+	let renaming = Renaming::new(
+		sigil,
+		args.get_one::<String>("old").unwrap(),
+		args.get_one::<String>("new").unwrap(),
+	);
+	let confirmation = ConfirmationStatus::from_argmatches(args);
+	let save_mode = SaveMode::from_argmatches(args);
+
+	let before = list.serialize();
+	let mut confirmer = Confirmer::new(
+		&mut outputter,
+		confirmation,
+		"Rename?",
+		"Renaming",
+		"Skipping",
+	);
+	let outcome = run(list, &renaming, &mut confirmer)?;
+	if outcome.count > 0 {
+		save_mode.save_with_diff(&outcome.list, &before, &mut outputter)?;
+	}
+	for status in save_mode.outcome_status(&outcome) {
+		outputter.write_status(status);
+	}
+
+	outputter.write_warnings(housekeeping_warnings(
+		&outcome.list,
+		&Config::load().hints,
+	));
+
+	Ok(outcome.exit_status())
+}
+
+/// Run the `rename-tag` or `rename-context` action on a list, without
+/// saving it.
+///
+/// The observer will be used to check whether each individual item should be
+/// altered.
+pub fn run(
+	list: List,
+	renaming: &Renaming,
+	observer: &mut dyn MutationObserver,
+) -> Result<ActionOutcome, Error> {
+	let (new_list, count) = rename_in_list(list, renaming, observer);
+	let message = format!("renamed {} on {} lines", renaming, count);
+	Ok(ActionOutcome::new(new_list, count, message))
+}
+
+/// A tag or context to rename, and what to rename it to.
+#[derive(Clone, Debug)]
+pub struct Renaming {
+	pub sigil: char,
+	pub from: String,
+	pub to: String,
+	/// Matches a whole word which is the old tag or context, in any case.
+	pattern: Regex,
+}
+
+impl Renaming {
+	/// Rename `from` to `to`, which are names without the sigil.
+	pub fn new(sigil: char, from: &str, to: &str) -> Self {
+		let pattern = Regex::new(&format!(
+			"(?i)^{}$",
+			regex::escape(&format!("{}{}", sigil, from))
+		))
+		.unwrap();
+		Self {
+			sigil,
+			from: String::from(from),
+			to: String::from(to),
+			pattern,
+		}
+	}
+
+	/// Rename every occurrence in a description.
+	pub fn apply(&self, description: &str) -> String {
+		RE_WORD
+			.replace_all(description, |caps: &Captures| {
+				if self.pattern.is_match(&caps[0]) {
+					format!("{}{}", self.sigil, self.to)
+				} else {
+					String::from(&caps[0])
+				}
+			})
+			.into_owned()
+	}
+
+	/// Whether a description mentions the old tag or context.
+	pub fn matches(&self, description: &str) -> bool {
+		RE_WORD
+			.find_iter(description)
+			.any(|w| self.pattern.is_match(w.as_str()))
+	}
+}
+
+impl fmt::Display for Renaming {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{}{} to {}{}",
+			self.sigil, self.from, self.sigil, self.to
+		)
+	}
+}
+
+/// Given a list and a renaming, creates a copy of the list with the tag or
+/// context renamed in every task which has it.
+///
+/// The observer will be used to check whether each individual item should be
+/// altered.
+///
+/// Also returns the number of items changed.
+pub fn rename_in_list(
+	list: List,
+	renaming: &Renaming,
+	observer: &mut dyn MutationObserver,
+) -> (List, usize) {
+	let mut new_list = list;
+	let mut count = 0;
+	observer.will_change(
+		new_list
+			.items()
+			.into_iter()
+			.filter(|i| renaming.matches(i.description()))
+			.count(),
+	);
+	for mut item in new_list.iter_items_mut() {
+		if renaming.matches(item.description()) && observer.before_change(&item)
+		{
+			let mut renamed = item.clone();
+			renamed.set_description(renaming.apply(item.description()));
+			if renamed.description() != item.description() {
+				count += 1;
+				observer.after_change(&item, &renamed);
+				*item = renamed;
+			}
+		}
+	}
+	(new_list, count)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("rename-tag"), get_action().name);
+	}
+
+	#[test]
+	fn test_parse_name() {
+		assert_eq!(Ok(String::from("site")), parse_name('+', "+site"));
+		assert_eq!(Ok(String::from("site")), parse_name('+', "site"));
+		assert_eq!(Ok(String::from("@site")), parse_name('+', "@site"));
+		assert!(parse_name('+', "+").is_err());
+		assert!(parse_name('@', "my work").is_err());
+
+		let cmd = get_action().command;
+		let m = cmd.clone().get_matches_from(vec![
+			"rename-tag",
+			"+website",
+			"site",
+		]);
+		assert_eq!(Some(&String::from("website")), m.get_one::<String>("old"));
+		assert_eq!(Some(&String::from("site")), m.get_one::<String>("new"));
+		assert!(cmd
+			.try_get_matches_from(vec!["rename-tag", "+a"])
+			.is_err());
+	}
+
+	#[test]
+	fn test_renaming_apply() {
+		let r = Renaming::new('+', "website", "site");
+		assert_eq!("+website to +site", r.to_string());
+		assert_eq!("Fix +site", r.apply("Fix +website"));
+		assert_eq!("Fix +site", r.apply("Fix +WebSite"));
+		assert_eq!(
+			"+site Fix +site menu +site",
+			r.apply("+website Fix +website menu +website")
+		);
+		// Other tags which start or end the same way are left alone.
+		for d in [
+			"Fix +website-v2",
+			"Fix +mywebsite",
+			"Fix website",
+			"Fix @website",
+			"Fix +web",
+		] {
+			assert_eq!(d, r.apply(d));
+			assert!(!r.matches(d));
+		}
+
+		// Characters which mean something in a regex are taken literally.
+		let r = Renaming::new('@', "c++", "cpp");
+		assert_eq!("Learn @cpp", r.apply("Learn @c++"));
+		assert_eq!("Learn @cc", r.apply("Learn @cc"));
+		let r = Renaming::new('@', "a.b", "ab");
+		assert_eq!("@axb", r.apply("@axb"));
+		assert_eq!("@ab", r.apply("@a.b"));
+	}
+
+	#[test]
+	fn test_rename_in_list() {
+		let source_list = List::from_string(String::from(
+			"(A) Foo +website due:2024-06-01\n\
+			\n\
+			Bar +website-v2\n\
+			x Baz +Website +website\n\
+			Bat +site\n",
+		))
+		.unwrap();
+
+		let (got, count) = rename_in_list(
+			source_list,
+			&Renaming::new('+', "website", "site"),
+			&mut NoObserver,
+		);
+		assert_eq!(2, count);
+		assert_eq!(
+			"(A) Foo +site due:2024-06-01\n\
+			\n\
+			Bar +website-v2\n\
+			x Baz +site +site\n\
+			Bat +site\n",
+			got.serialize()
+		);
+	}
+}
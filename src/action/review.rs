@@ -0,0 +1,364 @@
+//! Interactively review incomplete tasks one at a time
+
+use crate::action::done::preserve_priority_default;
+use crate::action::*;
+use crate::item::{today, Item, Urgency};
+use crate::list::{Line, List};
+use clap::{ArgMatches, Command};
+use std::io::IsTerminal;
+
+/// Options for the `review` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("review");
+	let mut command = Command::new("review")
+		.about("Interactively review incomplete tasks one at a time")
+		.after_help(
+			"For each task, press a key: [k]eep, [d]one, [x] delete, pull to \
+			[t]oday, [s]oon, or next [w]eek, change [p]riority, [n]ext to skip, \
+			or [q]uit. Changes are saved when the review ends, including when \
+			quitting early.",
+		);
+
+	command = FileType::TodoTxt.add_args(command);
+	command = Outputter::add_args(command);
+	command = SortOrder::add_args(command, default_sort_order());
+
+	Action { name, command }
+}
+
+/// The default sort order for reviewing tasks.
+pub fn default_sort_order() -> SortOrder {
+	SortOrder::Smart
+}
+
+/// A decision made about a task during review.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReviewChoice {
+	/// Leave the task as it is.
+	Keep,
+	/// Mark the task as done.
+	Done,
+	/// Delete the task.
+	Delete,
+	/// Reschedule the task.
+	Pull(Urgency),
+	/// Set the task's priority.
+	Priority(char),
+	/// Move on without deciding.
+	Skip,
+	/// Stop reviewing.
+	Quit,
+}
+
+impl ReviewChoice {
+	/// Get a choice from a keypress.
+	///
+	/// Changing priority needs a second keypress, so is not handled here.
+	pub fn from_key(c: char) -> Option<Self> {
+		match c.to_ascii_lowercase() {
+			'k' => Some(Self::Keep),
+			'd' => Some(Self::Done),
+			'x' => Some(Self::Delete),
+			't' => Some(Self::Pull(Urgency::Today)),
+			's' => Some(Self::Pull(Urgency::Soon)),
+			'w' => Some(Self::Pull(Urgency::NextWeek)),
+			'n' | ' ' | '\n' => Some(Self::Skip),
+			'q' => Some(Self::Quit),
+			_ => None,
+		}
+	}
+}
+
+/// Counts of the decisions made during a review.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReviewSummary {
+	pub kept: usize,
+	pub done: usize,
+	pub deleted: usize,
+	pub pulled: usize,
+	pub reprioritised: usize,
+	pub skipped: usize,
+}
+
+impl ReviewSummary {
+	/// Number of tasks changed.
+	pub fn changed(&self) -> usize {
+		self.done + self.deleted + self.pulled + self.reprioritised
+	}
+
+	/// Write the summary to an outputter.
+	pub fn write_to(&self, outputter: &mut Outputter) {
+		outputter.write_status(format!(
+			"Kept {}, done {}, deleted {}, rescheduled {}, reprioritised {}, skipped {}.",
+			self.kept,
+			self.done,
+			self.deleted,
+			self.pulled,
+			self.reprioritised,
+			self.skipped,
+		));
+	}
+}
+
+/// Execute the `review` subcommand.
+#[cfg(not(tarpaulin_include))]
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches(args);
+	if !io::stdin().is_terminal() {
+		outputter.write_error(String::from(
+			"The review action needs an interactive terminal.",
+		));
+		return Ok(ExitStatus::Error);
+	}
+
+	let list = FileType::TodoTxt.load(args)?;
+	outputter.fit_to_list(&list);
+	let sort_order = SortOrder::from_argmatches(args, default_sort_order());
+
+	let term = console::Term::stdout();
+	let (new_list, summary) = review_list(
+		list,
+		&sort_order,
+		&mut outputter,
+		preserve_priority_default(),
+		|_item, outputter| prompt_for_choice(&term, outputter),
+	);
+
+	if summary.changed() > 0 {
+		new_list.save()?;
+	}
+	summary.write_to(&mut outputter);
+
+	Ok(ExitStatus::Success)
+}
+
+/// Read keypresses from the terminal until a valid choice is made.
+#[cfg(not(tarpaulin_include))]
+fn prompt_for_choice(
+	term: &console::Term,
+	outputter: &mut Outputter,
+) -> ReviewChoice {
+	loop {
+		outputter.write_notice(String::from(
+			"[k]eep [d]one [x] delete [t]oday [s]oon next [w]eek [p]riority [n]ext [q]uit",
+		));
+		let c = term.read_char().unwrap_or('q');
+		if c.eq_ignore_ascii_case(&'p') {
+			outputter.write_notice(String::from("New priority letter?"));
+			if let Ok(p) =
+				parse_priority(&term.read_char().unwrap_or('?').to_string())
+			{
+				return ReviewChoice::Priority(p);
+			}
+		} else if let Some(choice) = ReviewChoice::from_key(c) {
+			return choice;
+		}
+	}
+}
+
+/// Walk through the incomplete items in a list in the given sort order,
+/// asking `choose` what to do with each one.
+///
+/// Returns a copy of the list with the decisions applied, plus a summary.
+/// Decisions made before quitting are kept.
+pub fn review_list<F>(
+	list: List,
+	sort_order: &SortOrder,
+	outputter: &mut Outputter,
+	preserve_priority: bool,
+	mut choose: F,
+) -> (List, ReviewSummary)
+where
+	F: FnMut(&Item, &mut Outputter) -> ReviewChoice,
+{
+	let order: Vec<usize> = sort_order
+		.sort_items(
+			list.items()
+				.into_iter()
+				.filter(|i| !i.completion())
+				.collect(),
+		)
+		.iter()
+		.map(|i| i.line_number())
+		.collect();
+
+	let mut new_list = list.new_like();
+	let mut lines = list.lines;
+	let mut summary = ReviewSummary::default();
+	for n in order {
+		let idx = match lines.iter().position(|l| l.num == n) {
+			Some(idx) => idx,
+			None => continue,
+		};
+		let item = lines[idx].item.clone().unwrap();
+		outputter.write_item(&item);
+		let choice = choose(&item, outputter);
+		let (notice, new_line) = match choice {
+			ReviewChoice::Keep => {
+				summary.kept += 1;
+				("Keeping", None)
+			}
+			ReviewChoice::Done => {
+				summary.done += 1;
+				(
+					"Marking done",
+					Some(lines[idx].but_done(
+						Some(today()),
+						preserve_priority,
+						true,
+					)),
+				)
+			}
+			ReviewChoice::Delete => {
+				summary.deleted += 1;
+				("Deleting", Some(Line::new_blank()))
+			}
+			ReviewChoice::Pull(urgency) => {
+				summary.pulled += 1;
+				("Rescheduling", Some(lines[idx].but_pull(urgency)))
+			}
+			ReviewChoice::Priority(p) => {
+				summary.reprioritised += 1;
+				let mut new = item.clone();
+				new.set_priority(p);
+				("Changing priority", Some(Line::from_item(new)))
+			}
+			ReviewChoice::Skip => {
+				summary.skipped += 1;
+				("Skipping", None)
+			}
+			ReviewChoice::Quit => {
+				outputter.write_notice(String::from("Quitting\n"));
+				break;
+			}
+		};
+		outputter.write_notice(format!("{}\n", notice));
+		if let Some(new_line) = new_line {
+			lines[idx] = Line { num: n, ..new_line };
+		}
+	}
+
+	new_list.lines = lines;
+	(new_list, summary)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("review"), get_action().name);
+	}
+
+	#[test]
+	fn test_default_sort_order() {
+		assert_eq!(SortOrder::Smart, default_sort_order());
+	}
+
+	#[test]
+	fn test_from_key() {
+		assert_eq!(Some(ReviewChoice::Keep), ReviewChoice::from_key('k'));
+		assert_eq!(Some(ReviewChoice::Done), ReviewChoice::from_key('D'));
+		assert_eq!(
+			Some(ReviewChoice::Pull(Urgency::NextWeek)),
+			ReviewChoice::from_key('w')
+		);
+		assert_eq!(Some(ReviewChoice::Skip), ReviewChoice::from_key(' '));
+		assert_eq!(None, ReviewChoice::from_key('p'));
+		assert_eq!(None, ReviewChoice::from_key('?'));
+	}
+
+	#[test]
+	fn test_review_list() {
+		let source_list = List::from_string(String::from(
+			"Alpha\n\
+			x Done already\n\
+			Bravo\n\
+			\n\
+			Charlie\n\
+			Delta\n\
+			Echo\n\
+			Foxtrot\n",
+		))
+		.unwrap();
+
+		let mut choices = Vec::from([
+			ReviewChoice::Done,
+			ReviewChoice::Delete,
+			ReviewChoice::Priority('B'),
+			ReviewChoice::Keep,
+			ReviewChoice::Skip,
+			ReviewChoice::Quit,
+		])
+		.into_iter();
+		let mut seen: Vec<String> = Vec::new();
+
+		let mut o = Outputter::new(1000);
+		o.io = Box::new(Vec::<u8>::new());
+		let (got, summary) = review_list(
+			source_list,
+			&SortOrder::Alphabetical,
+			&mut o,
+			false,
+			|item, _| {
+				seen.push(item.description().to_string());
+				choices.next().unwrap()
+			},
+		);
+
+		assert_eq!(
+			Vec::from([
+				"Alpha", "Bravo", "Charlie", "Delta", "Echo", "Foxtrot"
+			]),
+			seen
+		);
+		assert_eq!(
+			ReviewSummary {
+				kept: 1,
+				done: 1,
+				deleted: 1,
+				pulled: 0,
+				reprioritised: 1,
+				skipped: 1,
+			},
+			summary
+		);
+		assert_eq!(3, summary.changed());
+
+		let lines: Vec<String> = got
+			.serialize()
+			.lines()
+			.map(String::from)
+			.collect();
+		assert_eq!(8, lines.len());
+		assert!(lines[0].starts_with("x "));
+		assert!(lines[0].ends_with(" Alpha"));
+		assert_eq!("x Done already", lines[1]);
+		assert_eq!("", lines[2]);
+		assert_eq!("", lines[3]);
+		assert_eq!("(B) Charlie", lines[4]);
+		assert_eq!("Delta", lines[5]);
+		assert_eq!("Echo", lines[6]);
+		assert_eq!("Foxtrot", lines[7]);
+	}
+
+	#[test]
+	fn test_review_list_pull() {
+		let source_list = List::from_string(String::from("Alpha\n")).unwrap();
+		let mut o = Outputter::new(1000);
+		o.io = Box::new(Vec::<u8>::new());
+		let (got, summary) = review_list(
+			source_list,
+			&SortOrder::Original,
+			&mut o,
+			false,
+			|_, _| ReviewChoice::Pull(Urgency::Today),
+		);
+		assert_eq!(1, summary.pulled);
+		assert_eq!(
+			Some(Urgency::Today),
+			got.items().first().unwrap().urgency()
+		);
+	}
+}
@@ -1,90 +1,361 @@
 //! Show the full todo list
 
+use crate::action::find::find_results;
 use crate::action::*;
-use crate::item::{TshirtSize, Urgency};
-use crate::util::*;
-use clap::{ArgMatches, Command};
+use crate::item::Urgency;
+use crate::list::Line;
+use chrono::NaiveDate;
+use clap::{Arg, ArgAction, ArgMatches, Command};
 
 /// Options for the `show` subcommand.
 pub fn get_action() -> Action {
 	let name = String::from("show");
-	let mut command = Command::new("show").about("Show the full todo list");
+	let mut command = Command::new("show")
+		.about("Show the full todo list")
+		.after_help(
+			"Completed tasks are hidden, unless --all or --done-only is given. \
+			The summary still counts them.\n\n\
+			With --blocked, each task is followed by the tasks it is \
+			waiting for.\n\n\
+			With --calendar, tasks are shown under a heading for each of \
+			the next few days, even days with nothing due. Overdue tasks \
+			come first, and tasks due later or with no due date are only \
+			counted.",
+		);
 
 	command = FileType::TodoTxt.add_args(command);
 	command = Outputter::add_args(command);
 	command = SortOrder::add_args(command, default_sort_order());
 	command = Grouping::add_args(command);
+	command = FileType::add_all_profiles_arg(command);
+	command = Filters::add_args(command);
+	command = command
+		.arg(
+			Arg::new("filter")
+				.long("filter")
+				.value_name("TERM")
+				.action(ArgAction::Append)
+				.help("Only show tasks matching this, like find"),
+		)
+		.arg(
+			Arg::new("all")
+				.num_args(0)
+				.long("all")
+				.help("Include completed tasks"),
+		)
+		.arg(
+			Arg::new("done-only")
+				.num_args(0)
+				.long("done-only")
+				.alias("done")
+				.conflicts_with_all(["all", "blocked"])
+				.help("Only show completed tasks"),
+		)
+		.arg(
+			Arg::new("calendar")
+				.num_args(0)
+				.long("calendar")
+				.conflicts_with_all(["blocked", "done-only"])
+				.help("Show tasks due each day this week"),
+		)
+		.arg(
+			Arg::new("days")
+				.num_args(1)
+				.long("days")
+				.value_name("N")
+				.value_parser(clap::value_parser!(u32).range(1..))
+				.requires("calendar")
+				.help("Days to show with --calendar (default: 7)"),
+		)
+		.arg(
+			Arg::new("no-summary")
+				.num_args(0)
+				.long("no-summary")
+				.help("Don't end with a count of tasks"),
+		);
 
 	Action { name, command }
 }
 
+/// Which tasks to show, by whether they're completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionFilter {
+	/// Only tasks which aren't completed. (The default.)
+	Incomplete,
+	/// Completed tasks only.
+	Completed,
+	/// All tasks.
+	All,
+}
+
+impl CompletionFilter {
+	/// Read the filter from ArgMatches.
+	pub fn from_argmatches(args: &ArgMatches) -> Self {
+		if *args.get_one::<bool>("done-only").unwrap() {
+			Self::Completed
+		} else if *args.get_one::<bool>("all").unwrap() {
+			Self::All
+		} else {
+			Self::Incomplete
+		}
+	}
+
+	/// Whether an item should be shown.
+	pub fn keeps(&self, item: &Item) -> bool {
+		match self {
+			Self::Incomplete => !item.completion(),
+			Self::Completed => item.completion(),
+			Self::All => true,
+		}
+	}
+
+	/// Only the items which should be shown.
+	pub fn apply<'a>(&self, items: Vec<&'a Item>) -> Vec<&'a Item> {
+		items
+			.into_iter()
+			.filter(|i| self.keeps(i))
+			.collect()
+	}
+}
+
 /// The default sort order for output.
 pub fn default_sort_order() -> SortOrder {
 	SortOrder::Smart
 }
 
 /// Execute the `show` subcommand.
-pub fn execute(args: &ArgMatches) {
-	let list = FileType::TodoTxt.load(args);
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches(args);
+	let lists = FileType::TodoTxt.load_all(args)?;
+	outputter.fit_to_lists(&lists);
+	let list = ProfileList::merge(&lists);
+	outputter.note_blocked(&list);
 	let sort_order = SortOrder::from_argmatches(args, default_sort_order());
 	let grouping = Grouping::from_argmatches(args);
-	let mut outputter = Outputter::from_argmatches(args);
-	outputter.line_number_digits = list.lines.len().to_string().len();
+	let completion = CompletionFilter::from_argmatches(args);
+	let summary = !*args.get_one::<bool>("no-summary").unwrap();
+	let filters = Filters::from_argmatches(args);
+	let calendar = match *args.get_one::<bool>("calendar").unwrap() {
+		true => Some(*args.get_one::<u32>("days").unwrap_or(&7) as usize),
+		false => None,
+	};
+
+	match args.get_many::<String>("filter") {
+		_ if filters.blocked => {
+			show_blocked_list(&list, &filters, &sort_order, &mut outputter);
+		}
+		terms
+			if terms.is_some() || filters.is_active() || calendar.is_some() =>
+		{
+			let filtered = terms.is_some() || filters.is_active();
+			let filter = SearchTerms::from_vec(
+				terms
+					.map(|t| t.cloned().collect())
+					.unwrap_or_default(),
+			);
+			match calendar {
+				Some(days) => show_calendar(
+					completion.apply(
+						filters.apply(&list, find_results(&filter, &list)),
+					),
+					days,
+					&sort_order,
+					&mut outputter,
+				),
+				None => show_filtered_list(
+					&list,
+					&filter,
+					&filters,
+					completion,
+					&grouping,
+					&sort_order,
+					&mut outputter,
+				),
+			}
+			if summary {
+				let shown = shown_list(
+					filters.apply(&list, find_results(&filter, &list)),
+				);
+				write_summary(&shown, filtered, completion, &mut outputter);
+			}
+		}
+		_ => {
+			show_list(
+				&list,
+				completion,
+				&grouping,
+				&sort_order,
+				&mut outputter,
+			);
+			if summary {
+				write_summary(&list, false, completion, &mut outputter);
+			}
+		}
+	}
+	// Warnings are about the whole list, even if only some was shown.
+	outputter
+		.write_warnings(housekeeping_warnings(&list, &Config::load().hints));
 
-	show_list(&list, &grouping, &sort_order, &mut outputter);
-	maybe_housekeeping_warnings(&mut outputter, &list);
+	Ok(ExitStatus::Success)
 }
 
 /// Guts for the show command.
 ///
-/// Outputs an entire todo list with a given grouping and sort order.
+/// Outputs a todo list with a given grouping and sort order. Tasks are
+/// filtered by completion before grouping, so no empty groups are shown.
 pub fn show_list(
 	list: &List,
+	completion: CompletionFilter,
 	grouping: &Grouping,
 	sort_order: &SortOrder,
 	outputter: &mut Outputter,
 ) {
-	match grouping {
-		Grouping::Urgency => {
-			let split = group_items_by_urgency(list.items());
-			for u in Urgency::all() {
-				if let Some(items) = split.get(&u) {
-					outputter.write_heading(String::from(u.to_string()));
-					for i in sort_order.sort_items(items.to_vec()).iter() {
-						outputter.write_item(i);
-					}
-					outputter.write_separator();
-				}
-			}
+	grouping.write_items(completion.apply(list.items()), sort_order, outputter);
+}
+
+/// Like `show_list`, but only outputs tasks matching all the search terms,
+/// the same way as the find command, and all the filters.
+pub fn show_filtered_list(
+	list: &List,
+	filter: &SearchTerms,
+	filters: &Filters,
+	completion: CompletionFilter,
+	grouping: &Grouping,
+	sort_order: &SortOrder,
+	outputter: &mut Outputter,
+) {
+	grouping.write_items(
+		completion.apply(filters.apply(list, find_results(filter, list))),
+		sort_order,
+		outputter,
+	);
+}
+
+/// Outputs tasks as a calendar of the next `days` days, starting today.
+///
+/// Every day gets a heading, with a dash if nothing is due, so the shape
+/// of the week is visible. Overdue tasks come first. Tasks due after the
+/// last day, and tasks with no due date, are only counted.
+pub fn show_calendar(
+	items: Vec<&Item>,
+	days: usize,
+	sort_order: &SortOrder,
+	outputter: &mut Outputter,
+) {
+	let today = crate::item::today();
+	let mut split = group_items_by_due_date(items);
+	let mut write_group = |heading: String, group: Vec<&Item>| {
+		outputter.write_heading(heading);
+		if group.is_empty() {
+			outputter.write_empty_group();
 		}
-		Grouping::Importance => {
-			let split = group_items_by_importance(list.items());
-			for u in Importance::all() {
-				if let Some(items) = split.get(&u) {
-					outputter.write_heading(String::from(u.to_string()));
-					for i in sort_order.sort_items(items.to_vec()).iter() {
-						outputter.write_item(i);
-					}
-					outputter.write_separator();
-				}
-			}
+		for i in sort_order.sort_items(group) {
+			outputter.write_item(i);
 		}
-		Grouping::TshirtSize => {
-			let split = group_items_by_size(list.items());
-			for u in TshirtSize::all() {
-				if let Some(items) = split.get(&u) {
-					outputter.write_heading(String::from(u.to_string()));
-					for i in sort_order.sort_items(items.to_vec()).iter() {
-						outputter.write_item(i);
-					}
-					outputter.write_separator();
-				}
-			}
+		outputter.write_separator();
+	};
+
+	let mut past: Vec<NaiveDate> = split
+		.keys()
+		.flatten()
+		.filter(|d| **d < today)
+		.copied()
+		.collect();
+	past.sort();
+	let overdue: Vec<&Item> = past
+		.iter()
+		.flat_map(|d| split.remove(&Some(*d)).unwrap())
+		.collect();
+	if !overdue.is_empty() {
+		write_group(String::from("Overdue"), overdue);
+	}
+	for day in today.iter_days().take(days) {
+		let group = split.remove(&Some(day)).unwrap_or_default();
+		write_group(day_heading(day), group);
+	}
+
+	// Only tasks due after the last day are left with a due date.
+	let undated = split.remove(&None).map_or(0, |g| g.len());
+	let later: usize = split.values().map(|g| g.len()).sum();
+	for (heading, count) in [("Later", later), ("No due date", undated)] {
+		if count > 0 {
+			outputter.write_heading(format!("{} ({})", heading, count));
+			outputter.write_separator();
 		}
-		Grouping::None => {
-			for i in sort_order.sort_items(list.items()).iter() {
-				outputter.write_item(i);
-			}
+	}
+}
+
+/// A list of the tasks shown, keeping their line numbers, unlike
+/// `List::from_items`, so tasks noted as blocked are still recognised.
+pub fn shown_list(items: Vec<&Item>) -> List {
+	let mut list = List::new();
+	for item in items {
+		list.lines.push(Line {
+			num: item.line_number(),
+			..Line::from_item(item.clone())
+		});
+	}
+	list
+}
+
+/// Outputs a line counting the tasks in a list, and how many are overdue,
+/// due this week, completed, and blocked.
+///
+/// If `filtered` is true, the line says the counts are only of matching
+/// tasks. The counts include tasks hidden by `completion`, and the line
+/// says if completed tasks were hidden.
+pub fn write_summary(
+	list: &List,
+	filtered: bool,
+	completion: CompletionFilter,
+	outputter: &mut Outputter,
+) {
+	let overdue = list.count_overdue();
+	let this_week = list.count_due_by(Urgency::ThisWeek.due_date()) - overdue;
+	let blocked = list
+		.items()
+		.iter()
+		.filter(|i| !i.completion() && outputter.is_blocked(i))
+		.count();
+	let completed = list.count_completed();
+	outputter.write_status(format!(
+		"{} {}tasks: {} overdue, {} due this week, {} completed{}, {} blocked",
+		list.count_items(),
+		if filtered { "matching " } else { "" },
+		overdue,
+		this_week,
+		completed,
+		if completion == CompletionFilter::Incomplete && completed > 0 {
+			" (hidden)"
+		} else {
+			""
+		},
+		blocked,
+	));
+}
+
+/// Outputs the unfinished tasks which are waiting for other unfinished
+/// tasks and match the filters, each followed by the tasks it is waiting
+/// for.
+pub fn show_blocked_list(
+	list: &List,
+	filters: &Filters,
+	sort_order: &SortOrder,
+	outputter: &mut Outputter,
+) {
+	let blocked = list.blocked_items();
+	let items = filters.apply(list, blocked.iter().map(|(i, _)| *i).collect());
+	for item in sort_order.sort_items(items) {
+		outputter.write_item(item);
+		let (_, blockers) = blocked
+			.iter()
+			.find(|(i, _)| std::ptr::eq(*i, item))
+			.unwrap();
+		for blocker in blockers {
+			outputter.write_notice(format!(
+				"    waiting for: {}",
+				blocker.description()
+			));
 		}
 	}
 }
@@ -92,7 +363,7 @@ pub fn show_list(
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use crate::Line;
+	use crate::list::LineEnding;
 	use tempfile::tempdir;
 
 	#[test]
@@ -122,12 +393,26 @@ mod tests {
 				Line::from_string(String::from("Bat"), 4),
 			]),
 			path: None,
+			url: None,
+			etag: None,
+			last_modified: None,
+			invalid_lines: Vec::new(),
+			line_ending: LineEnding::Lf,
+			missing_final_newline: false,
+			bom: false,
+			partial: false,
 		};
 
 		let mut o = Outputter::new(9999);
 		o.colour = false;
 		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
-		show_list(&source_list, &Grouping::None, &SortOrder::Original, &mut o);
+		show_list(
+			&source_list,
+			CompletionFilter::All,
+			&Grouping::None,
+			&SortOrder::Original,
+			&mut o,
+		);
 		let got_output = fs::read_to_string(buffer_filename.clone()).unwrap();
 		assert_eq!(
 			String::from(
@@ -145,6 +430,7 @@ mod tests {
 		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
 		show_list(
 			&source_list,
+			CompletionFilter::All,
 			&Grouping::None,
 			&SortOrder::Alphabetical,
 			&mut o,
@@ -166,6 +452,7 @@ mod tests {
 		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
 		show_list(
 			&source_list,
+			CompletionFilter::All,
 			&Grouping::Importance,
 			&SortOrder::Alphabetical,
 			&mut o,
@@ -193,6 +480,7 @@ mod tests {
 		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
 		show_list(
 			&source_list,
+			CompletionFilter::All,
 			&Grouping::Importance,
 			&SortOrder::Original,
 			&mut o,
@@ -221,6 +509,7 @@ mod tests {
 		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
 		show_list(
 			&source_list,
+			CompletionFilter::All,
 			&Grouping::Importance,
 			&SortOrder::Original,
 			&mut o,
@@ -240,4 +529,295 @@ mod tests {
 			got_output
 		);
 	}
+
+	#[test]
+	fn test_show_blocked_list() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let source_list = List::from_string(String::from(
+			"(A) Foo id:a\n(B) Bar id:b\n(C) Baz after:a,b\nBat after:b\nx Qux after:a\n",
+		))
+		.unwrap();
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		let buffer = fs::File::create(buffer_filename.clone()).unwrap();
+		o.msg_io = Box::new(buffer.try_clone().unwrap());
+		o.io = Box::new(buffer);
+		show_blocked_list(
+			&source_list,
+			&Filters::default(),
+			&SortOrder::Original,
+			&mut o,
+		);
+		let got_output = fs::read_to_string(buffer_filename).unwrap();
+		assert_eq!(
+			"  (C) Baz after:a,b\n    \
+			waiting for: Foo id:a\n    \
+			waiting for: Bar id:b\n  \
+			(?) Bat after:b\n    \
+			waiting for: Bar id:b\n",
+			got_output
+		);
+	}
+
+	#[test]
+	fn test_show_filtered_list() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let source_list = List::from_string(String::from(
+			"(A) Foo @work\n(B) Bar @home\n(D) Baz @work +x\nBat\n",
+		))
+		.unwrap();
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		show_filtered_list(
+			&source_list,
+			&SearchTerms::from_string("@work"),
+			&Filters::default(),
+			CompletionFilter::All,
+			&Grouping::Importance,
+			&SortOrder::Original,
+			&mut o,
+		);
+		let got_output = fs::read_to_string(buffer_filename.clone()).unwrap();
+		assert_eq!(
+			"# Critical\n  (A) Foo @work\n\n# Normal\n  (D) Baz @work +x\n\n",
+			got_output
+		);
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		show_filtered_list(
+			&source_list,
+			&SearchTerms::from_vec(Vec::from([
+				String::from("@work"),
+				String::from("foo"),
+			])),
+			&Filters::default(),
+			CompletionFilter::All,
+			&Grouping::None,
+			&SortOrder::Original,
+			&mut o,
+		);
+		let got_output = fs::read_to_string(buffer_filename.clone()).unwrap();
+		assert_eq!("  (A) Foo @work\n", got_output);
+
+		let source_list = List::from_string(String::from(
+			"Foo due:2024-06-05\nBar due:2024-06-07\nBaz due:2024-06-08\nBat\n",
+		))
+		.unwrap();
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		let friday = chrono::NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+		crate::item::with_today(friday, || {
+			show_filtered_list(
+				&source_list,
+				&SearchTerms::new(),
+				&Filters {
+					urgencies: Vec::from([Urgency::Overdue, Urgency::Soon]),
+					..Filters::default()
+				},
+				CompletionFilter::All,
+				&Grouping::None,
+				&SortOrder::Original,
+				&mut o,
+			);
+		});
+		let got_output = fs::read_to_string(buffer_filename).unwrap();
+		assert_eq!(
+			"  (?) Foo due:2024-06-05\n  (?) Baz due:2024-06-08\n",
+			got_output
+		);
+	}
+
+	#[test]
+	fn test_show_calendar() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let list = List::from_string(String::from(
+			"Foo due:2024-06-05\n\
+			Bar due:2024-06-09\n\
+			Baz due:2024-06-01\n\
+			Bat due:2024-06-30\n\
+			Quux\n\
+			Quuux due:2024-06-07\n",
+		))
+		.unwrap();
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		let friday = chrono::NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+		crate::item::with_today(friday, || {
+			show_calendar(list.items(), 3, &SortOrder::Original, &mut o);
+		});
+		let expected = [
+			"# Overdue",
+			"  (?) Foo due:2024-06-05",
+			"  (?) Baz due:2024-06-01",
+			"",
+			"# Friday 2024-06-07",
+			"  (?) Quuux due:2024-06-07",
+			"",
+			"# Saturday 2024-06-08",
+			"  —",
+			"",
+			"# Sunday 2024-06-09",
+			"  (?) Bar due:2024-06-09",
+			"",
+			"# Later (1)",
+			"",
+			"# No due date (1)",
+			"",
+		];
+		assert_eq!(
+			format!("{}\n", expected.join("\n")),
+			fs::read_to_string(buffer_filename).unwrap()
+		);
+
+		let cmd = get_action().command;
+		let m = cmd.clone().get_matches_from(vec![
+			"show",
+			"--calendar",
+			"--days",
+			"3",
+		]);
+		assert_eq!(Some(&3), m.get_one::<u32>("days"));
+		assert!(cmd
+			.clone()
+			.try_get_matches_from(vec!["show", "--days", "3"])
+			.is_err());
+		assert!(cmd
+			.try_get_matches_from(vec!["show", "--calendar", "--days", "0"])
+			.is_err());
+	}
+
+	#[test]
+	fn test_write_summary() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let source_list = List::from_string(String::from(
+			"Foo id:a due:2024-06-06\nBar after:a due:2024-06-08\n\
+			Baz due:2024-06-20\nx Bat after:a\n",
+		))
+		.unwrap();
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.note_blocked(&source_list);
+		let buffer = fs::File::create(buffer_filename.clone()).unwrap();
+		o.msg_io = Box::new(buffer.try_clone().unwrap());
+		o.io = Box::new(buffer);
+		let friday = chrono::NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+		crate::item::with_today(friday, || {
+			write_summary(&source_list, false, CompletionFilter::All, &mut o);
+			write_summary(
+				&shown_list(source_list.items()[1..].to_vec()),
+				true,
+				CompletionFilter::All,
+				&mut o,
+			);
+			write_summary(
+				&source_list,
+				false,
+				CompletionFilter::Incomplete,
+				&mut o,
+			);
+		});
+		let got_output = fs::read_to_string(buffer_filename).unwrap();
+		assert_eq!(
+			"4 tasks: 1 overdue, 1 due this week, 1 completed, 1 blocked\n\
+			3 matching tasks: 0 overdue, 1 due this week, 1 completed, 1 blocked\n\
+			4 tasks: 1 overdue, 1 due this week, 1 completed (hidden), 1 blocked\n",
+			got_output
+		);
+	}
+
+	#[test]
+	fn test_show_list_completion() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let source_list = List::from_string(String::from(
+			"x (A) Foo\n(B) Bar\nx 2024-06-01 Baz\n",
+		))
+		.unwrap();
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		show_list(
+			&source_list,
+			CompletionFilter::Incomplete,
+			&Grouping::Importance,
+			&SortOrder::Original,
+			&mut o,
+		);
+		let got_output = fs::read_to_string(buffer_filename.clone()).unwrap();
+		assert_eq!("# Important\n  (B) Bar\n\n", got_output);
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		show_list(
+			&source_list,
+			CompletionFilter::Completed,
+			&Grouping::None,
+			&SortOrder::Original,
+			&mut o,
+		);
+		let got_output = fs::read_to_string(buffer_filename).unwrap();
+		assert_eq!("x (A) Foo\nx (?) Baz\n", got_output);
+	}
+
+	#[test]
+	fn test_completion_filter_from_argmatches() {
+		let cmd = get_action().command;
+		let m = cmd.clone().get_matches_from(vec!["show"]);
+		assert_eq!(
+			CompletionFilter::Incomplete,
+			CompletionFilter::from_argmatches(&m)
+		);
+		let m = cmd
+			.clone()
+			.get_matches_from(vec!["show", "--all"]);
+		assert_eq!(
+			CompletionFilter::All,
+			CompletionFilter::from_argmatches(&m)
+		);
+		let m = cmd
+			.clone()
+			.get_matches_from(vec!["show", "--done"]);
+		assert_eq!(
+			CompletionFilter::Completed,
+			CompletionFilter::from_argmatches(&m)
+		);
+		assert!(cmd
+			.try_get_matches_from(vec!["show", "--all", "--done-only"])
+			.is_err());
+	}
 }
@@ -0,0 +1,252 @@
+//! Hide a task or tasks until a later date
+
+use crate::action::*;
+use crate::item::{parse_date_arg, today};
+use crate::list::List;
+use chrono::NaiveDate;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+
+/// Options for the `snooze` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("snooze");
+	let mut command = Command::new("snooze")
+		.about("Hide a task or tasks until a later date")
+		.after_help(
+			"Sets the start date of each task, so urgent, important, and \
+			quick skip it until then. Any existing start date is replaced.\n\n\
+			Periods are a number of days, weeks, months, or years, like \
+			3d, 2w, 1m, or 1y. With --until, leave the period out.",
+		);
+
+	command = FileType::TodoTxt.add_args(command);
+	command = Outputter::add_args(command);
+	command = command
+		.arg(
+			Arg::new("until")
+				.long("until")
+				.value_name("DATE")
+				.value_parser(parse_until_date)
+				.help("Snooze until a date, like 2024-06-01, not for a period"),
+		)
+		.arg(
+			Arg::new("period")
+				.required(true)
+				.value_name("PERIOD")
+				.help("How long to snooze for, like 3d, 2w, or 1m"),
+		)
+		.arg(
+			Arg::new("search-term")
+				.action(ArgAction::Append)
+				.help("A tag, context, line number, id, or string"),
+		);
+	command = ConfirmationStatus::add_args(command);
+	command = SaveMode::add_args(command);
+
+	Action { name, command }
+}
+
+/// Accept a date to snooze until, like "2024-06-01" or "next_monday".
+fn parse_until_date(s: &str) -> Result<NaiveDate, String> {
+	parse_date_arg(s).ok_or_else(|| {
+		String::from("expected a date like 2024-06-01 or next_monday")
+	})
+}
+
+/// Execute the `snooze` subcommand.
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches(args);
+
+	// With --until, there's no period, so the first argument is a search
+	// term after all.
+	let mut terms: Vec<String> = args
+		.get_many::<String>("period")
+		.into_iter()
+		.flatten()
+		.chain(
+			args.get_many::<String>("search-term")
+				.into_iter()
+				.flatten(),
+		)
+		.cloned()
+		.collect();
+	let until = match args.get_one::<NaiveDate>("until") {
+		Some(date) => *date,
+		None => {
+			let period = terms.remove(0);
+			match date_after_period(today(), &period) {
+				Some(date) => date,
+				None => {
+					outputter.write_error(format!(
+						"Can't snooze for '{}'; try a period like 3d, 2w, or 1m.",
+						period
+					));
+					return Ok(ExitStatus::Error);
+				}
+			}
+		}
+	};
+	if terms.is_empty() {
+		outputter.write_error(String::from(
+			"Which tasks? Give a tag, context, line number, id, or string.",
+		));
+		return Ok(ExitStatus::Error);
+	}
+
+	let list = FileType::TodoTxt.load(args)?;
+	outputter.fit_to_list(&list);
+
+	let cfg = SnoozeActionConfig {
+		search_terms: SearchTerms::from_vec(terms),
+		until,
+	};
+	let confirmation = ConfirmationStatus::from_argmatches(args);
+	let save_mode = SaveMode::from_argmatches(args);
+
+	let before = list.serialize();
+	let mut confirmer = Confirmer::new(
+		&mut outputter,
+		confirmation,
+		"Snooze?",
+		"Snoozing",
+		"Skipping",
+	);
+	let outcome = run(list, &cfg, &mut confirmer)?;
+	if outcome.count > 0 {
+		save_mode.save_with_diff(&outcome.list, &before, &mut outputter)?;
+	}
+	for status in save_mode.outcome_status(&outcome) {
+		outputter.write_status(status);
+	}
+
+	outputter.write_warnings(housekeeping_warnings(
+		&outcome.list,
+		&Config::load().hints,
+	));
+
+	Ok(outcome.exit_status())
+}
+
+/// Config object for the `snooze` action.
+#[derive(Clone)]
+pub struct SnoozeActionConfig {
+	pub search_terms: SearchTerms,
+	/// The date tasks are hidden until.
+	pub until: NaiveDate,
+}
+
+/// Run the `snooze` action on a list, without saving it.
+///
+/// The observer will be used to check whether each individual item should be
+/// altered.
+pub fn run(
+	list: List,
+	cfg: &SnoozeActionConfig,
+	observer: &mut dyn MutationObserver,
+) -> Result<ActionOutcome, Error> {
+	let (new_list, count) = snooze_items_in_list(
+		list,
+		cfg.search_terms.clone(),
+		cfg.until,
+		observer,
+	);
+	let message = format!(
+		"snoozed {} tasks in {} until {}",
+		count,
+		new_list.display_name(),
+		cfg.until
+	);
+	Ok(ActionOutcome::new(new_list, count, message))
+}
+
+/// Given a list, set of search terms, and a date, creates a copy of the list
+/// with all incomplete items matching the search terms snoozed until that
+/// date.
+///
+/// The observer will be used to check whether each individual item should be
+/// altered.
+///
+/// Also returns the number of items changed.
+pub fn snooze_items_in_list(
+	list: List,
+	search_terms: SearchTerms,
+	until: NaiveDate,
+	observer: &mut dyn MutationObserver,
+) -> (List, usize) {
+	let mut new_list = list;
+	let mut count = 0;
+	observer.will_change(
+		new_list
+			.items()
+			.into_iter()
+			.filter(|i| search_terms.item_matches(i) && !i.completion())
+			.count(),
+	);
+	for mut item in new_list.iter_items_mut() {
+		if search_terms.item_matches(&item)
+			&& (!item.completion())
+			&& observer.before_change(&item)
+		{
+			count += 1;
+			let snoozed = item.but_snooze(until);
+			observer.after_change(&item, &snoozed);
+			*item = snoozed;
+		}
+	}
+	(new_list, count)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("snooze"), get_action().name);
+	}
+
+	#[test]
+	fn test_args() {
+		let cmd = get_action().command;
+		let m = cmd
+			.clone()
+			.get_matches_from(vec!["snooze", "1w", "@someday"]);
+		assert_eq!(Some(&String::from("1w")), m.get_one::<String>("period"));
+		assert_eq!(None, m.get_one::<NaiveDate>("until"));
+
+		let m = cmd.clone().get_matches_from(vec![
+			"snooze",
+			"--until",
+			"2024-06-10",
+			"@someday",
+		]);
+		assert_eq!(
+			Some(&NaiveDate::from_ymd_opt(2024, 6, 10).unwrap()),
+			m.get_one::<NaiveDate>("until")
+		);
+		assert!(cmd
+			.try_get_matches_from(vec!["snooze", "--until", "soonish", "x"])
+			.is_err());
+	}
+
+	#[test]
+	fn test_snooze_items_in_list() {
+		let source_list = List::from_string(String::from(
+			"Foo1 start:2024-06-01\nFoo2 due:2024-06-20\n\nBar\nx Foo3\n",
+		))
+		.unwrap();
+		let until = NaiveDate::from_ymd_opt(2024, 6, 14).unwrap();
+
+		let (got, count) = snooze_items_in_list(
+			source_list,
+			SearchTerms::from_string("foo"),
+			until,
+			&mut NoObserver,
+		);
+		assert_eq!(2, count);
+		assert_eq!(
+			"Foo1 start:2024-06-14\nFoo2 due:2024-06-20 start:2024-06-14\n\n\
+			Bar\nx Foo3\n",
+			got.serialize()
+		);
+	}
+}
@@ -0,0 +1,248 @@
+//! Start tracking time on a task
+
+use crate::action::find::find_results;
+use crate::action::stop::write_stopped;
+use crate::action::*;
+use crate::item::{now, Item};
+use crate::list::List;
+use chrono::NaiveDateTime;
+use clap::{ArgMatches, Command};
+
+/// Options for the `start` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("start");
+	let mut command = Command::new("start")
+		.about("Start tracking time on a task")
+		.after_help(
+			"The search terms must match exactly one unfinished task. Any other \
+			task being tracked is stopped first, so only one task is tracked at \
+			a time.\n\n\
+			Use `tada stop` to stop tracking time and record it in a `spent:` tag.",
+		);
+
+	command = FileType::TodoTxt.add_args(command);
+	command = Outputter::add_args(command);
+	command = SearchTerms::add_args(command);
+	command = SaveMode::add_args(command);
+
+	Action { name, command }
+}
+
+/// Execute the `start` subcommand.
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches(args);
+	let list = FileType::TodoTxt.load(args)?;
+	outputter.fit_to_list(&list);
+
+	let search_terms = SearchTerms::from_argmatches(args);
+	let save_mode = SaveMode::from_argmatches(args);
+
+	let found: Vec<usize> = find_results(&search_terms, &list)
+		.into_iter()
+		.filter(|i| !i.completion())
+		.map(|i| i.line_number())
+		.collect();
+	let num = match found[..] {
+		[num] => num,
+		[] => {
+			outputter.write_status(String::from("No actions taken."));
+			return Ok(ExitStatus::NoMatch);
+		}
+		_ => {
+			outputter.write_error(format!(
+				"{} tasks matched; be more specific, like `tada start #{}`.",
+				found.len(),
+				found[0]
+			));
+			return Ok(ExitStatus::Error);
+		}
+	};
+
+	let already = list
+		.item_at(num)
+		.is_some_and(|i| i.started().is_some());
+	let before = list.serialize();
+	let cfg = StartActionConfig {
+		line: num,
+		at: now(),
+	};
+	let outcome = run(
+		list,
+		&cfg,
+		&mut ChangeWriter {
+			outputter: &mut outputter,
+			write: write_started,
+		},
+	)?;
+	let new_list = outcome.list;
+	if already {
+		outputter.write_item(new_list.item_at(num).unwrap());
+	}
+	if save_mode.save_with_diff(&new_list, &before, &mut outputter)? {
+		outputter.write_status(save_mode.status(
+			String::from("Started tracking time."),
+			String::from("started tracking time"),
+		));
+	} else {
+		outputter.write_status(String::from("Already tracking time."));
+	}
+
+	Ok(ExitStatus::Success)
+}
+
+/// Config object for the `start` action.
+#[derive(Clone)]
+pub struct StartActionConfig {
+	/// The line number of the task to start tracking, which can be found
+	/// with `find::find_results`.
+	pub line: usize,
+	/// When time tracking starts.
+	pub at: NaiveDateTime,
+}
+
+/// Run the `start` action on a list, without saving it.
+///
+/// The observer is told about each task stopped or started. Nothing changes
+/// for a task which is already being tracked.
+pub fn run(
+	list: List,
+	cfg: &StartActionConfig,
+	observer: &mut dyn MutationObserver,
+) -> Result<ActionOutcome, Error> {
+	let (new_list, count) =
+		start_item_in_list(list, cfg.line, cfg.at, observer);
+	let message = format!(
+		"started tracking time on line {} of {}",
+		cfg.line,
+		new_list.display_name()
+	);
+	Ok(ActionOutcome::new(new_list, count, message))
+}
+
+/// Given a list and a line number, creates a copy of the list with time
+/// tracking started on that line's task at the given time.
+///
+/// Any other task being tracked is stopped first. A task which is already
+/// being tracked keeps its original start time. The observer will be used
+/// to check whether each task should be stopped or started.
+///
+/// Also returns the number of tasks stopped or started.
+pub fn start_item_in_list(
+	list: List,
+	num: usize,
+	at: NaiveDateTime,
+	observer: &mut dyn MutationObserver,
+) -> (List, usize) {
+	let changes =
+		|item: &Item| (item.line_number() == num) != item.started().is_some();
+	let mut new_list = list;
+	let mut count = 0;
+	observer.will_change(
+		new_list
+			.items()
+			.into_iter()
+			.filter(|i| changes(i))
+			.count(),
+	);
+	for mut item in new_list.iter_items_mut() {
+		if item.line_number() != num
+			&& changes(&item)
+			&& observer.before_change(&item)
+		{
+			count += 1;
+			let stopped = item.but_stopped(at);
+			observer.after_change(&item, &stopped);
+			*item = stopped;
+		}
+	}
+	for mut item in new_list.iter_items_mut() {
+		if item.line_number() == num
+			&& changes(&item)
+			&& observer.before_change(&item)
+		{
+			count += 1;
+			let started = item.but_started(at);
+			observer.after_change(&item, &started);
+			*item = started;
+		}
+	}
+	(new_list, count)
+}
+
+/// Writes a task which has started or stopped being tracked.
+pub fn write_started(old: &Item, new: &Item, outputter: &mut Outputter) {
+	if new.started().is_some() {
+		outputter.write_item(new);
+	} else {
+		write_stopped(old, new, outputter);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::NaiveDate;
+	use tempfile::tempdir;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("start"), get_action().name);
+	}
+
+	#[test]
+	fn test_start_item_in_list() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let at = NaiveDate::from_ymd_opt(2024, 6, 1)
+			.unwrap()
+			.and_hms_opt(10, 0, 0)
+			.unwrap();
+		let source_list = List::from_string(String::from(
+			"Foo started:2024-06-01T09:15\nBar\n(A) Baz spent:5m\n",
+		))
+		.unwrap();
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		let buffer = fs::File::create(buffer_filename.clone()).unwrap();
+		o.msg_io = Box::new(buffer.try_clone().unwrap());
+		o.io = Box::new(buffer);
+		let (got, count) = start_item_in_list(
+			source_list,
+			3,
+			at,
+			&mut ChangeWriter {
+				outputter: &mut o,
+				write: write_started,
+			},
+		);
+		assert_eq!(2, count);
+		assert_eq!("Foo spent:45m", got.lines[0].text);
+		assert_eq!("Bar", got.lines[1].text);
+		assert_eq!(
+			"(A) Baz spent:5m started:2024-06-01T10:00",
+			got.lines[2].text
+		);
+		let got_output = fs::read_to_string(buffer_filename.clone()).unwrap();
+		assert_eq!(
+			"  (?) Foo spent:45m\n    \
+			spent 45m, 45m in total\n  \
+			(A) Baz spent:5m started:2024-06-01T10:00\n",
+			got_output
+		);
+
+		// Starting it again keeps the original start time.
+		let later = at + chrono::Duration::minutes(10);
+		let cfg = StartActionConfig { line: 3, at: later };
+		let outcome = run(got, &cfg, &mut NoObserver).unwrap();
+		assert_eq!(0, outcome.count);
+		assert_eq!(
+			"(A) Baz spent:5m started:2024-06-01T10:00",
+			outcome.list.lines[2].text
+		);
+	}
+}
@@ -0,0 +1,749 @@
+//! Show statistics about the todo list
+
+use crate::action::*;
+use crate::item::{format_duration, today, Urgency};
+use chrono::{Datelike, Duration, NaiveDate};
+use clap::{Arg, ArgMatches, Command};
+
+/// Options for the `stats` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("stats");
+	let mut command = Command::new("stats")
+		.about("Show statistics about the todo list")
+		.after_help(
+			"Counts of completed tasks include tasks from both todo.txt \
+			and done.txt.\n\n\
+			Time spent by context totals the `spent:` tags of open and \
+			completed tasks.\n\n\
+			With --velocity, counts tasks completed each week for the last \
+			8 weeks, or each day for the last 14 days with --daily. Tasks \
+			with no completion date can't be counted, but are mentioned.",
+		);
+
+	command = FileType::TodoTxt.add_args(command);
+	command = FileType::DoneTxt.add_args(command);
+	command = Outputter::add_args_minimal(command);
+	command = command
+		.arg(
+			Arg::new("json")
+				.num_args(0)
+				.long("json")
+				.help("Output raw numbers as JSON"),
+		)
+		.arg(
+			Arg::new("velocity")
+				.num_args(0)
+				.long("velocity")
+				.help("Count tasks completed each week"),
+		)
+		.arg(
+			Arg::new("daily")
+				.num_args(0)
+				.long("daily")
+				.requires("velocity")
+				.help("Count each day instead of each week"),
+		)
+		.arg(
+			Arg::new("last")
+				.num_args(1)
+				.long("last")
+				.value_name("N")
+				.value_parser(clap::value_parser!(u32).range(1..))
+				.requires("velocity")
+				.help("How many weeks, or days, to count"),
+		)
+		.arg(
+			Arg::new("by")
+				.num_args(1)
+				.long("by")
+				.value_name("BY")
+				.value_parser(|s: &str| {
+					Grouping::from_string(s).map_err(|e| e.to_string())
+				})
+				.requires("velocity")
+				.help("Also count each group, like 'context'"),
+		)
+		.arg(
+			Arg::new("chart")
+				.num_args(0)
+				.long("chart")
+				.requires("velocity")
+				.conflicts_with("json")
+				.help("Draw the counts as a bar chart"),
+		);
+
+	Action { name, command }
+}
+
+/// Execute the `stats` subcommand.
+#[cfg(not(tarpaulin_include))]
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let todo = FileType::TodoTxt.load(args)?;
+	let done = FileType::DoneTxt
+		.filename(args)
+		.ok()
+		.and_then(|f| List::from_url(f).ok())
+		.unwrap_or_default();
+	let mut outputter = Outputter::from_argmatches_minimal(args);
+
+	if *args.get_one::<bool>("velocity").unwrap() {
+		let period = match *args.get_one::<bool>("daily").unwrap() {
+			true => Period::Day,
+			false => Period::Week,
+		};
+		let count = args
+			.get_one::<u32>("last")
+			.map(|n| *n as usize)
+			.unwrap_or(period.default_count());
+		let grouping = args
+			.get_one::<Grouping>("by")
+			.copied()
+			.unwrap_or(Grouping::None);
+		let velocity =
+			Velocity::from_lists(&todo, &done, period, count, grouping);
+		if *args.get_one::<bool>("json").unwrap() {
+			outputter.write_data(velocity.to_json());
+		} else {
+			let chart = *args.get_one::<bool>("chart").unwrap();
+			velocity.write_to(&mut outputter, chart);
+		}
+		return Ok(ExitStatus::Success);
+	}
+
+	let stats = Statistics::from_lists(&todo, &done);
+	if *args.get_one::<bool>("json").unwrap() {
+		outputter.write_data(stats.to_json());
+	} else {
+		stats.write_to(&mut outputter);
+	}
+
+	Ok(ExitStatus::Success)
+}
+
+/// Summary statistics for a todo list and its done list.
+#[derive(Debug, Default)]
+pub struct Statistics {
+	pub open: usize,
+	pub completed: usize,
+	pub overdue: usize,
+	pub completed_last_7_days: usize,
+	pub completed_last_30_days: usize,
+	pub by_urgency: Vec<(Urgency, usize)>,
+	pub by_importance: Vec<(Importance, usize)>,
+	pub by_context: Vec<(String, usize)>,
+	pub time_by_context: Vec<(String, Duration)>,
+}
+
+impl Statistics {
+	/// Calculate statistics for a todo list and a done list.
+	///
+	/// Breakdowns by urgency, importance, and context only consider open tasks,
+	/// but time spent by context considers all tasks.
+	pub fn from_lists(todo: &List, done: &List) -> Self {
+		let open: Vec<&Item> = todo
+			.items()
+			.into_iter()
+			.filter(|i| !i.completion())
+			.collect();
+		let completed: Vec<&Item> = todo
+			.items()
+			.into_iter()
+			.chain(done.items())
+			.filter(|i| i.completion())
+			.collect();
+
+		let today = today();
+		let urgency_counts = count_items_by_urgency(open.clone());
+		let importance_counts = count_items_by_importance(open.clone());
+		let mut by_context: Vec<(String, usize)> =
+			count_items_by_context(open.clone())
+				.into_iter()
+				.collect();
+		by_context.sort();
+		let mut time_by_context: Vec<(String, Duration)> =
+			sum_time_spent_by_context(
+				todo.items()
+					.into_iter()
+					.chain(done.items())
+					.collect(),
+			)
+			.into_iter()
+			.collect();
+		time_by_context.sort();
+
+		Self {
+			open: open.len(),
+			completed: completed.len(),
+			overdue: *urgency_counts
+				.get(&Urgency::Overdue)
+				.unwrap_or(&0),
+			completed_last_7_days: count_items_completed_since(
+				completed.clone(),
+				today - Duration::days(6),
+			),
+			completed_last_30_days: count_items_completed_since(
+				completed,
+				today - Duration::days(29),
+			),
+			by_urgency: Urgency::all()
+				.into_iter()
+				.filter_map(|u| urgency_counts.get(&u).map(|n| (u, *n)))
+				.collect(),
+			by_importance: Importance::all()
+				.into_iter()
+				.filter_map(|i| importance_counts.get(&i).map(|n| (i, *n)))
+				.collect(),
+			by_context,
+			time_by_context,
+		}
+	}
+
+	/// Write the statistics in a human-readable format.
+	pub fn write_to(&self, outputter: &mut Outputter) {
+		outputter.write_heading(String::from("Tasks"));
+		outputter.write_data(format!("Open:                {}", self.open));
+		outputter
+			.write_data(format!("Completed:           {}", self.completed));
+		outputter.write_data(format!("Overdue:             {}", self.overdue));
+		outputter.write_data(format!(
+			"Completed (7 days):  {}",
+			self.completed_last_7_days
+		));
+		outputter.write_data(format!(
+			"Completed (30 days): {}",
+			self.completed_last_30_days
+		));
+
+		if !self.by_urgency.is_empty() {
+			outputter.write_separator();
+			outputter.write_heading(String::from("Urgency"));
+			for (u, n) in &self.by_urgency {
+				outputter.write_data(format!("{:<20} {}", u, n));
+			}
+		}
+
+		if !self.by_importance.is_empty() {
+			outputter.write_separator();
+			outputter.write_heading(String::from("Importance"));
+			for (i, n) in &self.by_importance {
+				outputter.write_data(format!(
+					"({}) {:<16} {}",
+					i.to_char(),
+					i,
+					n
+				));
+			}
+		}
+
+		if !self.by_context.is_empty() {
+			outputter.write_separator();
+			outputter.write_heading(String::from("Contexts"));
+			for (c, n) in &self.by_context {
+				let label = format!("@{}", c);
+				outputter.write_data(format!("{:<20} {}", label, n));
+			}
+		}
+
+		if !self.time_by_context.is_empty() {
+			outputter.write_separator();
+			outputter.write_heading(String::from("Time spent"));
+			for (c, d) in &self.time_by_context {
+				let label = format!("@{}", c);
+				outputter.write_data(format!(
+					"{:<20} {}",
+					label,
+					format_duration(*d)
+				));
+			}
+		}
+	}
+
+	/// Serialize the statistics as a JSON object.
+	pub fn to_json(&self) -> String {
+		let urgency = self
+			.by_urgency
+			.iter()
+			.map(|(u, n)| format!("{}:{}", json_string(u.label()), n))
+			.collect::<Vec<String>>()
+			.join(",");
+		let importance = self
+			.by_importance
+			.iter()
+			.map(|(i, n)| format!("\"{}\":{}", i.to_char(), n))
+			.collect::<Vec<String>>()
+			.join(",");
+		let contexts = self
+			.by_context
+			.iter()
+			.map(|(c, n)| format!("{}:{}", json_string(c), n))
+			.collect::<Vec<String>>()
+			.join(",");
+		let time = self
+			.time_by_context
+			.iter()
+			.map(|(c, d)| format!("{}:{}", json_string(c), d.num_minutes()))
+			.collect::<Vec<String>>()
+			.join(",");
+		format!(
+			"{{\"open\":{},\"completed\":{},\"overdue\":{},\
+			\"completed_last_7_days\":{},\"completed_last_30_days\":{},\
+			\"urgency\":{{{}}},\"importance\":{{{}}},\"contexts\":{{{}}},\
+			\"minutes_by_context\":{{{}}}}}",
+			self.open,
+			self.completed,
+			self.overdue,
+			self.completed_last_7_days,
+			self.completed_last_30_days,
+			urgency,
+			importance,
+			contexts,
+			time,
+		)
+	}
+}
+
+/// How long each period counted by a velocity report is.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Period {
+	Day,
+	Week,
+}
+
+impl Period {
+	/// How many periods are counted, unless told otherwise.
+	pub fn default_count(&self) -> usize {
+		match self {
+			Self::Day => 14,
+			Self::Week => 8,
+		}
+	}
+
+	/// The first day of the period which includes a date. Weeks start on
+	/// Monday, like ISO weeks.
+	pub fn start_of(&self, date: NaiveDate) -> NaiveDate {
+		match self {
+			Self::Day => date,
+			Self::Week => {
+				date - Duration::days(
+					date.weekday().num_days_from_monday().into(),
+				)
+			}
+		}
+	}
+
+	/// How many days long the period is.
+	pub fn days(&self) -> i64 {
+		match self {
+			Self::Day => 1,
+			Self::Week => 7,
+		}
+	}
+
+	/// A label for the period starting on a date, like "2024-06-07" or
+	/// "2024-W23".
+	pub fn label(&self, start: NaiveDate) -> String {
+		match self {
+			Self::Day => start.format("%Y-%m-%d").to_string(),
+			Self::Week => start.format("%G-W%V").to_string(),
+		}
+	}
+
+	/// The canonical name for this period.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::Day => "day",
+			Self::Week => "week",
+		}
+	}
+}
+
+/// How many tasks were completed in each of the last few days or weeks.
+#[derive(Debug)]
+pub struct Velocity {
+	pub period: Period,
+	/// The first day of each period, oldest first.
+	pub starts: Vec<NaiveDate>,
+	/// How many tasks were completed in each period.
+	pub counts: Vec<usize>,
+	/// The same counts for each group, if grouped. Groups with nothing
+	/// completed in any period are left out.
+	pub by_group: Vec<(String, Vec<usize>)>,
+	/// Completed tasks with no completion date, which can't be counted.
+	pub unknown: usize,
+}
+
+impl Velocity {
+	/// Count tasks completed in a todo list and a done list, for the last
+	/// `count` periods, up to and including the current one.
+	pub fn from_lists(
+		todo: &List,
+		done: &List,
+		period: Period,
+		count: usize,
+		grouping: Grouping,
+	) -> Self {
+		let completed: Vec<&Item> = todo
+			.items()
+			.into_iter()
+			.chain(done.items())
+			.filter(|i| i.completion())
+			.collect();
+
+		let current = period.start_of(today());
+		let starts: Vec<NaiveDate> = (0..count)
+			.rev()
+			.map(|n| current - Duration::days(period.days() * n as i64))
+			.collect();
+		let tally = |items: &[&Item]| {
+			let mut counts = vec![0; count];
+			for d in items.iter().filter_map(|i| i.completion_date()) {
+				if d < starts[0] {
+					continue;
+				}
+				let n = ((d - starts[0]).num_days() / period.days()) as usize;
+				if let Some(c) = counts.get_mut(n) {
+					*c += 1;
+				}
+			}
+			counts
+		};
+
+		let by_group = match grouping {
+			Grouping::None => Vec::new(),
+			g => g
+				.split_items(completed.clone())
+				.into_iter()
+				.map(|(heading, group)| (heading, tally(&group)))
+				.filter(|(_, counts)| counts.iter().any(|n| *n > 0))
+				.collect(),
+		};
+
+		Self {
+			period,
+			counts: tally(&completed),
+			starts,
+			by_group,
+			unknown: completed
+				.iter()
+				.filter(|i| i.completion_date().is_none())
+				.count(),
+		}
+	}
+
+	/// Write the counts in a human-readable format, optionally with a bar
+	/// chart scaled to fit the output width.
+	pub fn write_to(&self, outputter: &mut Outputter, chart: bool) {
+		// Room for the label and count before each bar.
+		let bar_width = match outputter.width {
+			0 => 40,
+			w => w.saturating_sub(17),
+		};
+		let max = self
+			.by_group
+			.iter()
+			.flat_map(|(_, counts)| counts)
+			.chain(&self.counts)
+			.max()
+			.copied()
+			.unwrap_or(0);
+		let write_counts =
+			|o: &mut Outputter, heading: String, counts: &[usize]| {
+				o.write_heading(heading);
+				for (start, n) in self.starts.iter().zip(counts) {
+					let mut line =
+						format!("{:<10} {:>5}", self.period.label(*start), n);
+					if chart && *n > 0 {
+						line = format!("{} {}", line, bar(*n, max, bar_width));
+					}
+					o.write_data(line);
+				}
+			};
+
+		write_counts(
+			outputter,
+			format!("Completed each {}", self.period.name()),
+			&self.counts,
+		);
+		for (heading, counts) in &self.by_group {
+			outputter.write_separator();
+			write_counts(outputter, heading.clone(), counts);
+		}
+		if self.unknown > 0 {
+			outputter.write_separator();
+			outputter.write_notice(format!(
+				"{} completed {} no completion date, so can't be counted.",
+				self.unknown,
+				if self.unknown == 1 {
+					"task has"
+				} else {
+					"tasks have"
+				},
+			));
+		}
+	}
+
+	/// Serialize the counts as a JSON object.
+	pub fn to_json(&self) -> String {
+		let counts = |counts: &[usize]| {
+			self.starts
+				.iter()
+				.zip(counts)
+				.map(|(start, n)| {
+					format!("{}:{}", json_string(&self.period.label(*start)), n)
+				})
+				.collect::<Vec<String>>()
+				.join(",")
+		};
+		let groups = self
+			.by_group
+			.iter()
+			.map(|(g, c)| format!("{}:{{{}}}", json_string(g), counts(c)))
+			.collect::<Vec<String>>()
+			.join(",");
+		format!(
+			"{{\"period\":{},\"counts\":{{{}}},\"groups\":{{{}}},\
+			\"unknown\":{}}}",
+			json_string(self.period.name()),
+			counts(&self.counts),
+			groups,
+			self.unknown,
+		)
+	}
+}
+
+/// Quote a string for use in JSON.
+fn json_string(s: &str) -> String {
+	let mut out = String::from("\"");
+	for c in s.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c if (c as u32) < 0x20 => {
+				out.push_str(&format!("\\u{:04x}", c as u32))
+			}
+			c => out.push(c),
+		}
+	}
+	out.push('"');
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tempfile::tempdir;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("stats"), get_action().name);
+	}
+
+	fn _fixture() -> (List, List) {
+		let today = today();
+		let todo = List::from_string(format!(
+			"(A) Foo @home due:1970-01-01\n\
+			(B) Bar @Home @work due:3970-01-01 spent:1h\n\
+			Baz\n\
+			\n\
+			x {} 2000-01-01 Bat @home spent:15m\n",
+			today.format("%Y-%m-%d")
+		))
+		.unwrap();
+		let done = List::from_string(format!(
+			"x {} 2000-01-01 Old1\n\
+			x 2000-01-01 2000-01-01 Old2\n\
+			x Old3\n",
+			(today - Duration::days(10)).format("%Y-%m-%d")
+		))
+		.unwrap();
+		(todo, done)
+	}
+
+	#[test]
+	fn test_from_lists() {
+		let (todo, done) = _fixture();
+		let stats = Statistics::from_lists(&todo, &done);
+		assert_eq!(3, stats.open);
+		assert_eq!(4, stats.completed);
+		assert_eq!(1, stats.overdue);
+		assert_eq!(1, stats.completed_last_7_days);
+		assert_eq!(2, stats.completed_last_30_days);
+		assert_eq!(
+			Vec::from([(Urgency::Overdue, 1), (Urgency::Later, 1)]),
+			stats.by_urgency
+		);
+		assert_eq!(
+			Vec::from([(Importance::A, 1), (Importance::B, 1)]),
+			stats.by_importance
+		);
+		assert_eq!(
+			Vec::from([(String::from("home"), 2), (String::from("work"), 1)]),
+			stats.by_context
+		);
+		assert_eq!(
+			Vec::from([
+				(String::from("home"), Duration::minutes(75)),
+				(String::from("work"), Duration::minutes(60)),
+			]),
+			stats.time_by_context
+		);
+	}
+
+	#[test]
+	fn test_to_json() {
+		let (todo, done) = _fixture();
+		let stats = Statistics::from_lists(&todo, &done);
+		assert_eq!(
+			"{\"open\":3,\"completed\":4,\"overdue\":1,\
+			\"completed_last_7_days\":1,\"completed_last_30_days\":2,\
+			\"urgency\":{\"Overdue\":1,\"Later\":1},\
+			\"importance\":{\"A\":1,\"B\":1},\
+			\"contexts\":{\"home\":2,\"work\":1},\
+			\"minutes_by_context\":{\"home\":75,\"work\":60}}",
+			stats.to_json()
+		);
+		assert_eq!("\"a\\\"b\\\\c\\n\"", json_string("a\"b\\c\n"));
+	}
+
+	#[test]
+	fn test_write_to() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let (todo, done) = _fixture();
+		let stats = Statistics::from_lists(&todo, &done);
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		stats.write_to(&mut o);
+		let got_output = fs::read_to_string(buffer_filename).unwrap();
+		assert_eq!(
+			String::from(
+				"# Tasks\n\
+				Open:                3\n\
+				Completed:           4\n\
+				Overdue:             1\n\
+				Completed (7 days):  1\n\
+				Completed (30 days): 2\n\
+				\n\
+				# Urgency\n\
+				Overdue              1\n\
+				Later                1\n\
+				\n\
+				# Importance\n\
+				(A) Critical         1\n\
+				(B) Important        1\n\
+				\n\
+				# Contexts\n\
+				@home                2\n\
+				@work                1\n\
+				\n\
+				# Time spent\n\
+				@home                75m\n\
+				@work                60m\n"
+			),
+			got_output
+		);
+	}
+
+	fn _velocity(period: Period, grouping: Grouping) -> Velocity {
+		let todo = List::from_string(String::from(
+			"x 2024-06-07 2024-01-01 Foo @home\n\
+			x 2024-06-04 2024-01-01 Bar @work\n\
+			Baz @home\n",
+		))
+		.unwrap();
+		let done = List::from_string(String::from(
+			"x 2024-06-06 2024-01-01 Bat @home\n\
+			x 2024-05-29 2024-01-01 Quux @home\n\
+			x 2024-01-01 2024-01-01 Ancient\n\
+			x Undated\n",
+		))
+		.unwrap();
+		let friday = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+		crate::item::with_today(friday, || {
+			Velocity::from_lists(&todo, &done, period, 3, grouping)
+		})
+	}
+
+	#[test]
+	fn test_velocity_from_lists() {
+		let v = _velocity(Period::Week, Grouping::Context);
+		assert_eq!(
+			Vec::from([
+				NaiveDate::from_ymd_opt(2024, 5, 20).unwrap(),
+				NaiveDate::from_ymd_opt(2024, 5, 27).unwrap(),
+				NaiveDate::from_ymd_opt(2024, 6, 3).unwrap(),
+			]),
+			v.starts
+		);
+		assert_eq!(Vec::from([0, 1, 3]), v.counts);
+		assert_eq!(
+			Vec::from([
+				(String::from("@home"), Vec::from([0, 1, 2])),
+				(String::from("@work"), Vec::from([0, 0, 1])),
+			]),
+			v.by_group
+		);
+		assert_eq!(1, v.unknown);
+
+		let v = _velocity(Period::Day, Grouping::None);
+		assert_eq!(Vec::from([0, 1, 1]), v.counts);
+		assert!(v.by_group.is_empty());
+	}
+
+	#[test]
+	fn test_velocity_to_json() {
+		let v = _velocity(Period::Day, Grouping::Context);
+		assert_eq!(
+			"{\"period\":\"day\",\
+			\"counts\":{\"2024-06-05\":0,\"2024-06-06\":1,\"2024-06-07\":1},\
+			\"groups\":{\"@home\":\
+			{\"2024-06-05\":0,\"2024-06-06\":1,\"2024-06-07\":1}},\
+			\"unknown\":1}",
+			v.to_json()
+		);
+	}
+
+	#[test]
+	fn test_velocity_write_to() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let v = _velocity(Period::Week, Grouping::None);
+
+		let mut o = Outputter::new(29);
+		o.colour = false;
+		let msg_filename = dir
+			.path()
+			.join("messages.txt")
+			.display()
+			.to_string();
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		o.msg_io = Box::new(fs::File::create(msg_filename.clone()).unwrap());
+		v.write_to(&mut o, true);
+		let got_output = fs::read_to_string(buffer_filename).unwrap();
+		let expected = [
+			"# Completed each week",
+			"2024-W21       0",
+			"2024-W22       1 ####",
+			"2024-W23       3 ############",
+			"",
+		];
+		assert_eq!(format!("{}\n", expected.join("\n")), got_output);
+		assert_eq!(
+			"1 completed task has no completion date, so can't be counted.\n",
+			fs::read_to_string(msg_filename).unwrap()
+		);
+	}
+}
@@ -0,0 +1,184 @@
+//! Stop tracking time on a task
+
+use crate::action::*;
+use crate::item::{format_duration, now};
+use crate::list::List;
+use chrono::{Duration, NaiveDateTime};
+use clap::{ArgMatches, Command};
+
+/// Options for the `stop` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("stop");
+	let mut command = Command::new("stop")
+		.about("Stop tracking time on a task")
+		.after_help(
+			"The time since `tada start` is added to the task's `spent:` tag.\n\n\
+			Exits with status 1 if no task was being tracked.",
+		);
+
+	command = FileType::TodoTxt.add_args(command);
+	command = Outputter::add_args(command);
+	command = SaveMode::add_args(command);
+
+	Action { name, command }
+}
+
+/// Execute the `stop` subcommand.
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches(args);
+	let list = FileType::TodoTxt.load(args)?;
+	outputter.fit_to_list(&list);
+	let save_mode = SaveMode::from_argmatches(args);
+
+	let before = list.serialize();
+	let outcome = run(
+		list,
+		&StopActionConfig::default(),
+		&mut ChangeWriter {
+			outputter: &mut outputter,
+			write: write_stopped,
+		},
+	)?;
+	let (new_list, count) = (outcome.list, outcome.count);
+	if count > 0 {
+		save_mode.save_with_diff(&new_list, &before, &mut outputter)?;
+		outputter.write_status(save_mode.status(
+			String::from("Stopped tracking time."),
+			String::from("stopped tracking time"),
+		));
+	} else {
+		outputter.write_status(String::from("No task is being tracked."));
+	}
+
+	Ok(ExitStatus::from_count(count))
+}
+
+/// Config object for the `stop` action.
+#[derive(Clone)]
+pub struct StopActionConfig {
+	/// When time tracking stops.
+	pub at: NaiveDateTime,
+}
+
+impl Default for StopActionConfig {
+	fn default() -> Self {
+		Self { at: now() }
+	}
+}
+
+/// Run the `stop` action on a list, without saving it.
+///
+/// The observer is told about each task stopped.
+pub fn run(
+	list: List,
+	cfg: &StopActionConfig,
+	observer: &mut dyn MutationObserver,
+) -> Result<ActionOutcome, Error> {
+	let (new_list, count) = stop_items_in_list(list, cfg.at, observer);
+	let message = format!(
+		"stopped tracking time on {} tasks in {}",
+		count,
+		new_list.display_name()
+	);
+	Ok(ActionOutcome::new(new_list, count, message))
+}
+
+/// Given a list, creates a copy of the list with time tracking stopped on
+/// every task being tracked, at the given time.
+///
+/// The observer will be used to check whether each task should be stopped.
+///
+/// Also returns the number of tasks stopped.
+pub fn stop_items_in_list(
+	list: List,
+	at: NaiveDateTime,
+	observer: &mut dyn MutationObserver,
+) -> (List, usize) {
+	let mut new_list = list;
+	let mut count = 0;
+	observer.will_change(
+		new_list
+			.items()
+			.into_iter()
+			.filter(|i| i.started().is_some())
+			.count(),
+	);
+	for mut item in new_list.iter_items_mut() {
+		if item.started().is_some() && observer.before_change(&item) {
+			count += 1;
+			let stopped = item.but_stopped(at);
+			observer.after_change(&item, &stopped);
+			*item = stopped;
+		}
+	}
+	(new_list, count)
+}
+
+/// Writes a task which has stopped being tracked, with the time spent on
+/// it since it was started, and in total.
+pub fn write_stopped(old: &Item, new: &Item, outputter: &mut Outputter) {
+	let total = new.spent().unwrap_or_else(Duration::zero);
+	let before = old.spent().unwrap_or_else(Duration::zero);
+	outputter.write_item(new);
+	outputter.write_notice(format!(
+		"    spent {}, {} in total",
+		format_duration(total - before),
+		format_duration(total)
+	));
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::NaiveDate;
+	use tempfile::tempdir;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("stop"), get_action().name);
+	}
+
+	#[test]
+	fn test_stop_items_in_list() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let at = NaiveDate::from_ymd_opt(2024, 6, 1)
+			.unwrap()
+			.and_hms_opt(10, 0, 0)
+			.unwrap();
+		let source_list = List::from_string(String::from(
+			"Foo spent:1h started:2024-06-01T09:15\nBar spent:5m\n",
+		))
+		.unwrap();
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		let buffer = fs::File::create(buffer_filename.clone()).unwrap();
+		o.msg_io = Box::new(buffer.try_clone().unwrap());
+		o.io = Box::new(buffer);
+		let (got, count) = stop_items_in_list(
+			source_list,
+			at,
+			&mut ChangeWriter {
+				outputter: &mut o,
+				write: write_stopped,
+			},
+		);
+		assert_eq!(1, count);
+		assert_eq!("Foo spent:105m", got.lines[0].text);
+		assert_eq!("Bar spent:5m", got.lines[1].text);
+		let got_output = fs::read_to_string(buffer_filename).unwrap();
+		assert_eq!(
+			"  (?) Foo spent:105m\n    spent 45m, 105m in total\n",
+			got_output
+		);
+
+		// Nothing is being tracked any more.
+		let outcome = run(got, &StopActionConfig { at }, &mut NoObserver);
+		assert_eq!(0, outcome.unwrap().count);
+	}
+}
@@ -0,0 +1,272 @@
+//! Synchronise your todo list with a remote copy
+
+use crate::action::*;
+use crate::list::{merge, Merge, MergePreference};
+use clap::{Arg, ArgMatches, Command};
+
+/// Options for the `sync` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("sync");
+	let mut command = Command::new("sync")
+		.about("Synchronise your todo list with a remote copy")
+		.after_help(
+			"Tasks found in only one copy are added to the other, and \
+			duplicate tasks are removed. Comments and blank lines are \
+			taken from the local copy.\n\n\
+			If both copies have a different task on the same line, and \
+			neither task is found anywhere in the other copy, that is a \
+			conflict. Nothing is saved until conflicts are resolved \
+			using --prefer.",
+		)
+		.arg(
+			Arg::new("remote")
+				.long("remote")
+				.value_name("URL")
+				.required(true)
+				.help("The path or URL for the remote copy"),
+		)
+		.arg(
+			Arg::new("prefer")
+				.long("prefer")
+				.value_name("SIDE")
+				.value_parser(["local", "remote"])
+				.help("Resolve conflicts in favour of one copy"),
+		)
+		.arg(
+			Arg::new("dry-run")
+				.num_args(0)
+				.short('n')
+				.long("dry-run")
+				.help("Show changes without saving them"),
+		);
+
+	command = FileType::TodoTxt.add_args(command);
+	command = Outputter::add_args_minimal(command);
+
+	Action { name, command }
+}
+
+/// Execute the `sync` subcommand.
+#[cfg(not(tarpaulin_include))]
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches_minimal(args);
+	let local_filename = FileType::TodoTxt.filename(args)?;
+	let local = FileType::TodoTxt.load(args)?;
+	let remote_url = args
+		.get_one::<String>("remote")
+		.unwrap()
+		.to_string();
+	let remote =
+		List::from_url(remote_url.clone()).map_err(|e| Error::Unreadable {
+			label: String::from("remote list"),
+			message: e.to_string(),
+		})?;
+	let prefer = preference_from_argmatches(args);
+	let dry_run = *args.get_one::<bool>("dry-run").unwrap();
+
+	let m = merge(&local, &remote, prefer);
+	write_conflicts(&m, prefer, &mut outputter);
+	if prefer.is_none() && !m.conflicts.is_empty() {
+		outputter.write_error(format!(
+			"Found {} conflicts; use --prefer local or --prefer remote.",
+			m.conflicts.len()
+		));
+		return Ok(ExitStatus::Error);
+	}
+
+	let sides = [
+		("local", &local, local_filename),
+		("remote", &remote, remote_url),
+	];
+	let mut changed = false;
+	for (label, before, _) in sides.iter() {
+		changed |= write_changes(label, before, &m.list, &mut outputter);
+	}
+
+	if !changed {
+		outputter.write_status(String::from("Already in sync."));
+		return Ok(ExitStatus::Success);
+	}
+	if dry_run {
+		outputter.write_status(String::from("Dry run; nothing saved."));
+		return Ok(ExitStatus::Success);
+	}
+
+	for (label, before, url) in sides {
+		if before.serialize() != m.list.serialize() {
+			let mut after = before.new_like();
+			after.lines = m.list.lines.clone();
+			after.to_url(url)?;
+			outputter.write_status(format!("Saved {} list.", label));
+		}
+	}
+
+	Ok(ExitStatus::Success)
+}
+
+/// Read the `--prefer` option.
+pub fn preference_from_argmatches(
+	args: &ArgMatches,
+) -> Option<MergePreference> {
+	match args
+		.get_one::<String>("prefer")
+		.map(|s| s.as_str())
+	{
+		Some("local") => Some(MergePreference::Local),
+		Some("remote") => Some(MergePreference::Remote),
+		_ => None,
+	}
+}
+
+/// Tasks which are in `after` but not `before`, and vice versa.
+pub fn list_changes(before: &List, after: &List) -> (Vec<String>, Vec<String>) {
+	let only_in = |a: &List, b: &List| -> Vec<String> {
+		let mut found = Vec::new();
+		for i in a
+			.lines
+			.iter()
+			.filter(|l| l.kind == LineKind::Item)
+		{
+			let in_b = b.lines.iter().any(|l| l.text == i.text);
+			if !in_b && !found.contains(&i.text) {
+				found.push(i.text.clone());
+			}
+		}
+		found
+	};
+	(only_in(after, before), only_in(before, after))
+}
+
+/// Describe the conflicts found by a merge, and how they were resolved.
+pub fn write_conflicts(
+	m: &Merge,
+	prefer: Option<MergePreference>,
+	outputter: &mut Outputter,
+) {
+	for c in m.conflicts.iter() {
+		let resolution = match prefer {
+			Some(MergePreference::Local) => " (keeping local)",
+			Some(MergePreference::Remote) => " (keeping remote)",
+			None => "",
+		};
+		outputter
+			.write_notice(format!("Conflict on line {}{}:", c.num, resolution));
+		outputter.write_notice(format!("  local:  {}", c.local));
+		outputter.write_notice(format!("  remote: {}", c.remote));
+	}
+}
+
+/// Describe the changes a merge will make to one copy of a list.
+///
+/// Returns true if the copy will change at all, which may be true even if
+/// no changes are described, as comments, blank lines, and duplicates
+/// are not listed.
+pub fn write_changes(
+	label: &str,
+	before: &List,
+	after: &List,
+	outputter: &mut Outputter,
+) -> bool {
+	if before.serialize() == after.serialize() {
+		return false;
+	}
+	let (added, removed) = list_changes(before, after);
+	outputter.write_heading(format!("Changes to {} list", label));
+	for text in added {
+		outputter.write_data(format!("+ {}", text));
+	}
+	for text in removed {
+		outputter.write_data(format!("- {}", text));
+	}
+	true
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tempfile::tempdir;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("sync"), get_action().name);
+	}
+
+	#[test]
+	fn test_preference_from_argmatches() {
+		let cmd = get_action().command;
+		let m = cmd.clone().get_matches_from(vec![
+			"sync", "--remote", "x", "--prefer", "remote",
+		]);
+		assert_eq!(
+			Some(MergePreference::Remote),
+			preference_from_argmatches(&m)
+		);
+		let m = cmd
+			.clone()
+			.get_matches_from(vec!["sync", "--remote", "x"]);
+		assert_eq!(None, preference_from_argmatches(&m));
+		assert!(cmd
+			.try_get_matches_from(vec![
+				"sync", "--remote", "x", "--prefer", "both"
+			])
+			.is_err());
+	}
+
+	#[test]
+	fn test_list_changes() {
+		let before =
+			List::from_string(String::from("Foo\nBar\nBar\n")).unwrap();
+		let after =
+			List::from_string(String::from("# Hi\nFoo\nBaz\nBaz\n")).unwrap();
+		assert_eq!(
+			(
+				Vec::from([String::from("Baz")]),
+				Vec::from([String::from("Bar")])
+			),
+			list_changes(&before, &after)
+		);
+		assert_eq!((Vec::new(), Vec::new()), list_changes(&before, &before));
+	}
+
+	#[test]
+	fn test_write_output() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let local = List::from_string(String::from("Foo\nBar\n")).unwrap();
+		let remote =
+			List::from_string(String::from("Foo\nBaz\nQuux\n")).unwrap();
+		let prefer = Some(MergePreference::Remote);
+		let m = merge(&local, &remote, prefer);
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		let msg_filename = dir
+			.path()
+			.join("messages.txt")
+			.display()
+			.to_string();
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		o.msg_io = Box::new(fs::File::create(msg_filename.clone()).unwrap());
+		write_conflicts(&m, prefer, &mut o);
+		assert!(write_changes("local", &local, &m.list, &mut o));
+		assert!(!write_changes("remote", &remote, &m.list, &mut o));
+
+		assert_eq!(
+			"Conflict on line 2 (keeping remote):\n\
+			\x20 local:  Bar\n\
+			\x20 remote: Baz\n",
+			fs::read_to_string(msg_filename).unwrap()
+		);
+		assert_eq!(
+			"# Changes to local list\n\
+			+ Baz\n\
+			+ Quux\n\
+			- Bar\n",
+			fs::read_to_string(buffer_filename).unwrap()
+		);
+	}
+}
@@ -1,16 +1,76 @@
 //! Remove blank lines and comments from a todo list
 
 use crate::action::*;
-use clap::{ArgMatches, Command};
+use crate::list::{Line, LineEnding, List, TidyOptions};
+use clap::{Arg, ArgMatches, Command};
 
 /// Options for the `tidy` subcommand.
 pub fn get_action() -> Action {
 	let name = String::from("tidy");
 	let mut command = Command::new("tidy").about("Remove blank lines and comments from a todo list")
-		.after_help("This is the only command which will renumber tasks in your todo list.");
+		.after_help(
+			"This is the only command which will renumber tasks in your todo list. \
+			Each task which moves is listed with its old and new line numbers, \
+			like `#14 -> #3  (A) Pay rent`, unless --quiet is given.\n\n\
+			Comments and blank lines which are kept divide the list into \
+			sections, and tasks are only sorted within their section.",
+		)
+		.arg(
+			Arg::new("dedupe")
+				.num_args(0)
+				.long("dedupe")
+				.help("Also remove duplicate tasks"),
+		)
+		.arg(
+			Arg::new("confirm")
+				.num_args(0)
+				.long("confirm")
+				.requires("dedupe")
+				.help("Ask before removing each duplicate"),
+		)
+		.arg(
+			Arg::new("ensure-ids")
+				.num_args(0)
+				.long("ensure-ids")
+				.help("Give every task without an id a unique id"),
+		)
+		.arg(
+			Arg::new("keep-comments")
+				.num_args(0)
+				.long("keep-comments")
+				.help("Keep comments, like section headings"),
+		)
+		.arg(
+			Arg::new("squash-blanks")
+				.num_args(0)
+				.long("squash-blanks")
+				.help("Squash runs of blank lines, not removing them"),
+		)
+		.arg(
+			Arg::new("normalize-endings")
+				.num_args(0)
+				.long("normalize-endings")
+				.help("Use Unix line endings, ending with a newline"),
+		)
+		.arg(
+			Arg::new("quiet")
+				.num_args(0)
+				.short('q')
+				.long("quiet")
+				.help("Don't list tasks which were renumbered"),
+		)
+		.arg(
+			Arg::new("renumber-preview")
+				.num_args(0)
+				.long("renumber-preview")
+				.conflicts_with("quiet")
+				.help("Only list how tasks would be renumbered"),
+		);
 
 	command = FileType::TodoTxt.add_args(command);
 	command = SortOrder::add_args(command, default_sort_order());
+	command = Outputter::add_args_minimal(command);
+	command = SaveMode::add_args(command);
 
 	Action { name, command }
 }
@@ -21,24 +81,295 @@ pub fn default_sort_order() -> SortOrder {
 }
 
 /// Execute the `tidy` subcommand.
-pub fn execute(args: &ArgMatches) {
-	let todo_filename = FileType::TodoTxt.filename(args);
-	let list = FileType::TodoTxt.load(args);
-	let sort_order = SortOrder::from_argmatches(args, default_sort_order());
-	list.but_tidy(&sort_order).to_url(todo_filename);
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches_minimal(args);
+	let mut list = FileType::TodoTxt.load(args)?;
+	let before = list.serialize();
+	let mut cfg = TidyActionConfig::from_argmatches(args);
+	let preview = *args
+		.get_one::<bool>("renumber-preview")
+		.unwrap();
+	let save_mode = if preview {
+		SaveMode::DryRun
+	} else {
+		SaveMode::from_argmatches(args)
+	};
+	// Duplicates are removed here when each one needs confirming.
+	let mut confirmed = 0;
+	if cfg.dedupe && *args.get_one::<bool>("confirm").unwrap() {
+		let mut status = ConfirmationStatus::Ask;
+		confirmed = list.remove_duplicates(|orig, dupe| {
+			check_if_remove(orig, dupe, &mut outputter, &mut status)
+		});
+		cfg.dedupe = false;
+	}
+	let (mut outcome, moved) = run(list, &cfg)?;
+	if confirmed > 0 {
+		outcome.count += confirmed;
+		outcome
+			.messages
+			.insert(0, format!("removed {} duplicate tasks", confirmed));
+	}
+	if !*args.get_one::<bool>("quiet").unwrap() {
+		write_renumbering(&moved, &outcome.list, &mut outputter);
+	}
+	if !save_mode.save_with_diff(&outcome.list, &before, &mut outputter)? {
+		outputter.write_status(String::from("No changes."));
+		return Ok(ExitStatus::Success);
+	}
+	for status in save_mode.outcome_status(&outcome) {
+		outputter.write_status(status);
+	}
+
+	Ok(ExitStatus::Success)
+}
+
+/// Config object for the `tidy` action.
+#[derive(Clone)]
+pub struct TidyActionConfig {
+	pub sort_order: SortOrder,
+	pub options: TidyOptions,
+	/// Remove tasks which duplicate an earlier task.
+	pub dedupe: bool,
+	/// Give every task without an id a unique id.
+	pub ensure_ids: bool,
+	/// Use Unix line endings, ending with a newline.
+	pub normalize_endings: bool,
+}
+
+impl Default for TidyActionConfig {
+	fn default() -> Self {
+		Self {
+			sort_order: default_sort_order(),
+			options: TidyOptions::default(),
+			dedupe: false,
+			ensure_ids: false,
+			normalize_endings: false,
+		}
+	}
+}
+
+impl TidyActionConfig {
+	/// Create a TidyActionConfig from an appropriate ArgMatches.
+	pub fn from_argmatches(args: &ArgMatches) -> Self {
+		Self {
+			sort_order: SortOrder::from_argmatches(args, default_sort_order()),
+			options: TidyOptions {
+				keep_comments: *args.get_one::<bool>("keep-comments").unwrap(),
+				squash_blanks: *args.get_one::<bool>("squash-blanks").unwrap(),
+			},
+			dedupe: *args.get_one::<bool>("dedupe").unwrap(),
+			ensure_ids: *args.get_one::<bool>("ensure-ids").unwrap(),
+			normalize_endings: *args
+				.get_one::<bool>("normalize-endings")
+				.unwrap(),
+		}
+	}
+}
+
+/// Run the `tidy` action on a list, without saving it.
+///
+/// The outcome counts the tasks removed as duplicates, given an id, or
+/// moved, and the blank lines and comments removed. Also returns the old and
+/// new line numbers of each task which moved.
+pub fn run(
+	list: List,
+	cfg: &TidyActionConfig,
+) -> Result<(ActionOutcome, Vec<(usize, usize)>), Error> {
+	let mut list = list;
+	let mut messages = Vec::new();
+	let dupes = if cfg.dedupe { list.dedupe() } else { 0 };
+	if dupes > 0 {
+		messages.push(format!("removed {} duplicate tasks", dupes));
+	}
+	let ids = if cfg.ensure_ids { list.ensure_ids() } else { 0 };
+	if ids > 0 {
+		messages.push(format!("added ids to {} tasks", ids));
+	}
+	let (mut new_list, moved) = list.but_tidy(&cfg.sort_order, cfg.options);
+	let removed = list.lines.len() - new_list.lines.len();
+	if removed > 0 {
+		messages.push(format!("removed {} blank lines and comments", removed));
+	}
+	if !moved.is_empty() {
+		messages.push(format!("renumbered {} tasks", moved.len()));
+	}
+	if cfg.normalize_endings
+		&& (new_list.line_ending != LineEnding::Lf
+			|| new_list.missing_final_newline)
+	{
+		new_list.normalize_endings();
+		messages.push(String::from("normalized line endings"));
+	}
+	let outcome = ActionOutcome {
+		list: new_list,
+		count: dupes + ids + removed + moved.len(),
+		messages,
+	};
+	Ok((outcome, moved))
+}
+
+/// Lists tasks which were renumbered by tidying, with their old and new line
+/// numbers, so notes which refer to tasks by number can be updated.
+pub fn write_renumbering(
+	moved: &[(usize, usize)],
+	list: &List,
+	outputter: &mut Outputter,
+) {
+	let width = moved
+		.iter()
+		.map(|(old, _)| old.to_string().len())
+		.max()
+		.unwrap_or(0);
+	for (old, new) in moved {
+		if let Some(item) = list.item_at(*new) {
+			writeln!(
+				outputter.io,
+				"#{:<width$} -> #{:<width$}  {}",
+				old,
+				new,
+				item,
+				width = width,
+			)
+			.expect("panik");
+		}
+	}
+}
+
+/// Shows a task and its duplicate, and asks whether to remove the duplicate.
+pub fn check_if_remove(
+	orig: &Line,
+	dupe: &Line,
+	outputter: &mut Outputter,
+	status: &mut ConfirmationStatus,
+) -> bool {
+	outputter.write_status(format!(
+		"Line {} duplicates line {}:",
+		dupe.num, orig.num
+	));
+	outputter.write_item(orig.item.as_ref().unwrap());
+	outputter.write_item(dupe.item.as_ref().unwrap());
+	status.confirm(outputter, "Remove duplicate?", "Removing", "Keeping", None)
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use tempfile::tempdir;
 
 	#[test]
 	fn test_get_action() {
 		assert_eq!(String::from("tidy"), get_action().name);
 	}
 
+	#[test]
+	fn test_check_if_remove() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let orig = Line::from_string(String::from("(A) 2000-01-01 Foo"), 1);
+		let dupe = Line::from_string(String::from("(A) 2000-01-02 Foo"), 3);
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		let buffer = fs::File::create(buffer_filename.clone()).unwrap();
+		o.msg_io = Box::new(buffer.try_clone().unwrap());
+		o.io = Box::new(buffer);
+		assert!(check_if_remove(
+			&orig,
+			&dupe,
+			&mut o,
+			&mut ConfirmationStatus::Yes
+		));
+		let got_output = fs::read_to_string(buffer_filename.clone()).unwrap();
+		assert_eq!(
+			"Line 3 duplicates line 1:\n  (A) Foo\n  (A) Foo\nRemoving\n\n",
+			got_output
+		);
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		let buffer = fs::File::create(buffer_filename.clone()).unwrap();
+		o.msg_io = Box::new(buffer.try_clone().unwrap());
+		o.io = Box::new(buffer);
+		assert!(!check_if_remove(
+			&orig,
+			&dupe,
+			&mut o,
+			&mut ConfirmationStatus::No
+		));
+		let got_output = fs::read_to_string(buffer_filename).unwrap();
+		assert!(got_output.ends_with("Keeping\n\n"));
+	}
+
+	#[test]
+	fn test_write_renumbering() {
+		let list = List::from_string(String::from(
+			"(A) Pay rent\nCall Mum\nBuy milk\n",
+		))
+		.unwrap();
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		write_renumbering(&[(14, 1), (3, 2), (1, 3)], &list, &mut o);
+		let got_output = fs::read_to_string(buffer_filename).unwrap();
+		assert_eq!(
+			"#14 -> #1   (A) Pay rent\n\
+			#3  -> #2   Call Mum\n\
+			#1  -> #3   Buy milk\n",
+			got_output
+		);
+	}
+
 	#[test]
 	fn test_default_sort_order() {
 		assert_eq!(SortOrder::Original, default_sort_order());
 	}
+
+	#[test]
+	fn test_run() {
+		let list = List::from_string(String::from(
+			"Foo\r\n\r\n# Bar\r\nFoo\r\nBaz id:x",
+		))
+		.unwrap();
+		let cfg = TidyActionConfig {
+			sort_order: SortOrder::Alphabetical,
+			dedupe: true,
+			ensure_ids: true,
+			normalize_endings: true,
+			..TidyActionConfig::default()
+		};
+		let (outcome, moved) = run(list, &cfg).unwrap();
+		assert_eq!("Baz id:x", outcome.list.lines[0].text);
+		assert!(outcome.list.lines[1]
+			.text
+			.starts_with("Foo id:"));
+		assert_eq!(Vec::from([(5, 1), (1, 2)]), moved);
+		assert_eq!(
+			Vec::from([
+				String::from("removed 1 duplicate tasks"),
+				String::from("added ids to 1 tasks"),
+				String::from("removed 2 blank lines and comments"),
+				String::from("renumbered 2 tasks"),
+				String::from("normalized line endings"),
+			]),
+			outcome.messages
+		);
+		assert_eq!(6, outcome.count);
+
+		let list = List::from_string(String::from("Foo\nBar\n")).unwrap();
+		let (outcome, moved) = run(list, &TidyActionConfig::default()).unwrap();
+		assert!(moved.is_empty());
+		assert_eq!(0, outcome.count);
+		assert!(outcome.messages.is_empty());
+	}
 }
@@ -0,0 +1,207 @@
+//! Show what needs doing today
+
+use crate::action::*;
+use clap::{Arg, ArgMatches, Command};
+
+/// Options for the `today` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("today");
+	let mut command = Command::new("today")
+		.about("Show tasks which are overdue, due today, or start today")
+		.after_help(
+			"Ignores tasks which are marked as already complete or \
+			can't be started yet.",
+		);
+	command = FileType::TodoTxt.add_args(command);
+	command = Outputter::add_args(command);
+	command = SortOrder::add_args(command, default_sort_order());
+	command = command.arg(
+		Arg::new("include-soon")
+			.num_args(0)
+			.long("include-soon")
+			.help("Also show tasks due tomorrow or overmorrow"),
+	);
+	Action { name, command }
+}
+
+/// The default sort order for output.
+pub fn default_sort_order() -> SortOrder {
+	SortOrder::Smart
+}
+
+/// Execute the `today` subcommand.
+#[cfg(not(tarpaulin_include))]
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let sort_order = SortOrder::from_argmatches(args, default_sort_order());
+	let include_soon = *args.get_one::<bool>("include-soon").unwrap();
+
+	let list = FileType::TodoTxt.load(args)?;
+
+	let mut outputter = Outputter::from_argmatches(args);
+	outputter.fit_to_list(&list);
+
+	let sections = today_sections(&list, include_soon);
+	let count = sections
+		.iter()
+		.map(|(_, items)| items.len())
+		.sum();
+	write_sections(sections, &sort_order, &mut outputter);
+
+	Ok(ExitStatus::from_count(count))
+}
+
+/// Split the tasks which need doing today into headed sections: overdue,
+/// due today, due soon (if `include_soon`), and starting today.
+///
+/// Completed tasks and tasks which can't be started yet are left out, as
+/// are empty sections. A task is only in the first section it fits.
+pub fn today_sections(
+	list: &List,
+	include_soon: bool,
+) -> Vec<(String, Vec<&Item>)> {
+	let items: Vec<&Item> = list
+		.items()
+		.into_iter()
+		.filter(|i| !i.completion() && list.is_item_startable(i))
+		.collect();
+	let mut buckets =
+		vec![(Urgency::Overdue, "Overdue"), (Urgency::Today, "Due today")];
+	if include_soon {
+		buckets.push((Urgency::Soon, "Due soon"));
+	}
+	let mut split = group_items_by_urgency(
+		items
+			.iter()
+			.filter(|i| i.due_date().is_some())
+			.cloned()
+			.collect(),
+	);
+	let mut sections = Vec::new();
+	for (u, heading) in &buckets {
+		if let Some(group) = split.remove(u) {
+			sections.push((String::from(*heading), group));
+		}
+	}
+	let today = today();
+	let starting: Vec<&Item> = items
+		.into_iter()
+		.filter(|i| i.start_date() == Some(today))
+		.filter(|i| {
+			!buckets
+				.iter()
+				.any(|(u, _)| i.urgency() == Some(*u))
+		})
+		.collect();
+	if !starting.is_empty() {
+		sections.push((String::from("Starts today"), starting));
+	}
+	sections
+}
+
+/// Write each section under its heading, sorted within the section.
+pub fn write_sections(
+	sections: Vec<(String, Vec<&Item>)>,
+	sort_order: &SortOrder,
+	outputter: &mut Outputter,
+) {
+	for (heading, items) in sections {
+		outputter.write_heading(heading);
+		for i in sort_order.sort_items(items) {
+			outputter.write_item(i);
+		}
+		outputter.write_separator();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tempfile::tempdir;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("today"), get_action().name);
+	}
+
+	#[test]
+	fn test_default_sort_order() {
+		assert_eq!(SortOrder::Smart, default_sort_order());
+	}
+
+	#[test]
+	fn test_today_sections() {
+		let list = List::from_string(String::from(
+			"Foo due:2024-06-05\n\
+			x Done due:2024-06-05\n\
+			Bar due:2024-06-07\n\
+			Baz due:2024-06-08\n\
+			Bat start:2024-06-07\n\
+			Qux start:2024-06-08 due:2024-06-01\n\
+			Quux start:2024-06-07 due:2024-06-09\n\
+			Waiting after:a due:2024-06-07\n\
+			Blocker id:a\n",
+		))
+		.unwrap();
+		let friday = chrono::NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+		let describe = |sections: Vec<(String, Vec<&Item>)>| {
+			sections
+				.into_iter()
+				.map(|(h, items)| {
+					let d: Vec<&str> =
+						items.iter().map(|i| i.description()).collect();
+					format!("{}: {}", h, d.join(", "))
+				})
+				.collect::<Vec<String>>()
+		};
+		crate::item::with_today(friday, || {
+			assert_eq!(
+				vec![
+					"Overdue: Foo due:2024-06-05",
+					"Due today: Bar due:2024-06-07",
+					"Starts today: Bat start:2024-06-07, \
+					Quux start:2024-06-07 due:2024-06-09",
+				],
+				describe(today_sections(&list, false))
+			);
+			assert_eq!(
+				vec![
+					"Overdue: Foo due:2024-06-05",
+					"Due today: Bar due:2024-06-07",
+					"Due soon: Baz due:2024-06-08, \
+					Quux start:2024-06-07 due:2024-06-09",
+					"Starts today: Bat start:2024-06-07",
+				],
+				describe(today_sections(&list, true))
+			);
+		});
+	}
+
+	#[test]
+	fn test_write_sections() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let list =
+			List::from_string(String::from("(B) Foo\n(A) Bar\nBaz\n")).unwrap();
+		let items = list.items();
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		write_sections(
+			vec![
+				(String::from("Overdue"), vec![items[0], items[1]]),
+				(String::from("Due today"), vec![items[2]]),
+			],
+			&SortOrder::Importance,
+			&mut o,
+		);
+		assert_eq!(
+			"# Overdue\n  (A) Bar\n  (B) Foo\n\n# Due today\n  (?) Baz\n\n",
+			fs::read_to_string(buffer_filename).unwrap()
+		);
+	}
+}
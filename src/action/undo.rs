@@ -0,0 +1,113 @@
+//! Revert the last change made to your lists
+
+use crate::action::*;
+use crate::backup::{self, Operation};
+use crate::config::Config;
+use clap::{Arg, ArgMatches, Command};
+
+/// Options for the `undo` subcommand.
+pub fn get_action() -> Action {
+	let name = String::from("undo");
+	let mut command = Command::new("undo")
+		.about("Revert the last change made to your lists")
+		.after_help(
+			"Local files are backed up whenever they are changed, unless \
+			--no-backup is given. Undo restores every file changed by the \
+			last command, and can be run again to go further back.",
+		)
+		.arg(
+			Arg::new("list")
+				.num_args(0)
+				.long("list")
+				.help("Show recent changes instead"),
+		);
+	command = Outputter::add_args_minimal(command);
+	Action { name, command }
+}
+
+/// Execute the `undo` subcommand.
+#[cfg(not(tarpaulin_include))]
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches_minimal(args);
+	let dir = match Config::load().backup.dir() {
+		Some(d) => d,
+		None => return Err(Error::NothingToUndo),
+	};
+
+	if *args.get_one::<bool>("list").unwrap() {
+		let mut ops = backup::operations(&dir);
+		ops.reverse();
+		for op in ops.iter().take(10) {
+			write_operation(op, &mut outputter);
+		}
+		return Ok(ExitStatus::from_count(ops.len()));
+	}
+
+	let undone = backup::undo(&dir).map_err(|e| Error::Unwritable {
+		url: String::from("backup"),
+		message: e.to_string(),
+	})?;
+	match undone {
+		Some(op) => {
+			outputter.write_status(String::from("Reverted:"));
+			write_operation(&op, &mut outputter);
+			Ok(ExitStatus::Success)
+		}
+		None => Err(Error::NothingToUndo),
+	}
+}
+
+/// Describe an operation from the journal.
+pub fn write_operation(op: &Operation, outputter: &mut Outputter) {
+	let files: Vec<&str> = op
+		.files
+		.iter()
+		.map(|(p, _)| p.as_str())
+		.collect();
+	outputter.write_data(format!(
+		"{}  {}  {}",
+		op.time,
+		op.command,
+		files.join(", ")
+	));
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tempfile::tempdir;
+
+	#[test]
+	fn test_get_action() {
+		assert_eq!(String::from("undo"), get_action().name);
+	}
+
+	#[test]
+	fn test_write_operation() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let op = Operation {
+			id: String::from("1"),
+			time: String::from("2000-01-01 12:00:00"),
+			command: String::from("archive"),
+			files: Vec::from([
+				(String::from("/todo.txt"), None),
+				(String::from("/done.txt"), None),
+			]),
+		};
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		o.io = Box::new(fs::File::create(buffer_filename.clone()).unwrap());
+		write_operation(&op, &mut o);
+		let got_output = fs::read_to_string(buffer_filename).unwrap();
+		assert_eq!(
+			"2000-01-01 12:00:00  archive  /todo.txt, /done.txt\n",
+			got_output
+		);
+	}
+}
@@ -1,8 +1,10 @@
 //! Automatically reschedule overdue tasks
 
 use crate::action::*;
-use crate::list::Line;
-use clap::{ArgMatches, Command};
+use crate::config::Config;
+use crate::item::{Item, Urgency, ZenPolicy};
+use crate::list::List;
+use clap::{Arg, ArgMatches, Command};
 use rand::seq::SliceRandom;
 
 /// Options for the `zen` subcommand.
@@ -11,34 +13,191 @@ pub fn get_action() -> Action {
 	let mut command = Command::new("zen").about("Automatically reschedule overdue tasks")
 		.after_help(
 			"Zen will reschedule any overdue tasks on your todo list. It does not consult you \
-			to ask for a new due date, but guesses when a sensible due date might be."
+			to ask for a new due date, but guesses when a sensible due date might be.\n\n\
+			If search terms are given, only matching tasks are rescheduled.\n\n\
+			The [zen] section of the config file can choose the urgency for each kind \
+			of task."
 		);
 	command = FileType::TodoTxt.add_args(command);
-	command = Outputter::add_args_minimal(command);
+	command = Outputter::add_args(command);
+	command = SearchTerms::add_optional_args(command);
+	command = command
+		.arg(
+			Arg::new("all-to")
+				.num_args(1)
+				.long("all-to")
+				.value_name("URGENCY")
+				.value_parser(parse_urgency)
+				.help("Reschedule every task with this urgency"),
+		)
+		.arg(
+			Arg::new("max")
+				.num_args(1)
+				.long("max")
+				.value_name("URGENCY")
+				.value_parser(parse_urgency)
+				.help("Reschedule nothing later than this urgency"),
+		);
+	command = SaveMode::add_args(command);
 	Action { name, command }
 }
 
 /// Execute the `zen` subcommand.
-pub fn execute(args: &ArgMatches) {
-	let mut outputter = Outputter::from_argmatches_minimal(args);
-	let todo_filename = FileType::TodoTxt.filename(args);
-	let list = List::from_url(todo_filename.clone())
-		.expect("Could not read todo list");
-	let mut new_list = List::new();
-
-	for line in list.lines {
-		match line.kind {
-			LineKind::Item => {
-				let new_line = Line::from_item(line.item.unwrap().zen());
-				new_list.lines.push(new_line);
-			}
-			_ => new_list.lines.push(line),
+pub fn execute(args: &ArgMatches) -> Result<ExitStatus, Error> {
+	let mut outputter = Outputter::from_argmatches(args);
+	let list = FileType::TodoTxt.load(args)?;
+	outputter.fit_to_list(&list);
+
+	let save_mode = SaveMode::from_argmatches(args);
+	let cfg = ZenActionConfig::from_argmatches(args);
+
+	let before = list.serialize();
+	let outcome = run(
+		list,
+		&cfg,
+		&mut ChangeWriter {
+			outputter: &mut outputter,
+			write: write_rescheduled,
+		},
+	)?;
+	let (new_list, count) = (outcome.list, outcome.count);
+	if count > 0 {
+		save_mode.save_with_diff(&new_list, &before, &mut outputter)?;
+	}
+
+	let name = new_list.display_name();
+	outputter.write_status(save_mode.status(
+		format!("Rescheduled {} overdue tasks in {}.", count, name),
+		format!("rescheduled {} overdue tasks in {}", count, name),
+	));
+	outputter.write_status(String::from(zen_quote()));
+
+	outputter.write_warnings(housekeeping_warnings(
+		&new_list,
+		&Config::load().hints,
+	));
+
+	Ok(ExitStatus::Success)
+}
+
+/// Accept an urgency for rescheduling a task, which can't be overdue.
+fn parse_urgency(s: &str) -> Result<Urgency, String> {
+	match Urgency::from_string(s) {
+		Ok(Urgency::Overdue) => {
+			Err(String::from("can't reschedule tasks to be overdue"))
 		}
+		Ok(u) => Ok(u),
+		Err(e) => Err(e.to_string()),
 	}
+}
+
+/// Read the rescheduling policy from ArgMatches, starting from the given
+/// policy.
+///
+/// `--all-to` replaces every urgency in the policy, and `--max` caps them.
+pub fn policy_from_argmatches(
+	args: &ArgMatches,
+	policy: ZenPolicy,
+) -> ZenPolicy {
+	let mut policy = match args.get_one::<Urgency>("all-to") {
+		Some(u) => ZenPolicy::all_to(*u),
+		None => policy,
+	};
+	if let Some(u) = args.get_one::<Urgency>("max") {
+		policy = policy.with_max(*u);
+	}
+	policy
+}
 
-	new_list.to_url(todo_filename);
+/// Config object for the `zen` action.
+#[derive(Clone, Default)]
+pub struct ZenActionConfig {
+	pub search_terms: SearchTerms,
+	pub policy: ZenPolicy,
+}
 
-	outputter.write_status(String::from(zen_quote()));
+impl ZenActionConfig {
+	/// Create a ZenActionConfig from an appropriate ArgMatches, starting
+	/// from the policy in the config file.
+	pub fn from_argmatches(args: &ArgMatches) -> Self {
+		Self {
+			search_terms: SearchTerms::from_argmatches(args),
+			policy: policy_from_argmatches(args, Config::load().zen.policy()),
+		}
+	}
+}
+
+/// Run the `zen` action on a list, without saving it.
+///
+/// The observer is told about each task rescheduled.
+pub fn run(
+	list: List,
+	cfg: &ZenActionConfig,
+	observer: &mut dyn MutationObserver,
+) -> Result<ActionOutcome, Error> {
+	let (new_list, count) =
+		zen_items_in_list(list, &cfg.search_terms, &cfg.policy, observer);
+	let message = format!(
+		"rescheduled {} overdue tasks in {}",
+		count,
+		new_list.display_name()
+	);
+	Ok(ActionOutcome::new(new_list, count, message))
+}
+
+/// Given a list, set of search terms, and policy, creates a copy of the list with
+/// all overdue items matching the search terms rescheduled according to the
+/// policy. If there are no
+/// search terms, all overdue items are rescheduled.
+///
+/// The observer will be used to check whether each individual item should be
+/// rescheduled. Lines for other items are left untouched.
+///
+/// Also returns the number of items changed.
+pub fn zen_items_in_list(
+	list: List,
+	search_terms: &SearchTerms,
+	policy: &ZenPolicy,
+	observer: &mut dyn MutationObserver,
+) -> (List, usize) {
+	let rescheduled = |item: &Item| {
+		if item.completion()
+			|| (!search_terms.terms.is_empty()
+				&& !search_terms.item_matches(item))
+		{
+			return None;
+		}
+		Some(item.zen(policy)).filter(|new| new.due_date() != item.due_date())
+	};
+	let mut new_list = list;
+	let mut count = 0;
+	observer.will_change(
+		new_list
+			.items()
+			.into_iter()
+			.filter(|i| rescheduled(i).is_some())
+			.count(),
+	);
+	for mut item in new_list.iter_items_mut() {
+		if let Some(new) = rescheduled(&item) {
+			if observer.before_change(&item) {
+				count += 1;
+				observer.after_change(&item, &new);
+				*item = new;
+			}
+		}
+	}
+	(new_list, count)
+}
+
+/// Writes an item which has been rescheduled, with its old and new due dates.
+pub fn write_rescheduled(old: &Item, new: &Item, outputter: &mut Outputter) {
+	let date = |i: &Item| match i.due_date() {
+		Some(d) => d.format("%Y-%m-%d").to_string(),
+		None => String::from("none"),
+	};
+	outputter.write_item(new);
+	outputter.write_notice(format!("    due {} -> {}", date(old), date(new)));
 }
 
 pub fn zen_quote() -> &'static str {
@@ -80,9 +239,112 @@ pub fn zen_quote() -> &'static str {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::item::with_today;
+	use crate::list::LineEnding;
+	use crate::Line;
+	use chrono::NaiveDate;
+	use tempfile::tempdir;
 
 	#[test]
 	fn test_get_action() {
 		assert_eq!(String::from("zen"), get_action().name);
 	}
+
+	#[test]
+	fn test_policy_from_argmatches() {
+		let cmd = get_action().command;
+		let policy = |argv: &[&str]| {
+			let m = cmd.clone().try_get_matches_from(argv).unwrap();
+			policy_from_argmatches(&m, ZenPolicy::default())
+		};
+
+		assert_eq!(ZenPolicy::default(), policy(&["zen"]));
+		assert_eq!(
+			ZenPolicy::all_to(Urgency::Soon),
+			policy(&["zen", "--all-to", "soon"])
+		);
+		assert_eq!(
+			ZenPolicy::default().with_max(Urgency::NextWeek),
+			policy(&["zen", "--max", "NextWeek"])
+		);
+		assert_eq!(
+			ZenPolicy::all_to(Urgency::NextMonth).with_max(Urgency::Today),
+			policy(&["zen", "--all-to", "next-month", "--max", "today"])
+		);
+
+		assert!(cmd
+			.clone()
+			.try_get_matches_from(["zen", "--all-to", "overdue"])
+			.is_err());
+		assert!(cmd
+			.clone()
+			.try_get_matches_from(["zen", "--max", "whenever"])
+			.is_err());
+	}
+
+	#[test]
+	fn test_zen_items_in_list() {
+		let dir = tempdir().unwrap();
+		let buffer_filename = dir
+			.path()
+			.join("buffer.txt")
+			.display()
+			.to_string();
+		let source_list = || List {
+			lines: Vec::from([
+				Line::from_string(String::from("Foo1 @work due:2000-01-01"), 1),
+				Line::from_string(String::from("Foo2  due:2000-01-01"), 2),
+				Line::from_string(String::from("Foo3  due:3999-01-01"), 3),
+				Line::from_string(String::from("Foo4 @work due:2024-06-08"), 4),
+				Line::from_string(String::from("x Foo5 due:2000-01-01"), 5),
+			]),
+			path: None,
+			url: None,
+			etag: None,
+			last_modified: None,
+			invalid_lines: Vec::new(),
+			line_ending: LineEnding::Lf,
+			missing_final_newline: false,
+			bom: false,
+			partial: false,
+		};
+		let day = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+
+		let mut o = Outputter::new(9999);
+		o.colour = false;
+		let buffer = fs::File::create(buffer_filename.clone()).unwrap();
+		o.msg_io = Box::new(buffer.try_clone().unwrap());
+		o.io = Box::new(buffer);
+		let (got, count) = with_today(day, || {
+			zen_items_in_list(
+				source_list(),
+				&SearchTerms::from_string("@work"),
+				&ZenPolicy::default(),
+				&mut ChangeWriter {
+					outputter: &mut o,
+					write: write_rescheduled,
+				},
+			)
+		});
+		assert_eq!(1, count);
+		assert_eq!("Foo1 @work due:2024-07-31", got.lines[0].text);
+		assert_eq!("Foo2  due:2000-01-01", got.lines[1].text);
+		let got_output = fs::read_to_string(buffer_filename.clone()).unwrap();
+		assert_eq!(
+			"  (?) Foo1 @work due:2024-07-31\n    due 2000-01-01 -> 2024-07-31\n",
+			got_output
+		);
+
+		let outcome = with_today(day, || {
+			run(source_list(), &ZenActionConfig::default(), &mut NoObserver)
+		})
+		.unwrap();
+		assert_eq!(2, outcome.count);
+		let got = outcome.list;
+		assert_eq!("Foo2  due:2024-07-31", got.lines[1].text);
+
+		// Untouched lines keep their original text.
+		assert_eq!("Foo3  due:3999-01-01", got.lines[2].text);
+		assert_eq!("x Foo5 due:2000-01-01", got.lines[4].text);
+	}
 }
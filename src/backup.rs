@@ -0,0 +1,491 @@
+//! Backups of local lists, made before they are overwritten.
+//!
+//! Each backup is a copy of the file in the backup directory (see
+//! `BackupConfig`), named after the file's absolute path and the time the
+//! backup was made, so the most recent backup sorts last.
+//!
+//! Each backup is also recorded in a journal in the same directory, along
+//! with the command which made it. All the files saved by one run of
+//! `tada` make up one operation, which `undo` reverts as a whole.
+
+use crate::config::BackupConfig;
+use chrono::Local;
+use lazy_static::lazy_static;
+use path_absolutize::*;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Mutex;
+
+lazy_static! {
+	/// Identifies the operation being carried out by this process.
+	static ref OPERATION_ID: String = format!(
+		"{}-{}",
+		Local::now().format("%Y%m%d%H%M%S%6f"),
+		process::id()
+	);
+	/// The command being run, recorded in the journal.
+	static ref COMMAND: Mutex<String> = Mutex::new(String::from("tada"));
+}
+
+/// Name of the journal file in the backup directory.
+const JOURNAL: &str = "journal.txt";
+
+/// How many operations the journal remembers.
+const JOURNAL_LENGTH: usize = 50;
+
+/// A run of `tada` which saved one or more files.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Operation {
+	pub id: String,
+	pub time: String,
+	pub command: String,
+	/// Each file saved, with its backup, or None if the file was created.
+	pub files: Vec<(String, Option<PathBuf>)>,
+}
+
+/// Characters which are escaped when turning a path into a backup name.
+const ESCAPED: &AsciiSet = &NON_ALPHANUMERIC
+	.remove(b'.')
+	.remove(b'-')
+	.remove(b'_');
+
+/// The prefix shared by all backups of a file.
+fn _prefix(path: &str) -> String {
+	let abs = Path::new(path)
+		.absolutize()
+		.map(|p| p.display().to_string())
+		.unwrap_or_else(|_| String::from(path));
+	format!("{}.", utf8_percent_encode(&abs, ESCAPED))
+}
+
+/// All backups of a file in a directory, oldest first.
+pub fn list_backups(path: &str, dir: &str) -> Vec<PathBuf> {
+	let prefix = _prefix(path);
+	let entries = match fs::read_dir(dir) {
+		Ok(e) => e,
+		Err(_) => return Vec::new(),
+	};
+	let mut found: Vec<PathBuf> = entries
+		.filter_map(|e| e.ok())
+		.filter(|e| {
+			let name = e.file_name().to_string_lossy().to_string();
+			match name
+				.strip_prefix(&prefix)
+				.and_then(|n| n.strip_suffix(".bak"))
+			{
+				Some(stamp) => stamp.chars().all(|c| c.is_ascii_digit()),
+				None => false,
+			}
+		})
+		.map(|e| e.path())
+		.collect();
+	found.sort();
+	found
+}
+
+/// Copy a file into a directory, then remove all but the newest `keep`
+/// backups of it.
+///
+/// Returns the path of the new backup.
+pub fn backup_file(path: &str, dir: &str, keep: usize) -> io::Result<PathBuf> {
+	fs::create_dir_all(dir)?;
+	let stamp = Local::now().format("%Y%m%d%H%M%S%6f");
+	let mut backup =
+		Path::new(dir).join(format!("{}{}.bak", _prefix(path), stamp));
+	// Two saves in the same microsecond shouldn't overwrite a backup.
+	let mut n = 0;
+	while backup.exists() {
+		n += 1;
+		backup = Path::new(dir).join(format!(
+			"{}{}{:02}.bak",
+			_prefix(path),
+			stamp,
+			n
+		));
+	}
+	fs::copy(path, &backup)?;
+	prune(path, dir, keep)?;
+	Ok(backup)
+}
+
+/// Remove all but the newest `keep` backups of a file.
+///
+/// Returns the number of backups removed.
+pub fn prune(path: &str, dir: &str, keep: usize) -> io::Result<usize> {
+	let backups = list_backups(path, dir);
+	let excess = backups.len().saturating_sub(keep);
+	for old in backups.iter().take(excess) {
+		fs::remove_file(old)?;
+	}
+	Ok(excess)
+}
+
+/// Set the command name recorded in the journal by this process.
+pub fn set_command(command: &str) {
+	*COMMAND.lock().unwrap() = String::from(command);
+}
+
+/// Back up a file before it is overwritten, and record it in the journal,
+/// if backups are enabled.
+///
+/// If the file doesn't exist yet, that is recorded in the journal, so
+/// `undo` can remove it.
+///
+/// Returns the path of the new backup, if one was made.
+pub fn maybe_backup(
+	path: &str,
+	cfg: &BackupConfig,
+) -> io::Result<Option<PathBuf>> {
+	if !cfg.enabled() {
+		return Ok(None);
+	}
+	let dir = match cfg.dir() {
+		Some(d) => d,
+		None => return Ok(None),
+	};
+	let backup = if Path::new(path).is_file() {
+		Some(backup_file(path, &dir, cfg.keep())?)
+	} else {
+		None
+	};
+	let time = Local::now()
+		.format("%Y-%m-%d %H:%M:%S")
+		.to_string();
+	let command = COMMAND.lock().unwrap().clone();
+	_record(
+		&dir,
+		&OPERATION_ID,
+		&time,
+		&command,
+		path,
+		backup.as_deref(),
+	)?;
+	Ok(backup)
+}
+
+/// Append a line to the journal, forgetting the oldest operations if
+/// there are too many.
+fn _record(
+	dir: &str,
+	id: &str,
+	time: &str,
+	command: &str,
+	path: &str,
+	backup: Option<&Path>,
+) -> io::Result<()> {
+	fs::create_dir_all(dir)?;
+	let abs = Path::new(path)
+		.absolutize()
+		.map(|p| p.display().to_string())
+		.unwrap_or_else(|_| String::from(path));
+	let backup = backup
+		.map(|b| b.display().to_string())
+		.unwrap_or_else(|| String::from("-"));
+	let mut lines = _journal_lines(dir);
+	lines.push(format!(
+		"{}\t{}\t{}\t{}\t{}",
+		id, time, command, abs, backup
+	));
+
+	let mut ids: Vec<&str> = Vec::new();
+	for l in lines.iter() {
+		let id = l.split('\t').next().unwrap_or_default();
+		if !ids.contains(&id) {
+			ids.push(id);
+		}
+	}
+	let keep_from = ids.len().saturating_sub(JOURNAL_LENGTH);
+	let keep = ids[keep_from..].to_vec();
+	let lines: Vec<&String> = lines
+		.iter()
+		.filter(|l| keep.contains(&l.split('\t').next().unwrap_or_default()))
+		.collect();
+
+	let mut f = fs::File::create(Path::new(dir).join(JOURNAL))?;
+	for l in lines {
+		writeln!(f, "{}", l)?;
+	}
+	Ok(())
+}
+
+/// The lines of the journal, or nothing if there's no journal.
+fn _journal_lines(dir: &str) -> Vec<String> {
+	fs::read_to_string(Path::new(dir).join(JOURNAL))
+		.map(|s| s.lines().map(String::from).collect())
+		.unwrap_or_default()
+}
+
+/// The operations recorded in the journal, oldest first.
+pub fn operations(dir: &str) -> Vec<Operation> {
+	let mut ops: Vec<Operation> = Vec::new();
+	for line in _journal_lines(dir) {
+		let f: Vec<&str> = line.split('\t').collect();
+		if f.len() != 5 {
+			continue;
+		}
+		let backup = match f[4] {
+			"-" => None,
+			b => Some(PathBuf::from(b)),
+		};
+		match ops.last_mut() {
+			Some(op) if op.id == f[0] => {
+				// If a file was saved twice, undo should restore the earlier
+				// version.
+				if !op.files.iter().any(|(p, _)| p == f[3]) {
+					op.files.push((String::from(f[3]), backup));
+				}
+			}
+			_ => ops.push(Operation {
+				id: String::from(f[0]),
+				time: String::from(f[1]),
+				command: String::from(f[2]),
+				files: Vec::from([(String::from(f[3]), backup)]),
+			}),
+		}
+	}
+	ops
+}
+
+/// Revert the most recent operation in the journal, and forget it.
+///
+/// Either every file changed by the operation is restored, or none are.
+/// Files which the operation created are removed.
+///
+/// Returns the operation which was reverted, or None if the journal is
+/// empty.
+pub fn undo(dir: &str) -> io::Result<Option<Operation>> {
+	let op = match operations(dir).pop() {
+		Some(op) => op,
+		None => return Ok(None),
+	};
+
+	// Copy every backup alongside its file first, so that nothing is
+	// changed if any backup is missing or can't be copied.
+	let mut staged: Vec<(&String, Option<String>)> = Vec::new();
+	for (path, backup) in op.files.iter() {
+		let result = match backup {
+			Some(b) => {
+				let tmp = format!("{}.tada-undo", path);
+				fs::copy(b, &tmp).map(|_| Some(tmp))
+			}
+			None => Ok(None),
+		};
+		match result {
+			Ok(tmp) => staged.push((path, tmp)),
+			Err(e) => {
+				for tmp in staged.into_iter().filter_map(|(_, t)| t) {
+					let _ = fs::remove_file(tmp);
+				}
+				return Err(e);
+			}
+		}
+	}
+	for (path, tmp) in staged {
+		match tmp {
+			Some(t) => fs::rename(t, path)?,
+			None if Path::new(path).exists() => fs::remove_file(path)?,
+			None => (),
+		}
+	}
+
+	for b in op.files.iter().filter_map(|(_, b)| b.as_ref()) {
+		let _ = fs::remove_file(b);
+	}
+	let remaining: Vec<String> = _journal_lines(dir)
+		.into_iter()
+		.filter(|l| l.split('\t').next() != Some(op.id.as_str()))
+		.collect();
+	let mut f = fs::File::create(Path::new(dir).join(JOURNAL))?;
+	for l in remaining {
+		writeln!(f, "{}", l)?;
+	}
+	Ok(Some(op))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tempfile::tempdir;
+
+	#[test]
+	fn test_backup_file() {
+		let dir = tempdir().unwrap();
+		let backups = dir.path().join("backups").display().to_string();
+		let todo = dir
+			.path()
+			.join("todo.txt")
+			.display()
+			.to_string();
+		let other = dir
+			.path()
+			.join("todo.txt2")
+			.display()
+			.to_string();
+		fs::write(&todo, "Foo\n").unwrap();
+		fs::write(&other, "Bar\n").unwrap();
+
+		let b = backup_file(&todo, &backups, 10).unwrap();
+		backup_file(&other, &backups, 10).unwrap();
+		assert_eq!("Foo\n", fs::read_to_string(&b).unwrap());
+		assert_eq!(Vec::from([b]), list_backups(&todo, &backups));
+		assert_eq!(1, list_backups(&other, &backups).len());
+	}
+
+	#[test]
+	fn test_prune() {
+		let dir = tempdir().unwrap();
+		let backups = dir.path().join("backups").display().to_string();
+		let todo = dir
+			.path()
+			.join("todo.txt")
+			.display()
+			.to_string();
+		for i in 1..=5 {
+			fs::write(&todo, format!("Foo{}\n", i)).unwrap();
+			backup_file(&todo, &backups, 3).unwrap();
+		}
+		let got: Vec<String> = list_backups(&todo, &backups)
+			.iter()
+			.map(|b| fs::read_to_string(b).unwrap())
+			.collect();
+		assert_eq!(Vec::from(["Foo3\n", "Foo4\n", "Foo5\n"]), got);
+		assert_eq!(2, prune(&todo, &backups, 1).unwrap());
+		assert_eq!(0, prune(&todo, &backups, 1).unwrap());
+	}
+
+	#[test]
+	fn test_operations() {
+		let dir = tempdir().unwrap();
+		let backups = dir.path().join("backups").display().to_string();
+		assert!(operations(&backups).is_empty());
+
+		let b = Path::new("/b/1.bak");
+		_record(&backups, "1", "t1", "tidy", "/todo.txt", Some(b)).unwrap();
+		_record(&backups, "2", "t2", "archive", "/done.txt", None).unwrap();
+		_record(&backups, "2", "t2", "archive", "/todo.txt", Some(b)).unwrap();
+		_record(&backups, "2", "t2", "archive", "/done.txt", Some(b)).unwrap();
+
+		let ops = operations(&backups);
+		assert_eq!(2, ops.len());
+		assert_eq!("tidy", ops[0].command);
+		assert_eq!(
+			Operation {
+				id: String::from("2"),
+				time: String::from("t2"),
+				command: String::from("archive"),
+				files: Vec::from([
+					(String::from("/done.txt"), None),
+					(String::from("/todo.txt"), Some(PathBuf::from(b))),
+				]),
+			},
+			ops[1]
+		);
+
+		for i in 0..JOURNAL_LENGTH {
+			let id = format!("x{}", i);
+			_record(&backups, &id, "t", "tidy", "/todo.txt", None).unwrap();
+		}
+		let ops = operations(&backups);
+		assert_eq!(JOURNAL_LENGTH, ops.len());
+		assert_eq!("x0", ops[0].id);
+	}
+
+	#[test]
+	fn test_undo() {
+		let dir = tempdir().unwrap();
+		let backups = dir.path().join("backups").display().to_string();
+		let todo = dir
+			.path()
+			.join("todo.txt")
+			.display()
+			.to_string();
+		let done = dir
+			.path()
+			.join("done.txt")
+			.display()
+			.to_string();
+		assert_eq!(None, undo(&backups).unwrap());
+
+		// Like `tidy`, then `archive` creating a done.txt.
+		fs::write(&todo, "x Foo\n\nBar\n").unwrap();
+		let b = backup_file(&todo, &backups, 10).unwrap();
+		_record(&backups, "1", "t1", "tidy", &todo, Some(&b)).unwrap();
+		fs::write(&todo, "x Foo\nBar\n").unwrap();
+		_record(&backups, "2", "t2", "archive", &done, None).unwrap();
+		let b = backup_file(&todo, &backups, 10).unwrap();
+		_record(&backups, "2", "t2", "archive", &todo, Some(&b)).unwrap();
+		fs::write(&done, "x Foo\n").unwrap();
+		fs::write(&todo, "\nBar\n").unwrap();
+
+		assert_eq!("archive", undo(&backups).unwrap().unwrap().command);
+		assert_eq!("x Foo\nBar\n", fs::read_to_string(&todo).unwrap());
+		assert!(!Path::new(&done).exists());
+
+		assert_eq!("tidy", undo(&backups).unwrap().unwrap().command);
+		assert_eq!("x Foo\n\nBar\n", fs::read_to_string(&todo).unwrap());
+		assert_eq!(None, undo(&backups).unwrap());
+		assert!(list_backups(&todo, &backups).is_empty());
+	}
+
+	#[test]
+	fn test_undo_is_atomic() {
+		let dir = tempdir().unwrap();
+		let backups = dir.path().join("backups").display().to_string();
+		let todo = dir
+			.path()
+			.join("todo.txt")
+			.display()
+			.to_string();
+		let done = dir
+			.path()
+			.join("done.txt")
+			.display()
+			.to_string();
+		fs::write(&todo, "x Foo\n").unwrap();
+		fs::write(&done, "").unwrap();
+		let b1 = backup_file(&todo, &backups, 10).unwrap();
+		let b2 = backup_file(&done, &backups, 10).unwrap();
+		_record(&backups, "1", "t1", "archive", &todo, Some(&b1)).unwrap();
+		_record(&backups, "1", "t1", "archive", &done, Some(&b2)).unwrap();
+		fs::write(&todo, "\n").unwrap();
+		fs::write(&done, "x Foo\n").unwrap();
+		fs::remove_file(&b2).unwrap();
+
+		assert!(undo(&backups).is_err());
+		assert_eq!("\n", fs::read_to_string(&todo).unwrap());
+		assert_eq!("x Foo\n", fs::read_to_string(&done).unwrap());
+		assert!(!Path::new(&format!("{}.tada-undo", todo)).exists());
+		assert_eq!(1, operations(&backups).len());
+	}
+
+	#[test]
+	fn test_maybe_backup() {
+		let dir = tempdir().unwrap();
+		let backups = dir.path().join("backups").display().to_string();
+		let todo = dir
+			.path()
+			.join("todo.txt")
+			.display()
+			.to_string();
+		let cfg = BackupConfig {
+			enabled: Some(true),
+			keep: None,
+			dir: Some(backups.clone()),
+		};
+		assert!(maybe_backup(&todo, &cfg).unwrap().is_none());
+		fs::write(&todo, "Foo\n").unwrap();
+		assert!(maybe_backup(&todo, &cfg).unwrap().is_some());
+		let files = &operations(&backups)[0].files;
+		assert_eq!(1, files.len());
+		assert_eq!(None, files[0].1);
+		let cfg = BackupConfig {
+			enabled: Some(false),
+			..cfg
+		};
+		assert!(maybe_backup(&todo, &cfg).unwrap().is_none());
+		assert_eq!(1, list_backups(&todo, &backups).len());
+	}
+}
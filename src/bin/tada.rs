@@ -1,28 +1,50 @@
 //! Provides the `tada` command
 
-use clap::Command;
-use std::process;
+use clap::{Arg, ArgAction, Command};
+use std::{env, process};
 use tada::action;
 use tada::action::Action;
+use tada::backup;
+use tada::config::Config;
+use tada::logging;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// Get a list of valid subcommands.
 fn actions() -> Vec<Action> {
 	Vec::from([
+		action::init::get_action(),
 		action::add::get_action(),
 		action::remove::get_action(),
 		action::edit::get_action(),
+		action::modify::get_action(),
+		action::rename_tag::get_action(),
+		action::rename_context::get_action(),
 		action::pull::get_action(),
+		action::snooze::get_action(),
+		action::pri::get_action(),
+		action::review::get_action(),
 		action::done::get_action(),
 		action::find::get_action(),
 		action::show::get_action(),
+		action::stats::get_action(),
+		action::start::get_action(),
+		action::stop::get_action(),
+		action::export::get_action(),
+		action::import::get_action(),
 		action::important::get_action(),
 		action::urgent::get_action(),
 		action::quick::get_action(),
+		action::today::get_action(),
+		action::next::get_action(),
 		action::archive::get_action(),
+		action::count::get_action(),
+		action::sync::get_action(),
 		action::tidy::get_action(),
+		action::lint::get_action(),
+		action::undo::get_action(),
 		action::zen::get_action(),
+		action::age::get_action(),
 		action::path::get_action(),
 	])
 }
@@ -34,7 +56,43 @@ fn main() {
 		.about("A todo list manager")
 		.subcommand_required(true)
 		.term_width(72)
-		.allow_external_subcommands(true);
+		.allow_external_subcommands(true)
+		.arg(
+			Arg::new("profile")
+				.long("profile")
+				.value_name("NAME")
+				.global(true)
+				.help("Use a profile from the config file"),
+		)
+		.arg(
+			Arg::new("no-backup")
+				.num_args(0)
+				.long("no-backup")
+				.global(true)
+				.help("Don't back up files before changing them"),
+		)
+		.arg(
+			Arg::new("strict")
+				.num_args(0)
+				.long("strict")
+				.global(true)
+				.help("Fail on lines which aren't valid UTF-8"),
+		)
+		.arg(
+			Arg::new("no-hints")
+				.num_args(0)
+				.long("no-hints")
+				.global(true)
+				.help("Don't suggest tidying lists or improving tasks"),
+		)
+		.arg(
+			Arg::new("verbose")
+				.short('v')
+				.long("verbose")
+				.action(ArgAction::Count)
+				.global(true)
+				.help("Log details to stderr; repeat for more"),
+		);
 
 	for action in actions() {
 		cmd = cmd.subcommand(action.command);
@@ -49,23 +107,52 @@ fn main() {
 		}
 	};
 
-	match subcommand {
+	logging::init(subcommand.1.get_count("verbose"));
+	if let Err(e) = Config::try_load() {
+		eprintln!("{}", e);
+		process::exit(e.code());
+	}
+	backup::set_command(subcommand.0);
+	if subcommand.1.get_flag("no-backup") {
+		env::set_var("TADA_BACKUP", "false");
+	}
+
+	let status = match subcommand {
 		("add", args) => action::add::execute(args),
+		("age", args) => action::age::execute(args),
 		("archive", args) => action::archive::execute(args),
+		("count", args) => action::count::execute(args),
 		("done", args) => action::done::execute(args),
 		("edit", args) => action::edit::execute(args),
+		("export", args) => action::export::execute(args),
 		("find", args) => action::find::execute(args),
+		("import", args) => action::import::execute(args),
 		("important", args) => action::important::execute(args),
+		("init", args) => action::init::execute(args),
+		("lint", args) => action::lint::execute(args),
+		("modify", args) => action::modify::execute(args),
+		("next", args) => action::next::execute(args),
 		("path", args) => action::path::execute(args),
+		("pri", args) => action::pri::execute(args),
 		("pull", args) => action::pull::execute(args),
 		("quick", args) => action::quick::execute(args),
 		("remove", args) => action::remove::execute(args),
+		("rename-context", args) => action::rename_context::execute(args),
+		("rename-tag", args) => action::rename_tag::execute(args),
+		("review", args) => action::review::execute(args),
 		("show", args) => action::show::execute(args),
+		("snooze", args) => action::snooze::execute(args),
+		("start", args) => action::start::execute(args),
+		("stats", args) => action::stats::execute(args),
+		("stop", args) => action::stop::execute(args),
+		("sync", args) => action::sync::execute(args),
 		("tidy", args) => action::tidy::execute(args),
+		("today", args) => action::today::execute(args),
+		("undo", args) => action::undo::execute(args),
 		("urgent", args) => action::urgent::execute(args),
 		("zen", args) => action::zen::execute(args),
 		(tag, _) => match tag.chars().next() {
-			Some('@') | Some('+') | Some('#') => {
+			Some('@') | Some('+') | Some('#') | Some('%') => {
 				action::find::execute_shortcut(tag)
 			}
 			_ => {
@@ -73,5 +160,13 @@ fn main() {
 				process::exit(1);
 			}
 		},
+	};
+
+	match status {
+		Ok(s) => process::exit(s.code()),
+		Err(e) => {
+			eprintln!("{}", e);
+			process::exit(e.code());
+		}
 	}
 }
@@ -0,0 +1,861 @@
+//! Reading the tada configuration file.
+//!
+//! The configuration file is TOML, found at `$TADA_CONFIG`, or else
+//! `$XDG_CONFIG_HOME/tada/config.toml`, or else `~/.config/tada/config.toml`.
+//!
+//! ```toml
+//! [profiles.work]
+//! todo = "~/work/todo.txt"
+//! done = "~/work/done.txt"
+//!
+//! [profiles.home]
+//! todo = "~/Documents/todo.txt"
+//!
+//! [http]
+//! timeout = 10
+//! retries = 3
+//! max_redirects = 5
+//!
+//! [backup]
+//! enabled = true
+//! keep = 10
+//! dir = "~/.local/state/tada/backups"
+//!
+//! [dates]
+//! natural = true
+//! week_start = "monday"
+//! weekday_contexts = ["work", "school"]
+//! stale_days = 30
+//!
+//! [ids]
+//! auto = false
+//!
+//! [done]
+//! archive = false
+//!
+//! [edit]
+//! editor = "code --wait"
+//!
+//! [files]
+//! preserve_bom = false
+//!
+//! [hints]
+//! max_completed = 9
+//! max_blank = 9
+//!
+//! [lint]
+//! keys = ["ref", "at"]
+//! max_age = 365
+//!
+//! [age]
+//! overdue = "A"
+//! today = "A"
+//! soon = "B"
+//! this_week = "none"
+//!
+//! [icons]
+//! overdue = "[!]"
+//! today = "[*]"
+//! soon = "[~]"
+//! small = "."
+//! medium = ".."
+//! large = "..."
+//!
+//! [zen]
+//! important_small = "soon"
+//! important_or_small = "next-week"
+//! other = "next-month"
+//! max = "next-month"
+//! ```
+
+use crate::action::Icons;
+use crate::error::Error;
+use crate::item::{AgePolicy, Importance, Urgency, ZenPolicy};
+use chrono::Weekday;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::str::FromStr;
+use std::time::Duration;
+use std::{env, fs};
+
+lazy_static! {
+	static ref LOADED: Result<Config, Error> = Config::read();
+}
+
+/// Contents of the configuration file.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct Config {
+	#[serde(default)]
+	pub profiles: BTreeMap<String, Profile>,
+	#[serde(default)]
+	pub http: HttpConfig,
+	#[serde(default)]
+	pub backup: BackupConfig,
+	#[serde(default)]
+	pub dates: DatesConfig,
+	#[serde(default)]
+	pub ids: IdsConfig,
+	#[serde(default)]
+	pub done: DoneConfig,
+	#[serde(default)]
+	pub edit: EditConfig,
+	#[serde(default)]
+	pub zen: ZenConfig,
+	#[serde(default)]
+	pub files: FilesConfig,
+	#[serde(default)]
+	pub hints: HintsConfig,
+	#[serde(default)]
+	pub lint: LintConfig,
+	#[serde(default)]
+	pub icons: IconsConfig,
+	#[serde(default)]
+	pub age: AgeConfig,
+}
+
+/// A named todo list, with its own todo.txt and done.txt.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct Profile {
+	pub todo: Option<String>,
+	pub done: Option<String>,
+}
+
+/// Settings for reading and writing lists over HTTP.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct HttpConfig {
+	/// Connect and read timeout, in seconds.
+	pub timeout: Option<u64>,
+	/// How many times to retry a failed `GET`.
+	pub retries: Option<u32>,
+	/// How many redirects to follow.
+	pub max_redirects: Option<usize>,
+}
+
+/// Settings for backing up local lists before they are overwritten.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct BackupConfig {
+	/// Whether to make backups at all.
+	pub enabled: Option<bool>,
+	/// How many backups of each list to keep.
+	pub keep: Option<usize>,
+	/// Where to keep backups.
+	pub dir: Option<String>,
+}
+
+/// Settings for reading dates in tasks.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct DatesConfig {
+	/// Whether to understand dates like `due:next_friday`.
+	pub natural: Option<bool>,
+	/// The first day of the week, like "monday" or "sun".
+	pub week_start: Option<String>,
+	/// Contexts for tasks which shouldn't be rescheduled onto a weekend.
+	pub weekday_contexts: Option<Vec<String>>,
+	/// Tasks older than this many days are highlighted by `--show-age`.
+	pub stale_days: Option<i64>,
+}
+
+/// Settings for giving tasks unique `id:` tags.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct IdsConfig {
+	/// Whether to give every new task an id.
+	pub auto: Option<bool>,
+}
+
+/// Settings for marking tasks as done.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct DoneConfig {
+	/// Whether `done` should move tasks to done.txt straight away.
+	pub archive: Option<bool>,
+}
+
+/// Settings for opening lists in an editor.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct EditConfig {
+	/// The editor command, which may include arguments.
+	pub editor: Option<String>,
+}
+
+/// Settings for how list files are written.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct FilesConfig {
+	/// Whether to keep a byte order mark at the start of a file.
+	pub preserve_bom: Option<bool>,
+}
+
+/// Settings for the hints suggesting it's time to tidy a list.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct HintsConfig {
+	/// Suggest archiving when there are more completed tasks than this.
+	pub max_completed: Option<usize>,
+	/// Suggest tidying when there are more blank/comment lines than this.
+	pub max_blank: Option<usize>,
+}
+
+/// Settings for the checks `tada lint` makes.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct LintConfig {
+	/// Keys allowed in key-value tags, besides the ones tada uses.
+	pub keys: Option<Vec<String>>,
+	/// Report tasks with no due date created more than this many days ago.
+	pub max_age: Option<i64>,
+}
+
+/// Settings for how `age` raises the priority of tasks.
+///
+/// Each value is a priority like "A", or "none" for tasks with that
+/// urgency to be left alone.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct AgeConfig {
+	/// Priority for overdue tasks.
+	pub overdue: Option<String>,
+	/// Priority for tasks due today.
+	pub today: Option<String>,
+	/// Priority for tasks due soon.
+	pub soon: Option<String>,
+	/// Priority for tasks due this week.
+	pub this_week: Option<String>,
+	/// Priority for tasks due next week.
+	pub next_week: Option<String>,
+	/// Priority for tasks due next month.
+	pub next_month: Option<String>,
+}
+
+/// Glyphs shown before tasks with `--icons`.
+///
+/// Any which aren't set use the defaults from `Icons`, so terminals
+/// without emoji can use ASCII instead.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct IconsConfig {
+	/// Shown for overdue tasks.
+	pub overdue: Option<String>,
+	/// Shown for tasks due today.
+	pub today: Option<String>,
+	/// Shown for tasks due soon.
+	pub soon: Option<String>,
+	/// Shown for small tasks. There are no size icons by default.
+	pub small: Option<String>,
+	/// Shown for medium tasks.
+	pub medium: Option<String>,
+	/// Shown for large tasks.
+	pub large: Option<String>,
+}
+
+/// Settings for how `zen` reschedules overdue tasks.
+///
+/// Each value is an urgency like "today", "soon", or "next-week".
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct ZenConfig {
+	/// Urgency for tasks which are both important and small.
+	pub important_small: Option<String>,
+	/// Urgency for tasks which are important or small, but not both.
+	pub important_or_small: Option<String>,
+	/// Urgency for all other tasks.
+	pub other: Option<String>,
+	/// The least urgent a rescheduled task may be.
+	pub max: Option<String>,
+}
+
+impl Config {
+	/// Determine the path of the configuration file.
+	pub fn path() -> Option<String> {
+		if let Ok(f) = env::var("TADA_CONFIG") {
+			return Some(f);
+		}
+		if let Ok(d) = env::var("XDG_CONFIG_HOME") {
+			return Some(d + "/tada/config.toml");
+		}
+		env::var("HOME")
+			.ok()
+			.map(|d| d + "/.config/tada/config.toml")
+	}
+
+	/// Parse configuration from a string.
+	pub fn from_string(s: &str) -> Result<Self, toml::de::Error> {
+		toml::from_str(s)
+	}
+
+	/// Load the configuration file, which is only read once.
+	///
+	/// A missing file is treated as an empty configuration, and so is one
+	/// which can't be read or parsed; see `try_load`.
+	pub fn load() -> Self {
+		LOADED.clone().unwrap_or_default()
+	}
+
+	/// Load the configuration file, which is only read once, failing if it
+	/// exists but can't be read or parsed.
+	pub fn try_load() -> Result<Self, Error> {
+		LOADED.clone()
+	}
+
+	/// Read the configuration file, without caching it.
+	pub fn read() -> Result<Self, Error> {
+		match Self::path() {
+			Some(p) => Self::read_from(&p),
+			None => Ok(Self::default()),
+		}
+	}
+
+	/// Read a configuration file from a path.
+	///
+	/// A missing file is treated as an empty configuration.
+	pub fn read_from(path: &str) -> Result<Self, Error> {
+		let bad = |message: String| Error::BadConfig {
+			path: String::from(path),
+			message,
+		};
+		match fs::read_to_string(path) {
+			Ok(s) => Self::from_string(&s)
+				.map_err(|e| bad(e.to_string().trim_end().to_string())),
+			Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+			Err(e) => Err(bad(e.to_string())),
+		}
+	}
+
+	/// Look up a profile by name.
+	pub fn profile(&self, name: &str) -> Option<&Profile> {
+		self.profiles.get(name)
+	}
+}
+
+impl Profile {
+	/// Path to this profile's todo.txt.
+	pub fn todo_filename(&self) -> Option<String> {
+		self.todo.as_deref().map(expand_home)
+	}
+
+	/// Path to this profile's done.txt.
+	///
+	/// If not given explicitly, a done.txt alongside the todo.txt is assumed.
+	pub fn done_filename(&self) -> Option<String> {
+		if let Some(d) = &self.done {
+			return Some(expand_home(d));
+		}
+		let todo = self.todo_filename()?;
+		match todo.rfind('/') {
+			Some(ix) => Some(format!("{}/done.txt", &todo[..ix])),
+			None => Some(String::from("done.txt")),
+		}
+	}
+}
+
+impl HttpConfig {
+	/// The timeout, from `TADA_HTTP_TIMEOUT` or the config file.
+	///
+	/// Defaults to 30 seconds.
+	pub fn timeout(&self) -> Duration {
+		Duration::from_secs(setting(
+			env::var("TADA_HTTP_TIMEOUT").ok(),
+			self.timeout,
+			30,
+		))
+	}
+
+	/// The number of retries, from `TADA_HTTP_RETRIES` or the config file.
+	///
+	/// Defaults to 2.
+	pub fn retries(&self) -> u32 {
+		setting(env::var("TADA_HTTP_RETRIES").ok(), self.retries, 2)
+	}
+
+	/// The maximum number of redirects to follow.
+	///
+	/// Defaults to 5.
+	pub fn max_redirects(&self) -> usize {
+		self.max_redirects.unwrap_or(5)
+	}
+}
+
+impl BackupConfig {
+	/// Whether to make backups, from `TADA_BACKUP` or the config file.
+	///
+	/// Defaults to true, except in unit tests, which shouldn't write to the
+	/// user's state directory.
+	pub fn enabled(&self) -> bool {
+		setting(env::var("TADA_BACKUP").ok(), self.enabled, cfg!(not(test)))
+	}
+
+	/// How many backups of each list to keep, from `TADA_BACKUP_KEEP` or
+	/// the config file.
+	///
+	/// Defaults to 10. At least one backup is always kept.
+	pub fn keep(&self) -> usize {
+		setting(env::var("TADA_BACKUP_KEEP").ok(), self.keep, 10).max(1)
+	}
+
+	/// The backup directory, from `TADA_BACKUP_DIR` or the config file.
+	///
+	/// Defaults to `$XDG_STATE_HOME/tada/backups`, or else
+	/// `~/.local/state/tada/backups`.
+	pub fn dir(&self) -> Option<String> {
+		if let Ok(d) = env::var("TADA_BACKUP_DIR") {
+			return Some(d);
+		}
+		if let Some(d) = &self.dir {
+			return Some(expand_home(d));
+		}
+		if let Ok(d) = env::var("XDG_STATE_HOME") {
+			return Some(d + "/tada/backups");
+		}
+		env::var("HOME")
+			.ok()
+			.map(|d| d + "/.local/state/tada/backups")
+	}
+}
+
+impl DatesConfig {
+	/// Whether to understand natural language dates in `due:` and `start:`
+	/// tags, from `TADA_NATURAL_DATES` or the config file.
+	///
+	/// Defaults to true.
+	pub fn natural(&self) -> bool {
+		setting(env::var("TADA_NATURAL_DATES").ok(), self.natural, true)
+	}
+
+	/// The first day of the week, from `TADA_WEEK_START` or the config file.
+	///
+	/// Defaults to Monday, so weeks end on Sunday.
+	pub fn week_start(&self) -> Weekday {
+		setting(
+			env::var("TADA_WEEK_START").ok(),
+			self.week_start
+				.as_deref()
+				.and_then(|d| d.trim().parse().ok()),
+			Weekday::Mon,
+		)
+	}
+
+	/// Contexts for tasks which shouldn't be rescheduled onto a Saturday or
+	/// Sunday, from the config file.
+	///
+	/// Defaults to `work` and `school`. An empty list disables this.
+	pub fn weekday_contexts(&self) -> Vec<String> {
+		match &self.weekday_contexts {
+			Some(c) => c.clone(),
+			None => Vec::from([String::from("work"), String::from("school")]),
+		}
+	}
+
+	/// How many days old a task can be before `--show-age` highlights it,
+	/// from `TADA_STALE_DAYS` or the config file.
+	///
+	/// Defaults to 30.
+	pub fn stale_days(&self) -> i64 {
+		setting(env::var("TADA_STALE_DAYS").ok(), self.stale_days, 30)
+	}
+}
+
+impl IdsConfig {
+	/// Whether to give every new task an id, from `TADA_AUTO_IDS` or the
+	/// config file.
+	///
+	/// Defaults to false.
+	pub fn auto(&self) -> bool {
+		setting(env::var("TADA_AUTO_IDS").ok(), self.auto, false)
+	}
+}
+
+impl DoneConfig {
+	/// Whether `done` archives the tasks it marks complete, from
+	/// `TADA_AUTO_ARCHIVE` or the config file.
+	///
+	/// Defaults to false.
+	pub fn archive(&self) -> bool {
+		setting(env::var("TADA_AUTO_ARCHIVE").ok(), self.archive, false)
+	}
+}
+
+impl EditConfig {
+	/// The editor command from the config file, if one is set.
+	///
+	/// This takes precedence over `VISUAL` and `EDITOR`.
+	pub fn editor(&self) -> Option<String> {
+		self.editor
+			.as_deref()
+			.map(str::trim)
+			.filter(|e| !e.is_empty())
+			.map(String::from)
+	}
+}
+
+impl FilesConfig {
+	/// Whether a list which started with a byte order mark keeps it when
+	/// saved, from `TADA_PRESERVE_BOM` or the config file.
+	///
+	/// Defaults to false.
+	pub fn preserve_bom(&self) -> bool {
+		setting(env::var("TADA_PRESERVE_BOM").ok(), self.preserve_bom, false)
+	}
+}
+
+impl HintsConfig {
+	/// How many completed tasks a list may have before suggesting
+	/// `tada archive`, from `TADA_MAX_COMPLETED` or the config file.
+	///
+	/// Defaults to 9.
+	pub fn max_completed(&self) -> usize {
+		setting(env::var("TADA_MAX_COMPLETED").ok(), self.max_completed, 9)
+	}
+
+	/// How many blank/comment lines a list may have before suggesting
+	/// `tada tidy`, from `TADA_MAX_BLANK` or the config file.
+	///
+	/// Defaults to 9.
+	pub fn max_blank(&self) -> usize {
+		setting(env::var("TADA_MAX_BLANK").ok(), self.max_blank, 9)
+	}
+}
+
+impl LintConfig {
+	/// Keys which tada gives a meaning to in key-value tags.
+	pub const KNOWN_KEYS: [&'static str; 7] =
+		["after", "due", "id", "pri", "spent", "start", "started"];
+
+	/// Keys allowed in key-value tags: the ones tada uses, plus any from
+	/// the config file.
+	pub fn keys(&self) -> Vec<String> {
+		let mut keys: Vec<String> = Self::KNOWN_KEYS
+			.iter()
+			.map(|k| k.to_string())
+			.collect();
+		keys.extend(self.keys.clone().unwrap_or_default());
+		keys
+	}
+
+	/// How many days old a task with no due date may be before it is
+	/// reported, from `TADA_LINT_MAX_AGE` or the config file.
+	///
+	/// Defaults to 365.
+	pub fn max_age(&self) -> i64 {
+		setting(env::var("TADA_LINT_MAX_AGE").ok(), self.max_age, 365)
+	}
+}
+
+impl AgeConfig {
+	/// The policy described by the config file.
+	///
+	/// Anything missing or unparseable is taken from `AgePolicy::default`.
+	pub fn policy(&self) -> AgePolicy {
+		let importance =
+			|i: &Option<String>, default: Option<Importance>| match i
+				.as_deref()
+				.map(str::trim)
+			{
+				Some("none") => None,
+				Some(i) => i.parse().ok().or(default),
+				None => default,
+			};
+		let default = AgePolicy::default();
+		AgePolicy {
+			overdue: importance(&self.overdue, default.overdue),
+			today: importance(&self.today, default.today),
+			soon: importance(&self.soon, default.soon),
+			this_week: importance(&self.this_week, default.this_week),
+			next_week: importance(&self.next_week, default.next_week),
+			next_month: importance(&self.next_month, default.next_month),
+		}
+	}
+}
+
+impl IconsConfig {
+	/// The icons described by the config file, with defaults for any
+	/// which aren't set.
+	pub fn icons(&self) -> Icons {
+		let default = Icons::default();
+		let icon = |i: &Option<String>, d: String| i.clone().unwrap_or(d);
+		Icons {
+			overdue: icon(&self.overdue, default.overdue),
+			today: icon(&self.today, default.today),
+			soon: icon(&self.soon, default.soon),
+			small: icon(&self.small, default.small),
+			medium: icon(&self.medium, default.medium),
+			large: icon(&self.large, default.large),
+		}
+	}
+}
+
+impl ZenConfig {
+	/// The rescheduling policy described by the config file.
+	///
+	/// Anything missing or unparseable is taken from `ZenPolicy::default`.
+	pub fn policy(&self) -> ZenPolicy {
+		let urgency = |u: &Option<String>| {
+			u.as_deref()
+				.and_then(|u| Urgency::from_string(u.trim()).ok())
+		};
+		let default = ZenPolicy::default();
+		ZenPolicy {
+			important_small: urgency(&self.important_small)
+				.unwrap_or(default.important_small),
+			important_or_small: urgency(&self.important_or_small)
+				.unwrap_or(default.important_or_small),
+			other: urgency(&self.other).unwrap_or(default.other),
+			max: urgency(&self.max),
+		}
+	}
+}
+
+/// Pick a setting from an environment variable, the config file, or a default.
+///
+/// Unparseable environment variables are ignored.
+fn setting<T: FromStr>(
+	from_env: Option<String>,
+	from_config: Option<T>,
+	default: T,
+) -> T {
+	from_env
+		.and_then(|v| v.trim().parse().ok())
+		.or(from_config)
+		.unwrap_or(default)
+}
+
+/// Expand a leading `~/` to the user's home directory.
+fn expand_home(path: &str) -> String {
+	match (path.strip_prefix("~/"), env::var("HOME")) {
+		(Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+		_ => String::from(path),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_from_string() {
+		let cfg = Config::from_string(
+			"[profiles.work]\n\
+			todo = \"/w/todo.txt\"\n\
+			done = \"/w/archive.txt\"\n\
+			\n\
+			[profiles.home]\n\
+			todo = \"/h/todo.txt\"\n",
+		)
+		.unwrap();
+		assert_eq!(
+			Vec::from(["home", "work"]),
+			cfg.profiles
+				.keys()
+				.map(|k| k.as_str())
+				.collect::<Vec<&str>>()
+		);
+
+		let work = cfg.profile("work").unwrap();
+		assert_eq!(Some(String::from("/w/todo.txt")), work.todo_filename());
+		assert_eq!(Some(String::from("/w/archive.txt")), work.done_filename());
+
+		let home = cfg.profile("home").unwrap();
+		assert_eq!(Some(String::from("/h/done.txt")), home.done_filename());
+
+		assert_eq!(None, cfg.profile("school"));
+		assert_eq!(Config::default(), Config::from_string("").unwrap());
+		assert!(Config::from_string("[profiles").is_err());
+	}
+
+	#[test]
+	fn test_read_from() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir
+			.path()
+			.join("config.toml")
+			.display()
+			.to_string();
+		assert_eq!(Ok(Config::default()), Config::read_from(&path));
+
+		fs::write(&path, "[ids]\nauto = true\n").unwrap();
+		assert!(Config::read_from(&path).unwrap().ids.auto());
+
+		fs::write(&path, "[ids]\nauto = \"yes\"\n").unwrap();
+		match Config::read_from(&path) {
+			Err(Error::BadConfig { path: p, message }) => {
+				assert_eq!(path, p);
+				assert!(message.contains("expected a boolean"), "{}", message);
+				assert!(!message.ends_with('\n'));
+			}
+			other => panic!("expected an error, got {:?}", other),
+		}
+	}
+
+	#[test]
+	fn test_http_config() {
+		let cfg = Config::from_string(
+			"[http]\n\
+			timeout = 5\n\
+			max_redirects = 0\n",
+		)
+		.unwrap();
+		assert_eq!(Some(5), cfg.http.timeout);
+		assert_eq!(None, cfg.http.retries);
+		assert_eq!(0, cfg.http.max_redirects());
+		assert_eq!(5, Config::default().http.max_redirects());
+	}
+
+	#[test]
+	fn test_backup_config() {
+		let cfg = Config::from_string(
+			"[backup]\n\
+			enabled = true\n\
+			keep = 0\n\
+			dir = \"/b\"\n",
+		)
+		.unwrap();
+		assert!(cfg.backup.enabled());
+		assert_eq!(1, cfg.backup.keep());
+		assert_eq!(Some(String::from("/b")), cfg.backup.dir());
+		assert!(!Config::default().backup.enabled());
+		assert_eq!(10, Config::default().backup.keep());
+	}
+
+	#[test]
+	fn test_dates_config() {
+		let cfg = Config::from_string(
+			"[dates]\n\
+			natural = false\n\
+			week_start = \"Saturday\"\n\
+			weekday_contexts = []\n\
+			stale_days = 7\n",
+		)
+		.unwrap();
+		assert_eq!(Some(false), cfg.dates.natural);
+		assert_eq!(Weekday::Sat, cfg.dates.week_start());
+		assert!(cfg.dates.weekday_contexts().is_empty());
+		assert_eq!(7, cfg.dates.stale_days());
+
+		let cfg = Config::default();
+		assert_eq!(None, cfg.dates.natural);
+		assert_eq!(Weekday::Mon, cfg.dates.week_start());
+		assert_eq!(Vec::from(["work", "school"]), cfg.dates.weekday_contexts());
+		assert_eq!(30, cfg.dates.stale_days());
+
+		let cfg =
+			Config::from_string("[dates]\nweek_start = \"sun\"\n").unwrap();
+		assert_eq!(Weekday::Sun, cfg.dates.week_start());
+		let cfg = Config::from_string("[dates]\nweek_start = \"x\"\n").unwrap();
+		assert_eq!(Weekday::Mon, cfg.dates.week_start());
+	}
+
+	#[test]
+	fn test_ids_config() {
+		let cfg = Config::from_string("[ids]\nauto = true\n").unwrap();
+		assert_eq!(Some(true), cfg.ids.auto);
+		assert_eq!(None, Config::default().ids.auto);
+	}
+
+	#[test]
+	fn test_done_config() {
+		let cfg = Config::from_string("[done]\narchive = true\n").unwrap();
+		assert_eq!(Some(true), cfg.done.archive);
+		assert_eq!(None, Config::default().done.archive);
+	}
+
+	#[test]
+	fn test_edit_config() {
+		let cfg = Config::from_string("[edit]\neditor = \" code --wait \"\n")
+			.unwrap();
+		assert_eq!(Some(String::from("code --wait")), cfg.edit.editor());
+		let cfg = Config::from_string("[edit]\neditor = \"\"\n").unwrap();
+		assert_eq!(None, cfg.edit.editor());
+		assert_eq!(None, Config::default().edit.editor());
+	}
+
+	#[test]
+	fn test_files_config() {
+		let cfg =
+			Config::from_string("[files]\npreserve_bom = true\n").unwrap();
+		assert_eq!(Some(true), cfg.files.preserve_bom);
+		assert_eq!(None, Config::default().files.preserve_bom);
+	}
+
+	#[test]
+	fn test_hints_config() {
+		let cfg = Config::from_string("[hints]\nmax_blank = 50\n").unwrap();
+		assert_eq!(9, cfg.hints.max_completed());
+		assert_eq!(50, cfg.hints.max_blank());
+		assert_eq!(9, Config::default().hints.max_blank());
+	}
+
+	#[test]
+	fn test_lint_config() {
+		let cfg =
+			Config::from_string("[lint]\nkeys = [\"ref\"]\nmax_age = 90\n")
+				.unwrap();
+		assert!(cfg.lint.keys().contains(&String::from("ref")));
+		assert!(cfg.lint.keys().contains(&String::from("due")));
+		assert_eq!(90, cfg.lint.max_age());
+		assert!(!Config::default()
+			.lint
+			.keys()
+			.contains(&String::from("ref")));
+		assert_eq!(365, Config::default().lint.max_age());
+	}
+
+	#[test]
+	fn test_icons_config() {
+		let cfg =
+			Config::from_string("[icons]\noverdue = \"[!]\"\nsmall = \".\"\n")
+				.unwrap();
+		let icons = cfg.icons.icons();
+		assert_eq!("[!]", icons.overdue);
+		assert_eq!("📅", icons.today);
+		assert_eq!(".", icons.small);
+		assert_eq!("", icons.large);
+		assert_eq!(Icons::default(), Config::default().icons.icons());
+	}
+
+	#[test]
+	fn test_age_config() {
+		let cfg = Config::from_string(
+			"[age]\n\
+			soon = \"A\"\n\
+			this_week = \"none\"\n\
+			next_week = \"important\"\n\
+			next_month = \"bogus\"\n",
+		)
+		.unwrap();
+		let policy = cfg.age.policy();
+		assert_eq!(Some(Importance::A), policy.overdue);
+		assert_eq!(Some(Importance::A), policy.soon);
+		assert_eq!(None, policy.this_week);
+		assert_eq!(Some(Importance::B), policy.next_week);
+		assert_eq!(None, policy.next_month);
+
+		assert_eq!(AgePolicy::default(), Config::default().age.policy());
+	}
+
+	#[test]
+	fn test_zen_config() {
+		let cfg = Config::from_string(
+			"[zen]\n\
+			important_small = \"today\"\n\
+			other = \"whenever\"\n\
+			max = \"next week\"\n",
+		)
+		.unwrap();
+		let policy = cfg.zen.policy();
+		assert_eq!(Urgency::Today, policy.important_small);
+		assert_eq!(Urgency::NextWeek, policy.important_or_small);
+		assert_eq!(Urgency::NextMonth, policy.other);
+		assert_eq!(Some(Urgency::NextWeek), policy.max);
+
+		assert_eq!(ZenPolicy::default(), Config::default().zen.policy());
+	}
+
+	#[test]
+	fn test_setting() {
+		assert_eq!(7, setting(Some(String::from("7")), Some(5), 30));
+		assert_eq!(7, setting(Some(String::from(" 7 ")), None, 30));
+		assert_eq!(5, setting(Some(String::from("soon")), Some(5), 30));
+		assert_eq!(5, setting(None, Some(5), 30));
+		assert_eq!(30, setting::<u64>(None, None, 30));
+	}
+
+	#[test]
+	fn test_expand_home() {
+		let home = env::var("HOME").unwrap();
+		assert_eq!(format!("{}/todo.txt", home), expand_home("~/todo.txt"));
+		assert_eq!("/tmp/todo.txt", expand_home("/tmp/todo.txt"));
+	}
+}
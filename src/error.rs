@@ -0,0 +1,201 @@
+//! Errors which can stop an action from running.
+
+use std::fmt;
+
+/// An error which stops an action from running.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+	/// No file with any of the expected names was found by `--local`.
+	FileNotFound(String),
+	/// A file would be overwritten without `--force`.
+	AlreadyExists(String),
+	/// A list could not be saved.
+	Unwritable { url: String, message: String },
+	/// A list was changed on the server since it was read.
+	Conflict(String),
+	/// A file was found but could not be read.
+	Unreadable { label: String, message: String },
+	/// A profile was requested which isn't in the config file.
+	UnknownProfile(String),
+	/// `--all-profiles` was used but the config file has no profiles.
+	NoProfiles,
+	/// A list was saved, but it wasn't read from a file or URL.
+	NoSource,
+	/// None of the places a list's path can come from are set.
+	NoPath(String),
+	/// An environment variable names a directory which doesn't exist.
+	MissingDir { var: String, path: String },
+	/// A list's path was found, but there's no file there.
+	MissingList { path: String, source: String },
+	/// `undo` was used, but there are no changes to revert.
+	NothingToUndo,
+	/// With `--strict`, a list had a line which wasn't valid UTF-8.
+	InvalidText { label: String, line: usize },
+	/// A list's URL has a scheme other than `file`, `http`, or `https`.
+	UnsupportedUrl(String),
+	/// A list which was only partly read was saved.
+	PartialList(String),
+	/// A task being added already has a different priority to the one
+	/// requested, and replacing it wasn't forced.
+	ConflictingPriority { existing: char, requested: char },
+	/// The config file couldn't be read or parsed.
+	BadConfig { path: String, message: String },
+}
+
+impl Error {
+	/// The process exit code for this error.
+	pub fn code(&self) -> i32 {
+		match self {
+			Self::FileNotFound(_) => 1,
+			_ => 2,
+		}
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::FileNotFound(name) => {
+				write!(f, "no {} found in this directory or any parent", name)
+			}
+			Self::AlreadyExists(path) => {
+				write!(
+					f,
+					"{} already exists; use --force to overwrite it",
+					path
+				)
+			}
+			Self::Unwritable { url, message } => {
+				write!(f, "could not save {}: {}", url, message)
+			}
+			Self::Conflict(url) => {
+				write!(f, "{} changed on the server; re-run your command", url)
+			}
+			Self::Unreadable { label, message } => {
+				write!(f, "could not read {}: {}", label, message)
+			}
+			Self::UnknownProfile(name) => {
+				write!(f, "no profile called '{}' in the config file", name)
+			}
+			Self::NoProfiles => {
+				write!(f, "no profiles are defined in the config file")
+			}
+			Self::NoSource => {
+				write!(f, "list has no path or URL to save to")
+			}
+			Self::NoPath(label) => {
+				write!(
+					f,
+					"could not determine path to {}; set TODO_FILE, TODO_DIR, \
+					or HOME",
+					label
+				)
+			}
+			Self::MissingDir { var, path } => {
+				write!(
+					f,
+					"{} is set to {}, which doesn't exist; create it, or run \
+					`tada init` to set up a new list there",
+					var, path
+				)
+			}
+			Self::MissingList { path, source } => {
+				write!(
+					f,
+					"{} (from {}) doesn't exist; run `tada init` to create it",
+					path, source
+				)
+			}
+			Self::NothingToUndo => write!(f, "nothing to undo"),
+			Self::InvalidText { label, line } => {
+				write!(f, "line {} of {} isn't valid UTF-8", line, label)
+			}
+			Self::UnsupportedUrl(url) => {
+				write!(
+					f,
+					"can't use {}; lists must be local files or HTTP URLs",
+					url
+				)
+			}
+			Self::PartialList(url) => {
+				write!(
+					f,
+					"can't save {}; only some of its lines were read",
+					url
+				)
+			}
+			Self::ConflictingPriority {
+				existing,
+				requested,
+			} => {
+				write!(
+					f,
+					"task already has priority ({}); use --force to replace \
+					it with ({})",
+					existing, requested
+				)
+			}
+			Self::BadConfig { path, message } => {
+				write!(f, "could not read config file {}: {}", path, message)
+			}
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_display() {
+		assert_eq!(
+			"no todo.txt found in this directory or any parent",
+			Error::FileNotFound(String::from("todo.txt")).to_string()
+		);
+		assert_eq!(
+			"no profile called 'x' in the config file",
+			Error::UnknownProfile(String::from("x")).to_string()
+		);
+		assert_eq!(
+			"/x/todo.txt (from TODO_FILE) doesn't exist; run `tada init` to \
+			create it",
+			Error::MissingList {
+				path: String::from("/x/todo.txt"),
+				source: String::from("TODO_FILE"),
+			}
+			.to_string()
+		);
+		assert_eq!(
+			"line 3 of todo list /x/todo.txt isn't valid UTF-8",
+			Error::InvalidText {
+				label: String::from("todo list /x/todo.txt"),
+				line: 3,
+			}
+			.to_string()
+		);
+		assert_eq!(
+			"task already has priority (B); use --force to replace it with (A)",
+			Error::ConflictingPriority {
+				existing: 'B',
+				requested: 'A',
+			}
+			.to_string()
+		);
+		assert_eq!(
+			"could not read config file /x/config.toml: bad",
+			Error::BadConfig {
+				path: String::from("/x/config.toml"),
+				message: String::from("bad"),
+			}
+			.to_string()
+		);
+	}
+
+	#[test]
+	fn test_code() {
+		assert_eq!(1, Error::FileNotFound(String::from("todo.txt")).code());
+		assert_eq!(2, Error::NoProfiles.code());
+	}
+}
@@ -0,0 +1,254 @@
+//! Custom output formats for tasks, given as templates like `%n %-40d %D`.
+
+use crate::item::Item;
+use std::fmt;
+
+/// A piece of task data which a template can show.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Field {
+	/// `%n`: line number.
+	LineNumber,
+	/// `%x`: "x" if the task is complete.
+	Completion,
+	/// `%p`: priority letter.
+	Priority,
+	/// `%d`: description.
+	Description,
+	/// `%D`: due date.
+	DueDate,
+	/// `%C`: creation date.
+	CreationDate,
+	/// `%F`: completion date.
+	CompletionDate,
+	/// `%u`: urgency, like "Next week".
+	Urgency,
+	/// `%c`: contexts, joined by commas.
+	Contexts,
+	/// `%t`: tags, joined by commas.
+	Tags,
+}
+
+impl Field {
+	/// Get a field from its placeholder letter.
+	pub fn from_char(c: char) -> Option<Self> {
+		match c {
+			'n' => Some(Self::LineNumber),
+			'x' => Some(Self::Completion),
+			'p' => Some(Self::Priority),
+			'd' => Some(Self::Description),
+			'D' => Some(Self::DueDate),
+			'C' => Some(Self::CreationDate),
+			'F' => Some(Self::CompletionDate),
+			'u' => Some(Self::Urgency),
+			'c' => Some(Self::Contexts),
+			't' => Some(Self::Tags),
+			_ => None,
+		}
+	}
+
+	/// The value of this field for an item, or an empty string if the item
+	/// doesn't have one.
+	pub fn value(&self, i: &Item) -> String {
+		let date = |d: Option<chrono::NaiveDate>| {
+			d.map(|d| d.format("%Y-%m-%d").to_string())
+				.unwrap_or_default()
+		};
+		match self {
+			Self::LineNumber => i.line_number().to_string(),
+			Self::Completion => {
+				String::from(if i.completion() { "x" } else { "" })
+			}
+			Self::Priority => match i.priority() {
+				'\0' => String::new(),
+				p => String::from(p),
+			},
+			Self::Description => i.description().to_string(),
+			Self::DueDate => date(i.due_date()),
+			Self::CreationDate => date(i.creation_date()),
+			Self::CompletionDate => date(i.completion_date()),
+			Self::Urgency => i
+				.urgency()
+				.map(|u| String::from(u.label()))
+				.unwrap_or_default(),
+			Self::Contexts => i.contexts().join(","),
+			Self::Tags => i.tags().join(","),
+		}
+	}
+}
+
+/// Part of a parsed template.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Part {
+	Literal(String),
+	Placeholder {
+		field: Field,
+		left: bool,
+		width: usize,
+		max: Option<usize>,
+	},
+}
+
+/// A parsed output template.
+///
+/// Placeholders are `%` followed by optional modifiers and a letter, like
+/// printf: `%-40d` pads the description to 40 characters on the right,
+/// `%5n` pads the line number on the left, and `%.20d` cuts the
+/// description to at most 20 characters. `%%` is a percent sign.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Template {
+	parts: Vec<Part>,
+}
+
+/// Error when a template can't be parsed.
+#[derive(Debug, Eq, PartialEq)]
+pub struct InvalidTemplate {
+	pub given: String,
+	pub placeholder: String,
+}
+
+impl fmt::Display for InvalidTemplate {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"unknown placeholder '{}' in '{}'; expected %n, %x, %p, %d, %D, \
+			%C, %F, %u, %c, %t, or %%",
+			self.placeholder, self.given
+		)
+	}
+}
+
+impl Template {
+	/// Parse a template string.
+	pub fn parse(given: &str) -> Result<Self, InvalidTemplate> {
+		let mut parts: Vec<Part> = Vec::new();
+		let mut literal = String::new();
+		let mut chars = given.chars().peekable();
+		while let Some(c) = chars.next() {
+			if c != '%' {
+				literal.push(c);
+				continue;
+			}
+			if chars.peek() == Some(&'%') {
+				chars.next();
+				literal.push('%');
+				continue;
+			}
+
+			let mut spec = String::from("%");
+			let left = chars.next_if_eq(&'-').is_some();
+			if left {
+				spec.push('-');
+			}
+			let mut width = String::new();
+			while let Some(d) = chars.next_if(|c| c.is_ascii_digit()) {
+				width.push(d);
+			}
+			spec.push_str(&width);
+			let mut max: Option<String> = None;
+			if chars.next_if_eq(&'.').is_some() {
+				let mut m = String::new();
+				while let Some(d) = chars.next_if(|c| c.is_ascii_digit()) {
+					m.push(d);
+				}
+				spec.push('.');
+				spec.push_str(&m);
+				max = Some(m);
+			}
+			let letter = chars.next();
+			if let Some(l) = letter {
+				spec.push(l);
+			}
+			let field = match letter.and_then(Field::from_char) {
+				Some(f) if max.as_deref() != Some("") => f,
+				_ => {
+					return Err(InvalidTemplate {
+						given: given.to_string(),
+						placeholder: spec,
+					})
+				}
+			};
+
+			if !literal.is_empty() {
+				parts.push(Part::Literal(literal.clone()));
+				literal.clear();
+			}
+			parts.push(Part::Placeholder {
+				field,
+				left,
+				width: width.parse().unwrap_or(0),
+				max: max.map(|m| m.parse().unwrap()),
+			});
+		}
+		if !literal.is_empty() {
+			parts.push(Part::Literal(literal));
+		}
+
+		Ok(Self { parts })
+	}
+
+	/// Fill in the template for an item.
+	pub fn render(&self, i: &Item) -> String {
+		self.parts
+			.iter()
+			.map(|p| match p {
+				Part::Literal(s) => s.clone(),
+				Part::Placeholder {
+					field,
+					left,
+					width,
+					max,
+				} => {
+					let mut value = field.value(i);
+					if let Some(m) = max {
+						value = value.chars().take(*m).collect();
+					}
+					if *left {
+						format!("{:<width$}", value, width = width)
+					} else {
+						format!("{:>width$}", value, width = width)
+					}
+				}
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_render() {
+		let i = Item::parse(
+			"(B) 2024-06-01 Call Mum @phone @home +family due:2024-06-10",
+		);
+		let t = Template::parse("%p|%C|%D|%c|%t|%x|%d").unwrap();
+		assert_eq!(
+			"B|2024-06-01|2024-06-10|phone,home|family||Call Mum @phone @home +family due:2024-06-10",
+			t.render(&i)
+		);
+
+		let i = Item::parse("x 2024-06-05 2024-06-01 Foo");
+		let t = Template::parse("[%3n] [%-5x] [%.2d] [%F] [%p] [%u] 100%%")
+			.unwrap();
+		assert_eq!("[  0] [x    ] [Fo] [2024-06-05] [] [] 100%", t.render(&i));
+	}
+
+	#[test]
+	fn test_parse_errors() {
+		let err = Template::parse("%n %-5z").unwrap_err();
+		assert_eq!("%-5z", err.placeholder);
+		assert_eq!(
+			"unknown placeholder '%-5z' in '%n %-5z'; expected %n, %x, %p, %d, \
+			%D, %C, %F, %u, %c, %t, or %%",
+			err.to_string()
+		);
+		assert_eq!(
+			"%",
+			Template::parse("foo %")
+				.unwrap_err()
+				.placeholder
+		);
+		assert_eq!("%.d", Template::parse("%.d").unwrap_err().placeholder);
+	}
+}
@@ -0,0 +1,252 @@
+//! Export tasks as an iCalendar (RFC 5545) file.
+
+use crate::item::{Importance, Item};
+use chrono::NaiveDateTime;
+
+/// Longest line allowed by RFC 5545, in octets, excluding the line break.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Build a VCALENDAR containing a VTODO for each task with a due date.
+///
+/// Tasks without a due date are skipped. `stamp` is used for each task's
+/// DTSTAMP and should be the current time in UTC.
+pub fn calendar(items: &[&Item], stamp: NaiveDateTime) -> String {
+	let mut lines = vec![
+		String::from("BEGIN:VCALENDAR"),
+		String::from("VERSION:2.0"),
+		String::from("PRODID:-//tada//NONSGML tada//EN"),
+		String::from("CALSCALE:GREGORIAN"),
+	];
+	for i in items.iter().filter(|i| i.due_date().is_some()) {
+		lines.append(&mut vtodo(i, stamp));
+	}
+	lines.push(String::from("END:VCALENDAR"));
+
+	lines
+		.iter()
+		.map(|l| fold_line(l))
+		.collect::<Vec<String>>()
+		.join("")
+}
+
+/// The unfolded content lines of a VTODO for a task.
+pub fn vtodo(item: &Item, stamp: NaiveDateTime) -> Vec<String> {
+	let mut lines = vec![
+		String::from("BEGIN:VTODO"),
+		format!("UID:{}", escape_text(&uid(item))),
+		format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")),
+		format!("SUMMARY:{}", escape_text(&item.description_without_kv())),
+	];
+	if let Some(start) = item.start_date() {
+		lines.push(format!("DTSTART;VALUE=DATE:{}", start.format("%Y%m%d")));
+	}
+	match (item.due_date(), item.due_time()) {
+		(Some(d), Some(t)) => lines.push(format!(
+			"DUE:{}T{}",
+			d.format("%Y%m%d"),
+			t.format("%H%M%S")
+		)),
+		(Some(d), None) => {
+			lines.push(format!("DUE;VALUE=DATE:{}", d.format("%Y%m%d")))
+		}
+		_ => (),
+	}
+	if let Some(p) = item.importance().map(priority) {
+		lines.push(format!("PRIORITY:{}", p));
+	}
+	if item.completion() {
+		lines.push(String::from("STATUS:COMPLETED"));
+		if let Some(d) = item.completion_date() {
+			lines.push(format!("COMPLETED:{}T000000Z", d.format("%Y%m%d")));
+		}
+	} else {
+		lines.push(String::from("STATUS:NEEDS-ACTION"));
+	}
+	let categories = item
+		.contexts()
+		.iter()
+		.chain(item.tags().iter())
+		.map(|c| escape_text(c))
+		.collect::<Vec<String>>();
+	if !categories.is_empty() {
+		lines.push(format!("CATEGORIES:{}", categories.join(",")));
+	}
+	lines.push(String::from("END:VTODO"));
+	lines
+}
+
+/// A UID for a task which stays the same across exports.
+///
+/// Tasks with an `id:` tag use that. Otherwise the UID is a hash of the
+/// description, so it changes if the description is edited.
+pub fn uid(item: &Item) -> String {
+	match item.id() {
+		Some(id) => format!("{}@tada", id),
+		None => format!("{:016x}@tada", fnv1a(item.description().as_bytes())),
+	}
+}
+
+/// The iCalendar PRIORITY for an importance, where 1 is the highest.
+pub fn priority(importance: Importance) -> u8 {
+	match importance {
+		Importance::A => 1,
+		Importance::B => 3,
+		Importance::C => 5,
+		Importance::D => 7,
+		Importance::E(_) => 9,
+	}
+}
+
+/// Escape a TEXT value.
+pub fn escape_text(text: &str) -> String {
+	let mut out = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'\\' => out.push_str("\\\\"),
+			';' => out.push_str("\\;"),
+			',' => out.push_str("\\,"),
+			'\n' => out.push_str("\\n"),
+			'\r' => (),
+			_ => out.push(c),
+		}
+	}
+	out
+}
+
+/// Fold a content line so no line is longer than 75 octets, ending each
+/// line with CRLF.
+///
+/// Continuation lines start with a space, and multi-byte characters are
+/// never split.
+pub fn fold_line(line: &str) -> String {
+	let mut out = String::with_capacity(line.len() + 8);
+	let mut octets = 0;
+	for c in line.chars() {
+		if octets + c.len_utf8() > MAX_LINE_OCTETS {
+			out.push_str("\r\n ");
+			octets = 1;
+		}
+		out.push(c);
+		octets += c.len_utf8();
+	}
+	out.push_str("\r\n");
+	out
+}
+
+/// 64-bit FNV-1a hash, which unlike `DefaultHasher` is stable between
+/// Rust releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+	let mut hash: u64 = 0xcbf29ce484222325;
+	for b in bytes {
+		hash ^= u64::from(*b);
+		hash = hash.wrapping_mul(0x100000001b3);
+	}
+	hash
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::NaiveDate;
+
+	fn stamp() -> NaiveDateTime {
+		NaiveDate::from_ymd_opt(2024, 6, 7)
+			.unwrap()
+			.and_hms_opt(12, 0, 0)
+			.unwrap()
+	}
+
+	#[test]
+	fn test_escape_text() {
+		assert_eq!("a\\, b\\; c\\\\d\\ne", escape_text("a, b; c\\d\ne"));
+	}
+
+	#[test]
+	fn test_fold_line() {
+		assert_eq!("short\r\n", fold_line("short"));
+
+		let long = "x".repeat(160);
+		let folded = fold_line(&long);
+		let lines: Vec<&str> = folded.split("\r\n").collect();
+		assert_eq!(
+			vec![75, 75, 12, 0],
+			lines
+				.iter()
+				.map(|l| l.len())
+				.collect::<Vec<_>>()
+		);
+		assert!(lines[1].starts_with(' '));
+		assert_eq!(long, folded.replace("\r\n ", "").trim_end());
+
+		// Multi-byte characters are kept whole.
+		let long = format!("{}é", "x".repeat(74));
+		assert_eq!(format!("{}\r\n é\r\n", "x".repeat(74)), fold_line(&long));
+	}
+
+	#[test]
+	fn test_uid() {
+		let a = Item::parse("Foo id:ab3f due:2024-06-10");
+		assert_eq!("ab3f@tada", uid(&a));
+
+		let b = Item::parse("(A) Foo due:2024-06-10");
+		let c = Item::parse("x 2024-06-08 Foo due:2024-06-10");
+		assert_eq!(uid(&b), uid(&c));
+		assert_ne!(uid(&b), uid(&Item::parse("Bar due:2024-06-10")));
+	}
+
+	#[test]
+	fn test_calendar() {
+		let items = [
+			Item::parse("(A) Call Mum, about the party @phone +birthday id:m1 due:2024-06-10"),
+			Item::parse("(C) Pay rent due:2024-06-09T09:00 start:2024-06-01"),
+			Item::parse("x 2024-06-06 2024-06-01 Buy milk; eggs due:2024-06-05"),
+			Item::parse("No due date @home"),
+			Item::parse("Write a really long description which will certainly need to be folded due:2024-06-11"),
+		];
+		let refs: Vec<&Item> = items.iter().collect();
+		let expected = format!(
+			"BEGIN:VCALENDAR\r\n\
+			VERSION:2.0\r\n\
+			PRODID:-//tada//NONSGML tada//EN\r\n\
+			CALSCALE:GREGORIAN\r\n\
+			BEGIN:VTODO\r\n\
+			UID:m1@tada\r\n\
+			DTSTAMP:20240607T120000Z\r\n\
+			SUMMARY:Call Mum\\, about the party @phone +birthday\r\n\
+			DUE;VALUE=DATE:20240610\r\n\
+			PRIORITY:1\r\n\
+			STATUS:NEEDS-ACTION\r\n\
+			CATEGORIES:phone,birthday\r\n\
+			END:VTODO\r\n\
+			BEGIN:VTODO\r\n\
+			UID:{}\r\n\
+			DTSTAMP:20240607T120000Z\r\n\
+			SUMMARY:Pay rent\r\n\
+			DTSTART;VALUE=DATE:20240601\r\n\
+			DUE:20240609T090000\r\n\
+			PRIORITY:5\r\n\
+			STATUS:NEEDS-ACTION\r\n\
+			END:VTODO\r\n\
+			BEGIN:VTODO\r\n\
+			UID:{}\r\n\
+			DTSTAMP:20240607T120000Z\r\n\
+			SUMMARY:Buy milk\\; eggs\r\n\
+			DUE;VALUE=DATE:20240605\r\n\
+			STATUS:COMPLETED\r\n\
+			COMPLETED:20240606T000000Z\r\n\
+			END:VTODO\r\n\
+			BEGIN:VTODO\r\n\
+			UID:{}\r\n\
+			DTSTAMP:20240607T120000Z\r\n\
+			SUMMARY:Write a really long description which will certainly need to be fol\r\n ded\r\n\
+			DUE;VALUE=DATE:20240611\r\n\
+			STATUS:NEEDS-ACTION\r\n\
+			END:VTODO\r\n\
+			END:VCALENDAR\r\n",
+			uid(&items[1]),
+			uid(&items[2]),
+			uid(&items[4]),
+		);
+		assert_eq!(expected, calendar(&refs, stamp()));
+	}
+}
@@ -0,0 +1,167 @@
+//! Import tasks from other todo list managers.
+
+use crate::item::Item;
+use std::collections::BTreeMap;
+use std::fmt;
+
+pub mod todoist;
+
+/// A format which tasks can be imported from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+	Todoist,
+}
+
+/// Error when a format name isn't recognized.
+#[derive(Debug, Eq, PartialEq)]
+pub struct InvalidFormat {
+	pub given: String,
+}
+
+impl fmt::Display for InvalidFormat {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"unknown import format '{}'; expected one of: {}",
+			self.given,
+			Format::all()
+				.iter()
+				.map(|x| x.to_string())
+				.collect::<Vec<&str>>()
+				.join(", ")
+		)
+	}
+}
+
+/// Error when a file can't be imported.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ImportError {
+	pub message: String,
+}
+
+impl fmt::Display for ImportError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.message)
+	}
+}
+
+impl Format {
+	/// All formats.
+	pub fn all() -> Vec<Self> {
+		Vec::from([Self::Todoist])
+	}
+
+	/// Stringify the format.
+	pub fn to_string(&self) -> &str {
+		match self {
+			Self::Todoist => "todoist",
+		}
+	}
+
+	/// Get a format from its name.
+	pub fn from_string(format: &str) -> Result<Self, InvalidFormat> {
+		match format.to_lowercase().as_str() {
+			"todoist" => Ok(Self::Todoist),
+			_ => Err(InvalidFormat {
+				given: format.to_string(),
+			}),
+		}
+	}
+
+	/// Read tasks from the text of a file in this format.
+	pub fn parse(
+		&self,
+		text: &str,
+		options: &ImportOptions,
+	) -> Result<Import, ImportError> {
+		match self {
+			Self::Todoist => todoist::parse(text, options),
+		}
+	}
+}
+
+/// Options which apply to every format.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ImportOptions {
+	/// Import tasks which are already completed, instead of skipping them.
+	pub include_done: bool,
+}
+
+/// Tasks read from another format.
+#[derive(Debug, Default)]
+pub struct Import {
+	/// The tasks to add to the todo list.
+	pub items: Vec<Item>,
+	/// How many completed tasks were skipped.
+	pub skipped_done: usize,
+	/// Counts of things with no place in todo.txt, like comments.
+	pub dropped: BTreeMap<String, usize>,
+}
+
+impl Import {
+	/// Note that something couldn't be imported.
+	pub fn drop_field(&mut self, what: &str) {
+		*self
+			.dropped
+			.entry(what.to_string())
+			.or_insert(0) += 1;
+	}
+
+	/// Describe what was dropped, like "2 comments, 1 attachment".
+	pub fn dropped_summary(&self) -> Option<String> {
+		if self.dropped.is_empty() {
+			return None;
+		}
+		Some(
+			self.dropped
+				.iter()
+				.map(|(what, n)| match n {
+					1 => format!("1 {}", what),
+					_ => format!("{} {}s", n, what),
+				})
+				.collect::<Vec<String>>()
+				.join(", "),
+		)
+	}
+}
+
+/// Turn a project or label name into something usable as a `+tag` or
+/// `@context`, by replacing whitespace with underscores.
+pub fn tag_name(name: &str) -> String {
+	name.split_whitespace()
+		.collect::<Vec<&str>>()
+		.join("_")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_format_from_string() {
+		assert_eq!(Ok(Format::Todoist), Format::from_string("Todoist"));
+		let err = Format::from_string("trello").unwrap_err();
+		assert_eq!(
+			"unknown import format 'trello'; expected one of: todoist",
+			err.to_string()
+		);
+	}
+
+	#[test]
+	fn test_dropped_summary() {
+		let mut import = Import::default();
+		assert_eq!(None, import.dropped_summary());
+		import.drop_field("comment");
+		import.drop_field("comment");
+		import.drop_field("attachment");
+		assert_eq!(
+			Some(String::from("1 attachment, 2 comments")),
+			import.dropped_summary()
+		);
+	}
+
+	#[test]
+	fn test_tag_name() {
+		assert_eq!("Home_Stuff", tag_name(" Home  Stuff "));
+	}
+}
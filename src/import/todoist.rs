@@ -0,0 +1,318 @@
+//! Import tasks from a Todoist JSON export.
+//!
+//! This reads the JSON returned by Todoist's sync API, which has lists of
+//! `projects`, `items` (the tasks), `labels`, and `notes` (comments).
+
+use crate::import::{tag_name, Import, ImportError, ImportOptions};
+use crate::item::Item;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Debug, Default, Deserialize)]
+struct Export {
+	#[serde(default)]
+	projects: Vec<Project>,
+	#[serde(default)]
+	items: Vec<Task>,
+	#[serde(default)]
+	labels: Vec<Label>,
+	#[serde(default)]
+	notes: Vec<Note>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Project {
+	id: Value,
+	name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Label {
+	id: Value,
+	name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Task {
+	#[serde(default)]
+	id: Value,
+	#[serde(default)]
+	project_id: Value,
+	content: String,
+	#[serde(default)]
+	description: String,
+	#[serde(default)]
+	priority: u8,
+	#[serde(default)]
+	labels: Vec<Value>,
+	#[serde(default)]
+	due: Option<Due>,
+	#[serde(default)]
+	checked: Value,
+	#[serde(default)]
+	completed_at: Option<String>,
+	#[serde(default)]
+	added_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Due {
+	date: String,
+	#[serde(default)]
+	is_recurring: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Note {
+	#[serde(default)]
+	item_id: Value,
+	#[serde(default)]
+	file_attachment: Option<Value>,
+}
+
+/// Ids are strings in current exports, but numbers in older ones.
+fn id_string(id: &Value) -> String {
+	match id {
+		Value::String(s) => s.clone(),
+		other => other.to_string(),
+	}
+}
+
+/// Whether a value is `true` or a non-zero number.
+fn truthy(v: &Value) -> bool {
+	match v {
+		Value::Bool(b) => *b,
+		Value::Number(n) => n.as_i64().unwrap_or(0) != 0,
+		_ => false,
+	}
+}
+
+/// The importance letter for a Todoist priority.
+///
+/// Todoist shows priorities as p1 (highest) to p4, but stores them as
+/// numbers from 4 (p1) down to 1 (p4).
+pub fn priority_letter(priority: u8) -> Option<char> {
+	match priority {
+		4 => Some('A'),
+		3 => Some('B'),
+		2 => Some('C'),
+		1 => Some('D'),
+		_ => None,
+	}
+}
+
+/// Todoist dates are like "2024-06-10", "2024-06-10T09:00:00", or
+/// "2024-06-10T09:00:00Z"; keep the date and, if given, hours and minutes.
+fn due_value(date: &str) -> Option<String> {
+	let day = date.get(0..10)?;
+	match date.get(10..16) {
+		Some(time) if time.starts_with('T') => Some(format!("{}{}", day, time)),
+		_ => Some(day.to_string()),
+	}
+}
+
+/// Read tasks from the text of a Todoist JSON export.
+pub fn parse(
+	text: &str,
+	options: &ImportOptions,
+) -> Result<Import, ImportError> {
+	let export: Export =
+		serde_json::from_str(text).map_err(|e| ImportError {
+			message: format!("not a Todoist JSON export: {}", e),
+		})?;
+
+	let projects: HashMap<String, &str> = export
+		.projects
+		.iter()
+		.map(|p| (id_string(&p.id), p.name.as_str()))
+		.collect();
+	let labels: HashMap<String, &str> = export
+		.labels
+		.iter()
+		.map(|l| (id_string(&l.id), l.name.as_str()))
+		.collect();
+	let mut notes: HashMap<String, Vec<&Note>> = HashMap::new();
+	for n in &export.notes {
+		notes
+			.entry(id_string(&n.item_id))
+			.or_default()
+			.push(n);
+	}
+
+	let mut import = Import::default();
+	for task in &export.items {
+		let done = truthy(&task.checked) || task.completed_at.is_some();
+		if done && !options.include_done {
+			import.skipped_done += 1;
+			continue;
+		}
+
+		let mut parts: Vec<String> = Vec::new();
+		if done {
+			parts.push(String::from("x"));
+			if let Some(d) = task
+				.completed_at
+				.as_deref()
+				.and_then(|d| d.get(0..10))
+			{
+				parts.push(d.to_string());
+			}
+		} else if let Some(p) = priority_letter(task.priority) {
+			parts.push(format!("({})", p));
+		}
+		if let Some(d) = task
+			.added_at
+			.as_deref()
+			.and_then(|d| d.get(0..10))
+		{
+			parts.push(d.to_string());
+		}
+		parts.push(
+			task.content
+				.split_whitespace()
+				.collect::<Vec<&str>>()
+				.join(" "),
+		);
+		if let Some(name) = projects.get(&id_string(&task.project_id)) {
+			parts.push(format!("+{}", tag_name(name)));
+		}
+		for label in &task.labels {
+			let name = match label {
+				Value::String(s) => Some(s.as_str()),
+				other => labels.get(&id_string(other)).copied(),
+			};
+			if let Some(name) = name {
+				parts.push(format!("@{}", tag_name(name)));
+			}
+		}
+		if let Some(due) = &task.due {
+			if let Some(d) = due_value(&due.date) {
+				parts.push(format!("due:{}", d));
+			}
+			if due.is_recurring {
+				import.drop_field("recurrence");
+			}
+		}
+
+		if !task.description.trim().is_empty() {
+			import.drop_field("description");
+		}
+		for n in notes
+			.get(&id_string(&task.id))
+			.unwrap_or(&Vec::new())
+		{
+			match n.file_attachment {
+				Some(Value::Null) | None => import.drop_field("comment"),
+				Some(_) => import.drop_field("attachment"),
+			}
+		}
+
+		import.items.push(Item::parse(&parts.join(" ")));
+	}
+
+	Ok(import)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const EXPORT: &str = r#"{
+		"projects": [
+			{ "id": "p1", "name": "Home Stuff" },
+			{ "id": "p2", "name": "Work" }
+		],
+		"labels": [
+			{ "id": 7, "name": "phone" }
+		],
+		"items": [
+			{
+				"id": "t1",
+				"project_id": "p1",
+				"content": "Buy milk",
+				"priority": 4,
+				"labels": ["shops", "errands"],
+				"due": { "date": "2024-06-10", "is_recurring": false },
+				"checked": false,
+				"added_at": "2024-06-01T10:00:00.000000Z"
+			},
+			{
+				"id": "t2",
+				"project_id": "p2",
+				"content": "Call  the bank",
+				"description": "About the mortgage",
+				"priority": 1,
+				"labels": [7],
+				"due": { "date": "2024-06-11T09:30:00", "is_recurring": true },
+				"checked": 0
+			},
+			{
+				"id": "t3",
+				"project_id": "p2",
+				"content": "Send report",
+				"priority": 2,
+				"checked": true,
+				"completed_at": "2024-06-05T17:00:00Z",
+				"added_at": "2024-06-02T09:00:00Z"
+			}
+		],
+		"notes": [
+			{ "item_id": "t1", "content": "Semi-skimmed" },
+			{ "item_id": "t1", "content": "Receipt", "file_attachment": { "file_name": "r.pdf" } },
+			{ "item_id": "t3", "content": "Sent by email" }
+		]
+	}"#;
+
+	#[test]
+	fn test_priority_letter() {
+		assert_eq!(Some('A'), priority_letter(4));
+		assert_eq!(Some('D'), priority_letter(1));
+		assert_eq!(None, priority_letter(0));
+	}
+
+	#[test]
+	fn test_parse() {
+		let import = parse(EXPORT, &ImportOptions::default()).unwrap();
+		let lines: Vec<String> = import
+			.items
+			.iter()
+			.map(|i| i.to_string())
+			.collect();
+		assert_eq!(
+			vec![
+				"(A) 2024-06-01 Buy milk +Home_Stuff @shops @errands due:2024-06-10",
+				"(D) Call the bank +Work @phone due:2024-06-11T09:30",
+			],
+			lines
+		);
+		assert_eq!(1, import.skipped_done);
+		assert_eq!(
+			Some(String::from(
+				"1 attachment, 1 comment, 1 description, 1 recurrence"
+			)),
+			import.dropped_summary()
+		);
+	}
+
+	#[test]
+	fn test_parse_include_done() {
+		let options = ImportOptions { include_done: true };
+		let import = parse(EXPORT, &options).unwrap();
+		assert_eq!(3, import.items.len());
+		assert_eq!(
+			"x 2024-06-05 2024-06-02 Send report +Work",
+			import.items[2].to_string()
+		);
+		assert_eq!(0, import.skipped_done);
+		assert_eq!(Some(&2), import.dropped.get("comment"));
+	}
+
+	#[test]
+	fn test_parse_error() {
+		let err = parse("[1, 2", &ImportOptions::default()).unwrap_err();
+		assert!(err
+			.message
+			.starts_with("not a Todoist JSON export"));
+	}
+}
@@ -19,13 +19,22 @@
 //! println!("{}", i);
 //! ```
 
-use chrono::{Datelike, Duration, NaiveDate, Utc, Weekday};
+use crate::config::{Config, DatesConfig};
+use chrono::{
+	Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Weekday,
+};
 use date_time_parser::DateParser as NaturalDateParser;
 use freezebox::FreezeBox;
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::env;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
 
 lazy_static! {
 	/// Regular expression to capture the parts of a tada list line.
@@ -41,10 +50,15 @@ lazy_static! {
 	.unwrap();
 
 	/// Regular expression to find key-value tags within a description.
+	///
+	/// Values run to the next whitespace and may contain colons, but may not
+	/// start with a slash or backslash, so URLs like `https://example.com`
+	/// and Windows paths like `C:\Users` are not mistaken for tags.
 	static ref RE_KV: Regex = Regex::new(r##"(?x)
+		(?:^|\s)                        # whitespace or start of string
 		([^\s:]+)                       # capture: key
 		:                               # colon
-		([^\s:]+)                       # capture: value
+		([^\s:/\\]\S*)                  # capture: value
 	"##)
 	.unwrap();
 
@@ -64,6 +78,63 @@ lazy_static! {
 	"##)
 	.unwrap();
 
+	/// Regular expression to match durations like `1h30m`.
+	static ref RE_DURATION: Regex = Regex::new(r##"(?x)
+		^
+		(?: (\d+) d )?                 # capture: days
+		(?: (\d+) h )?                 # capture: hours
+		(?: (\d+) m )?                 # capture: minutes
+		$
+	"##)
+	.unwrap();
+
+	/// Regular expression to find date tags written with an equals sign,
+	/// like `due=2024-01-01`.
+	static ref RE_EQUALS_KV: Regex = Regex::new(r##"(?x)
+		(^|\s)                         # capture: whitespace or start of string
+		(due|start)                     # capture: key
+		=                               # equals sign
+		(\S+)                           # capture: value
+	"##)
+	.unwrap();
+
+	/// Regular expression to match a word which is a key-value tag with a
+	/// full stop after the value, like `due:2024-01-01.`.
+	static ref RE_TRAILING_DOT: Regex = Regex::new(r##"(?x)
+		^                               # start of word
+		([A-Za-z][\w-]*                 # capture: key,
+		:                               #   colon,
+		[^\s:/\\.]\S*?)                # and value
+		[.]+                            # full stops
+		$                               # end of word
+	"##)
+	.unwrap();
+
+	/// Regular expression to find words.
+	static ref RE_WORD: Regex = Regex::new(r"\S+").unwrap();
+
+	/// Regular expression to find contexts with a space after the at sign,
+	/// like `@ home`.
+	static ref RE_SPACED_CONTEXT: Regex = Regex::new(r##"(?x)
+		(^|\s)                         # capture: whitespace or start of string
+		@                               # at sign
+		\s+                             # stray whitespace
+		([^\s@+]\S*)                    # capture: context
+	"##)
+	.unwrap();
+
+	/// Regular expression to find a priority which isn't at the start of
+	/// the line, or isn't a capital letter, like `(a)`.
+	static ref RE_LOOSE_PRIORITY: Regex = Regex::new(r##"(?x)
+		(?:^|\s)                        # whitespace or start of string
+		[(] ([A-Za-z]) [)]              # capture: priority letter
+		(?:\s|$)                        # whitespace or end of string
+	"##)
+	.unwrap();
+
+	/// Regular expression to find runs of whitespace.
+	static ref RE_SPACES: Regex = Regex::new(r"\s{2,}").unwrap();
+
 	/// Regular expression to match contexts indicating a small tshirt size.
 	static ref RE_SMALL: Regex  = Regex::new("(?i)^X*S$").unwrap();
 
@@ -73,46 +144,157 @@ lazy_static! {
 	/// Regular expression to match contexts indicating a large tshirt size.
 	static ref RE_LARGE: Regex  = Regex::new("(?i)^X*L$").unwrap();
 
-	/// Constant for today's date.
-	///
-	/// These date constants are evaluated once to ensure predictable behaviour
-	/// when the application is run at midnight.
-	///
-	/// This may cause issues later on if we want to run a persistent tadalist
-	/// process.
-	static ref DATE_TODAY: NaiveDate = Utc::now().date_naive();
+	/// Date settings from the config file.
+	static ref DATES_CONFIG: DatesConfig = Config::load().dates;
 
-	/// Constant representing "soon".
-	///
-	/// Tomorrow or overmorrow.
-	static ref DATE_SOON: NaiveDate = Utc::now().date_naive() + Duration::days(2);
+	/// Whether to understand natural language dates like `due:next_friday`.
+	static ref NATURAL_DATES: bool = DATES_CONFIG.natural();
 
-	/// Constant representing the end of this week.
-	///
-	/// Weeks end on Sunday.
-	static ref DATE_WEEKEND: NaiveDate = Utc::now().date_naive().week(Weekday::Mon).last_day();
+	/// The first day of the week.
+	static ref WEEK_START: Weekday = DATES_CONFIG.week_start();
 
-	/// Constant representing the end of next week.
-	static ref DATE_NEXT_WEEKEND: NaiveDate = Utc::now().date_naive().week(Weekday::Mon).last_day() + Duration::days(7);
+	/// Contexts for tasks which shouldn't be rescheduled onto a weekend.
+	static ref WEEKDAY_CONTEXTS: Vec<String> = DATES_CONFIG.weekday_contexts();
+}
 
-	/// Constant representing the end of next month.
-	///
-	/// Who cares when *this* month ends?!
-	static ref DATE_NEXT_MONTH: NaiveDate = {
-		let date = Utc::now().date_naive();
-		match date.month() {
-			11 => NaiveDate::from_ymd_opt(date.year() + 1, 1, 1),
-			12 => NaiveDate::from_ymd_opt(date.year() + 1, 2, 1),
-			_ => NaiveDate::from_ymd_opt(date.year(), date.month() + 2, 1),
-		}
-		.unwrap()
-		.pred_opt()
-		.unwrap()
+thread_local! {
+	/// Date and time set by `set_now`, if any.
+	static NOW_OVERRIDE: Cell<Option<NaiveDateTime>> = const { Cell::new(None) };
+}
+
+/// The current date and time, in the local timezone.
+///
+/// This is read afresh on each call, so long-running processes see the
+/// date change. It can be overridden for the current thread with `set_now`,
+/// or for the whole process by setting `TADA_TODAY` to a date like
+/// `2000-01-01`, which is treated as the start of that day, or a date and
+/// time like `2000-01-01T09:00`.
+pub fn now() -> NaiveDateTime {
+	NOW_OVERRIDE
+		.with(Cell::get)
+		.or_else(|| {
+			env::var("TADA_TODAY")
+				.ok()
+				.and_then(|t| parse_datetime(t.trim()))
+		})
+		.unwrap_or_else(|| Local::now().naive_local())
+}
+
+/// Override the current date and time for this thread, or pass `None` to
+/// go back to using the clock.
+///
+/// This affects everything which depends on today's date, such as urgency,
+/// start dates, completion dates and rescheduling.
+pub fn set_now(x: Option<NaiveDateTime>) {
+	NOW_OVERRIDE.with(|n| n.set(x));
+}
+
+/// Run a closure as if it were the start of the given day, restoring any
+/// previous override afterwards.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use tada::item::{today, with_today};
+///
+/// let day = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+/// assert_eq!(day, with_today(day, today));
+/// ```
+pub fn with_today<T>(day: NaiveDate, f: impl FnOnce() -> T) -> T {
+	let previous = NOW_OVERRIDE.with(Cell::get);
+	set_now(day.and_hms_opt(0, 0, 0));
+	let result = f();
+	set_now(previous);
+	result
+}
+
+/// Parse a date and time like `2000-01-01T09:00`, or a date on its own,
+/// which is treated as the start of that day.
+fn parse_datetime(given: &str) -> Option<NaiveDateTime> {
+	NaiveDateTime::parse_from_str(given, "%Y-%m-%dT%H:%M")
+		.or_else(|_| NaiveDateTime::parse_from_str(given, "%Y-%m-%dT%H:%M:%S"))
+		.or_else(|_| {
+			NaiveDate::parse_from_str(given, "%Y-%m-%d")
+				.map(|d| d.and_hms_opt(0, 0, 0).unwrap())
+		})
+		.ok()
+}
+
+/// Parse a duration like `95m`, `2h`, or `1d2h30m`.
+pub fn parse_duration(given: &str) -> Option<Duration> {
+	let caps = RE_DURATION.captures(given.trim())?;
+	let part = |n: usize| -> Option<i64> {
+		caps.get(n)
+			.map(|m| m.as_str().parse().ok())
+			.unwrap_or(Some(0))
 	};
+	if caps.iter().skip(1).all(|c| c.is_none()) {
+		return None;
+	}
+	Some(
+		Duration::days(part(1)?)
+			+ Duration::hours(part(2)?)
+			+ Duration::minutes(part(3)?),
+	)
+}
+
+/// Format a duration in whole minutes, like `95m`, as used by `spent:` tags.
+pub fn format_duration(duration: Duration) -> String {
+	format!("{}m", duration.num_minutes())
+}
+
+/// Today's date, in the local timezone.
+///
+/// This can be overridden by setting `TADA_TODAY`.
+pub fn today() -> NaiveDate {
+	now().date()
+}
+
+/// The last day of the week which includes `today`.
+fn end_of_week(today: NaiveDate, week_start: Weekday) -> NaiveDate {
+	today.week(week_start).last_day()
+}
+
+/// The last day of next month.
+///
+/// Who cares when *this* month ends?!
+fn end_of_next_month(today: NaiveDate) -> NaiveDate {
+	match today.month() {
+		11 => NaiveDate::from_ymd_opt(today.year() + 1, 1, 1),
+		12 => NaiveDate::from_ymd_opt(today.year() + 1, 2, 1),
+		_ => NaiveDate::from_ymd_opt(today.year(), today.month() + 2, 1),
+	}
+	.unwrap()
+	.pred_opt()
+	.unwrap()
+}
+
+/// Move a date which falls on Saturday or Sunday back to the Friday before.
+fn avoid_weekend(date: NaiveDate) -> NaiveDate {
+	match date.weekday() {
+		Weekday::Sat => date - Duration::days(1),
+		Weekday::Sun => date - Duration::days(2),
+		_ => date,
+	}
 }
 
 /// Five levels of importance are defined.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
+///
+/// Priorities from E to Z are all unimportant, but remember their letter
+/// so they can still be sorted.
+#[derive(
+	Clone,
+	Copy,
+	Debug,
+	Eq,
+	PartialEq,
+	PartialOrd,
+	Ord,
+	Hash,
+	Serialize,
+	Deserialize,
+)]
 pub enum Importance {
 	/// Critical
 	A,
@@ -122,8 +304,8 @@ pub enum Importance {
 	C,
 	/// Normal
 	D,
-	/// Unimportant
-	E,
+	/// Unimportant, from E to Z
+	E(char),
 }
 
 impl Importance {
@@ -134,7 +316,7 @@ impl Importance {
 			'B' => Some(Self::B),
 			'C' => Some(Self::C),
 			'D' => Some(Self::D),
-			'E'..='Z' => Some(Self::E),
+			'E'..='Z' => Some(Self::E(c)),
 			_ => None,
 		}
 	}
@@ -146,24 +328,68 @@ impl Importance {
 			Self::B => 'B',
 			Self::C => 'C',
 			Self::D => 'D',
-			Self::E => 'E',
+			Self::E(c) => *c,
 		}
 	}
 
 	/// Returns a heading suitable for items of this importance.
-	pub fn to_string(&self) -> &str {
+	pub fn label(&self) -> &'static str {
 		match self {
 			Self::A => "Critical",
 			Self::B => "Important",
 			Self::C => "Semi-important",
 			Self::D => "Normal",
-			Self::E => "Unimportant",
+			Self::E(_) => "Unimportant",
 		}
 	}
 
+	/// Returns a heading suitable for items of this importance.
+	#[deprecated(since = "0.1.1", note = "use `label`, or `Display`")]
+	#[allow(clippy::inherent_to_string_shadow_display)]
+	pub fn to_string(&self) -> &str {
+		self.label()
+	}
+
 	/// Returns a list of known importances, in a sane order.
 	pub fn all() -> Vec<Self> {
-		Vec::from([Self::A, Self::B, Self::C, Self::D, Self::E])
+		Vec::from([Self::A, Self::B, Self::C, Self::D, Self::E('E')])
+	}
+
+	/// Returns the named level for this importance, which is the same
+	/// importance, except all unimportant letters become E.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use tada::Importance;
+	/// assert_eq!(Importance::E('E'), Importance::E('G').level());
+	/// assert_eq!(Importance::B, Importance::B.level());
+	/// ```
+	pub fn level(&self) -> Self {
+		match self {
+			Self::E(_) => Self::E('E'),
+			_ => *self,
+		}
+	}
+
+	/// Returns the next more important level. A stays as A.
+	pub fn raised(&self) -> Self {
+		match self {
+			Self::A | Self::B => Self::A,
+			Self::C => Self::B,
+			Self::D => Self::C,
+			Self::E(_) => Self::D,
+		}
+	}
+
+	/// Returns the next less important level. Anything from D on becomes E.
+	pub fn lowered(&self) -> Self {
+		match self {
+			Self::A => Self::B,
+			Self::B => Self::C,
+			Self::C => Self::D,
+			Self::D | Self::E(_) => Self::E('E'),
+		}
 	}
 }
 
@@ -174,8 +400,88 @@ impl Default for Importance {
 	}
 }
 
+impl fmt::Display for Importance {
+	/// Writes the label, followed by the letter for unimportant letters
+	/// after E, like "Unimportant (G)", so every importance parses back
+	/// to itself.
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::E(c) if *c != 'E' => {
+				f.pad(&format!("{} ({})", self.label(), c))
+			}
+			_ => f.pad(self.label()),
+		}
+	}
+}
+
+impl FromStr for Importance {
+	type Err = InvalidImportance;
+
+	/// Accept importances like "critical" or a priority letter like "B",
+	/// or unimportant letters written like "Unimportant (G)".
+	///
+	/// Case, spaces, underscores and hyphens are ignored, so
+	/// "Semi-important" and "semi_important" work too.
+	fn from_str(importance: &str) -> Result<Self, Self::Err> {
+		let normalized = importance
+			.to_lowercase()
+			.replace([' ', '_', '-'], "");
+		match normalized.as_str() {
+			"critical" | "crit" => Ok(Self::A),
+			"important" | "imp" => Ok(Self::B),
+			"semiimportant" | "semi" => Ok(Self::C),
+			"normal" => Ok(Self::D),
+			"unimportant" | "low" => Ok(Self::E('E')),
+			_ => {
+				let letter = normalized
+					.strip_prefix("unimportant(")
+					.and_then(|l| l.strip_suffix(')'))
+					.filter(|l| *l != "e");
+				let mut chars = letter.unwrap_or(&normalized).chars();
+				match (chars.next(), chars.next()) {
+					(Some(c), None) => Self::from_char(c.to_ascii_uppercase())
+						.filter(|i| {
+							letter.is_none() || i.level() == Self::E('E')
+						}),
+					_ => None,
+				}
+				.ok_or_else(|| InvalidImportance {
+					given: String::from(importance),
+				})
+			}
+		}
+	}
+}
+
+/// An error raised when given an unknown importance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidImportance {
+	pub given: String,
+}
+
+impl fmt::Display for InvalidImportance {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"unknown importance '{}' (expected a priority letter, or one of: critical, important, semi-important, normal, unimportant)",
+			self.given
+		)
+	}
+}
+
 /// Seven levels of urgency are defined.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[derive(
+	Clone,
+	Copy,
+	Debug,
+	Eq,
+	PartialEq,
+	PartialOrd,
+	Ord,
+	Hash,
+	Serialize,
+	Deserialize,
+)]
 pub enum Urgency {
 	/// A due date earlier than today.
 	Overdue,
@@ -201,25 +507,78 @@ pub enum Urgency {
 impl Urgency {
 	/// Calculate urgency from a due date.
 	pub fn from_due_date(due: NaiveDate) -> Self {
-		if due < *DATE_TODAY {
+		Self::from_due_date_on(due, today(), *WEEK_START)
+	}
+
+	/// Calculate urgency from a due date, as it would be on the given day,
+	/// with weeks starting on `week_start`.
+	///
+	/// "Soon" means tomorrow or overmorrow.
+	pub fn from_due_date_on(
+		due: NaiveDate,
+		today: NaiveDate,
+		week_start: Weekday,
+	) -> Self {
+		let end_of_week = end_of_week(today, week_start);
+		if due < today {
 			Self::Overdue
-		} else if due == *DATE_TODAY {
+		} else if due == today {
 			Self::Today
-		} else if due <= *DATE_SOON {
+		} else if due <= today + Duration::days(2) {
 			Self::Soon
-		} else if due <= *DATE_WEEKEND {
+		} else if due <= end_of_week {
 			Self::ThisWeek
-		} else if due <= *DATE_NEXT_WEEKEND {
+		} else if due <= end_of_week + Duration::days(7) {
 			Self::NextWeek
-		} else if due <= *DATE_NEXT_MONTH {
+		} else if due <= end_of_next_month(today) {
 			Self::NextMonth
 		} else {
 			Self::Later
 		}
 	}
 
+	/// The latest due date with this urgency, as it would be on the given
+	/// day, with weeks starting on `week_start`.
+	///
+	/// For `Overdue` this is yesterday, and for `Later` about six months'
+	/// time.
+	pub fn due_date_on(
+		&self,
+		today: NaiveDate,
+		week_start: Weekday,
+	) -> NaiveDate {
+		match self {
+			Self::Overdue => today.pred_opt().unwrap(),
+			Self::Today => today,
+			Self::Soon => today + Duration::days(2),
+			Self::ThisWeek => end_of_week(today, week_start),
+			Self::NextWeek => {
+				end_of_week(today, week_start) + Duration::days(7)
+			}
+			Self::NextMonth => end_of_next_month(today),
+			Self::Later => today + Duration::days(183),
+		}
+	}
+
+	/// The latest due date with this urgency, as of today.
+	pub fn due_date(&self) -> NaiveDate {
+		self.due_date_on(today(), *WEEK_START)
+	}
+
+	/// Calculate urgency from a due date and time.
+	///
+	/// This is like `from_due_date`, but a task due earlier today is overdue
+	/// once its time has passed.
+	pub fn from_due_datetime(due: NaiveDateTime) -> Self {
+		if due < now() {
+			Self::Overdue
+		} else {
+			Self::from_due_date(due.date())
+		}
+	}
+
 	/// Returns a heading suitable for items of this urgency.
-	pub fn to_string(&self) -> &str {
+	pub fn label(&self) -> &'static str {
 		match self {
 			Self::Overdue => "Overdue",
 			Self::Today => "Today",
@@ -231,6 +590,35 @@ impl Urgency {
 		}
 	}
 
+	/// Returns a heading suitable for items of this urgency.
+	#[deprecated(since = "0.1.1", note = "use `label`, or `Display`")]
+	#[allow(clippy::inherent_to_string_shadow_display)]
+	pub fn to_string(&self) -> &str {
+		self.label()
+	}
+
+	/// Accept string urgencies like "next-week" and return an Urgency.
+	///
+	/// Case, spaces and underscores are ignored, so "Next week" and
+	/// "next_week" work too.
+	pub fn from_string(urgency: &str) -> Result<Self, InvalidUrgency> {
+		let normalized = urgency
+			.to_lowercase()
+			.replace([' ', '_', '-'], "");
+		match normalized.as_str() {
+			"overdue" => Ok(Self::Overdue),
+			"today" => Ok(Self::Today),
+			"soon" | "overmorrow" => Ok(Self::Soon),
+			"thisweek" => Ok(Self::ThisWeek),
+			"nextweek" => Ok(Self::NextWeek),
+			"nextmonth" => Ok(Self::NextMonth),
+			"later" => Ok(Self::Later),
+			_ => Err(InvalidUrgency {
+				given: String::from(urgency),
+			}),
+		}
+	}
+
 	/// Returns a list of known urgencies, in a sane order.
 	pub fn all() -> Vec<Self> {
 		Vec::from([
@@ -253,8 +641,50 @@ impl Default for Urgency {
 	}
 }
 
+impl fmt::Display for Urgency {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.pad(self.label())
+	}
+}
+
+impl FromStr for Urgency {
+	type Err = InvalidUrgency;
+
+	/// Same as `Urgency::from_string`.
+	fn from_str(urgency: &str) -> Result<Self, Self::Err> {
+		Self::from_string(urgency)
+	}
+}
+
+/// An error raised when given an unknown urgency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidUrgency {
+	pub given: String,
+}
+
+impl fmt::Display for InvalidUrgency {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"unknown urgency '{}' (expected one of: overdue, today, soon, this-week, next-week, next-month, later)",
+			self.given
+		)
+	}
+}
+
 /// Three sizes are defined.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[derive(
+	Clone,
+	Copy,
+	Debug,
+	Eq,
+	PartialEq,
+	PartialOrd,
+	Ord,
+	Hash,
+	Serialize,
+	Deserialize,
+)]
 pub enum TshirtSize {
 	Small,
 	Medium,
@@ -263,7 +693,7 @@ pub enum TshirtSize {
 
 impl TshirtSize {
 	/// Returns a heading suitable for items of this size.
-	pub fn to_string(&self) -> &str {
+	pub fn label(&self) -> &'static str {
 		match self {
 			Self::Small => "Small",
 			Self::Medium => "Medium",
@@ -271,6 +701,13 @@ impl TshirtSize {
 		}
 	}
 
+	/// Returns a heading suitable for items of this size.
+	#[deprecated(since = "0.1.1", note = "use `label`, or `Display`")]
+	#[allow(clippy::inherent_to_string_shadow_display)]
+	pub fn to_string(&self) -> &str {
+		self.label()
+	}
+
 	/// Returns a list of known sizes, in a sane order.
 	pub fn all() -> Vec<Self> {
 		Vec::from([Self::Small, Self::Medium, Self::Large])
@@ -284,6 +721,374 @@ impl Default for TshirtSize {
 	}
 }
 
+impl fmt::Display for TshirtSize {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.pad(self.label())
+	}
+}
+
+impl FromStr for TshirtSize {
+	type Err = InvalidTshirtSize;
+
+	/// Accept sizes like "small", or their initials like "S".
+	fn from_str(size: &str) -> Result<Self, Self::Err> {
+		match size.trim().to_lowercase().as_str() {
+			"small" | "s" | "quick" => Ok(Self::Small),
+			"medium" | "m" => Ok(Self::Medium),
+			"large" | "l" => Ok(Self::Large),
+			_ => Err(InvalidTshirtSize {
+				given: String::from(size),
+			}),
+		}
+	}
+}
+
+/// An error raised when given an unknown size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidTshirtSize {
+	pub given: String,
+}
+
+impl fmt::Display for InvalidTshirtSize {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"unknown size '{}' (expected one of: small, medium, large)",
+			self.given
+		)
+	}
+}
+
+/// How `Item::zen` chooses a new urgency for an overdue task.
+///
+/// Tasks are bucketed by whether they are important (priority A or B) and
+/// whether they are small, and each bucket has its own urgency. The result
+/// can be capped so nothing is pushed back further than `max`.
+///
+/// # Examples
+///
+/// ```
+/// use tada::item::{Item, Urgency, ZenPolicy};
+///
+/// let policy = ZenPolicy::default();
+/// let item = Item::parse("(A) call mum @S");
+/// assert_eq!(Urgency::Soon, policy.urgency_for(&item));
+///
+/// let policy = ZenPolicy::default().with_max(Urgency::NextWeek);
+/// assert_eq!(Urgency::NextWeek, policy.urgency_for(&Item::parse("paint")));
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ZenPolicy {
+	/// Urgency for tasks which are both important and small.
+	pub important_small: Urgency,
+	/// Urgency for tasks which are important or small, but not both.
+	pub important_or_small: Urgency,
+	/// Urgency for all other tasks.
+	pub other: Urgency,
+	/// The least urgent a rescheduled task may be.
+	pub max: Option<Urgency>,
+}
+
+impl ZenPolicy {
+	/// A policy which reschedules every task with the same urgency.
+	pub fn all_to(urgency: Urgency) -> Self {
+		Self {
+			important_small: urgency,
+			important_or_small: urgency,
+			other: urgency,
+			max: None,
+		}
+	}
+
+	/// A copy of this policy which reschedules nothing later than `max`.
+	pub fn with_max(&self, max: Urgency) -> Self {
+		Self {
+			max: Some(max),
+			..*self
+		}
+	}
+
+	/// The urgency this policy gives an overdue task.
+	pub fn urgency_for(&self, item: &Item) -> Urgency {
+		let important = matches!(
+			item.importance(),
+			Some(Importance::A) | Some(Importance::B)
+		);
+		let small = matches!(item.tshirt_size(), Some(TshirtSize::Small));
+		let urgency = if important && small {
+			self.important_small
+		} else if important || small {
+			self.important_or_small
+		} else {
+			self.other
+		};
+		match self.max {
+			Some(max) => urgency.min(max),
+			None => urgency,
+		}
+	}
+}
+
+impl Default for ZenPolicy {
+	/// Important and small tasks are due soon, important or small tasks
+	/// next week, and everything else next month.
+	fn default() -> Self {
+		Self {
+			important_small: Urgency::Soon,
+			important_or_small: Urgency::NextWeek,
+			other: Urgency::NextMonth,
+			max: None,
+		}
+	}
+}
+
+/// How `tada age` raises the priority of tasks as their due dates approach.
+///
+/// Each urgency may have a priority which tasks with that urgency are
+/// raised to. A task which already has that priority or a higher one is
+/// left alone, so priorities are never lowered.
+///
+/// # Examples
+///
+/// ```
+/// use tada::item::{AgePolicy, Importance, Item, Urgency};
+///
+/// let policy = AgePolicy::default();
+/// assert_eq!(Some(Importance::B), policy.importance_for(Urgency::Soon));
+///
+/// let item = Item::parse("(A) call mum due:1970-01-01");
+/// assert_eq!(None, policy.raise(&item));
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AgePolicy {
+	pub overdue: Option<Importance>,
+	pub today: Option<Importance>,
+	pub soon: Option<Importance>,
+	pub this_week: Option<Importance>,
+	pub next_week: Option<Importance>,
+	pub next_month: Option<Importance>,
+}
+
+impl AgePolicy {
+	/// The priority tasks with an urgency are raised to, if any.
+	pub fn importance_for(&self, urgency: Urgency) -> Option<Importance> {
+		match urgency {
+			Urgency::Overdue => self.overdue,
+			Urgency::Today => self.today,
+			Urgency::Soon => self.soon,
+			Urgency::ThisWeek => self.this_week,
+			Urgency::NextWeek => self.next_week,
+			Urgency::NextMonth => self.next_month,
+			Urgency::Later => None,
+		}
+	}
+
+	/// The priority an open task should be raised to, or `None` if it
+	/// should be left alone.
+	pub fn raise(&self, item: &Item) -> Option<Importance> {
+		if item.completion() {
+			return None;
+		}
+		let target = self.importance_for(item.urgency()?)?;
+		match item.importance() {
+			Some(current) if current <= target => None,
+			_ => Some(target),
+		}
+	}
+}
+
+impl Default for AgePolicy {
+	/// Overdue tasks and tasks due today are raised to (A), tasks due soon
+	/// to (B), and tasks due this week to (C).
+	fn default() -> Self {
+		Self {
+			overdue: Some(Importance::A),
+			today: Some(Importance::A),
+			soon: Some(Importance::B),
+			this_week: Some(Importance::C),
+			next_week: None,
+			next_month: None,
+		}
+	}
+}
+
+/// A rule `Item::fixup` follows to fix a task's syntax.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum FixupRule {
+	/// Change date tags like `due:tomorrow` to YYYY-MM-DD format.
+	Dates,
+	/// Move a priority like `(a)` to the front of the task, as a capital.
+	Priority,
+	/// Remove the space from contexts like `@ home`.
+	Contexts,
+	/// Squash runs of whitespace into a single space.
+	Spacing,
+	/// Remove full stops after tag values, like `due:2024-01-01.`.
+	Punctuation,
+	/// Change date tags like `due=2024-01-01` to use a colon.
+	Equals,
+}
+
+impl FixupRule {
+	/// Returns the name used for this rule on the command line.
+	pub fn to_string(&self) -> &str {
+		match self {
+			Self::Dates => "dates",
+			Self::Priority => "priority",
+			Self::Contexts => "contexts",
+			Self::Spacing => "spacing",
+			Self::Punctuation => "punctuation",
+			Self::Equals => "equals",
+		}
+	}
+
+	/// Accept a rule name like "spacing" and return a FixupRule.
+	pub fn from_string(rule: &str) -> Result<Self, InvalidFixupRule> {
+		Self::all()
+			.into_iter()
+			.find(|r| r.to_string().eq_ignore_ascii_case(rule.trim()))
+			.ok_or_else(|| InvalidFixupRule {
+				given: String::from(rule),
+			})
+	}
+
+	/// Returns a list of known rules, in the order they are followed.
+	pub fn all() -> Vec<Self> {
+		Vec::from([
+			Self::Equals,
+			Self::Punctuation,
+			Self::Contexts,
+			Self::Priority,
+			Self::Spacing,
+			Self::Dates,
+		])
+	}
+}
+
+/// An error raised when given an unknown fixup rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidFixupRule {
+	pub given: String,
+}
+
+impl fmt::Display for InvalidFixupRule {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let valid: Vec<String> = FixupRule::all()
+			.iter()
+			.map(|r| String::from(r.to_string()))
+			.collect();
+		write!(
+			f,
+			"unknown fixup rule '{}' (expected one of: {})",
+			self.given,
+			valid.join(", ")
+		)
+	}
+}
+
+/// An error raised when text given as a task isn't one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidItem {
+	pub given: String,
+	pub reason: &'static str,
+}
+
+impl fmt::Display for InvalidItem {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"'{}' {}, so isn't a task",
+			self.given.trim(),
+			self.reason
+		)
+	}
+}
+
+/// A fix made, or suggested, by `Item::fixup`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FixupNote {
+	/// The task has no priority.
+	MissingPriority,
+	/// A priority like `(a)` was moved to the front of the task.
+	MovedPriority { original: String, priority: char },
+	/// A context like `@ home` had its space removed.
+	SpacedContext { context: String },
+	/// Runs of whitespace were squashed.
+	ExtraSpaces,
+	/// Full stops were removed from after a tag's value.
+	TrailingDot { tag: String },
+	/// A tag like `due=2024-01-01` was changed to use a colon.
+	EqualsForColon { key: String },
+	/// A date tag wasn't in YYYY-MM-DD format. If it could be understood,
+	/// it was changed to the corrected date.
+	NonIsoDate {
+		slot: String,
+		original: String,
+		corrected: Option<NaiveDate>,
+	},
+	/// The task has no due date.
+	MissingDueDate,
+	/// The task has no size.
+	MissingSize,
+	/// The description is over 120 characters long.
+	TooLong,
+	/// The description is under 30 characters long.
+	TooShort,
+}
+
+impl fmt::Display for FixupNote {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::MissingPriority => write!(f, "Hint: a task can be given an importance be prefixing it with a parenthesized capital letter, like `(A)`."),
+			Self::MovedPriority { original, priority } => write!(
+				f,
+				"Notice: priority `{}` moved to the front as `({})`.",
+				original, priority
+			),
+			Self::SpacedContext { context } => write!(
+				f,
+				"Notice: context `@ {}` changed to `@{}`.",
+				context, context
+			),
+			Self::ExtraSpaces => {
+				write!(f, "Notice: repeated spaces squashed into one.")
+			}
+			Self::TrailingDot { tag } => {
+				write!(f, "Notice: full stop removed from after `{}`.", tag)
+			}
+			Self::EqualsForColon { key } => write!(
+				f,
+				"Notice: `{}=` changed to `{}:`.",
+				key, key
+			),
+			Self::NonIsoDate {
+				slot,
+				original,
+				corrected: Some(date),
+			} => write!(
+				f,
+				"Notice: {} date `{}` changed to `{}`.",
+				slot,
+				original,
+				date.format("%Y-%m-%d")
+			),
+			Self::NonIsoDate {
+				slot,
+				original,
+				corrected: None,
+			} => write!(
+				f,
+				"Notice: {} date `{}` should be in YYYY-MM-DD format.",
+				slot, original
+			),
+			Self::MissingDueDate => write!(f, "Hint: a task can be given a due date by including `due:YYYY-MM-DD`."),
+			Self::MissingSize => write!(f, "Hint: a task can be given a size by including `@S`, `@M`, or `@L`."),
+			Self::TooLong => write!(f, "Hint: long descriptions can make a task list slower to skim read."),
+			Self::TooShort => write!(f, "Hint: short descriptions can make it hard to remember what a task means!"),
+		}
+	}
+}
+
 /// An item in a todo list.
 ///
 /// # Examples
@@ -297,6 +1102,7 @@ impl Default for TshirtSize {
 /// ```
 pub struct Item {
 	line_number: usize,
+	source: Option<String>,
 	completion: bool,
 	priority: char,
 	completion_date: Option<NaiveDate>,
@@ -316,6 +1122,7 @@ impl Item {
 	pub fn new() -> Item {
 		Item {
 			line_number: 0,
+			source: None,
 			completion: false,
 			priority: '\0',
 			completion_date: None,
@@ -368,36 +1175,100 @@ impl Item {
 		}
 	}
 
+	/// Parse an item from a line of text, failing if it isn't a task.
+	///
+	/// Unlike `parse`, which makes the best of anything, this rejects text
+	/// which is blank, a comment, more than one line, or wouldn't read back
+	/// the way it was written.
+	pub fn try_parse(text: &str) -> Result<Item, InvalidItem> {
+		let invalid = |reason: &'static str| InvalidItem {
+			given: String::from(text),
+			reason,
+		};
+		let trimmed = text.trim();
+		if trimmed.contains(['\n', '\r']) {
+			return Err(invalid("is more than one line"));
+		}
+		if trimmed.is_empty() {
+			return Err(invalid("is blank"));
+		}
+		if trimmed.starts_with('#') {
+			return Err(invalid("is a comment"));
+		}
+		let item = Self::parse(trimmed);
+		let squash = |s: &str| {
+			s.split_whitespace()
+				.collect::<Vec<_>>()
+				.join(" ")
+		};
+		if squash(trimmed) != squash(&item.to_string()) {
+			return Err(invalid("can't be read properly"));
+		}
+		Ok(item)
+	}
+
 	/// Create a version of this item but representing a completed task.
-	pub fn but_done(&self, include_date: bool) -> Item {
+	///
+	/// If a `completion_date` is given, it is set as the completion date,
+	/// and as the creation date if the task doesn't have one yet or if its
+	/// creation date is later, so a task is never finished before it was
+	/// created.
+	///
+	/// If `preserve_priority` is true, the priority is moved out of the `(X)`
+	/// prefix into a `pri:X` tag, as many todo.txt tools ignore priorities on
+	/// completed tasks.
+	///
+	/// If `clear_start` is true, a start date after the completion date (or
+	/// today, if there is none) is brought back to it, as the task was
+	/// evidently started by the time it was finished.
+	pub fn but_done(
+		&self,
+		completion_date: Option<NaiveDate>,
+		preserve_priority: bool,
+		clear_start: bool,
+	) -> Item {
 		let mut i = self.clone();
 		i.set_completion(true);
-		if include_date {
-			i.set_completion_date(*DATE_TODAY);
-			if i.creation_date().is_none() {
-				i.set_creation_date(*DATE_TODAY);
+		if let Some(d) = completion_date {
+			i.set_completion_date(d);
+			if i.creation_date().is_none_or(|c| c > d) {
+				i.set_creation_date(d);
 			}
 		}
+		let finished = completion_date.unwrap_or_else(today);
+		if clear_start && i.start_date().is_some_and(|s| s > finished) {
+			i.set_kv("start", &finished.format("%Y-%m-%d").to_string());
+		}
+		if preserve_priority && i.priority() != '\0' {
+			i.set_pri_tag(i.priority());
+			i.clear_importance();
+		}
 		i
 	}
 
-	/// Provide zen-like calm by rescheduling an overdue task.
-	pub fn zen(&self) -> Item {
+	/// Create a version of this item but representing an incomplete task.
+	///
+	/// Clears the completion date, and restores any priority which was
+	/// preserved in a `pri:X` tag.
+	pub fn but_undone(&self) -> Item {
+		let mut i = self.clone();
+		i.set_completion(false);
+		i.clear_completion_date();
+		if let Some(p) = i.pri_tag() {
+			if i.priority() == '\0' {
+				i.set_priority(p);
+			}
+			i.clear_pri_tag();
+		}
+		i
+	}
+
+	/// Provide zen-like calm by rescheduling an overdue task, with a new
+	/// urgency chosen by the policy.
+	pub fn zen(&self, policy: &ZenPolicy) -> Item {
 		if self.urgency() == Some(Urgency::Overdue) {
 			let mut new = self.clone();
-			let important = matches!(
-				new.importance(),
-				Some(Importance::A) | Some(Importance::B)
-			);
-			let small = matches!(new.tshirt_size(), Some(TshirtSize::Small));
-			let new_urgency = if important && small {
-				Urgency::Soon
-			} else if important || small {
-				Urgency::NextWeek
-			} else {
-				Urgency::NextMonth
-			};
-			new.set_urgency(new_urgency);
+			new.set_urgency(policy.urgency_for(self));
 			return new;
 		}
 		self.clone()
@@ -412,7 +1283,7 @@ impl Item {
 		new.set_urgency(new_urgency);
 
 		let re = Regex::new(r"start:(?:[^\s:]+)").unwrap();
-		let new_start = format!("start:{}", DATE_TODAY.format("%Y-%m-%d"));
+		let new_start = format!("start:{}", today().format("%Y-%m-%d"));
 		new.set_description(format!(
 			"{}",
 			re.replace(&new.description, new_start)
@@ -421,67 +1292,155 @@ impl Item {
 		new
 	}
 
+	/// Snooze a task, so it can't be started until the given date, by
+	/// setting its start date. Completed tasks are left alone.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use tada::Item;
+	/// let i = Item::parse("Foo start:2000-01-01");
+	/// let day = chrono::NaiveDate::from_ymd_opt(2000, 2, 1).unwrap();
+	/// assert_eq!("Foo start:2000-02-01", i.but_snooze(day).description());
+	/// ```
+	pub fn but_snooze(&self, until: NaiveDate) -> Item {
+		let mut new = self.clone();
+		if !new.completion() {
+			new.set_kv("start", &until.format("%Y-%m-%d").to_string());
+		}
+		new
+	}
+
 	/// Performs a bunch of small fixes on the item syntax.
-	pub fn fixup(&self, warnings: bool) -> Item {
-		let maybe_warn = |w| {
-			if warnings {
-				eprintln!("{}", w);
-			}
-		};
+	///
+	/// Also returns notes about the fixes, and about ways the task could
+	/// be improved, for the caller to show or not. Fixing a task which has
+	/// already been fixed changes nothing.
+	pub fn fixup(&self) -> (Item, Vec<FixupNote>) {
+		self.fixup_except(&[])
+	}
+
+	/// Like `fixup`, but without following the `disabled` rules.
+	pub fn fixup_except(
+		&self,
+		disabled: &[FixupRule],
+	) -> (Item, Vec<FixupNote>) {
+		let mut notes: Vec<FixupNote> = Vec::new();
 		let mut new = self.clone();
+		let enabled = |rule| !disabled.contains(&rule);
+
+		if enabled(FixupRule::Equals) {
+			for cap in RE_EQUALS_KV.captures_iter(&new.description) {
+				notes.push(FixupNote::EqualsForColon {
+					key: cap[2].to_string(),
+				});
+			}
+			let fixed = RE_EQUALS_KV.replace_all(&new.description, "$1$2:$3");
+			new.set_description(fixed.to_string());
+		}
+
+		if enabled(FixupRule::Punctuation) {
+			let fixed =
+				RE_WORD.replace_all(&new.description, |c: &regex::Captures| {
+					match RE_TRAILING_DOT.captures(&c[0]) {
+						Some(word) => {
+							notes.push(FixupNote::TrailingDot {
+								tag: word[1].to_string(),
+							});
+							word[1].to_string()
+						}
+						None => c[0].to_string(),
+					}
+				});
+			new.set_description(fixed.to_string());
+		}
+
+		if enabled(FixupRule::Contexts) {
+			for cap in RE_SPACED_CONTEXT.captures_iter(&new.description) {
+				notes.push(FixupNote::SpacedContext {
+					context: cap[2].to_string(),
+				});
+			}
+			let fixed =
+				RE_SPACED_CONTEXT.replace_all(&new.description, "$1@$2");
+			new.set_description(fixed.to_string());
+		}
+
+		if enabled(FixupRule::Priority) && new.priority() == '\0' {
+			if let Some(cap) = RE_LOOSE_PRIORITY.captures(&new.description) {
+				let whole = cap.get(0).unwrap();
+				let priority = cap[1]
+					.to_ascii_uppercase()
+					.chars()
+					.next()
+					.unwrap();
+				notes.push(FixupNote::MovedPriority {
+					original: whole.as_str().trim().to_string(),
+					priority,
+				});
+				let fixed = format!(
+					"{} {}",
+					&new.description[..whole.start()],
+					&new.description[whole.end()..],
+				);
+				new.set_description(fixed.trim().to_string());
+				new.set_priority(priority);
+			}
+		}
+
+		if enabled(FixupRule::Spacing) && RE_SPACES.is_match(&new.description) {
+			notes.push(FixupNote::ExtraSpaces);
+			let fixed = RE_SPACES.replace_all(&new.description, " ");
+			new.set_description(fixed.to_string());
+		}
 
 		if new.priority() == '\0' {
-			maybe_warn(String::from("Hint: a task can be given an importance be prefixing it with a parenthesized capital letter, like `(A)`."));
+			notes.push(FixupNote::MissingPriority);
 		}
 
 		for slot in ["due", "start"] {
-			match new.kv().get(slot) {
+			match new.kv().get(slot).cloned() {
 				Some(given_date) => {
-					if NaiveDate::parse_from_str(given_date, "%Y-%m-%d")
-						.is_err()
+					if enabled(FixupRule::Dates)
+						&& parse_date(&given_date, false).is_none()
 					{
 						let processed_date = given_date.replace('_', " ");
-						if let Some(naive_date) =
-							NaturalDateParser::parse(&processed_date)
-						{
-							new.set_description(new.description().replace(
-								&format!("{}:{}", slot, given_date),
-								&format!(
-									"{}:{}",
-									slot,
-									naive_date.format("%Y-%m-%d")
-								),
-							));
-							maybe_warn(format!(
-								"Notice: {} date `{}` changed to `{}`.",
+						let corrected = NaturalDateParser::parse_relative(
+							&processed_date,
+							today(),
+						);
+						if let Some(naive_date) = corrected {
+							new.set_kv(
 								slot,
-								given_date,
-								naive_date.format("%Y-%m-%d")
-							));
-						} else {
-							maybe_warn(format!("Notice: {} date `{}` should be in YYYY-MM-DD format.", slot, given_date));
+								&naive_date.format("%Y-%m-%d").to_string(),
+							);
 						}
+						notes.push(FixupNote::NonIsoDate {
+							slot: String::from(slot),
+							original: given_date,
+							corrected,
+						});
 					}
 				}
 				None => {
 					if slot == "due" {
-						maybe_warn(format!("Hint: a task can be given a {} date by including `{}:YYYY-MM-DD`.", slot, slot));
+						notes.push(FixupNote::MissingDueDate);
 					}
 				}
 			}
 		}
 
 		if new.tshirt_size().is_none() {
-			maybe_warn(String::from("Hint: a task can be given a size by including `@S`, `@M`, or `@L`."));
+			notes.push(FixupNote::MissingSize);
 		}
 
 		if new.description().len() > 120 {
-			maybe_warn(String::from("Hint: long descriptions can make a task list slower to skim read."));
+			notes.push(FixupNote::TooLong);
 		} else if new.description().len() < 30 {
-			maybe_warn(String::from("Hint: short descriptions can make it hard to remember what a task means!"));
+			notes.push(FixupNote::TooShort);
 		}
 
-		new
+		(new, notes)
 	}
 
 	/// Whether the task is complete.
@@ -504,6 +1463,16 @@ impl Item {
 		self.line_number = x;
 	}
 
+	/// Name of the profile the task was loaded from, if any.
+	pub fn source(&self) -> Option<&str> {
+		self.source.as_deref()
+	}
+
+	/// Set the name of the profile the task was loaded from.
+	pub fn set_source(&mut self, x: Option<String>) {
+		self.source = x;
+	}
+
 	/// Task priority/importance as given in a todo.txt file.
 	///
 	/// A is highest, then B and C. D should be considered normal. E is low priority.
@@ -517,6 +1486,29 @@ impl Item {
 	/// Set task priority.
 	pub fn set_priority(&mut self, x: char) {
 		self.priority = x;
+		self._importance = FreezeBox::default();
+	}
+
+	/// Priority recorded in a `pri:X` tag, usually by completing a task.
+	pub fn pri_tag(&self) -> Option<char> {
+		let kv = self.kv();
+		let mut chars = kv.get("pri")?.chars();
+		match (chars.next(), chars.next()) {
+			(Some(c), None) if c.is_ascii_alphabetic() => {
+				Some(c.to_ascii_uppercase())
+			}
+			_ => None,
+		}
+	}
+
+	/// Set the `pri:X` tag, replacing any existing one.
+	pub fn set_pri_tag(&mut self, x: char) {
+		self.set_kv("pri", &x.to_string());
+	}
+
+	/// Remove any `pri:X` tag.
+	pub fn clear_pri_tag(&mut self) {
+		self.clear_kv("pri");
 	}
 
 	/// Completion date.
@@ -554,8 +1546,8 @@ impl Item {
 	}
 
 	/// Task description.
-	pub fn description(&self) -> String {
-		self.description.clone()
+	pub fn description(&self) -> &str {
+		&self.description
 	}
 
 	/// Set the task description.
@@ -564,6 +1556,7 @@ impl Item {
 	pub fn set_description(&mut self, x: String) {
 		self._importance = FreezeBox::default();
 		self._due_date = FreezeBox::default();
+		self._start_date = FreezeBox::default();
 		self._urgency = FreezeBox::default();
 		self._tshirt_size = FreezeBox::default();
 		self._tags = FreezeBox::default();
@@ -609,10 +1602,22 @@ impl Item {
 	}
 
 	fn _build_due_date(&self) -> Option<NaiveDate> {
-		match self.kv().get("due") {
-			Some(dd) => NaiveDate::parse_from_str(dd, "%Y-%m-%d").ok(),
-			None => None,
-		}
+		self.kv_date("due")
+	}
+
+	/// Return the time of day when this task is due by, if it has one.
+	pub fn due_time(&self) -> Option<NaiveTime> {
+		self.kv_time("due")
+	}
+
+	/// Return the date and time when this task is due by.
+	///
+	/// Tasks with a due date but no time are due by the end of the day.
+	pub fn due_datetime(&self) -> Option<NaiveDateTime> {
+		let time = self
+			.due_time()
+			.unwrap_or_else(|| NaiveTime::from_hms_opt(23, 59, 59).unwrap());
+		Some(self.due_date()?.and_time(time))
 	}
 
 	/// Return the date when this task may be started.
@@ -625,20 +1630,79 @@ impl Item {
 	}
 
 	fn _build_start_date(&self) -> Option<NaiveDate> {
-		match self.kv().get("start") {
-			Some(dd) => NaiveDate::parse_from_str(dd, "%Y-%m-%d").ok(),
-			None => None,
-		}
+		self.kv_date("start")
 	}
 
 	/// A task is startable if it doesn't have a start date which is in the future.
+	///
+	/// This doesn't consider tasks it is waiting for with `after:`; see
+	/// `List::is_item_startable` for that.
 	pub fn is_startable(&self) -> bool {
 		match self.start_date() {
-			Some(day) => day <= *DATE_TODAY,
+			Some(day) => day <= today(),
 			None => true,
 		}
 	}
 
+	/// Return the identifier given to this task by an `id:` tag.
+	pub fn id(&self) -> Option<String> {
+		self.kv().get("id").cloned()
+	}
+
+	/// Return the identifiers of tasks which must be finished before this
+	/// one can start, given in an `after:` tag like `after:foo,bar`.
+	pub fn after(&self) -> Vec<String> {
+		match self.kv().get("after") {
+			Some(ids) => ids
+				.split(',')
+				.filter(|id| !id.is_empty())
+				.map(String::from)
+				.collect(),
+			None => Vec::new(),
+		}
+	}
+
+	/// Return when time tracking was started on this task, from a
+	/// `started:` tag.
+	pub fn started(&self) -> Option<NaiveDateTime> {
+		parse_datetime(self.kv().get("started")?)
+	}
+
+	/// Return how much time has been spent on this task, from a `spent:`
+	/// tag, not including any time since it was started.
+	pub fn spent(&self) -> Option<Duration> {
+		parse_duration(self.kv().get("spent")?)
+	}
+
+	/// Set how much time has been spent on this task.
+	pub fn set_spent(&mut self, spent: Duration) {
+		self.set_kv("spent", &format_duration(spent));
+	}
+
+	/// Create a version of this item with time tracking started at the
+	/// given time.
+	pub fn but_started(&self, at: NaiveDateTime) -> Item {
+		let mut new = self.clone();
+		new.set_kv("started", &at.format("%Y-%m-%dT%H:%M").to_string());
+		new
+	}
+
+	/// Create a version of this item with time tracking stopped at the
+	/// given time, adding the time since it was started to its `spent:` tag.
+	///
+	/// Items which weren't started are returned unchanged.
+	pub fn but_stopped(&self, at: NaiveDateTime) -> Item {
+		let started = match self.started() {
+			Some(s) => s,
+			None => return self.clone(),
+		};
+		let elapsed = (at - started).max(Duration::zero());
+		let mut new = self.clone();
+		new.clear_kv("started");
+		new.set_spent(self.spent().unwrap_or_else(Duration::zero) + elapsed);
+		new
+	}
+
 	/// Classify how urgent this task is.
 	pub fn urgency(&self) -> Option<Urgency> {
 		if !self._urgency.is_initialized() {
@@ -648,45 +1712,23 @@ impl Item {
 	}
 
 	fn _build_urgency(&self) -> Option<Urgency> {
-		self.due_date().map(Urgency::from_due_date)
+		self.due_datetime()
+			.map(Urgency::from_due_datetime)
 	}
 
 	/// Set task urgency.
 	pub fn set_urgency(&mut self, urg: Urgency) {
-		let mut d = match urg {
-			Urgency::Overdue => DATE_TODAY.pred_opt().unwrap(),
-			Urgency::Today => *DATE_TODAY,
-			Urgency::Soon => *DATE_SOON,
-			Urgency::ThisWeek => *DATE_WEEKEND,
-			Urgency::NextWeek => *DATE_NEXT_WEEKEND,
-			Urgency::NextMonth => *DATE_NEXT_MONTH,
-			Urgency::Later => *DATE_TODAY + Duration::days(183), // about 6 months
-		};
+		let mut d = urg.due_date_on(today(), *WEEK_START);
 		// Work and school tasks should be rescheduled from Saturday/Sunday.
 		if urg > Urgency::Today
-			&& (self.has_context("work") || self.has_context("school"))
+			&& WEEKDAY_CONTEXTS
+				.iter()
+				.any(|c| self.has_context(c))
 		{
-			d = match format!("{}", d.format("%u")).as_str() {
-				"6" => d.pred_opt().unwrap(),
-				"7" => d.pred_opt().unwrap().pred_opt().unwrap(),
-				_ => d,
-			};
+			d = avoid_weekend(d);
 		}
 
-		let formatted = d.format("%Y-%m-%d");
-
-		match self.kv().get("due") {
-			Some(str) => {
-				self.set_description(self.description().replace(
-					&format!("due:{str}"),
-					&format!("due:{formatted}"),
-				))
-			}
-			None => self.set_description(format!(
-				"{} due:{formatted}",
-				self.description()
-			)),
-		}
+		self.set_kv("due", &d.format("%Y-%m-%d").to_string());
 	}
 
 	/// Return the size of this task.
@@ -779,16 +1821,68 @@ impl Item {
 	}
 
 	/// Key-Value Tags.
-	pub fn kv(&self) -> HashMap<String, String> {
+	pub fn kv(&self) -> &HashMap<String, String> {
 		if !self._kv.is_initialized() {
 			self._kv.lazy_init(self._build_kv());
 		}
-		// Need to return a copy
-		let mut kv_clone: HashMap<String, String> = HashMap::new();
-		for (k, v) in &*self._kv {
-			kv_clone.insert(k.clone(), v.clone());
+		&self._kv
+	}
+
+	/// Parse a key-value tag which is expected to hold a date.
+	///
+	/// The date should be in YYYY-MM-DD format, but may be followed by a
+	/// time, like `due:2000-01-01T09:00`; the time is ignored. Unless natural
+	/// dates are disabled in the config file, dates like `due:next_friday`
+	/// are also understood.
+	pub fn kv_date(&self, key: &str) -> Option<NaiveDate> {
+		parse_date(self.kv().get(key)?, *NATURAL_DATES)
+	}
+
+	/// Parse the time from a key-value tag holding a date and time, like
+	/// `due:2000-01-01T09:00`.
+	pub fn kv_time(&self, key: &str) -> Option<NaiveTime> {
+		let kv = self.kv();
+		let (_date, time) = kv.get(key)?.split_once('T')?;
+		NaiveTime::parse_from_str(time, "%H:%M")
+			.or_else(|_| NaiveTime::parse_from_str(time, "%H:%M:%S"))
+			.ok()
+	}
+
+	/// Set a key-value tag, replacing any existing tags with that key, or
+	/// adding one to the end of the description.
+	pub fn set_kv(&mut self, key: &str, value: &str) {
+		let re =
+			Regex::new(&format!(r"(^|\s){}:[^\s:/\\]\S*", regex::escape(key)))
+				.unwrap();
+		if re.is_match(&self.description) {
+			let new = re
+				.replace_all(&self.description, |c: &regex::Captures| {
+					format!("{}{key}:{value}", &c[1])
+				});
+			self.set_description(new.to_string());
+		} else {
+			self.set_description(format!("{} {key}:{value}", self.description));
 		}
-		kv_clone
+	}
+
+	/// Remove all key-value tags with the given key from the description.
+	pub fn clear_kv(&mut self, key: &str) {
+		let re = Regex::new(&format!(
+			r"(?:^|\s+){}:[^\s:/\\]\S*",
+			regex::escape(key)
+		))
+		.unwrap();
+		let new = re.replace_all(&self.description, "");
+		self.set_description(new.trim().to_string());
+	}
+
+	/// The task description with any key-value tags removed.
+	pub fn description_without_kv(&self) -> String {
+		RE_KV
+			.replace_all(&self.description, "")
+			.split_whitespace()
+			.collect::<Vec<&str>>()
+			.join(" ")
 	}
 
 	fn _build_kv(&self) -> HashMap<String, String> {
@@ -807,6 +1901,45 @@ impl Item {
 			self.tshirt_size().unwrap_or_default(),
 		)
 	}
+
+	/// Whether two items represent the same underlying task.
+	///
+	/// Like `==`, but ignores completion status and dates, so a task is
+	/// the same task before and after it has been marked as done.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use tada::Item;
+	/// let i = Item::parse("(A) 2000-01-01 Foo");
+	/// let j = Item::parse("x (A) 2000-01-02 2000-01-01 Foo");
+	/// assert!(i.same_task(&j));
+	/// assert!(i != j);
+	/// ```
+	pub fn same_task(&self, other: &Self) -> bool {
+		self.priority == other.priority && self.description == other.description
+	}
+
+	/// Whether an item duplicates another, for the purposes of `tidy --dedupe`.
+	///
+	/// Like `==`, but ignores the creation date and any whitespace around the
+	/// description, so a task captured twice on different days is still a
+	/// duplicate. Tasks with a different priority are not duplicates.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use tada::Item;
+	/// let i = Item::parse("(A) 2000-01-01 Foo");
+	/// assert!(i.is_duplicate_of(&Item::parse("(A) 2000-01-02 Foo ")));
+	/// assert!(!i.is_duplicate_of(&Item::parse("(B) 2000-01-01 Foo")));
+	/// ```
+	pub fn is_duplicate_of(&self, other: &Self) -> bool {
+		self.completion == other.completion
+			&& self.priority == other.priority
+			&& self.completion_date == other.completion_date
+			&& self.description.trim() == other.description.trim()
+	}
 }
 
 impl Default for Item {
@@ -819,16 +1952,58 @@ impl Clone for Item {
 	fn clone(&self) -> Self {
 		Item {
 			line_number: self.line_number,
+			source: self.source.clone(),
 			completion: self.completion,
 			priority: self.priority,
 			completion_date: self.completion_date,
 			creation_date: self.creation_date,
 			description: self.description.clone(),
-			..Item::new()
+			_importance: clone_cache(&self._importance),
+			_due_date: clone_cache(&self._due_date),
+			_start_date: clone_cache(&self._start_date),
+			// Urgency depends on the current time, so is always rebuilt.
+			_urgency: FreezeBox::default(),
+			_tshirt_size: clone_cache(&self._tshirt_size),
+			_tags: clone_cache(&self._tags),
+			_contexts: clone_cache(&self._contexts),
+			_kv: clone_cache(&self._kv),
 		}
 	}
 }
 
+/// Copy a lazily-built field, keeping its value if it has been built.
+fn clone_cache<T: Clone>(cache: &FreezeBox<T>) -> FreezeBox<T> {
+	if cache.is_initialized() {
+		FreezeBox::new(Some((**cache).clone()))
+	} else {
+		FreezeBox::default()
+	}
+}
+
+impl PartialEq for Item {
+	/// Items are equal if they have the same completion status, priority,
+	/// dates, and description.
+	fn eq(&self, other: &Self) -> bool {
+		self.completion == other.completion
+			&& self.priority == other.priority
+			&& self.completion_date == other.completion_date
+			&& self.creation_date == other.creation_date
+			&& self.description == other.description
+	}
+}
+
+impl Eq for Item {}
+
+impl Hash for Item {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.completion.hash(state);
+		self.priority.hash(state);
+		self.completion_date.hash(state);
+		self.creation_date.hash(state);
+		self.description.hash(state);
+	}
+}
+
 impl fmt::Debug for Item {
 	/// Debugging output; used for format!("{:?}")
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -867,6 +2042,98 @@ impl fmt::Display for Item {
 	}
 }
 
+/// How an item looks when serialized.
+///
+/// Includes the raw text, plus everything parsed out of it.
+#[derive(Serialize)]
+struct SerializedItem {
+	text: String,
+	completion: bool,
+	priority: Option<char>,
+	completion_date: Option<String>,
+	creation_date: Option<String>,
+	description: String,
+	importance: Option<Importance>,
+	due_date: Option<String>,
+	start_date: Option<String>,
+	urgency: Option<Urgency>,
+	tshirt_size: Option<TshirtSize>,
+	contexts: Vec<String>,
+	tags: Vec<String>,
+	kv: BTreeMap<String, String>,
+}
+
+/// How an item looks when deserialized. Only the raw text is needed.
+#[derive(Deserialize)]
+struct DeserializedItem {
+	text: String,
+}
+
+impl Serialize for Item {
+	fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+		let date =
+			|d: Option<NaiveDate>| d.map(|d| d.format("%Y-%m-%d").to_string());
+		SerializedItem {
+			text: self.to_string(),
+			completion: self.completion(),
+			priority: Some(self.priority()).filter(|p| *p != '\0'),
+			completion_date: date(self.completion_date()),
+			creation_date: date(self.creation_date()),
+			description: self.description().to_string(),
+			importance: self.importance(),
+			due_date: date(self.due_date()),
+			start_date: date(self.start_date()),
+			urgency: self.urgency(),
+			tshirt_size: self.tshirt_size(),
+			contexts: self.contexts(),
+			tags: self.tags(),
+			kv: self
+				.kv()
+				.iter()
+				.map(|(k, v)| (k.clone(), v.clone()))
+				.collect(),
+		}
+		.serialize(s)
+	}
+}
+
+impl<'de> Deserialize<'de> for Item {
+	/// Parses the item's `text`; any other fields are ignored.
+	fn deserialize<D: serde::Deserializer<'de>>(
+		d: D,
+	) -> Result<Self, D::Error> {
+		let raw = DeserializedItem::deserialize(d)?;
+		Ok(Item::parse(&raw.text))
+	}
+}
+
+/// Parse a date given on the command line, like "2024-06-01",
+/// "yesterday", or "last_friday".
+///
+/// Natural language dates are always accepted here, even if they are
+/// disabled in the config file.
+pub fn parse_date_arg(value: &str) -> Option<NaiveDate> {
+	parse_date(value, true)
+}
+
+/// Parse the date from a key-value tag value.
+///
+/// Accepts YYYY-MM-DD, optionally followed by a time. If `natural` is true,
+/// also accepts natural language dates, with underscores for spaces.
+fn parse_date(value: &str, natural: bool) -> Option<NaiveDate> {
+	let date = match value.split_once('T') {
+		Some((date, _time)) => date,
+		None => value,
+	};
+	match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+		Ok(d) => Some(d),
+		Err(_) if natural => {
+			NaturalDateParser::parse_relative(&value.replace('_', " "), today())
+		}
+		Err(_) => None,
+	}
+}
+
 #[cfg(test)]
 mod tests_item {
 	use super::*;
@@ -887,6 +2154,84 @@ mod tests_item {
 		assert!(dbug.len() > 1);
 	}
 
+	#[test]
+	#[allow(clippy::mutable_key_type)]
+	fn test_eq() {
+		use std::collections::HashSet;
+
+		let i = Item::parse("(A) 2000-01-01 Foo @home");
+		assert_eq!(i, Item::parse("(A) 2000-01-01 Foo @home"));
+		assert_eq!(i, i.clone());
+		assert_ne!(i, Item::parse("(B) 2000-01-01 Foo @home"));
+		assert_ne!(i, Item::parse("(A) 2000-01-02 Foo @home"));
+		assert_ne!(i, Item::parse("(A) 2000-01-01 Foo @work"));
+		assert_ne!(i, Item::parse("x (A) 2000-01-01 Foo @home"));
+
+		// Line numbers and sources aren't part of the task.
+		let mut j = i.clone();
+		j.set_source(Some(String::from("work")));
+		assert_eq!(i, j);
+
+		let set: HashSet<Item> =
+			HashSet::from([i.clone(), j, Item::parse("Bar")]);
+		assert_eq!(2, set.len());
+		assert!(set.contains(&i));
+	}
+
+	#[test]
+	fn test_clone_keeps_caches() {
+		let i = Item::parse("(A) Foo @home +bar due:2000-01-01 t:2000-01-02");
+		assert!(!i._kv.is_initialized());
+		let j = i.clone();
+		assert!(!j._kv.is_initialized());
+
+		i.smart_key();
+		let j = i.clone();
+		assert!(j._kv.is_initialized());
+		assert!(j._due_date.is_initialized());
+		assert!(j._importance.is_initialized());
+		assert!(!j._urgency.is_initialized());
+		assert_eq!(i.due_date(), j.due_date());
+		assert_eq!(i.kv(), j.kv());
+
+		// Changing the clone mustn't leave stale caches behind.
+		let mut k = i.clone();
+		k.set_description(String::from("Foo @work"));
+		assert!(k.has_context("work"));
+		assert!(!k.has_context("home"));
+		assert_eq!(None, k.due_date());
+		assert!(i.has_context("home"));
+	}
+
+	#[test]
+	fn test_same_task() {
+		let i = Item::parse("(A) 2000-01-01 Foo");
+		assert!(i.same_task(&Item::parse("(A) Foo")));
+		assert!(i.same_task(&Item::parse("x (A) 2000-01-02 2000-01-01 Foo")));
+		assert!(!i.same_task(&Item::parse("(B) 2000-01-01 Foo")));
+		assert!(!i.same_task(&Item::parse("(A) 2000-01-01 Bar")));
+	}
+
+	#[test]
+	fn test_is_duplicate_of() {
+		let i = Item::parse("(A) 2000-01-01 Foo due:2000-02-01");
+		assert!(i.is_duplicate_of(&i));
+		assert!(i.is_duplicate_of(&Item::parse("(A) Foo due:2000-02-01")));
+		assert!(i.is_duplicate_of(&Item::parse(
+			"(A) 2000-01-02 Foo due:2000-02-01"
+		)));
+		assert!(!i.is_duplicate_of(&Item::parse(
+			"(B) 2000-01-01 Foo due:2000-02-01"
+		)));
+		assert!(!i.is_duplicate_of(&Item::parse("Foo due:2000-02-01")));
+		assert!(!i.is_duplicate_of(&Item::parse(
+			"(A) 2000-01-01 Foo due:2000-02-02"
+		)));
+		assert!(!i.is_duplicate_of(&Item::parse(
+			"x (A) 2000-01-03 2000-01-01 Foo due:2000-02-01"
+		)));
+	}
+
 	#[test]
 	fn test_display() {
 		let b = Item::new();
@@ -910,12 +2255,35 @@ mod tests_item {
 		assert_eq!("x (B) 2010-01-01 2000-12-31 foo bar baz", format!("{}", i));
 	}
 
+	#[test]
+	fn test_try_parse() {
+		let i = Item::try_parse("  (A) 2024-06-01 Foo +bar  ").unwrap();
+		assert_eq!("(A) 2024-06-01 Foo +bar", i.to_string());
+		assert!(Item::try_parse("x 2024-06-02 2024-06-01 Foo").is_ok());
+
+		for (text, reason) in [
+			("", "is blank"),
+			("   ", "is blank"),
+			("# Foo", "is a comment"),
+			("Foo\nBar", "is more than one line"),
+			("(A) 2024-13-45 Foo", "can't be read properly"),
+		] {
+			assert_eq!(reason, Item::try_parse(text).unwrap_err().reason);
+		}
+		assert_eq!(
+			"'# Foo' is a comment, so isn't a task",
+			Item::try_parse("# Foo")
+				.unwrap_err()
+				.to_string()
+		);
+	}
+
 	#[test]
 	fn test_parse() {
 		// Parse a complex line
 		let i = Item::parse("x (B) 2010-01-01 2000-12-31 foo bar baz");
 
-		assert_eq!(true, i.completion);
+		assert!(i.completion);
 		assert_eq!('B', i.priority);
 		assert_eq!(
 			NaiveDate::from_ymd_opt(2010, 1, 1).unwrap(),
@@ -931,7 +2299,7 @@ mod tests_item {
 		assert_eq!(None, i.tshirt_size());
 		assert_eq!(Vec::<String>::new(), i.tags());
 		assert_eq!(Vec::<String>::new(), i.contexts());
-		assert_eq!(HashMap::<String, String>::new(), i.kv());
+		assert_eq!(&HashMap::<String, String>::new(), i.kv());
 
 		// Parse a misleading line
 		let i = Item::parse("2010-01-01 (A) foo bar baz");
@@ -956,8 +2324,65 @@ mod tests_item {
 
 		assert_eq!('A', i.priority);
 		assert_eq!("foo bar abc:xyz def:123".to_string(), i.description);
-		assert_eq!(expected_kv, i.kv());
-		assert_eq!(expected_kv, i.kv());
+		assert_eq!(&expected_kv, i.kv());
+		assert_eq!(&expected_kv, i.kv());
+	}
+
+	#[test]
+	fn test_kv_with_colons() {
+		let i = Item::parse(
+			"read https://example.com/page ref:https://example.com/x",
+		);
+		let expected_kv = HashMap::from([(
+			"ref".to_string(),
+			"https://example.com/x".to_string(),
+		)]);
+		assert_eq!(&expected_kv, i.kv());
+
+		let i = Item::parse("meet at:09:30 due:2000-06-01T09:00");
+		assert_eq!("09:30", i.kv().get("at").unwrap());
+		assert_eq!("2000-06-01T09:00", i.kv().get("due").unwrap());
+		assert_eq!(NaiveDate::from_ymd_opt(2000, 6, 1), i.due_date());
+
+		let i = Item::parse(r"tidy C:\Users\bob and D:/Music folders");
+		assert_eq!(&HashMap::<String, String>::new(), i.kv());
+	}
+
+	#[test]
+	fn test_set_kv() {
+		let mut i = Item::parse("foo ref:a:b xref:c bar");
+		i.set_kv("ref", "https://example.com");
+		assert_eq!("foo ref:https://example.com xref:c bar", i.description());
+		assert_eq!("https://example.com", i.kv().get("ref").unwrap());
+
+		i.set_kv("id", "1");
+		assert_eq!(
+			"foo ref:https://example.com xref:c bar id:1",
+			i.description()
+		);
+	}
+
+	#[test]
+	fn test_clear_kv() {
+		let mut i = Item::parse("(A) foo due:2000-01-01 bar xdue:1 due:2");
+		i.clear_kv("due");
+		assert_eq!("foo bar xdue:1", i.description());
+		assert_eq!(None, i.due_date());
+
+		let mut i = Item::parse("due:2000-01-01 foo");
+		i.clear_kv("due");
+		assert_eq!("foo", i.description());
+
+		let mut i = Item::parse("foo due:2000-01-01T09:00 https://example.com");
+		i.clear_kv("due");
+		assert_eq!("foo https://example.com", i.description());
+	}
+
+	#[test]
+	fn test_description_without_kv() {
+		let i =
+			Item::parse("(A) foo due:2000-01-01 bar id:x1 https://example.com");
+		assert_eq!("foo bar https://example.com", i.description_without_kv());
 	}
 
 	#[test]
@@ -970,31 +2395,343 @@ mod tests_item {
 		);
 	}
 
+	/// A Friday.
+	fn friday() -> NaiveDate {
+		NaiveDate::from_ymd_opt(2024, 6, 7).unwrap()
+	}
+
 	#[test]
 	fn test_urgency() {
-		let i = Item::parse("(A) foo bar due:1970-06-01");
-		assert_eq!(Urgency::Overdue, i.urgency().unwrap());
+		with_today(friday(), || {
+			let urg = |due: &str| {
+				Item::parse(&format!("(A) foo bar due:{}", due))
+					.urgency()
+					.unwrap()
+			};
+			assert_eq!(Urgency::Overdue, urg("1970-06-01"));
+			assert_eq!(Urgency::Overdue, urg("2024-06-06"));
+			assert_eq!(Urgency::Today, urg("2024-06-07"));
+			assert_eq!(Urgency::Soon, urg("2024-06-08"));
+			assert_eq!(Urgency::Soon, urg("2024-06-09"));
+			assert_eq!(Urgency::NextWeek, urg("2024-06-10"));
+			assert_eq!(Urgency::NextWeek, urg("2024-06-16"));
+			assert_eq!(Urgency::NextMonth, urg("2024-06-17"));
+			assert_eq!(Urgency::NextMonth, urg("2024-07-31"));
+			assert_eq!(Urgency::Later, urg("2024-08-01"));
+			assert_eq!(Urgency::Later, urg("3970-06-01"));
+		});
+	}
 
-		let i = Item::parse(&format!(
-			"(A) foo bar due:{}",
-			Utc::now().date_naive().format("%Y-%m-%d")
-		));
-		assert_eq!(Urgency::Today, i.urgency().unwrap());
+	#[test]
+	fn test_urgency_this_week_vs_next_week() {
+		let thursday = friday().pred_opt().unwrap();
+		let sunday = NaiveDate::from_ymd_opt(2024, 6, 9).unwrap();
+		let monday = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
 
-		let i = Item::parse(&format!(
-			"(A) foo bar due:{}",
-			(Utc::now().date_naive() + Duration::days(1)).format("%Y-%m-%d")
-		));
-		assert_eq!(Urgency::Soon, i.urgency().unwrap());
+		// On Thursday, Sunday is still this week.
+		with_today(thursday, || {
+			assert_eq!(Urgency::ThisWeek, Urgency::from_due_date(sunday));
+			assert_eq!(Urgency::NextWeek, Urgency::from_due_date(monday));
+		});
 
-		let i = Item::parse(&format!(
-			"(A) foo bar due:{}",
-			(Utc::now().date_naive() + Duration::days(18)).format("%Y-%m-%d")
-		));
-		assert_eq!(Urgency::NextMonth, i.urgency().unwrap());
+		// On Friday, Sunday is soon, and nothing is left for this week.
+		with_today(friday(), || {
+			assert_eq!(Urgency::Soon, Urgency::from_due_date(sunday));
+			assert_eq!(Urgency::NextWeek, Urgency::from_due_date(monday));
+		});
+	}
+
+	#[test]
+	fn test_with_today() {
+		let day = friday();
+		assert_eq!(day, with_today(day, today));
+		assert_eq!(day.and_hms_opt(0, 0, 0).unwrap(), with_today(day, now));
+
+		// Overrides nest, and are restored afterwards.
+		let other = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+		with_today(day, || {
+			assert_eq!(other, with_today(other, today));
+			assert_eq!(day, today());
+		});
+
+		let time = day.and_hms_opt(9, 30, 0).unwrap();
+		set_now(Some(time));
+		assert_eq!(time, now());
+		assert_eq!(day, today());
+		set_now(None);
+	}
 
-		let i = Item::parse("(A) foo bar due:3970-06-01");
-		assert_eq!(Urgency::Later, i.urgency().unwrap());
+	#[test]
+	fn test_parse_datetime() {
+		let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+		assert_eq!(date.and_hms_opt(0, 0, 0), parse_datetime("2024-06-01"));
+		assert_eq!(
+			date.and_hms_opt(17, 0, 0),
+			parse_datetime("2024-06-01T17:00")
+		);
+		assert_eq!(
+			date.and_hms_opt(17, 0, 5),
+			parse_datetime("2024-06-01T17:00:05")
+		);
+		assert_eq!(None, parse_datetime("tomorrow"));
+	}
+
+	#[test]
+	fn test_parse_date() {
+		let wed = NaiveDate::from_ymd_opt(2024, 6, 5).unwrap();
+		with_today(wed, || {
+			assert_eq!(Some(wed), parse_date("today", true));
+			assert_eq!(
+				Some(wed + Duration::days(1)),
+				parse_date("tomorrow", true)
+			);
+			let friday = parse_date("next_friday", true).unwrap();
+			assert_eq!(Weekday::Fri, friday.weekday());
+			assert!(friday > wed);
+		});
+		assert_eq!(None, parse_date("today", false));
+		with_today(wed, || {
+			assert_eq!(
+				Some(wed - Duration::days(1)),
+				parse_date_arg("yesterday")
+			);
+			assert_eq!(
+				NaiveDate::from_ymd_opt(2024, 5, 31),
+				parse_date_arg("last_friday")
+			);
+		});
+		assert_eq!(None, parse_date("garbage", true));
+		assert_eq!(None, parse_date("2000-13-45", true));
+		assert_eq!(
+			NaiveDate::from_ymd_opt(2000, 1, 2),
+			parse_date("2000-01-02", false)
+		);
+	}
+
+	#[test]
+	fn test_natural_due_date() {
+		with_today(friday(), || {
+			let i = Item::parse("foo due:tomorrow");
+			assert_eq!(NaiveDate::from_ymd_opt(2024, 6, 8), i.due_date());
+			assert_eq!(Urgency::Soon, i.urgency().unwrap());
+		});
+		assert_eq!(None, Item::parse("foo due:whenever").due_date());
+	}
+
+	#[test]
+	fn test_due_datetime() {
+		let i = Item::parse("foo due:2000-06-01T17:00");
+		assert_eq!(NaiveTime::from_hms_opt(17, 0, 0), i.due_time());
+		assert_eq!(
+			NaiveDate::from_ymd_opt(2000, 6, 1)
+				.unwrap()
+				.and_hms_opt(17, 0, 0),
+			i.due_datetime()
+		);
+
+		let i = Item::parse("foo due:2000-06-01T17:00:30");
+		assert_eq!(NaiveTime::from_hms_opt(17, 0, 30), i.due_time());
+
+		let i = Item::parse("foo due:2000-06-01");
+		assert_eq!(None, i.due_time());
+		assert_eq!(
+			NaiveDate::from_ymd_opt(2000, 6, 1)
+				.unwrap()
+				.and_hms_opt(23, 59, 59),
+			i.due_datetime()
+		);
+
+		let i = Item::parse("foo due:2000-06-01T5pm");
+		assert_eq!(NaiveDate::from_ymd_opt(2000, 6, 1), i.due_date());
+		assert_eq!(None, i.due_time());
+
+		assert_eq!(None, Item::parse("foo").due_datetime());
+	}
+
+	#[test]
+	fn test_urgency_on_wednesday() {
+		let wed = NaiveDate::from_ymd_opt(2024, 6, 5).unwrap();
+		let day = |d| NaiveDate::from_ymd_opt(2024, 6, d).unwrap();
+		let urg = |d, ws| Urgency::from_due_date_on(day(d), wed, ws);
+
+		assert_eq!(Urgency::Overdue, urg(4, Weekday::Mon));
+		assert_eq!(Urgency::Today, urg(5, Weekday::Mon));
+		assert_eq!(Urgency::Soon, urg(7, Weekday::Mon));
+		assert_eq!(Urgency::ThisWeek, urg(9, Weekday::Mon));
+		assert_eq!(Urgency::NextWeek, urg(10, Weekday::Mon));
+		assert_eq!(Urgency::NextWeek, urg(16, Weekday::Mon));
+		assert_eq!(Urgency::NextMonth, urg(17, Weekday::Mon));
+
+		// Weeks ending on Friday.
+		assert_eq!(Urgency::Soon, urg(7, Weekday::Sat));
+		assert_eq!(Urgency::NextWeek, urg(8, Weekday::Sat));
+		assert_eq!(Urgency::NextWeek, urg(14, Weekday::Sat));
+		assert_eq!(Urgency::NextMonth, urg(15, Weekday::Sat));
+
+		assert_eq!(day(9), Urgency::ThisWeek.due_date_on(wed, Weekday::Mon));
+		assert_eq!(day(7), Urgency::ThisWeek.due_date_on(wed, Weekday::Sat));
+		assert_eq!(day(14), Urgency::NextWeek.due_date_on(wed, Weekday::Sat));
+		assert_eq!(
+			NaiveDate::from_ymd_opt(2024, 7, 31).unwrap(),
+			Urgency::NextMonth.due_date_on(wed, Weekday::Mon)
+		);
+	}
+
+	#[test]
+	fn test_urgency_on_saturday() {
+		let sat = NaiveDate::from_ymd_opt(2024, 6, 8).unwrap();
+		let day = |d| NaiveDate::from_ymd_opt(2024, 6, d).unwrap();
+		let urg = |d, ws| Urgency::from_due_date_on(day(d), sat, ws);
+
+		assert_eq!(Urgency::Soon, urg(9, Weekday::Mon));
+		assert_eq!(Urgency::Soon, urg(10, Weekday::Mon));
+		assert_eq!(Urgency::NextWeek, urg(11, Weekday::Mon));
+		assert_eq!(Urgency::NextWeek, urg(16, Weekday::Mon));
+		assert_eq!(Urgency::NextMonth, urg(17, Weekday::Mon));
+
+		// Weeks ending on Friday.
+		assert_eq!(Urgency::ThisWeek, urg(11, Weekday::Sat));
+		assert_eq!(Urgency::ThisWeek, urg(14, Weekday::Sat));
+		assert_eq!(Urgency::NextWeek, urg(15, Weekday::Sat));
+		assert_eq!(Urgency::NextWeek, urg(21, Weekday::Sat));
+		assert_eq!(Urgency::NextMonth, urg(22, Weekday::Sat));
+
+		assert_eq!(day(9), Urgency::ThisWeek.due_date_on(sat, Weekday::Mon));
+		assert_eq!(day(14), Urgency::ThisWeek.due_date_on(sat, Weekday::Sat));
+	}
+
+	#[test]
+	fn test_avoid_weekend() {
+		let day = |d| NaiveDate::from_ymd_opt(2024, 6, d).unwrap();
+		assert_eq!(day(7), avoid_weekend(day(7)));
+		assert_eq!(day(7), avoid_weekend(day(8)));
+		assert_eq!(day(7), avoid_weekend(day(9)));
+		assert_eq!(day(10), avoid_weekend(day(10)));
+	}
+
+	#[test]
+	fn test_urgency_with_time() {
+		set_now(friday().and_hms_opt(12, 0, 0));
+		let urg = |due: &str| {
+			Item::parse(&format!("foo due:{}", due))
+				.urgency()
+				.unwrap()
+		};
+		assert_eq!(Urgency::Overdue, urg("2024-06-07T00:00"));
+		assert_eq!(Urgency::Overdue, urg("2024-06-07T11:59"));
+		assert_eq!(Urgency::Today, urg("2024-06-07T12:30"));
+		assert_eq!(Urgency::Today, urg("2024-06-07"));
+		assert_eq!(Urgency::Soon, urg("2024-06-08T00:00"));
+		set_now(None);
+	}
+
+	#[test]
+	fn test_set_urgency() {
+		with_today(friday(), || {
+			let mut i =
+				Item::parse("foo due:1970-06-01T09:00 see https://x.com");
+			i.set_urgency(Urgency::Today);
+			assert_eq!("foo due:2024-06-07 see https://x.com", i.description());
+			assert_eq!(Urgency::Today, i.urgency().unwrap());
+
+			let mut i = Item::parse("foo");
+			i.set_urgency(Urgency::Overdue);
+			assert_eq!("foo due:2024-06-06", i.description());
+			assert_eq!(Urgency::Overdue, i.urgency().unwrap());
+
+			// Soon is Sunday, which work tasks avoid.
+			let mut i = Item::parse("foo @home");
+			i.set_urgency(Urgency::Soon);
+			assert_eq!("foo @home due:2024-06-09", i.description());
+			let mut i = Item::parse("foo @work");
+			i.set_urgency(Urgency::Soon);
+			assert_eq!("foo @work due:2024-06-07", i.description());
+		});
+	}
+
+	#[test]
+	fn test_is_startable() {
+		with_today(friday(), || {
+			assert!(Item::parse("foo").is_startable());
+			assert!(Item::parse("foo start:2024-06-07").is_startable());
+			assert!(!Item::parse("foo start:2024-06-08").is_startable());
+		});
+	}
+
+	#[test]
+	fn test_zen() {
+		with_today(friday(), || {
+			let policy = ZenPolicy::default();
+			let zen = |text: &str| {
+				Item::parse(text)
+					.zen(&policy)
+					.description()
+					.to_string()
+			};
+			assert_eq!(
+				"foo due:2024-06-09 @S",
+				zen("(A) foo due:2000-01-01 @S")
+			);
+			assert_eq!("foo due:2024-06-16", zen("(A) foo due:2000-01-01"));
+			assert_eq!("foo due:2024-06-16 @S", zen("foo due:2000-01-01 @S"));
+			assert_eq!("foo due:2024-07-31", zen("foo due:2000-01-01"));
+			assert_eq!("foo due:2024-06-08", zen("foo due:2024-06-08"));
+		});
+	}
+
+	#[test]
+	fn test_id_and_after() {
+		let i = Item::parse("foo id:a1 after:b2,c3");
+		assert_eq!(Some(String::from("a1")), i.id());
+		assert_eq!(Vec::from(["b2", "c3"]), i.after());
+
+		let i = Item::parse("foo after:b2,");
+		assert_eq!(None, i.id());
+		assert_eq!(Vec::from(["b2"]), i.after());
+		assert!(Item::parse("foo").after().is_empty());
+	}
+
+	#[test]
+	fn test_parse_duration() {
+		assert_eq!(Some(Duration::minutes(95)), parse_duration("95m"));
+		assert_eq!(Some(Duration::minutes(120)), parse_duration("2h"));
+		assert_eq!(Some(Duration::minutes(90)), parse_duration("1h30m"));
+		assert_eq!(Some(Duration::minutes(1590)), parse_duration("1d2h30m"));
+		assert_eq!(None, parse_duration(""));
+		assert_eq!(None, parse_duration("m"));
+		assert_eq!(None, parse_duration("30m1h"));
+		assert_eq!(None, parse_duration("ages"));
+		assert_eq!("95m", format_duration(Duration::minutes(95)));
+		assert_eq!("0m", format_duration(Duration::seconds(59)));
+	}
+
+	#[test]
+	fn test_time_tracking() {
+		let at = |h, m| {
+			NaiveDate::from_ymd_opt(2024, 6, 1)
+				.unwrap()
+				.and_hms_opt(h, m, 0)
+				.unwrap()
+		};
+		let i = Item::parse("foo spent:1h");
+		assert_eq!(None, i.started());
+		assert_eq!(Some(Duration::minutes(60)), i.spent());
+		assert_eq!("foo spent:1h", i.but_stopped(at(10, 0)).description());
+
+		let i = i.but_started(at(9, 12));
+		assert_eq!("foo spent:1h started:2024-06-01T09:12", i.description());
+		assert_eq!(Some(at(9, 12)), i.started());
+
+		let i = i.but_stopped(at(9, 47));
+		assert_eq!("foo spent:95m", i.description());
+		assert_eq!(None, i.started());
+
+		let i =
+			Item::parse("bar started:2024-06-01T09:00").but_stopped(at(10, 0));
+		assert_eq!("bar spent:60m", i.description());
+		let i =
+			Item::parse("bar started:2024-06-01T09:00").but_stopped(at(8, 0));
+		assert_eq!("bar spent:0m", i.description());
 	}
 
 	#[test]
@@ -1025,6 +2762,303 @@ mod tests_item {
 		assert!(!i.has_context("Fool"));
 	}
 
+	#[test]
+	fn test_pri_tag() {
+		let mut i = Item::parse("foo pri:b bar");
+		assert_eq!(Some('B'), i.pri_tag());
+
+		i.set_pri_tag('C');
+		assert_eq!("foo pri:C bar", i.description());
+		assert_eq!(Some('C'), i.pri_tag());
+
+		i.clear_pri_tag();
+		assert_eq!("foo bar", i.description());
+		assert_eq!(None, i.pri_tag());
+
+		i.set_pri_tag('A');
+		assert_eq!("foo bar pri:A", i.description());
+
+		let i = Item::parse("foo pri:AB");
+		assert_eq!(None, i.pri_tag());
+	}
+
+	#[test]
+	fn test_but_done_preserving_priority() {
+		let i = Item::parse("(B) 2000-01-01 foo bar");
+
+		let done = i.but_done(None, true, true);
+		assert!(done.completion());
+		assert_eq!('\0', done.priority());
+		assert_eq!(None, done.importance());
+		assert_eq!(Some('B'), done.pri_tag());
+		assert_eq!("x 2000-01-01 foo bar pri:B", format!("{}", done));
+
+		let undone = done.but_undone();
+		assert!(!undone.completion());
+		assert_eq!('B', undone.priority());
+		assert_eq!(Some(Importance::B), undone.importance());
+		assert_eq!(None, undone.pri_tag());
+		assert_eq!(format!("{}", i), format!("{}", undone));
+
+		let done = i.but_done(None, false, true);
+		assert_eq!("x (B) 2000-01-01 foo bar", format!("{}", done));
+		assert_eq!(format!("{}", i), format!("{}", done.but_undone()));
+	}
+
+	#[test]
+	fn test_age_policy() {
+		let friday = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+		with_today(friday, || {
+			let policy = AgePolicy::default();
+			let raise = |text: &str| policy.raise(&Item::parse(text));
+			assert_eq!(Some(Importance::A), raise("(B) Foo due:2024-06-07"));
+			assert_eq!(None, raise("(A) Foo due:2024-06-07"));
+			assert_eq!(Some(Importance::A), raise("Foo due:2024-06-01"));
+			assert_eq!(Some(Importance::B), raise("(D) Foo due:2024-06-08"));
+			assert_eq!(None, raise("(A) Foo due:2024-06-08"));
+			assert_eq!(None, raise("(B) Foo due:2024-06-08"));
+			assert_eq!(Some(Importance::B), raise("(Z) Foo due:2024-06-09"));
+			assert_eq!(None, raise("Foo due:2024-06-12"));
+			assert_eq!(None, raise("Foo"));
+			assert_eq!(None, raise("x (B) Foo due:2024-06-07"));
+
+			let policy = AgePolicy {
+				next_week: Some(Importance::D),
+				..AgePolicy::default()
+			};
+			assert_eq!(
+				Some(Importance::D),
+				policy.raise(&Item::parse("(E) Foo due:2024-06-12"))
+			);
+		});
+
+		let monday = NaiveDate::from_ymd_opt(2024, 6, 3).unwrap();
+		with_today(monday, || {
+			let policy = AgePolicy::default();
+			let raise = |text: &str| policy.raise(&Item::parse(text));
+			assert_eq!(Some(Importance::C), raise("(Z) Foo due:2024-06-07"));
+			assert_eq!(None, raise("(C) Foo due:2024-06-07"));
+			assert_eq!(None, raise("Foo due:2024-06-12"));
+		});
+	}
+
+	#[test]
+	fn test_zen_policy() {
+		with_today(friday(), || {
+			let zen = |text: &str, policy: ZenPolicy| {
+				Item::parse(text)
+					.zen(&policy)
+					.description()
+					.to_string()
+			};
+
+			// Everything overdue goes to today.
+			let policy = ZenPolicy::all_to(Urgency::Today);
+			assert_eq!("foo due:2024-06-07", zen("foo due:2000-01-01", policy));
+			assert_eq!(
+				"foo due:2024-06-07",
+				zen("(A) foo due:2000-01-01", policy)
+			);
+
+			// Never push beyond next week.
+			let policy = ZenPolicy::default().with_max(Urgency::NextWeek);
+			assert_eq!("foo due:2024-06-16", zen("foo due:2000-01-01", policy));
+			assert_eq!(
+				"foo due:2024-06-09 @S",
+				zen("(A) foo due:2000-01-01 @S", policy)
+			);
+
+			// Small tasks are important too.
+			let policy = ZenPolicy {
+				important_or_small: Urgency::Soon,
+				..ZenPolicy::default()
+			};
+			assert_eq!(
+				"foo due:2024-06-09 @S",
+				zen("foo due:2000-01-01 @S", policy)
+			);
+			assert_eq!("foo due:2024-07-31", zen("foo due:2000-01-01", policy));
+		});
+	}
+
+	#[test]
+	fn test_urgency_from_string() {
+		assert_eq!(Ok(Urgency::Today), Urgency::from_string("today"));
+		assert_eq!(Ok(Urgency::Soon), Urgency::from_string("Soon"));
+		assert_eq!(Ok(Urgency::ThisWeek), Urgency::from_string("this_week"));
+		assert_eq!(Ok(Urgency::NextWeek), Urgency::from_string("NextWeek"));
+		assert_eq!(Ok(Urgency::NextMonth), Urgency::from_string("next-month"));
+		assert_eq!(Ok(Urgency::Later), Urgency::from_string("later"));
+		for u in Urgency::all() {
+			assert_eq!(Ok(u), Urgency::from_string(u.label()));
+		}
+		let err = Urgency::from_string("whenever").unwrap_err();
+		assert_eq!("whenever", err.given);
+		assert!(err.to_string().contains("next-week"));
+	}
+
+	#[test]
+	fn test_display_from_str() {
+		for u in Urgency::all() {
+			assert_eq!(u.label(), format!("{}", u));
+			assert_eq!(Ok(u), format!("{}", u).parse());
+			assert_eq!(Ok(u), u.label().to_lowercase().parse());
+		}
+		for i in Importance::all() {
+			assert_eq!(i.label(), format!("{}", i));
+			assert_eq!(Ok(i), format!("{}", i).parse());
+			assert_eq!(Ok(i), i.label().to_lowercase().parse());
+			assert_eq!(Ok(i), i.to_char().to_string().parse());
+		}
+		for z in TshirtSize::all() {
+			assert_eq!(z.label(), format!("{}", z));
+			assert_eq!(Ok(z), format!("{}", z).parse());
+			assert_eq!(Ok(z), z.label().to_lowercase().parse());
+		}
+		assert_eq!("Next week  |", format!("{:<11}|", Urgency::NextWeek));
+
+		assert_eq!(Ok(Urgency::Soon), "overmorrow".parse());
+		assert_eq!(Ok(Importance::C), "semi_important".parse());
+		assert_eq!(Ok(Importance::E('G')), "g".parse());
+
+		// Every letter survives a round trip, not just the named levels.
+		for c in 'A'..='Z' {
+			let i = Importance::from_char(c).unwrap();
+			assert_eq!(Ok(i), format!("{}", i).parse(), "{}", c);
+		}
+		assert_eq!("Unimportant (G)", format!("{}", Importance::E('G')));
+		assert_eq!("Unimportant", format!("{}", Importance::E('E')));
+		assert_eq!(Ok(Importance::E('Z')), "unimportant (z)".parse());
+		assert!("Unimportant (B)".parse::<Importance>().is_err());
+		assert!("Unimportant (E)".parse::<Importance>().is_err());
+		assert!("Unimportant (GG)"
+			.parse::<Importance>()
+			.is_err());
+		assert_eq!(Ok(Importance::A), "crit".parse());
+		assert_eq!(Ok(TshirtSize::Small), "S".parse());
+		assert_eq!(Ok(TshirtSize::Small), "quick".parse());
+
+		assert!("whenever".parse::<Urgency>().is_err());
+		let err = "meh".parse::<Importance>().unwrap_err();
+		assert_eq!("meh", err.given);
+		assert!(err.to_string().contains("semi-important"));
+		assert!("1".parse::<Importance>().is_err());
+		assert!("".parse::<Importance>().is_err());
+		let err = "huge".parse::<TshirtSize>().unwrap_err();
+		assert_eq!("huge", err.given);
+		assert!(err.to_string().contains("medium"));
+	}
+
+	#[test]
+	fn test_but_done_with_date() {
+		with_today(friday(), || {
+			let done = Item::parse("foo").but_done(Some(today()), false, true);
+			assert_eq!("x 2024-06-07 2024-06-07 foo", format!("{}", done));
+
+			let done = Item::parse("2000-01-01 foo").but_done(
+				Some(today()),
+				false,
+				true,
+			);
+			assert_eq!("x 2024-06-07 2000-01-01 foo", format!("{}", done));
+
+			let d = NaiveDate::from_ymd_opt(2024, 6, 3);
+			let done = Item::parse("foo").but_done(d, false, true);
+			assert_eq!("x 2024-06-03 2024-06-03 foo", format!("{}", done));
+
+			// A backdated completion never comes before the creation date.
+			let done = Item::parse("2024-06-05 foo").but_done(d, false, true);
+			assert_eq!("x 2024-06-03 2024-06-03 foo", format!("{}", done));
+			assert!(done.completion_date() >= done.creation_date());
+		});
+	}
+
+	#[test]
+	fn test_but_done_clearing_start() {
+		with_today(friday(), || {
+			let i = Item::parse("2024-06-01 foo start:2025-01-01");
+
+			let done = i.but_done(Some(today()), false, true);
+			assert_eq!(
+				"x 2024-06-07 2024-06-01 foo start:2024-06-07",
+				format!("{}", done)
+			);
+			assert_eq!(Some(today()), done.start_date());
+
+			let d = NaiveDate::from_ymd_opt(2024, 6, 3);
+			let done = i.but_done(d, false, true);
+			assert_eq!(
+				"x 2024-06-03 2024-06-01 foo start:2024-06-03",
+				format!("{}", done)
+			);
+
+			let done = i.but_done(None, false, true);
+			assert_eq!(
+				"x 2024-06-01 foo start:2024-06-07",
+				format!("{}", done)
+			);
+
+			let done = i.but_done(Some(today()), false, false);
+			assert_eq!(
+				"x 2024-06-07 2024-06-01 foo start:2025-01-01",
+				format!("{}", done)
+			);
+
+			// Start dates which had already passed are left alone.
+			let i = Item::parse("foo start:2024-06-01");
+			let done = i.but_done(Some(today()), false, true);
+			assert_eq!(
+				"x 2024-06-07 2024-06-07 foo start:2024-06-01",
+				format!("{}", done)
+			);
+		});
+	}
+
+	#[test]
+	fn test_but_done_preserving_no_priority() {
+		let i = Item::parse("2000-01-01 foo bar");
+
+		let done = i.but_done(None, true, true);
+		assert!(done.completion());
+		assert_eq!('\0', done.priority());
+		assert_eq!(None, done.pri_tag());
+		assert_eq!("x 2000-01-01 foo bar", format!("{}", done));
+
+		let undone = done.but_undone();
+		assert!(!undone.completion());
+		assert_eq!('\0', undone.priority());
+		assert_eq!(format!("{}", i), format!("{}", undone));
+
+		let i = Item::parse("x 2000-01-02 2000-01-01 foo");
+		let undone = i.but_undone();
+		assert_eq!(None, undone.completion_date());
+		assert_eq!("2000-01-01 foo", format!("{}", undone));
+	}
+
+	#[test]
+	fn test_importance_raised_lowered() {
+		assert_eq!(Importance::A, Importance::A.raised());
+		assert_eq!(Importance::A, Importance::B.raised());
+		assert_eq!(Importance::D, Importance::E('E').raised());
+		assert_eq!(Importance::D, Importance::E('G').raised());
+		assert_eq!(Importance::B, Importance::A.lowered());
+		assert_eq!(Importance::E('E'), Importance::D.lowered());
+		assert_eq!(Importance::E('E'), Importance::E('E').lowered());
+		assert_eq!(Importance::E('E'), Importance::E('Z').lowered());
+	}
+
+	#[test]
+	fn test_importance_letters() {
+		assert_eq!(Some(Importance::E('G')), Importance::from_char('G'));
+		assert_eq!('G', Importance::E('G').to_char());
+		assert_eq!("Unimportant", Importance::E('G').label());
+		assert!(Importance::E('F') < Importance::E('G'));
+		assert!(Importance::D < Importance::E('E'));
+		assert_eq!(None, Importance::from_char('a'));
+		let i = Item::parse("(H) Foo");
+		assert_eq!(Some(Importance::E('H')), i.importance());
+	}
+
 	#[test]
 	fn test_tshirt_size() {
 		let i = Item::parse("@M Barble");
@@ -1036,4 +3070,197 @@ mod tests_item {
 		let i = Item::parse("Barble");
 		assert!(i.tshirt_size().is_none());
 	}
+
+	#[test]
+	fn test_fixup() {
+		let i = Item::parse("(A) Write the quarterly report due:2000-01-01 @S");
+		let (fixed, notes) = i.fixup();
+		assert_eq!(i.to_string(), fixed.to_string());
+		assert!(notes.is_empty());
+
+		let (_, notes) = Item::parse("Foo due:soonish").fixup();
+		assert_eq!(
+			vec![
+				FixupNote::MissingPriority,
+				FixupNote::NonIsoDate {
+					slot: String::from("due"),
+					original: String::from("soonish"),
+					corrected: None,
+				},
+				FixupNote::MissingSize,
+				FixupNote::TooShort,
+			],
+			notes
+		);
+		assert_eq!(
+			"Notice: due date `soonish` should be in YYYY-MM-DD format.",
+			notes[1].to_string()
+		);
+
+		let day = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+		let (fixed, notes) = with_today(day, || {
+			Item::parse("(B) Buy milk start:tomorrow @S").fixup()
+		});
+		assert_eq!("Buy milk start:2000-01-02 @S", fixed.description());
+		assert_eq!(
+			vec![
+				FixupNote::MissingDueDate,
+				FixupNote::NonIsoDate {
+					slot: String::from("start"),
+					original: String::from("tomorrow"),
+					corrected: NaiveDate::from_ymd_opt(2000, 1, 2),
+				},
+				FixupNote::TooShort,
+			],
+			notes
+		);
+		assert_eq!(
+			"Notice: start date `tomorrow` changed to `2000-01-02`.",
+			notes[1].to_string()
+		);
+	}
+
+	#[test]
+	fn test_fixup_rules() {
+		let day = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+		let cases = [
+			(FixupRule::Dates, "Foo due:tomorrow", "Foo due:2000-01-02"),
+			(FixupRule::Priority, "Foo (b) bar", "(B) Foo bar"),
+			(FixupRule::Priority, "(a) Foo", "(A) Foo"),
+			(FixupRule::Priority, "Foo (C)", "(C) Foo"),
+			(FixupRule::Contexts, "Foo @ home", "Foo @home"),
+			(FixupRule::Contexts, "@ home Foo @\twork", "@home Foo @work"),
+			(FixupRule::Spacing, "Foo   bar \t baz", "Foo bar baz"),
+			(
+				FixupRule::Punctuation,
+				"Foo due:2000-01-05. start:2000-01-02...",
+				"Foo due:2000-01-05 start:2000-01-02",
+			),
+			(
+				FixupRule::Equals,
+				"Foo due=2000-01-05",
+				"Foo due:2000-01-05",
+			),
+			(
+				FixupRule::Equals,
+				"start=2000-01-02 Foo",
+				"start:2000-01-02 Foo",
+			),
+		];
+		with_today(day, || {
+			for (rule, before, after) in cases {
+				let (fixed, notes) = Item::parse(before).fixup();
+				assert_eq!(after, fixed.to_string(), "{:?}: {}", rule, before);
+				assert!(!notes.is_empty(), "{:?}: {}", rule, before);
+
+				let (refixed, _) = fixed.fixup();
+				assert_eq!(after, refixed.to_string(), "{:?}: {}", rule, after);
+
+				let (unfixed, _) = Item::parse(before).fixup_except(&[rule]);
+				assert_eq!(
+					Item::parse(before).to_string(),
+					unfixed.to_string(),
+					"{:?}: {}",
+					rule,
+					before
+				);
+			}
+		});
+	}
+
+	#[test]
+	fn test_fixup_leaves_alone() {
+		for text in [
+			"(A) Foo (b) bar",
+			"Meet at 5:30.",
+			"Read https://example.com.",
+			"Foo x=1",
+			"Email foo@example.com",
+		] {
+			let (fixed, _) =
+				Item::parse(text).fixup_except(&[FixupRule::Dates]);
+			assert_eq!(text, fixed.to_string());
+		}
+	}
+
+	#[test]
+	fn test_fixup_rule_from_string() {
+		assert_eq!(Ok(FixupRule::Spacing), FixupRule::from_string("spacing"));
+		assert_eq!(Ok(FixupRule::Equals), FixupRule::from_string(" Equals"));
+		for rule in FixupRule::all() {
+			assert_eq!(Ok(rule), FixupRule::from_string(rule.to_string()));
+		}
+		assert_eq!(
+			"unknown fixup rule 'grammar' (expected one of: equals, punctuation, contexts, priority, spacing, dates)",
+			FixupRule::from_string("grammar").unwrap_err().to_string()
+		);
+	}
+}
+
+#[cfg(test)]
+mod tests_serde {
+	use super::*;
+
+	#[test]
+	fn test_serialize() {
+		let i = Item::parse("(A) 2000-01-01 foo @home +bar due:2000-02-01 @S");
+		let v: toml::Value =
+			toml::from_str(&toml::to_string(&i).unwrap()).unwrap();
+		assert_eq!(i.to_string(), v["text"].as_str().unwrap());
+		assert_eq!("A", v["priority"].as_str().unwrap());
+		assert_eq!("A", v["importance"].as_str().unwrap());
+		assert_eq!("2000-01-01", v["creation_date"].as_str().unwrap());
+		assert_eq!("2000-02-01", v["due_date"].as_str().unwrap());
+		assert_eq!("Overdue", v["urgency"].as_str().unwrap());
+		assert_eq!("Small", v["tshirt_size"].as_str().unwrap());
+		assert_eq!("2000-02-01", v["kv"]["due"].as_str().unwrap());
+		assert_eq!(toml::Value::try_from(i.contexts()).unwrap(), v["contexts"]);
+		assert_eq!(toml::Value::try_from(i.tags()).unwrap(), v["tags"]);
+		assert!(v.get("completion_date").is_none());
+		assert!(v.get("_kv").is_none());
+	}
+
+	#[test]
+	fn test_deserialize() {
+		let i: Item = toml::from_str("text = \"(A) foo @home\"").unwrap();
+		assert_eq!('A', i.priority());
+		assert_eq!("foo @home", i.description());
+		assert!(i.has_context("home"));
+
+		// Derived fields are ignored, and recalculated from the text.
+		let i: Item =
+			toml::from_str("text = \"foo\"\npriority = \"B\"\n").unwrap();
+		assert_eq!('\0', i.priority());
+
+		assert!(toml::from_str::<Item>("priority = \"B\"").is_err());
+	}
+
+	#[test]
+	fn test_round_trip() {
+		for text in [
+			"(A) 2000-01-01 foo @home +bar due:2000-02-01",
+			"x 2000-01-02 2000-01-01 foo",
+			"bar",
+		] {
+			let i = Item::parse(text);
+			let j: Item =
+				toml::from_str(&toml::to_string(&i).unwrap()).unwrap();
+			assert_eq!(i.to_string(), j.to_string());
+			assert_eq!(i.completion_date(), j.completion_date());
+			assert_eq!(i.kv(), j.kv());
+		}
+
+		for u in Urgency::all() {
+			let v = toml::Value::try_from(u).unwrap();
+			assert_eq!(u, v.try_into().unwrap());
+		}
+		for imp in Importance::all() {
+			let v = toml::Value::try_from(imp).unwrap();
+			assert_eq!(imp, v.try_into().unwrap());
+		}
+		for size in TshirtSize::all() {
+			let v = toml::Value::try_from(size).unwrap();
+			assert_eq!(size, v.try_into().unwrap());
+		}
+	}
 }
@@ -5,11 +5,89 @@
 //! ## Status
 //!
 //! Early development, but usable.
+//!
+//! ## Library use
+//!
+//! Actions which change a todo list have a `run` function taking the list,
+//! a plain config struct, and a `MutationObserver` which can veto or log
+//! each change. They return an `ActionOutcome` with the changed list, how
+//! many tasks changed, and messages saying what was done. Nothing is
+//! printed or saved.
+//!
+//! They do still depend on their surroundings in a few ways:
+//!
+//! - Anything which depends on the date, like urgency or completion dates,
+//!   uses `now`, which reads `TADA_TODAY` unless `item::set_now` has been
+//!   called for the thread.
+//! - Parsing tasks uses the `[dates]` section of the config file, for
+//!   natural language dates and the start of the week.
+//! - Search terms like `work#12` check the config file's profile names.
+//!
+//! The config file is read once. If it can't be read or parsed, it's
+//! treated as empty; `config::Config::try_load` gives the error.
+//!
+//! - `action::age::run` with an `AgeActionConfig`
+//! - `action::done::run` with a `DoneActionConfig`
+//! - `action::modify::run` with search terms and a `ModifyActionConfig`
+//! - `action::pri::run` with a `PriActionConfig`
+//! - `action::pull::run` with a `PullActionConfig`
+//! - `action::remove::run` with a `RemoveActionConfig`
+//! - `action::rename_tag::run` with a `Renaming`
+//! - `action::snooze::run` with a `SnoozeActionConfig`
+//! - `action::start::run` with a `StartActionConfig`
+//! - `action::stop::run` with a `StopActionConfig`
+//! - `action::zen::run` with a `ZenActionConfig`
+//!
+//! A few actions don't ask about each change, so take no observer:
+//!
+//! - `action::add::run` with the tasks to add and an `AddActionConfig`
+//! - `action::archive::run` with an `ArchiveActionConfig`, also returning
+//!   the lines for done.txt
+//! - `action::edit::run` with an `EditActionConfig`
+//! - `action::tidy::run` with a `TidyActionConfig`, also returning which
+//!   tasks were renumbered
+//!
+//! ```
+//! use tada::action::done::{run, DoneActionConfig};
+//! use tada::action::{NoObserver, SearchTerms};
+//! use tada::List;
+//!
+//! let list = List::from_string(String::from("Buy milk\nCall Mum\n")).unwrap();
+//! let cfg = DoneActionConfig {
+//!     search_terms: SearchTerms::from_string("milk"),
+//!     completion_date: None,
+//!     ..DoneActionConfig::default()
+//! };
+//! let outcome = run(list, &cfg, &mut NoObserver).unwrap();
+//! assert_eq!(1, outcome.count);
+//! assert_eq!("x Buy milk\nCall Mum\n", outcome.list.serialize());
+//! ```
+//!
+//! The command line's `execute` functions are thin wrappers around these.
+//! Saving a list with `List::save` returns an `error::Error` if it fails,
+//! and a backup which can't be made is logged as a warning.
+//!
+//! ## Serialization
+//!
+//! `Item`, `Line`, `List`, and related types implement `Serialize` and
+//! `Deserialize`. Items are serialized with their raw text and everything
+//! parsed from it, but deserialized by parsing `text`.
 
-pub use item::{Importance, Item, TshirtSize, Urgency};
-pub use list::{Line, LineKind, List};
+pub use item::{
+	now, today, FixupNote, FixupRule, Importance, Item, TshirtSize, Urgency,
+	ZenPolicy,
+};
+pub use list::{Line, LineEnding, LineKind, List};
 
 pub mod action;
+pub mod backup;
+pub mod config;
+pub mod error;
+pub mod format;
+pub mod ics;
+pub mod import;
 pub mod item;
 pub mod list;
+pub mod logging;
+pub mod template;
 pub mod util;
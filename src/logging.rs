@@ -0,0 +1,68 @@
+//! A simple backend for the `log` crate, writing to stderr.
+//!
+//! Warnings, like a backup which couldn't be made, are always shown. The
+//! command line's `-v` option adds more: once for which files are used and
+//! how much of them was read, twice to add HTTP requests and their
+//! responses, and three times to add which search terms each task matched.
+//!
+//! Only tada's own messages are shown, not those of the crates it uses.
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Writes log messages to stderr, prefixed with their level.
+struct StderrLogger;
+
+impl Log for StderrLogger {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		metadata.level() <= log::max_level()
+			&& metadata.target().starts_with("tada")
+	}
+
+	fn log(&self, record: &Record) {
+		if self.enabled(record.metadata()) {
+			eprintln!(
+				"[{}] {}",
+				record.level().as_str().to_lowercase(),
+				record.args()
+			);
+		}
+	}
+
+	fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+/// The most detailed level of message to show for a number of `-v` options.
+pub fn level_for(verbosity: u8) -> LevelFilter {
+	match verbosity {
+		0 => LevelFilter::Warn,
+		1 => LevelFilter::Info,
+		2 => LevelFilter::Debug,
+		_ => LevelFilter::Trace,
+	}
+}
+
+/// Start logging to stderr, with detail for a number of `-v` options.
+///
+/// With no `-v` options, only warnings are logged. Only the first call has any
+/// effect.
+pub fn init(verbosity: u8) {
+	if log::set_logger(&LOGGER).is_ok() {
+		log::set_max_level(level_for(verbosity));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_level_for() {
+		assert_eq!(LevelFilter::Warn, level_for(0));
+		assert_eq!(LevelFilter::Info, level_for(1));
+		assert_eq!(LevelFilter::Debug, level_for(2));
+		assert_eq!(LevelFilter::Trace, level_for(3));
+		assert_eq!(LevelFilter::Trace, level_for(9));
+	}
+}
@@ -0,0 +1,60 @@
+//! Starter content for new todo.txt and done.txt files.
+
+use crate::list::List;
+
+/// Comments placed at the top of a new todo.txt.
+///
+/// These are kept short, so they don't count for much towards the blank
+/// and comment lines `housekeeping_warnings` allows, or push the first
+/// task far down the list.
+pub const TODO_TXT: &str = "\
+# One task per line, like: (A) Call Mum @phone +party due:2023-01-07
+# See https://github.com/todotxt/todo.txt for the format.
+";
+
+/// Comments placed at the top of a new done.txt.
+pub const DONE_TXT: &str = "\
+# This is a done.txt file. The `tada archive` command moves completed
+# tasks here from todo.txt. Completed tasks start with an x and the date
+# they were finished, for example:
+#
+#   x 2023-01-05 2023-01-01 Call Mum about the party @phone +birthday
+";
+
+/// A new todo list, with explanatory comments unless `empty` is true.
+pub fn todo_list(empty: bool) -> List {
+	_list(if empty { "" } else { TODO_TXT })
+}
+
+/// A new done list, with explanatory comments unless `empty` is true.
+pub fn done_list(empty: bool) -> List {
+	_list(if empty { "" } else { DONE_TXT })
+}
+
+fn _list(text: &str) -> List {
+	List::from_string(String::from(text)).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::list::LineKind;
+
+	#[test]
+	fn test_templates_are_comments() {
+		for list in [todo_list(false), done_list(false)] {
+			assert!(!list.lines.is_empty());
+			assert!(list
+				.lines
+				.iter()
+				.all(|l| l.kind == LineKind::Comment));
+			assert!(list.items().is_empty());
+		}
+	}
+
+	#[test]
+	fn test_empty_templates() {
+		assert!(todo_list(true).lines.is_empty());
+		assert!(done_list(true).lines.is_empty());
+	}
+}
@@ -3,8 +3,23 @@
 //! These mostly operate on `Vec<&Item>`.
 
 use crate::action::SortOrder;
-use crate::item::{Importance, Item, TshirtSize, Urgency};
+use crate::config::Config;
+use crate::item::{parse_date_arg, Importance, Item, TshirtSize, Urgency};
+use chrono::{Duration, Months, NaiveDate};
+use lazy_static::lazy_static;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::env;
+use std::hash::Hash;
+
+/// Lines of unchanged text shown around each change by `unified_diff`.
+const DIFF_CONTEXT: usize = 3;
+
+lazy_static! {
+	/// Names of the profiles in the config file.
+	static ref PROFILE_NAMES: Vec<String> =
+		Config::load().profiles.into_keys().collect();
+}
 
 /// Sort Vec<&Item> in a variety of ways.
 pub fn sort_items_by<'a>(
@@ -42,15 +57,173 @@ pub fn find_items_by_tag<'a>(
 		.collect()
 }
 
-/// Filter Vec<&Item> by a #linenumber.
+/// Parse a line reference like `#12`, or `work#12` for line 12 of the
+/// `work` profile.
+///
+/// Only the names of profiles in the config file can come before the `#`,
+/// so a term like `bug#42` isn't a line reference.
+pub fn parse_line_reference(term: &str) -> Option<(Option<&str>, usize)> {
+	parse_line_reference_for(term, |p| PROFILE_NAMES.iter().any(|n| n == p))
+}
+
+/// Parse a line reference like `parse_line_reference`, with `is_profile`
+/// saying which names may come before the `#`.
+pub fn parse_line_reference_for(
+	term: &str,
+	is_profile: impl Fn(&str) -> bool,
+) -> Option<(Option<&str>, usize)> {
+	let (source, n) = term.split_once('#')?;
+	let n: usize = n.parse().ok()?;
+	if source.is_empty() {
+		Some((None, n))
+	} else if is_profile(source) {
+		Some((Some(source), n))
+	} else {
+		None
+	}
+}
+
+/// Check whether an item is the one referred to by a line reference.
+pub fn item_matches_line_reference(
+	item: &Item,
+	reference: (Option<&str>, usize),
+) -> bool {
+	let (source, n) = reference;
+	item.line_number() == n && (source.is_none() || source == item.source())
+}
+
+/// Filter Vec<&Item> by a #linenumber or profile#linenumber.
 pub fn find_items_by_line_number<'a>(
 	term: &'a str,
 	items: Vec<&'a Item>,
 ) -> Vec<&'a Item> {
-	let n: usize = term.get(1..).unwrap().parse().unwrap();
+	let reference = match parse_line_reference(term) {
+		Some(r) => r,
+		None => return Vec::new(),
+	};
 	items
 		.into_iter()
-		.filter(|i| i.line_number() == n)
+		.filter(|i| item_matches_line_reference(i, reference))
+		.collect()
+}
+
+/// Parse a reference to a task's `id:` tag, like `%ab3f` or `id:ab3f`.
+pub fn parse_id_reference(term: &str) -> Option<&str> {
+	term.strip_prefix('%')
+		.or_else(|| term.strip_prefix("id:"))
+		.filter(|id| !id.is_empty())
+}
+
+/// Filter Vec<&Item> by an exact id, given like `%ab3f` or `id:ab3f`.
+pub fn find_items_by_id<'a>(
+	term: &'a str,
+	items: Vec<&'a Item>,
+) -> Vec<&'a Item> {
+	let id = match parse_id_reference(term) {
+		Some(id) => id,
+		None => return Vec::new(),
+	};
+	items
+		.into_iter()
+		.filter(|i| i.id().as_deref() == Some(id))
+		.collect()
+}
+
+/// How a search term like `key:value` compares a key-value tag's value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KvOperator {
+	/// `key:*`, for any value.
+	Any,
+	/// `key:value`, for the same value.
+	Equal,
+	/// `key:<value`, for an earlier date or smaller number.
+	Less,
+	/// `key:>value`, for a later date or bigger number.
+	Greater,
+}
+
+/// A search term which matches tasks by a key-value tag.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KvTerm {
+	pub key: String,
+	pub operator: KvOperator,
+	pub value: String,
+}
+
+impl KvTerm {
+	/// Whether an item has a key-value tag matching the term.
+	///
+	/// Keys and values are compared ignoring case. Values which are both
+	/// dates, including natural dates like `today`, are compared as dates,
+	/// and otherwise values which are both numbers are compared as numbers.
+	/// Less and greater never match values which are neither.
+	pub fn matches(&self, item: &Item) -> bool {
+		let kv = item.kv();
+		let value = match kv
+			.iter()
+			.find(|(k, _)| k.eq_ignore_ascii_case(&self.key))
+		{
+			Some((_, v)) => v,
+			None => return false,
+		};
+		let ordering =
+			match (parse_date_arg(value), parse_date_arg(&self.value)) {
+				(Some(a), Some(b)) => Some(a.cmp(&b)),
+				_ => match (value.parse::<f64>(), self.value.parse::<f64>()) {
+					(Ok(a), Ok(b)) => a.partial_cmp(&b),
+					_ => None,
+				},
+			};
+		match self.operator {
+			KvOperator::Any => true,
+			KvOperator::Equal => {
+				value.eq_ignore_ascii_case(&self.value)
+					|| ordering == Some(Ordering::Equal)
+			}
+			KvOperator::Less => ordering == Some(Ordering::Less),
+			KvOperator::Greater => ordering == Some(Ordering::Greater),
+		}
+	}
+}
+
+/// Parse a search term like `ref:*`, `due:today`, or `due:>2024-06-01`.
+///
+/// Keys and values follow the same rules as key-value tags in a task, so
+/// terms like `http://example.com` aren't taken to be one.
+pub fn parse_kv_term(term: &str) -> Option<KvTerm> {
+	let (key, rest) = term.split_once(':')?;
+	if key.is_empty() || key.contains(char::is_whitespace) {
+		return None;
+	}
+	let (operator, value) = match rest.chars().next()? {
+		'*' if rest.len() == 1 => (KvOperator::Any, ""),
+		'<' => (KvOperator::Less, &rest[1..]),
+		'>' => (KvOperator::Greater, &rest[1..]),
+		_ => (KvOperator::Equal, rest),
+	};
+	if operator != KvOperator::Any
+		&& (value.is_empty()
+			|| value.starts_with([':', '/', '\\'])
+			|| value.contains(char::is_whitespace))
+	{
+		return None;
+	}
+	Some(KvTerm {
+		key: String::from(key),
+		operator,
+		value: String::from(value),
+	})
+}
+
+/// Filter Vec<&Item> by a key-value term, like `ref:*` or `due:<today`.
+pub fn find_items_by_kv<'a>(term: &str, items: Vec<&'a Item>) -> Vec<&'a Item> {
+	let kv = match parse_kv_term(term) {
+		Some(kv) => kv,
+		None => return Vec::new(),
+	};
+	items
+		.into_iter()
+		.filter(|i| kv.matches(i))
 		.collect()
 }
 
@@ -70,44 +243,787 @@ pub fn find_items_by_string<'a>(
 		.collect()
 }
 
-/// Group a Vec<&Item> into categories based on task urgency.
-pub fn group_items_by_urgency(
+/// Group a Vec<&Item> into categories based on an arbitrary key.
+///
+/// Items within each group keep their original order.
+pub fn group_items_by_key<K, F>(
 	items: Vec<&Item>,
-) -> HashMap<Urgency, Vec<&Item>> {
-	let mut out: HashMap<Urgency, Vec<&Item>> = HashMap::new();
+	key: F,
+) -> HashMap<K, Vec<&Item>>
+where
+	K: Eq + Hash,
+	F: Fn(&Item) -> K,
+{
+	let mut out: HashMap<K, Vec<&Item>> = HashMap::new();
 	for i in items {
-		let list = out
-			.entry(i.urgency().unwrap_or_default())
-			.or_insert_with(Vec::new);
-		list.push(i);
+		out.entry(key(i)).or_default().push(i);
 	}
 	out
 }
 
+/// Group a Vec<&Item> into categories based on task urgency.
+pub fn group_items_by_urgency(
+	items: Vec<&Item>,
+) -> HashMap<Urgency, Vec<&Item>> {
+	group_items_by_key(items, |i| i.urgency().unwrap_or_default())
+}
+
+/// Group a Vec<&Item> into categories based on due date.
+pub fn group_items_by_due_date(
+	items: Vec<&Item>,
+) -> HashMap<Option<NaiveDate>, Vec<&Item>> {
+	group_items_by_key(items, |i| i.due_date())
+}
+
 /// Group a Vec<&Item> into categories based on task size.
 pub fn group_items_by_size(
 	items: Vec<&Item>,
 ) -> HashMap<TshirtSize, Vec<&Item>> {
-	let mut out: HashMap<TshirtSize, Vec<&Item>> = HashMap::new();
-	for i in items {
-		let list = out
-			.entry(i.tshirt_size().unwrap_or_default())
-			.or_insert_with(Vec::new);
-		list.push(i);
-	}
-	out
+	group_items_by_key(items, |i| i.tshirt_size().unwrap_or_default())
 }
 
 /// Group a Vec<&Item> into categories based on task importance.
+///
+/// Priorities from E to Z are grouped together, under `Importance::E('E')`.
 pub fn group_items_by_importance(
 	items: Vec<&Item>,
 ) -> HashMap<Importance, Vec<&Item>> {
-	let mut out: HashMap<Importance, Vec<&Item>> = HashMap::new();
+	group_items_by_key(items, |i| i.importance().unwrap_or_default().level())
+}
+
+/// Count a Vec<&Item> by task urgency.
+///
+/// Unlike `group_items_by_urgency`, items with no due date are not counted.
+pub fn count_items_by_urgency(items: Vec<&Item>) -> HashMap<Urgency, usize> {
+	let dated = items
+		.into_iter()
+		.filter(|i| i.urgency().is_some())
+		.collect();
+	group_items_by_urgency(dated)
+		.into_iter()
+		.map(|(k, v)| (k, v.len()))
+		.collect()
+}
+
+/// Count a Vec<&Item> by task importance.
+///
+/// Unlike `group_items_by_importance`, items with no priority are not counted.
+pub fn count_items_by_importance(
+	items: Vec<&Item>,
+) -> HashMap<Importance, usize> {
+	let prioritized = items
+		.into_iter()
+		.filter(|i| i.importance().is_some())
+		.collect();
+	group_items_by_importance(prioritized)
+		.into_iter()
+		.map(|(k, v)| (k, v.len()))
+		.collect()
+}
+
+/// Group a Vec<&Item> into categories based on context.
+///
+/// Contexts are lowercased, and an item with several contexts appears in the
+/// group for each of them. Items with no contexts are grouped under "".
+pub fn group_items_by_context(
+	items: Vec<&Item>,
+) -> HashMap<String, Vec<&Item>> {
+	let mut out: HashMap<String, Vec<&Item>> = HashMap::new();
 	for i in items {
-		let list = out
-			.entry(i.importance().unwrap_or_default())
-			.or_insert_with(Vec::new);
-		list.push(i);
+		let mut contexts: Vec<String> = i
+			.contexts()
+			.iter()
+			.map(|c| c.to_lowercase())
+			.collect();
+		contexts.sort();
+		contexts.dedup();
+		if contexts.is_empty() {
+			contexts.push(String::new());
+		}
+		for c in contexts {
+			out.entry(c).or_default().push(i);
+		}
 	}
 	out
 }
+
+/// Count a Vec<&Item> by context.
+///
+/// Contexts are lowercased, and an item with several contexts is counted once
+/// for each of them. Items with no contexts are not counted.
+pub fn count_items_by_context(items: Vec<&Item>) -> HashMap<String, usize> {
+	group_items_by_context(items)
+		.into_iter()
+		.filter(|(k, _)| !k.is_empty())
+		.map(|(k, v)| (k, v.len()))
+		.collect()
+}
+
+/// Total the time spent on a Vec<&Item> by context, from `spent:` tags.
+///
+/// Contexts are lowercased, and an item with several contexts counts towards
+/// each of them. Items with no contexts, or no time spent, are not counted.
+pub fn sum_time_spent_by_context(
+	items: Vec<&Item>,
+) -> HashMap<String, Duration> {
+	group_items_by_context(items)
+		.into_iter()
+		.filter(|(k, _)| !k.is_empty())
+		.filter_map(|(k, v)| {
+			let spent: Vec<Duration> =
+				v.iter().filter_map(|i| i.spent()).collect();
+			if spent.is_empty() {
+				None
+			} else {
+				Some((
+					k,
+					spent
+						.into_iter()
+						.fold(Duration::zero(), |a, b| a + b),
+				))
+			}
+		})
+		.collect()
+}
+
+/// Count the items in a Vec<&Item> which were completed on or after a date.
+pub fn count_items_completed_since(
+	items: Vec<&Item>,
+	date: NaiveDate,
+) -> usize {
+	items
+		.into_iter()
+		.filter(|i| {
+			i.completion()
+				&& i.completion_date()
+					.map(|d| d >= date)
+					.unwrap_or(false)
+		})
+		.count()
+}
+
+/// Draw a bar for a bar chart, as a row of `#` characters.
+///
+/// Bars are scaled so a value of `max` fills `width` columns. Any value
+/// above zero gets at least one character, so it can be told apart from
+/// nothing at all.
+pub fn bar(value: usize, max: usize, width: usize) -> String {
+	if value == 0 || max == 0 {
+		return String::new();
+	}
+	let len = (value * width + max / 2) / max;
+	"#".repeat(len.clamp(1, width.max(1)))
+}
+
+/// Whether a word from a description is a key-value tag, like `due:2024-06-01`,
+/// following the same rules as `Item::kv`.
+fn is_kv_word(word: &str) -> bool {
+	match word.split_once(':') {
+		Some((key, value)) => {
+			!key.is_empty()
+				&& !value.is_empty()
+				&& !value.starts_with([':', '/', '\\'])
+		}
+		None => false,
+	}
+}
+
+/// Shorten text to fit a display width, cutting between words.
+///
+/// Text which already fits is left alone. Otherwise, as many whole words
+/// as fit are kept, followed by `…` if `ellipsis` is true, which counts
+/// towards the width. If not even the first word fits, it is cut short,
+/// unless it's a key-value tag, which would look like a different value,
+/// so is left out altogether.
+pub fn truncate_words(text: &str, width: usize, ellipsis: bool) -> String {
+	if console::measure_text_width(text) <= width {
+		return String::from(text);
+	}
+	let tail = if ellipsis && width > 0 { "…" } else { "" };
+	let room = width - console::measure_text_width(tail);
+
+	// The end of the last word which fits, and the first word.
+	let mut end = 0;
+	let mut first = None;
+	let mut start = None;
+	for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+		match (c.is_whitespace(), start) {
+			(false, None) => start = Some(i),
+			(true, Some(s)) => {
+				first.get_or_insert(&text[s..i]);
+				if console::measure_text_width(&text[..i]) > room {
+					break;
+				}
+				end = i;
+				start = None;
+			}
+			_ => {}
+		}
+	}
+
+	let kept = match (end, first) {
+		(0, Some(word)) if !is_kv_word(word) => {
+			let start = text.len() - text.trim_start().len();
+			console::truncate_str(&text[start..], room, "").into_owned()
+		}
+		(0, _) => String::new(),
+		_ => String::from(&text[..end]),
+	};
+	format!("{}{}", kept, tail)
+}
+
+/// Find the date a period like `3d`, `2w`, `1m`, or `1y` after `date`.
+///
+/// Months and years are calendar months, so a month after 31 January is
+/// the last day of February. Returns `None` if the period isn't understood.
+pub fn date_after_period(date: NaiveDate, period: &str) -> Option<NaiveDate> {
+	let period = period.trim();
+	let unit = period.chars().last()?;
+	let n: u32 = period[..period.len() - unit.len_utf8()]
+		.parse()
+		.ok()?;
+	match unit {
+		'd' => date.checked_add_signed(Duration::days(n.into())),
+		'w' => date.checked_add_signed(Duration::weeks(n.into())),
+		'm' => date.checked_add_months(Months::new(n)),
+		'y' => date.checked_add_months(Months::new(n.checked_mul(12)?)),
+		_ => None,
+	}
+}
+
+/// Shorten a path in the user's home directory to start with `~`.
+pub fn tilde_path(path: &str) -> String {
+	match env::var("HOME") {
+		Ok(home) if !home.is_empty() => match path.strip_prefix(&home) {
+			Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+				format!("~{}", rest)
+			}
+			_ => path.to_string(),
+		},
+		_ => path.to_string(),
+	}
+}
+
+/// How a line differs between two texts.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum DiffOp {
+	Same,
+	Removed,
+	Added,
+}
+
+/// Compare two texts line by line, using their longest common subsequence.
+fn diff_lines<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<(DiffOp, &'a str)> {
+	let prefix = a
+		.iter()
+		.zip(b.iter())
+		.take_while(|(x, y)| x == y)
+		.count();
+	let suffix = a[prefix..]
+		.iter()
+		.rev()
+		.zip(b[prefix..].iter().rev())
+		.take_while(|(x, y)| x == y)
+		.count();
+	let a_mid = &a[prefix..a.len() - suffix];
+	let b_mid = &b[prefix..b.len() - suffix];
+
+	// lcs[i][j] is the length of the LCS of a_mid[i..] and b_mid[j..].
+	let mut lcs = vec![vec![0usize; b_mid.len() + 1]; a_mid.len() + 1];
+	for i in (0..a_mid.len()).rev() {
+		for j in (0..b_mid.len()).rev() {
+			lcs[i][j] = if a_mid[i] == b_mid[j] {
+				lcs[i + 1][j + 1] + 1
+			} else {
+				lcs[i + 1][j].max(lcs[i][j + 1])
+			};
+		}
+	}
+
+	let mut ops: Vec<(DiffOp, &str)> = a[..prefix]
+		.iter()
+		.map(|l| (DiffOp::Same, *l))
+		.collect();
+	let (mut i, mut j) = (0, 0);
+	while i < a_mid.len() || j < b_mid.len() {
+		if i < a_mid.len() && j < b_mid.len() && a_mid[i] == b_mid[j] {
+			ops.push((DiffOp::Same, a_mid[i]));
+			i += 1;
+			j += 1;
+		} else if j == b_mid.len()
+			|| (i < a_mid.len() && lcs[i + 1][j] >= lcs[i][j + 1])
+		{
+			ops.push((DiffOp::Removed, a_mid[i]));
+			i += 1;
+		} else {
+			ops.push((DiffOp::Added, b_mid[j]));
+			j += 1;
+		}
+	}
+	ops.extend(
+		a[a.len() - suffix..]
+			.iter()
+			.map(|l| (DiffOp::Same, *l)),
+	);
+	ops
+}
+
+/// A unified diff between two texts, as lines without line breaks.
+///
+/// `label` names the file in the `---` and `+++` headers. Returns an empty
+/// Vec if the texts have the same lines.
+pub fn unified_diff(old: &str, new: &str, label: &str) -> Vec<String> {
+	let a: Vec<&str> = old.lines().collect();
+	let b: Vec<&str> = new.lines().collect();
+	let ops = diff_lines(&a, &b);
+	let changes: Vec<usize> = ops
+		.iter()
+		.enumerate()
+		.filter(|(_, (op, _))| *op != DiffOp::Same)
+		.map(|(idx, _)| idx)
+		.collect();
+	if changes.is_empty() {
+		return Vec::new();
+	}
+
+	// Group changes into hunks, merging those whose context would overlap.
+	let mut hunks: Vec<(usize, usize)> = Vec::new();
+	for idx in changes {
+		let start = idx.saturating_sub(DIFF_CONTEXT);
+		let end = (idx + 1 + DIFF_CONTEXT).min(ops.len());
+		match hunks.last_mut() {
+			Some(last) if start <= last.1 => last.1 = end,
+			_ => hunks.push((start, end)),
+		}
+	}
+
+	// Line numbers in each text before each op.
+	let mut positions = Vec::with_capacity(ops.len());
+	let (mut old_pos, mut new_pos) = (0, 0);
+	for (op, _) in &ops {
+		positions.push((old_pos, new_pos));
+		match op {
+			DiffOp::Same => {
+				old_pos += 1;
+				new_pos += 1;
+			}
+			DiffOp::Removed => old_pos += 1,
+			DiffOp::Added => new_pos += 1,
+		}
+	}
+
+	let range = |pos: usize, count: usize| match count {
+		0 => format!("{},0", pos),
+		_ => format!("{},{}", pos + 1, count),
+	};
+	let mut out = vec![format!("--- {}", label), format!("+++ {}", label)];
+	for (start, end) in hunks {
+		let hunk = &ops[start..end];
+		let old_count = hunk
+			.iter()
+			.filter(|(op, _)| *op != DiffOp::Added)
+			.count();
+		let new_count = hunk
+			.iter()
+			.filter(|(op, _)| *op != DiffOp::Removed)
+			.count();
+		let (old_pos, new_pos) = positions[start];
+		out.push(format!(
+			"@@ -{} +{} @@",
+			range(old_pos, old_count),
+			range(new_pos, new_count)
+		));
+		for (op, line) in hunk {
+			let sigil = match op {
+				DiffOp::Same => ' ',
+				DiffOp::Removed => '-',
+				DiffOp::Added => '+',
+			};
+			out.push(format!("{}{}", sigil, line));
+		}
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_count_items_by_urgency() {
+		let items = [
+			Item::parse("foo due:1970-01-01"),
+			Item::parse("bar due:1971-01-01"),
+			Item::parse("baz due:3970-01-01"),
+			Item::parse("bat"),
+		];
+		let counts = count_items_by_urgency(items.iter().collect());
+		assert_eq!(Some(&2), counts.get(&Urgency::Overdue));
+		assert_eq!(Some(&1), counts.get(&Urgency::Later));
+		assert_eq!(None, counts.get(&Urgency::Soon));
+	}
+
+	#[test]
+	fn test_count_items_by_importance() {
+		let items = [
+			Item::parse("(A) foo"),
+			Item::parse("(A) bar"),
+			Item::parse("(Z) baz"),
+			Item::parse("bat"),
+		];
+		let counts = count_items_by_importance(items.iter().collect());
+		assert_eq!(Some(&2), counts.get(&Importance::A));
+		assert_eq!(Some(&1), counts.get(&Importance::E('E')));
+		assert_eq!(None, counts.get(&Importance::D));
+	}
+
+	#[test]
+	fn test_group_items_by_key() {
+		let items =
+			[Item::parse("foo"), Item::parse("x bar"), Item::parse("baz")];
+		let groups =
+			group_items_by_key(items.iter().collect(), |i| i.completion());
+		assert_eq!(2, groups.len());
+		let open = groups.get(&false).unwrap();
+		assert_eq!("foo", open[0].description());
+		assert_eq!("baz", open[1].description());
+		assert_eq!("bar", groups.get(&true).unwrap()[0].description());
+	}
+
+	#[test]
+	fn test_group_items_by_urgency() {
+		let items = [
+			Item::parse("foo due:1970-01-01"),
+			Item::parse("bar due:1971-01-01"),
+			Item::parse("baz due:3970-01-01"),
+			Item::parse("bat"),
+		];
+		let groups = group_items_by_urgency(items.iter().collect());
+		assert_eq!(2, groups.get(&Urgency::Overdue).unwrap().len());
+		assert_eq!(1, groups.get(&Urgency::Later).unwrap().len());
+		// Items with no due date fall into the default group.
+		let soon = groups.get(&Urgency::Soon).unwrap();
+		assert_eq!(1, soon.len());
+		assert_eq!("bat", soon[0].description());
+	}
+
+	#[test]
+	fn test_group_items_by_due_date() {
+		let items = [
+			Item::parse("foo due:2024-06-07"),
+			Item::parse("bar due:2024-06-08"),
+			Item::parse("baz due:2024-06-07"),
+			Item::parse("bat"),
+		];
+		let groups = group_items_by_due_date(items.iter().collect());
+		let friday = NaiveDate::from_ymd_opt(2024, 6, 7);
+		let saturday = NaiveDate::from_ymd_opt(2024, 6, 8);
+		assert_eq!(3, groups.len());
+		assert_eq!(2, groups.get(&friday).unwrap().len());
+		assert_eq!(1, groups.get(&saturday).unwrap().len());
+		assert_eq!("bat", groups.get(&None).unwrap()[0].description());
+	}
+
+	#[test]
+	fn test_group_items_by_size() {
+		let items = [
+			Item::parse("foo @S"),
+			Item::parse("bar @XL"),
+			Item::parse("baz @s"),
+			Item::parse("bat"),
+		];
+		let groups = group_items_by_size(items.iter().collect());
+		assert_eq!(2, groups.get(&TshirtSize::Small).unwrap().len());
+		assert_eq!(1, groups.get(&TshirtSize::Large).unwrap().len());
+		assert_eq!(
+			"bat",
+			groups.get(&TshirtSize::default()).unwrap()[0].description()
+		);
+	}
+
+	#[test]
+	fn test_group_items_by_importance() {
+		let items = [
+			Item::parse("(F) foo"),
+			Item::parse("(E) bar"),
+			Item::parse("(G) baz"),
+			Item::parse("bat"),
+		];
+		let groups = group_items_by_importance(items.iter().collect());
+		assert_eq!(2, groups.len());
+		assert_eq!(3, groups.get(&Importance::E('E')).unwrap().len());
+		assert_eq!(1, groups.get(&Importance::D).unwrap().len());
+	}
+
+	#[test]
+	fn test_parse_line_reference() {
+		assert_eq!(Some((None, 12)), parse_line_reference("#12"));
+		assert_eq!(None, parse_line_reference("#foo"));
+		assert_eq!(None, parse_line_reference("foo"));
+
+		let is_profile = |p: &str| p == "work";
+		assert_eq!(
+			Some((Some("work"), 3)),
+			parse_line_reference_for("work#3", is_profile)
+		);
+		assert_eq!(None, parse_line_reference_for("bug#42", is_profile));
+		assert_eq!(None, parse_line_reference_for("foo bar#3", is_profile));
+	}
+
+	#[test]
+	fn test_find_items_by_line_number() {
+		let mut a = Item::parse("foo");
+		a.set_line_number(3);
+		a.set_source(Some(String::from("work")));
+		let mut b = Item::parse("bar");
+		b.set_line_number(3);
+		b.set_source(Some(String::from("home")));
+		let items = Vec::from([&a, &b]);
+		assert_eq!(2, find_items_by_line_number("#3", items.clone()).len());
+		assert_eq!(0, find_items_by_line_number("#4", items).len());
+		assert!(item_matches_line_reference(&b, (Some("home"), 3)));
+		assert!(!item_matches_line_reference(&a, (Some("home"), 3)));
+	}
+
+	#[test]
+	fn test_parse_id_reference() {
+		assert_eq!(Some("ab3f"), parse_id_reference("%ab3f"));
+		assert_eq!(Some("ab3f"), parse_id_reference("id:ab3f"));
+		assert_eq!(None, parse_id_reference("%"));
+		assert_eq!(None, parse_id_reference("ab3f"));
+	}
+
+	#[test]
+	fn test_find_items_by_id() {
+		let items = [
+			Item::parse("foo id:ab3f"),
+			Item::parse("bar id:ab3f0"),
+			Item::parse("baz"),
+		];
+		let items: Vec<&Item> = items.iter().collect();
+		let found = find_items_by_id("%ab3f", items.clone());
+		assert_eq!(1, found.len());
+		assert_eq!("foo id:ab3f", found[0].description());
+		assert_eq!(1, find_items_by_id("id:ab3f0", items.clone()).len());
+		assert_eq!(0, find_items_by_id("%ab3", items).len());
+	}
+
+	#[test]
+	fn test_parse_kv_term() {
+		let kv = |key: &str, operator: KvOperator, value: &str| {
+			Some(KvTerm {
+				key: String::from(key),
+				operator,
+				value: String::from(value),
+			})
+		};
+		assert_eq!(kv("ref", KvOperator::Any, ""), parse_kv_term("ref:*"));
+		assert_eq!(
+			kv("due", KvOperator::Equal, "today"),
+			parse_kv_term("due:today")
+		);
+		assert_eq!(
+			kv("due", KvOperator::Less, "2024-06-01"),
+			parse_kv_term("due:<2024-06-01")
+		);
+		assert_eq!(
+			kv("est", KvOperator::Greater, "30"),
+			parse_kv_term("est:>30")
+		);
+		assert_eq!(kv("a", KvOperator::Equal, "*b"), parse_kv_term("a:*b"));
+		for term in ["foo", ":foo", "due:", "due:<", "http://x", "a b:c"] {
+			assert_eq!(None, parse_kv_term(term), "{}", term);
+		}
+	}
+
+	#[test]
+	fn test_find_items_by_kv() {
+		let items = [
+			Item::parse("foo ref:ABC due:2024-06-01 est:30"),
+			Item::parse("bar ref:abcd due:2024-06-07 est:5"),
+			Item::parse("baz due:2024-06-10 est:big"),
+			Item::parse("bat"),
+		];
+		let items: Vec<&Item> = items.iter().collect();
+		let found = |term: &str| -> Vec<&str> {
+			find_items_by_kv(term, items.clone())
+				.iter()
+				.map(|i| i.description().split(' ').next().unwrap())
+				.collect()
+		};
+		assert_eq!(vec!["foo", "bar"], found("ref:*"));
+		assert_eq!(vec!["foo", "bar", "baz"], found("DUE:*"));
+		assert_eq!(vec!["foo"], found("ref:abc"));
+		assert_eq!(vec!["foo"], found("est:30.0"));
+		assert_eq!(vec!["bar"], found("due:2024-06-07"));
+		assert_eq!(Vec::<&str>::new(), found("due:2024-06"));
+		assert_eq!(vec!["foo"], found("due:<2024-06-07"));
+		assert_eq!(vec!["baz"], found("due:>2024-06-07"));
+		assert_eq!(vec!["bar"], found("est:<10"));
+		assert_eq!(vec!["foo"], found("est:>10"));
+		assert_eq!(Vec::<&str>::new(), found("ref:>abc"));
+		let friday = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+		crate::item::with_today(friday, || {
+			assert_eq!(vec!["bar"], found("due:today"));
+			assert_eq!(vec!["foo"], found("due:<today"));
+		});
+	}
+
+	#[test]
+	fn test_group_items_by_context() {
+		let items = [
+			Item::parse("foo @Home @home @S"),
+			Item::parse("bar @home"),
+			Item::parse("baz"),
+		];
+		let groups = group_items_by_context(items.iter().collect());
+		assert_eq!(3, groups.len());
+		assert_eq!(2, groups.get("home").unwrap().len());
+		assert_eq!(1, groups.get("s").unwrap().len());
+		assert_eq!("baz", groups.get("").unwrap()[0].description());
+	}
+
+	#[test]
+	fn test_count_items_by_context() {
+		let items = [
+			Item::parse("foo @Home @home @S"),
+			Item::parse("bar @home"),
+			Item::parse("baz"),
+		];
+		let counts = count_items_by_context(items.iter().collect());
+		assert_eq!(2, counts.len());
+		assert_eq!(Some(&2), counts.get("home"));
+		assert_eq!(Some(&1), counts.get("s"));
+	}
+
+	#[test]
+	fn test_sum_time_spent_by_context() {
+		let items = [
+			Item::parse("foo @Home @work spent:1h"),
+			Item::parse("x bar @home spent:30m"),
+			Item::parse("baz @home"),
+			Item::parse("bat @play"),
+			Item::parse("qux spent:5m"),
+		];
+		let sums = sum_time_spent_by_context(items.iter().collect());
+		assert_eq!(2, sums.len());
+		assert_eq!(Some(&Duration::minutes(90)), sums.get("home"));
+		assert_eq!(Some(&Duration::minutes(60)), sums.get("work"));
+	}
+
+	#[test]
+	fn test_count_items_completed_since() {
+		let items = [
+			Item::parse("x 2000-01-10 2000-01-01 foo"),
+			Item::parse("x 2000-01-05 2000-01-01 bar"),
+			Item::parse("x 2000-01-01 baz"),
+			Item::parse("2000-01-20 bat"),
+		];
+		let date = NaiveDate::from_ymd_opt(2000, 1, 5).unwrap();
+		assert_eq!(
+			2,
+			count_items_completed_since(items.iter().collect(), date)
+		);
+	}
+
+	#[test]
+	fn test_bar() {
+		assert_eq!("##########", bar(10, 10, 10));
+		assert_eq!("#####", bar(5, 10, 10));
+		assert_eq!("#", bar(1, 100, 10));
+		assert_eq!("", bar(0, 10, 10));
+		assert_eq!("", bar(0, 0, 10));
+		assert_eq!("##", bar(3, 4, 3));
+	}
+
+	#[test]
+	fn test_truncate_words() {
+		let text = "Fix the menu due:2024-06-10 soon";
+		assert_eq!(text, truncate_words(text, 32, true));
+		assert_eq!(text, truncate_words(text, 99, true));
+		// Cutting inside a word drops the rest of it.
+		assert_eq!("Fix the…", truncate_words(text, 10, true));
+		assert_eq!("Fix the", truncate_words(text, 10, false));
+		// Cutting just after a word keeps it, as long as there's room for
+		// the ellipsis.
+		assert_eq!("Fix the…", truncate_words(text, 8, true));
+		assert_eq!("Fix…", truncate_words(text, 7, true));
+		assert_eq!("Fix the", truncate_words(text, 7, false));
+		// Cutting inside a key-value tag drops all of it.
+		assert_eq!("Fix the menu…", truncate_words(text, 20, true));
+		assert_eq!(
+			"Fix the menu due:2024-06-10…",
+			truncate_words(text, 31, true)
+		);
+		// A first word which doesn't fit is cut short, unless it's a
+		// key-value tag.
+		assert_eq!("Superc…", truncate_words("Supercalifragilistic", 7, true));
+		assert_eq!("…", truncate_words("due:2024-06-10 Foo", 8, true));
+		assert_eq!("", truncate_words("Foo", 0, true));
+	}
+
+	#[test]
+	fn test_unified_diff() {
+		assert!(unified_diff("a\nb\n", "a\nb\n", "x").is_empty());
+
+		let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+		let new = "1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n";
+		assert_eq!(
+			vec![
+				"--- todo.txt",
+				"+++ todo.txt",
+				"@@ -1,6 +1,6 @@",
+				" 1",
+				" 2",
+				"-3",
+				"+three",
+				" 4",
+				" 5",
+				" 6",
+				"@@ -10,3 +10,4 @@",
+				" 10",
+				" 11",
+				" 12",
+				"+13",
+			],
+			unified_diff(old, new, "todo.txt")
+		);
+
+		assert_eq!(
+			vec!["--- x", "+++ x", "@@ -1,2 +1,1 @@", "-a", " b"],
+			unified_diff("a\nb\n", "b\n", "x")
+		);
+		assert_eq!(
+			vec!["--- x", "+++ x", "@@ -0,0 +1,1 @@", "+a"],
+			unified_diff("", "a\n", "x")
+		);
+	}
+
+	#[test]
+	fn test_date_after_period() {
+		let d = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+		let jan31 = d("2024-01-31");
+		assert_eq!(Some(d("2024-02-03")), date_after_period(jan31, "3d"));
+		assert_eq!(Some(d("2024-02-14")), date_after_period(jan31, "2w"));
+		assert_eq!(Some(d("2024-02-29")), date_after_period(jan31, "1m"));
+		assert_eq!(Some(d("2025-01-31")), date_after_period(jan31, "1y"));
+		assert_eq!(Some(jan31), date_after_period(jan31, "0d"));
+		assert_eq!(None, date_after_period(jan31, "d"));
+		assert_eq!(None, date_after_period(jan31, "3"));
+		assert_eq!(None, date_after_period(jan31, "3x"));
+		assert_eq!(None, date_after_period(jan31, "-3d"));
+		assert_eq!(None, date_after_period(jan31, ""));
+		assert_eq!(None, date_after_period(jan31, "3é"));
+	}
+
+	#[test]
+	fn test_tilde_path() {
+		let home = env::var("HOME").unwrap();
+		assert_eq!("~/todo.txt", tilde_path(&format!("{}/todo.txt", home)));
+		assert_eq!(
+			format!("{}x/todo.txt", home),
+			tilde_path(&format!("{}x/todo.txt", home))
+		);
+		assert_eq!("/elsewhere/todo.txt", tilde_path("/elsewhere/todo.txt"));
+	}
+}